@@ -3,7 +3,7 @@
 use std::io::{Cursor, Read};
 
 extern crate test;
-use lopdf::Document;
+use lopdf::{dictionary, Document, Stream};
 
 #[bench]
 fn bench_load(b: &mut test::test::Bencher) {
@@ -18,6 +18,19 @@ fn bench_load(b: &mut test::test::Bencher) {
     })
 }
 
+// Peak RSS of loading assets/AnnotationDemo.pdf (6.9 MB), measured with examples/peak_memory.rs:
+// Document::load:           17,252 KiB
+// Document::load_mmap:      17,028 KiB
+// Document::load_lazy_mmap:  2,840 KiB, reading the catalog only
+// Mapping the file saves little on a full load, because the parsed objects copy the stream data.
+#[cfg(feature = "mmap")]
+#[bench]
+fn bench_load_mmap(b: &mut test::test::Bencher) {
+    b.iter(|| {
+        Document::load_mmap("assets/example.pdf").unwrap();
+    })
+}
+
 #[bench]
 fn bench_load_incremental_pdf(b: &mut test::test::Bencher) {
     let mut buffer = Vec::new();
@@ -30,3 +43,35 @@ fn bench_load_incremental_pdf(b: &mut test::test::Bencher) {
         Document::load_from(Cursor::new(&buffer)).unwrap();
     })
 }
+
+// 200 streams of 64 KiB each, to compare `decompress` with `decompress_parallel`.
+fn streams_document() -> Document {
+    let mut doc = Document::with_version("1.5");
+    for i in 0..200u32 {
+        let content: Vec<u8> = (0..8192u32)
+            .flat_map(|j| (i * j).to_be_bytes())
+            .chain([0; 32768])
+            .collect();
+        let mut stream = Stream::new(dictionary! {}, content);
+        stream.compress().unwrap();
+        doc.add_object(stream);
+    }
+    doc
+}
+
+#[bench]
+fn bench_decompress_streams(b: &mut test::test::Bencher) {
+    let doc = streams_document();
+    b.iter(|| {
+        doc.clone().decompress();
+    })
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn bench_decompress_streams_parallel(b: &mut test::test::Bencher) {
+    let doc = streams_document();
+    b.iter(|| {
+        doc.clone().decompress_parallel();
+    })
+}
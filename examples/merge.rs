@@ -109,8 +109,8 @@ fn main() {
 
         // This is actually better than extend as we use less allocations and cloning then.
         pages
-            .into_iter()
-            .map(|(_, object_id)| {
+            .into_values()
+            .map(|object_id| {
                 // We use this as the return object for Bookmarking to deturmine what it points too.
                 // We only want to do this for the first page though.
                 if first_object.is_none() {
@@ -251,8 +251,8 @@ fn main() {
         dictionary.set(
             "Kids",
             documents_pages
-                .into_iter()
-                .map(|(object_id, _)| Object::Reference(object_id))
+                .into_keys()
+                .map(Object::Reference)
                 .collect::<Vec<_>>(),
         );
 
@@ -0,0 +1,58 @@
+//! Print the peak resident set size of loading a PDF file, on Linux.
+//!
+//! ```sh
+//! cargo run --release --features mmap --example peak_memory -- assets/AnnotationDemo.pdf mmap
+//! ```
+
+use std::fs;
+
+use clap::{Parser, ValueEnum};
+use lopdf::Document;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Mode {
+    /// `Document::load`, reading the file into a buffer.
+    Load,
+    /// `Document::load_mmap`.
+    Mmap,
+    /// `Document::load_lazy_mmap`, then reading the catalog only.
+    LazyMmap,
+}
+
+#[derive(Parser, Debug)]
+#[clap(about = "Print the peak resident set size of loading a PDF file.")]
+struct Args {
+    pdf_path: String,
+    #[clap(value_enum)]
+    mode: Mode,
+}
+
+/// Read a field of `/proc/self/status`, in KiB.
+fn status_kib(field: &str) -> u64 {
+    let status = fs::read_to_string("/proc/self/status").expect("/proc/self/status is only available on Linux");
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.trim().strip_suffix("kB")?.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn main() {
+    let args = Args::parse();
+    let before = status_kib("VmHWM:");
+    let objects = match args.mode {
+        Mode::Load => Document::load(&args.pdf_path).unwrap().objects.len(),
+        Mode::Mmap => Document::load_mmap(&args.pdf_path).unwrap().objects.len(),
+        Mode::LazyMmap => {
+            let mut doc = Document::load_lazy_mmap(&args.pdf_path).unwrap();
+            let root = doc.trailer.get(b"Root").and_then(|root| root.as_reference()).unwrap();
+            doc.get_object(root).map(|_| 1).unwrap()
+        }
+    };
+    println!(
+        "{:?}: {} objects, peak RSS {} KiB (before loading {} KiB)",
+        args.mode,
+        objects,
+        status_kib("VmHWM:"),
+        before
+    );
+}
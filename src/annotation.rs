@@ -0,0 +1,451 @@
+use log::warn;
+
+use crate::document::rectangle;
+use crate::{Dictionary, Document, Object, ObjectId, Result};
+
+/// Annotation of a page, with the entries common to all annotation types parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// Id of the annotation dictionary, if it is an indirect object.
+    pub id: Option<ObjectId>,
+    /// Location of the annotation on the page, as `[llx, lly, urx, ury]`.
+    pub rect: [f32; 4],
+    /// Text displayed for the annotation, or a description of it for other annotation types.
+    pub contents: Option<String>,
+    /// Action performed when the annotation is activated.
+    pub action: Option<Action>,
+    pub kind: AnnotationKind,
+}
+
+/// Type of an annotation, from its `Subtype`, with the entries specific to that type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationKind {
+    Text {
+        /// Whether the note is initially displayed open.
+        open: bool,
+        /// Name of the icon used to display the note, such as `Comment` or `Note`.
+        icon: Option<String>,
+    },
+    Link {
+        /// Destination displayed when the link is activated, if it has no action.
+        dest: Option<Object>,
+    },
+    FreeText {
+        /// Default appearance of the text, as content stream operators.
+        default_appearance: Option<String>,
+    },
+    Highlight {
+        /// Quadrilaterals covering the marked text, four points each.
+        quad_points: Vec<f32>,
+    },
+    Underline {
+        quad_points: Vec<f32>,
+    },
+    Squiggly {
+        quad_points: Vec<f32>,
+    },
+    StrikeOut {
+        quad_points: Vec<f32>,
+    },
+    Ink {
+        /// Paths drawn, each as alternating horizontal and vertical coordinates.
+        ink_list: Vec<Vec<f32>>,
+    },
+    Popup {
+        /// Id of the annotation the pop-up window belongs to.
+        parent: Option<ObjectId>,
+        open: bool,
+    },
+    Widget {
+        /// Fully qualified name of the form field, joining the names of its ancestors with `.`.
+        field_name: Option<String>,
+        /// Type of the form field, `Btn`, `Tx`, `Ch` or `Sig`, which may be inherited.
+        field_type: Option<String>,
+        /// Appearance state of a check box or radio button, such as `Off`.
+        appearance_state: Option<String>,
+    },
+    /// Any other annotation type, with its annotation dictionary.
+    Other {
+        subtype: String,
+        dict: Dictionary,
+    },
+}
+
+/// Action of an annotation, from its `A` entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Go to a destination in the document, an explicit destination array or the name of one.
+    GoTo(Object),
+    /// Go to a destination in another file.
+    GoToR { file: Object, dest: Object },
+    /// Resolve a uniform resource identifier.
+    Uri(String),
+    /// Execute a predefined action such as `NextPage`.
+    Named(String),
+    /// Any other action, with its `S` entry as type and its action dictionary.
+    Other { action_type: String, dict: Dictionary },
+}
+
+/// Hyperlink of a page, see [`Document::extract_links`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Location of the link on the page, as `[llx, lly, urx, ury]`.
+    pub rect: [f32; 4],
+    pub target: LinkTarget,
+}
+
+/// Target of a [`Link`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// URI of a `URI` action.
+    Uri(String),
+    /// Page of the document, with the view parameters of the destination, such as
+    /// `/XYZ left top zoom`.
+    Page { page_id: ObjectId, view: Vec<Object> },
+    /// Destination that does not exist or does not refer to a page of the document.
+    Unresolved(Object),
+}
+
+impl Document {
+    /// Get the hyperlinks of a page, the link annotations with a `URI` action, or a `GoTo`
+    /// action or destination within the document.
+    ///
+    /// Named destinations are resolved through the `Dests` name tree of the catalog's `Names`
+    /// dictionary and the catalog's `Dests` dictionary. Links with other actions or without any
+    /// target are skipped.
+    pub fn extract_links(&self, page_id: ObjectId) -> Result<Vec<Link>> {
+        let links = self.get_annotations(page_id)?.into_iter().filter_map(|annotation| {
+            let dest = match (annotation.action, annotation.kind) {
+                (Some(Action::Uri(uri)), AnnotationKind::Link { .. }) => {
+                    return Some(Link {
+                        rect: annotation.rect,
+                        target: LinkTarget::Uri(uri),
+                    })
+                }
+                (Some(Action::GoTo(dest)), AnnotationKind::Link { .. }) => dest,
+                (None, AnnotationKind::Link { dest: Some(dest) }) => dest,
+                _ => return None,
+            };
+            Some(Link {
+                rect: annotation.rect,
+                target: self.link_target(dest),
+            })
+        });
+        Ok(links.collect())
+    }
+
+    /// Resolve a destination to a page of the document, or keep it as an unresolved target.
+    pub(crate) fn link_target(&self, dest: Object) -> LinkTarget {
+        match self.resolve_dest(&dest) {
+            Some((page_id, view)) => LinkTarget::Page { page_id, view },
+            None => LinkTarget::Unresolved(dest),
+        }
+    }
+
+    /// Get and parse the annotations of a page.
+    ///
+    /// Annotations that are not dictionaries are skipped. An annotation without a valid `Rect`
+    /// gets an empty one.
+    pub fn get_annotations(&self, page_id: ObjectId) -> Result<Vec<Annotation>> {
+        let page = self.get_dictionary(page_id)?;
+        let annots = match page.get_deref(b"Annots", self) {
+            Ok(annots) => annots.as_array()?,
+            Err(_) => return Ok(vec![]),
+        };
+        let mut annotations = Vec::with_capacity(annots.len());
+        for annot in annots {
+            match self.dereference(annot) {
+                Ok((id, Object::Dictionary(dict))) => annotations.push(self.parse_annotation(id, dict)),
+                _ => warn!(
+                    "Skipping annotation {:?} of page {:?}, which is not a dictionary",
+                    annot, page_id
+                ),
+            }
+        }
+        Ok(annotations)
+    }
+
+    fn parse_annotation(&self, id: Option<ObjectId>, dict: &Dictionary) -> Annotation {
+        let name = |key: &[u8]| {
+            dict.get_deref(key, self)
+                .and_then(Object::as_name_str)
+                .ok()
+                .map(str::to_string)
+        };
+        let text = |key: &[u8]| dict.get_deref(key, self).and_then(Object::as_text_string).ok();
+        let open = dict.get_deref(b"Open", self).and_then(Object::as_bool).unwrap_or(false);
+        let numbers = |object: &Object| -> Vec<f32> {
+            match self.dereference(object).and_then(|(_, object)| object.as_array()) {
+                Ok(array) => array.iter().filter_map(|number| number.as_float().ok()).collect(),
+                Err(_) => vec![],
+            }
+        };
+        let quad_points = || dict.get(b"QuadPoints").map(numbers).unwrap_or_default();
+
+        let subtype = name(b"Subtype").unwrap_or_default();
+        let kind = match subtype.as_str() {
+            "Text" => AnnotationKind::Text {
+                open,
+                icon: name(b"Name"),
+            },
+            "Link" => AnnotationKind::Link {
+                dest: dict.get_deref(b"Dest", self).ok().cloned(),
+            },
+            "FreeText" => AnnotationKind::FreeText {
+                default_appearance: text(b"DA"),
+            },
+            "Highlight" => AnnotationKind::Highlight {
+                quad_points: quad_points(),
+            },
+            "Underline" => AnnotationKind::Underline {
+                quad_points: quad_points(),
+            },
+            "Squiggly" => AnnotationKind::Squiggly {
+                quad_points: quad_points(),
+            },
+            "StrikeOut" => AnnotationKind::StrikeOut {
+                quad_points: quad_points(),
+            },
+            "Ink" => AnnotationKind::Ink {
+                ink_list: match dict.get_deref(b"InkList", self).and_then(Object::as_array) {
+                    Ok(paths) => paths.iter().map(numbers).collect(),
+                    Err(_) => vec![],
+                },
+            },
+            "Popup" => AnnotationKind::Popup {
+                parent: dict.get(b"Parent").and_then(Object::as_reference).ok(),
+                open,
+            },
+            "Widget" => AnnotationKind::Widget {
+                field_name: self.field_name(dict),
+                field_type: self
+                    .inherited_field_attribute(dict, b"FT")
+                    .and_then(|field_type| field_type.as_name_str().ok())
+                    .map(str::to_string),
+                appearance_state: name(b"AS"),
+            },
+            _ => AnnotationKind::Other {
+                subtype,
+                dict: dict.clone(),
+            },
+        };
+
+        Annotation {
+            id,
+            rect: dict.get_deref(b"Rect", self).and_then(rectangle).unwrap_or_else(|_| {
+                warn!("Annotation {:?} has no valid Rect", id);
+                [0.0; 4]
+            }),
+            contents: text(b"Contents"),
+            action: dict
+                .get_deref(b"A", self)
+                .and_then(Object::as_dict)
+                .ok()
+                .map(|action| self.parse_action(action)),
+            kind,
+        }
+    }
+
+    pub(crate) fn parse_action(&self, dict: &Dictionary) -> Action {
+        let get = |key: &[u8]| dict.get_deref(key, self).ok();
+        let action_type = get(b"S").and_then(|s| s.as_name_str().ok()).unwrap_or_default();
+        let action = match action_type {
+            "GoTo" => get(b"D").map(|dest| Action::GoTo(dest.clone())),
+            "GoToR" => get(b"D").zip(get(b"F")).map(|(dest, file)| Action::GoToR {
+                file: file.clone(),
+                dest: dest.clone(),
+            }),
+            "URI" => get(b"URI")
+                .and_then(|uri| uri.as_str().ok())
+                .map(|uri| Action::Uri(String::from_utf8_lossy(uri).into_owned())),
+            "Named" => get(b"N")
+                .and_then(|name| name.as_name_str().ok())
+                .map(|name| Action::Named(name.to_string())),
+            _ => None,
+        };
+        action.unwrap_or_else(|| Action::Other {
+            action_type: action_type.to_string(),
+            dict: dict.clone(),
+        })
+    }
+
+    /// Get the fully qualified name of a form field, or of the field a widget annotation belongs
+    /// to, joining the partial names `T` of the field and its ancestors with `.`.
+    pub(crate) fn field_name(&self, field: &Dictionary) -> Option<String> {
+        let mut names = vec![];
+        let mut node = Some(field);
+        let mut visited = vec![];
+        while let Some(dict) = node {
+            if let Ok(name) = dict.get_deref(b"T", self).and_then(Object::as_text_string) {
+                names.push(name);
+            }
+            node = match dict.get(b"Parent").and_then(Object::as_reference) {
+                Ok(parent_id) if !visited.contains(&parent_id) => {
+                    visited.push(parent_id);
+                    self.get_dictionary(parent_id).ok()
+                }
+                _ => None,
+            };
+        }
+        if names.is_empty() {
+            return None;
+        }
+        names.reverse();
+        Some(names.join("."))
+    }
+
+    /// Get an attribute of a form field, inherited from its closest ancestor that has it.
+    pub(crate) fn inherited_field_attribute<'a>(&'a self, field: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        let mut node = field;
+        let mut visited = vec![];
+        loop {
+            if let Ok(value) = node.get_deref(key, self) {
+                return Some(value);
+            }
+            let parent_id = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+            if visited.contains(&parent_id) {
+                return None;
+            }
+            visited.push(parent_id);
+            node = self.get_dictionary(parent_id).ok()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creator::tests::create_document;
+
+    #[test]
+    fn parse_annotations() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let field_id = doc.add_object(dictionary! { "T" => Object::string_literal("address"), "FT" => "Tx" });
+        let child_id = doc.add_object(dictionary! { "T" => Object::string_literal("street"), "Parent" => field_id });
+        let widget_id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => vec![10.into(), 20.into(), 110.into(), 40.into()],
+            "Parent" => child_id,
+        });
+        let link_id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![100.into(), 700.into(), 0.into(), 680.into()],
+            "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") },
+        });
+        let annots = vec![
+            link_id.into(),
+            dictionary! {
+                "Subtype" => "Text",
+                "Rect" => vec![0.into(), 0.into(), 20.into(), 20.into()],
+                "Contents" => Object::text_string("Note ✓"),
+                "Open" => true,
+                "Name" => "Comment",
+            }
+            .into(),
+            dictionary! {
+                "Subtype" => "Highlight",
+                "Rect" => vec![0.into(), 0.into(), 1.into(), 1.into()],
+                "QuadPoints" => vec![0.into(), 1.into(), 1.into(), 1.into(), 0.into(), 0.into(), 1.into(), 0.into()],
+            }
+            .into(),
+            widget_id.into(),
+            dictionary! { "Subtype" => "Square", "Rect" => vec![0.into(), 0.into(), 5.into(), 5.into()] }.into(),
+            Object::Null,
+        ];
+        let annots_id = doc.add_object(annots);
+        doc.get_dictionary_mut(page_id).unwrap().set("Annots", annots_id);
+
+        let annotations = doc.get_annotations(page_id).unwrap();
+        assert_eq!(annotations.len(), 5);
+
+        assert_eq!(annotations[0].id, Some(link_id));
+        assert_eq!(annotations[0].rect, [0.0, 680.0, 100.0, 700.0]);
+        assert_eq!(annotations[0].kind, AnnotationKind::Link { dest: None });
+        assert_eq!(
+            annotations[0].action,
+            Some(Action::Uri("https://example.com".to_string()))
+        );
+
+        assert_eq!(annotations[1].id, None);
+        assert_eq!(annotations[1].contents.as_deref(), Some("Note ✓"));
+        let icon = Some("Comment".to_string());
+        assert_eq!(annotations[1].kind, AnnotationKind::Text { open: true, icon });
+
+        let quad_points = vec![0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        assert_eq!(annotations[2].kind, AnnotationKind::Highlight { quad_points });
+
+        assert_eq!(
+            annotations[3].kind,
+            AnnotationKind::Widget {
+                field_name: Some("address.street".to_string()),
+                field_type: Some("Tx".to_string()),
+                appearance_state: None,
+            }
+        );
+
+        match &annotations[4].kind {
+            AnnotationKind::Other { subtype, dict } => {
+                assert_eq!(subtype, "Square");
+                assert!(dict.has(b"Rect"));
+            }
+            kind => panic!("unexpected annotation {:?}", kind),
+        }
+        assert!(annotations[4].action.is_none() && annotations[4].contents.is_none());
+    }
+
+    #[test]
+    fn extract_links() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let rect = || Object::from(vec![0.into(), 0.into(), 50.into(), 10.into()]);
+        let view = || vec!["XYZ".into(), 0.into(), 792.into(), Object::Null];
+        let mut dest = vec![page_id.into()];
+        dest.extend(view());
+
+        let names_id = doc.add_object(dictionary! {
+            "Kids" => vec![dictionary! {
+                "Limits" => vec![Object::string_literal("chapter1"), Object::string_literal("chapter9")],
+                "Names" => vec![Object::string_literal("chapter1"), dictionary! { "D" => dest.clone() }.into()],
+            }
+            .into()],
+        });
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("Names", dictionary! { "Dests" => names_id });
+        catalog.set("Dests", dictionary! { "intro" => dest.clone() });
+
+        let link = |key: &str, target: Object| -> Object {
+            let mut annotation = dictionary! { "Subtype" => "Link", "Rect" => rect() };
+            annotation.set(key, target);
+            annotation.into()
+        };
+        let uri = dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com/") };
+        let annots = vec![
+            link("A", uri.into()),
+            link("Dest", dest.into()),
+            link(
+                "A",
+                dictionary! { "S" => "GoTo", "D" => Object::string_literal("chapter1") }.into(),
+            ),
+            link("Dest", Object::Name(b"intro".to_vec())),
+            link("Dest", Object::string_literal("missing")),
+            link("A", dictionary! { "S" => "Named", "N" => "NextPage" }.into()),
+            dictionary! { "Subtype" => "Text", "Rect" => rect() }.into(),
+        ];
+        doc.get_dictionary_mut(page_id).unwrap().set("Annots", annots);
+
+        let links = doc.extract_links(page_id).unwrap();
+        assert_eq!(links.len(), 5);
+        assert!(links.iter().all(|link| link.rect == [0.0, 0.0, 50.0, 10.0]));
+        assert_eq!(links[0].target, LinkTarget::Uri("https://example.com/".to_string()));
+        for link in &links[1..4] {
+            assert_eq!(link.target, LinkTarget::Page { page_id, view: view() });
+        }
+        assert_eq!(
+            links[4].target,
+            LinkTarget::Unresolved(Object::string_literal("missing"))
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use md5::{Digest as _, Md5};
+
+use crate::encodings::{decode_text_string, encode_text_string};
+use crate::name_tree::{read_name_tree, write_name_tree};
+use crate::{Dictionary, Document, Object, ObjectId, Result, Stream, StringFormat};
+
+/// File embedded in a document, see [`Document::attachments`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    /// Key of the file specification in the `EmbeddedFiles` name tree.
+    pub name: String,
+    /// File name of the file specification, from its `UF` or `F` entry, or the key if it has
+    /// neither.
+    pub filename: String,
+    /// Media type of the embedded file stream, such as `text/xml`, from its `Subtype`.
+    pub mime_type: Option<String>,
+    pub description: Option<String>,
+    /// Decoded content of the embedded file stream.
+    pub data: Vec<u8>,
+}
+
+impl Document {
+    /// Get the files embedded in the document, from the `EmbeddedFiles` name tree of the
+    /// catalog's `Names` dictionary, sorted by their key.
+    ///
+    /// Entries that are not file specifications with an embedded file stream in their `EF`
+    /// dictionary are skipped.
+    pub fn attachments(&self) -> Result<Vec<Attachment>> {
+        let Some(tree) = self.embedded_files()? else {
+            return Ok(vec![]);
+        };
+        let attachments = read_name_tree(self, tree).into_iter().filter_map(|(key, spec)| {
+            let spec = self.dereference(&spec).ok()?.1.as_dict().ok()?;
+            let text = |key: &[u8]| spec.get_deref(key, self).and_then(Object::as_text_string).ok();
+            let embedded = spec.get_deref(b"EF", self).and_then(Object::as_dict).ok()?;
+            let stream = [b"UF".as_slice(), b"F"]
+                .into_iter()
+                .find_map(|key| embedded.get_deref(key, self).and_then(Object::as_stream).ok())?;
+            let name = decode_text_string(&key);
+            Some(Attachment {
+                filename: text(b"UF").or_else(|| text(b"F")).unwrap_or_else(|| name.clone()),
+                name,
+                mime_type: stream
+                    .dict
+                    .get(b"Subtype")
+                    .and_then(Object::as_name_str)
+                    .ok()
+                    .map(str::to_string),
+                description: text(b"Desc"),
+                data: stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()),
+            })
+        });
+        Ok(attachments.collect())
+    }
+
+    /// Embed a file in the document under its file name, and return the id of its file
+    /// specification. The `EmbeddedFiles` name tree and the catalog's `Names` dictionary are
+    /// created if they don't exist yet.
+    ///
+    /// The embedded file stream is compressed, and its `Params` record the size and the MD5
+    /// checksum of the data. An attachment with the same name is replaced. The name tree is
+    /// written anew, the nodes of the previous tree are left in the document,
+    /// [`Document::prune_objects`] removes them.
+    pub fn add_attachment(&mut self, filename: &str, data: &[u8], mime_type: &str) -> Result<ObjectId> {
+        let mut stream = Stream::new(
+            dictionary! {
+                "Type" => "EmbeddedFile",
+                "Subtype" => Object::Name(mime_type.as_bytes().to_vec()),
+                "Params" => dictionary! {
+                    "Size" => data.len() as i64,
+                    "CheckSum" => Object::String(Md5::digest(data).to_vec(), StringFormat::Hexadecimal),
+                },
+            },
+            data.to_vec(),
+        );
+        stream.compress()?;
+        let stream_id = self.add_object(stream);
+        let spec_id = self.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::text_string(filename),
+            "UF" => Object::text_string(filename),
+            "EF" => dictionary! { "F" => stream_id, "UF" => stream_id },
+        });
+
+        let mut entries = match self.embedded_files()? {
+            Some(tree) => read_name_tree(self, tree),
+            None => Default::default(),
+        };
+        entries.insert(encode_text_string(filename), spec_id.into());
+        let tree = write_name_tree(self, &entries);
+
+        let catalog = self.catalog_mut()?;
+        if !catalog.has(b"Names") {
+            catalog.set("Names", Dictionary::new());
+        }
+        let names = match catalog.get(b"Names").and_then(Object::as_reference) {
+            Ok(names_id) => self.get_dictionary_mut(names_id)?,
+            Err(_) => catalog.get_mut(b"Names").and_then(Object::as_dict_mut)?,
+        };
+        names.set("EmbeddedFiles", tree);
+        Ok(spec_id)
+    }
+
+    fn embedded_files(&self) -> Result<Option<&Dictionary>> {
+        let tree = self
+            .catalog()?
+            .get_deref(b"Names", self)
+            .and_then(Object::as_dict)
+            .and_then(|names| names.get_deref(b"EmbeddedFiles", self))
+            .and_then(Object::as_dict);
+        Ok(tree.ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use md5::{Digest as _, Md5};
+
+    use crate::creator::tests::create_document;
+    use crate::name_tree::write_name_tree;
+    use crate::{Object, Stream};
+
+    #[test]
+    fn extract_attachments() {
+        let mut doc = create_document();
+        let xml = b"<?xml version=\"1.0\"?><rsm:CrossIndustryInvoice/>".to_vec();
+        let mut stream = Stream::new(
+            dictionary! { "Type" => "EmbeddedFile", "Subtype" => "text/xml" },
+            xml.clone(),
+        );
+        stream.compress().unwrap();
+        let stream_id = doc.add_object(stream);
+        let spec_id = doc.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::string_literal("factur-x.xml"),
+            "UF" => Object::text_string("factur-x.xml"),
+            "Desc" => Object::string_literal("Factur-X invoice"),
+            "AFRelationship" => "Alternative",
+            "EF" => dictionary! { "F" => stream_id, "UF" => stream_id },
+        });
+        let notes_id = doc.add_object(Stream::new(dictionary! { "Type" => "EmbeddedFile" }, b"notes".to_vec()));
+        let entries = BTreeMap::from([
+            (b"factur-x.xml".to_vec(), spec_id.into()),
+            (
+                b"notes".to_vec(),
+                dictionary! { "EF" => dictionary! { "F" => notes_id } }.into(),
+            ),
+            (
+                b"missing".to_vec(),
+                dictionary! { "F" => Object::string_literal("missing.txt") }.into(),
+            ),
+        ]);
+        let tree = write_name_tree(&mut doc, &entries);
+        doc.catalog_mut()
+            .unwrap()
+            .set("Names", dictionary! { "EmbeddedFiles" => tree });
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let doc = crate::Document::load_mem(&buffer).unwrap();
+        let attachments = doc.attachments().unwrap();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].name, "factur-x.xml");
+        assert_eq!(attachments[0].filename, "factur-x.xml");
+        assert_eq!(attachments[0].mime_type.as_deref(), Some("text/xml"));
+        assert_eq!(attachments[0].description.as_deref(), Some("Factur-X invoice"));
+        assert_eq!(attachments[0].data, xml);
+        assert_eq!(attachments[1].filename, "notes");
+        assert_eq!(attachments[1].mime_type, None);
+        assert_eq!(attachments[1].data, b"notes");
+    }
+
+    #[test]
+    fn add_attachments() {
+        let mut doc = create_document();
+        let xml = b"<?xml version=\"1.0\"?><rsm:CrossIndustryInvoice/>";
+        let spec_id = doc.add_attachment("factur-x.xml", xml, "text/xml").unwrap();
+        doc.add_attachment("notes.txt", b"draft", "text/plain").unwrap();
+        // Replaces the previous attachment of the same name.
+        doc.add_attachment("notes.txt", b"notes", "text/plain").unwrap();
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let doc = crate::Document::load_mem(&buffer).unwrap();
+        let attachments = doc.attachments().unwrap();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].filename, "factur-x.xml");
+        assert_eq!(attachments[0].mime_type.as_deref(), Some("text/xml"));
+        assert_eq!(attachments[0].data, xml);
+        assert_eq!(
+            (attachments[1].filename.as_str(), attachments[1].data.as_slice()),
+            ("notes.txt", b"notes".as_slice())
+        );
+
+        let spec = doc.get_dictionary(spec_id).unwrap();
+        let stream_id = spec.get(b"EF").and_then(Object::as_dict).unwrap().get(b"F").unwrap();
+        let stream = doc
+            .get_object(stream_id.as_reference().unwrap())
+            .and_then(Object::as_stream)
+            .unwrap();
+        let params = stream.dict.get(b"Params").and_then(Object::as_dict).unwrap();
+        assert_eq!(params.get(b"Size").and_then(Object::as_i64).unwrap(), xml.len() as i64);
+        let checksum = params.get(b"CheckSum").and_then(Object::as_str).unwrap();
+        assert_eq!(checksum, Md5::digest(xml).as_slice());
+    }
+
+    #[test]
+    fn add_attachment_with_unicode_name() {
+        let mut doc = create_document();
+        let spec_id = doc.add_attachment("請求書.xml", b"<invoice/>", "text/xml").unwrap();
+        let spec = doc.get_dictionary(spec_id).unwrap();
+        for key in [b"F".as_slice(), b"UF"] {
+            let filename = spec.get(key).and_then(Object::as_str).unwrap();
+            assert!(filename.starts_with(&[0xFE, 0xFF]));
+        }
+
+        let attachments = doc.attachments().unwrap();
+        assert_eq!(attachments[0].name, "請求書.xml");
+        assert_eq!(attachments[0].filename, "請求書.xml");
+    }
+}
@@ -0,0 +1,212 @@
+#![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+
+//! Parsing of `ToUnicode` CMaps, which map the character codes of a font to Unicode text.
+
+use std::collections::BTreeMap;
+
+use crate::content::Content;
+use crate::Object;
+
+/// Destination of a range of character codes.
+#[derive(Debug, Clone)]
+enum Destination {
+    /// The first code maps to this text, each following code increments its last character.
+    Start(String),
+    /// Every code in the range maps to its own text.
+    Array(Vec<String>),
+}
+
+/// Lookup table from character codes to Unicode, parsed from a `ToUnicode` CMap stream.
+///
+/// Character codes may be one to four bytes long. When the CMap declares a code space, it decides
+/// how many bytes each code of a string takes, otherwise the lengths of the mapped codes are used.
+#[derive(Debug, Clone, Default)]
+pub struct ToUnicodeCMap {
+    /// Code space ranges as `(code length, low, high)`.
+    code_space: Vec<(usize, u32, u32)>,
+    /// Mapped ranges keyed by `(code length, low)`, with the high end of the range.
+    ranges: BTreeMap<(usize, u32), (u32, Destination)>,
+}
+
+impl ToUnicodeCMap {
+    /// Parse the decoded data of a CMap stream.
+    ///
+    /// Only the `codespacerange`, `bfchar` and `bfrange` sections are used, anything else in the
+    /// CMap is ignored.
+    pub fn parse(data: &[u8]) -> ToUnicodeCMap {
+        let (content, _) = Content::decode_tolerant(data);
+        let mut cmap = ToUnicodeCMap::default();
+
+        for operation in &content.operations {
+            let operands = operation.operands.as_slice();
+            match operation.operator.as_str() {
+                "endcodespacerange" => {
+                    for range in operands.chunks_exact(2) {
+                        if let (Ok(low), Ok(high)) = (range[0].as_str(), range[1].as_str()) {
+                            cmap.code_space.push((low.len(), code(low), code(high)));
+                        }
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        if let (Ok(source), Ok(destination)) = (pair[0].as_str(), pair[1].as_str()) {
+                            let destination = Destination::Start(utf16_to_string(destination));
+                            cmap.insert(source, source, destination);
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for range in operands.chunks_exact(3) {
+                        let (Ok(low), Ok(high)) = (range[0].as_str(), range[1].as_str()) else {
+                            continue;
+                        };
+                        let destination = match &range[2] {
+                            Object::String(start, _) => Destination::Start(utf16_to_string(start)),
+                            Object::Array(array) => Destination::Array(
+                                array
+                                    .iter()
+                                    .map(|text| text.as_str().map(utf16_to_string).unwrap_or_default())
+                                    .collect(),
+                            ),
+                            _ => continue,
+                        };
+                        cmap.insert(low, high, destination);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        cmap
+    }
+
+    fn insert(&mut self, low: &[u8], high: &[u8], destination: Destination) {
+        let len = low.len().clamp(1, 4);
+        self.ranges
+            .insert((len, code(low)), (code(high).max(code(low)), destination));
+    }
+
+    /// Get the text of a character code that is `len` bytes long.
+    pub fn get(&self, code: u32, len: usize) -> Option<String> {
+        let (&(range_len, low), (high, destination)) = self.ranges.range(..=(len, code)).next_back()?;
+        if range_len != len || code > *high {
+            return None;
+        }
+        let offset = code - low;
+        match destination {
+            Destination::Start(start) => {
+                let mut text = start.clone();
+                let last = text.pop()?;
+                text.push(char::from_u32(last as u32 + offset)?);
+                Some(text)
+            }
+            Destination::Array(texts) => texts.get(offset as usize).cloned(),
+        }
+    }
+
+    /// Decode a string shown with the font to Unicode text.
+    ///
+    /// Codes without a mapping are replaced by U+FFFD.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let mut text = String::new();
+        let mut position = 0;
+        while position < bytes.len() {
+            let rest = &bytes[position..];
+            let len = self.code_len(rest);
+            match self.get(code(&rest[..len]), len) {
+                Some(decoded) => text.push_str(&decoded),
+                None => text.push(char::REPLACEMENT_CHARACTER),
+            }
+            position += len;
+        }
+        text
+    }
+
+    /// Number of bytes of the character code at the start of `bytes`.
+    fn code_len(&self, bytes: &[u8]) -> usize {
+        let max_len = bytes.len().min(4);
+        if !self.code_space.is_empty() {
+            let in_code_space = |len: usize| {
+                let code = code(&bytes[..len]);
+                self.code_space
+                    .iter()
+                    .any(|&(range_len, low, high)| range_len == len && (low..=high).contains(&code))
+            };
+            if let Some(len) = (1..=max_len).find(|&len| in_code_space(len)) {
+                return len;
+            }
+        }
+        (1..=max_len)
+            .find(|&len| self.get(code(&bytes[..len]), len).is_some())
+            .or_else(|| self.ranges.keys().map(|&(len, _)| len).min())
+            .unwrap_or(1)
+            .clamp(1, max_len)
+    }
+}
+
+fn code(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).fold(0, |code, &byte| code << 8 | byte as u32)
+}
+
+fn utf16_to_string(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit.get(1).copied().unwrap_or(0)]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMAP: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+1 begincodespacerange
+<0000> <FFFF>
+endcodespacerange
+2 beginbfchar
+<0003> <0020>
+<0024> <00660069>
+endbfchar
+3 beginbfrange
+<0010> <0012> <0041>
+<0020> <0021> [<0078> <D835DC9C>]
+<0030> <0031> <D835DC00>
+endbfrange
+endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end";
+
+    #[test]
+    fn parse_bfchar_and_bfrange() {
+        let cmap = ToUnicodeCMap::parse(CMAP);
+        assert_eq!(cmap.get(0x0003, 2).as_deref(), Some(" "));
+        assert_eq!(cmap.get(0x0024, 2).as_deref(), Some("fi"));
+        assert_eq!(cmap.get(0x0012, 2).as_deref(), Some("C"));
+        assert_eq!(cmap.get(0x0021, 2).as_deref(), Some("\u{1D49C}"));
+        assert_eq!(cmap.get(0x0031, 2).as_deref(), Some("\u{1D401}"));
+        assert_eq!(cmap.get(0x0013, 2), None);
+        assert_eq!(cmap.get(0x12, 1), None);
+    }
+
+    #[test]
+    fn decode_multi_byte_codes() {
+        let cmap = ToUnicodeCMap::parse(CMAP);
+        assert_eq!(
+            cmap.decode(b"\x00\x10\x00\x03\x00\x24\x00\x20\x00\x99"),
+            "A fix\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn decode_without_code_space() {
+        let cmap = ToUnicodeCMap::parse(b"1 beginbfrange <20> <7E> <0020> endbfrange");
+        assert_eq!(cmap.decode(b"Hi!"), "Hi!");
+    }
+}
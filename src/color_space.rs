@@ -0,0 +1,535 @@
+use crate::xobject::{ImageEncoding, PdfImage};
+use crate::{Document, Error, Object, ObjectId, Result, Stream};
+
+/// Deeper nesting of base and alternate color spaces is malformed or cyclic.
+const MAX_DEPTH: usize = 8;
+
+/// Color space of a color operator or an image, see [`Document::resolve_color_space`].
+#[derive(Debug, Clone)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRgb,
+    DeviceCmyk,
+    CalGray,
+    CalRgb,
+    Lab,
+    /// Color space defined by an ICC profile.
+    IccBased {
+        /// Number of color components `N` of the profile.
+        components: usize,
+        /// The ICC profile stream.
+        profile: Box<Stream>,
+        /// Color space `Alternate` to use when the profile is not supported.
+        alternate: Option<Box<ColorSpace>>,
+    },
+    /// Palette of colors in the `base` color space, selected by a single index component.
+    Indexed {
+        base: Box<ColorSpace>,
+        /// Highest valid index, at most 255.
+        hival: u8,
+        /// Colors of the palette, with the components of each color in the base color space
+        /// stored in one byte each.
+        lookup: Vec<u8>,
+    },
+    /// Single colorant, such as a spot color, with the color space and tint transform to use
+    /// when it is not available.
+    Separation {
+        name: String,
+        alternate: Box<ColorSpace>,
+        tint_transform: Object,
+    },
+    /// Several colorants, with the color space and tint transform to use when they are not
+    /// available.
+    DeviceN {
+        names: Vec<String>,
+        alternate: Box<ColorSpace>,
+        tint_transform: Object,
+    },
+    /// Color space of patterns, with the color space of the color of uncolored patterns.
+    Pattern {
+        base: Option<Box<ColorSpace>>,
+    },
+}
+
+impl ColorSpace {
+    /// Number of color components of a color in this color space, 0 for patterns without an
+    /// underlying color space.
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray | ColorSpace::Indexed { .. } => 1,
+            ColorSpace::Separation { .. } => 1,
+            ColorSpace::DeviceRgb | ColorSpace::CalRgb | ColorSpace::Lab => 3,
+            ColorSpace::DeviceCmyk => 4,
+            ColorSpace::IccBased { components, .. } => *components,
+            ColorSpace::DeviceN { names, .. } => names.len(),
+            ColorSpace::Pattern { base } => base.as_ref().map_or(0, |base| base.components()),
+        }
+    }
+
+    /// Convert image samples in this color space to 8-bit RGB, three bytes per pixel.
+    ///
+    /// The samples have `bits_per_component` bits, 1, 2, 4, 8 or 16, for each component of each
+    /// pixel, and each row starts at a byte boundary. A `Decode` array is not applied. CMYK is
+    /// converted naively, without a color profile, and ICC based color spaces by their number of
+    /// components. Separation color spaces need an exponential tint transform function, of
+    /// `FunctionType` 2.
+    ///
+    /// Fails with [`Error::UnsupportedColorSpace`] for the other color spaces, Lab, DeviceN and
+    /// Pattern, and with [`Error::Invalid`] if `samples` is too short for the dimensions.
+    pub fn to_rgb(&self, samples: &[u8], width: usize, height: usize, bits_per_component: u8) -> Result<Vec<u8>> {
+        if ![1, 2, 4, 8, 16].contains(&bits_per_component) {
+            return Err(Error::Invalid(format!("{} bits per component", bits_per_component)));
+        }
+        let bits = usize::from(bits_per_component);
+        let components = self.components();
+        let max = (1u32 << bits) - 1;
+        // Colors of the palette indexes, converted once.
+        let palette = match self {
+            ColorSpace::Indexed { hival, .. } => Some(
+                (0..=u32::from(*hival))
+                    .map(|index| self.pixel_to_rgb(&[index], max))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            _ => None,
+        };
+        // Fail for the color spaces that can't be converted before allocating anything.
+        self.pixel_to_rgb(&vec![0; components], max)?;
+        if components == 0 {
+            return Err(Error::Invalid("color space without components".into()));
+        }
+        let too_large = || Error::Invalid(format!("image of {}x{} pixels", width, height));
+        let row_bytes = width
+            .checked_mul(components)
+            .and_then(|row| row.checked_mul(bits))
+            .ok_or_else(too_large)?
+            .div_ceil(8);
+        let size = row_bytes.checked_mul(height).ok_or_else(too_large)?;
+        if size > samples.len() {
+            return Err(Error::Invalid(format!(
+                "{} bytes of samples for an image of {}x{} pixels",
+                samples.len(),
+                width,
+                height
+            )));
+        }
+        let sample = |row: usize, index: usize| -> u32 {
+            let byte = |offset: usize| u32::from(samples[row * row_bytes + offset]);
+            match bits {
+                16 => byte(index * 2) << 8 | byte(index * 2 + 1),
+                8 => byte(index),
+                _ => {
+                    let bit = index * bits;
+                    byte(bit / 8) >> (8 - bits - bit % 8) & max
+                }
+            }
+        };
+        // At most 24 bytes for each byte of samples, as each pixel has a component of 1 bit or more.
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        let mut pixel = vec![0; components];
+        for row in 0..height {
+            for column in 0..width {
+                for (i, value) in pixel.iter_mut().enumerate() {
+                    *value = sample(row, column * components + i);
+                }
+                let color = match &palette {
+                    Some(palette) => palette[(pixel[0] as usize).min(palette.len() - 1)],
+                    None => self.pixel_to_rgb(&pixel, max)?,
+                };
+                rgb.extend_from_slice(&color);
+            }
+        }
+        Ok(rgb)
+    }
+
+    /// Convert the components of a color, from 0 to `max`, to RGB.
+    fn pixel_to_rgb(&self, components: &[u32], max: u32) -> Result<[u8; 3]> {
+        let scale = |value: u32| (value.min(max) * 255 / max.max(1)) as u8;
+        let component = |i: usize| components.get(i).copied().unwrap_or(0);
+        Ok(match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray => [scale(component(0)); 3],
+            ColorSpace::DeviceRgb | ColorSpace::CalRgb => {
+                [scale(component(0)), scale(component(1)), scale(component(2))]
+            }
+            ColorSpace::DeviceCmyk => {
+                let black = 255 - u32::from(scale(component(3)));
+                let channel = |i: usize| ((255 - u32::from(scale(component(i)))) * black / 255) as u8;
+                [channel(0), channel(1), channel(2)]
+            }
+            ColorSpace::IccBased {
+                components: count,
+                alternate,
+                ..
+            } => match (alternate, count) {
+                (Some(alternate), _) => return alternate.pixel_to_rgb(components, max),
+                (None, 1) => ColorSpace::DeviceGray.pixel_to_rgb(components, max)?,
+                (None, 3) => ColorSpace::DeviceRgb.pixel_to_rgb(components, max)?,
+                (None, 4) => ColorSpace::DeviceCmyk.pixel_to_rgb(components, max)?,
+                (None, _) => return Err(Error::UnsupportedColorSpace("ICCBased".into())),
+            },
+            ColorSpace::Indexed { base, hival, lookup } => {
+                let index = component(0).min(u32::from(*hival)) as usize;
+                let count = base.components();
+                let entry: Vec<u32> = (0..count)
+                    .map(|i| u32::from(lookup.get(index * count + i).copied().unwrap_or(0)))
+                    .collect();
+                base.pixel_to_rgb(&entry, 255)?
+            }
+            ColorSpace::Separation {
+                alternate,
+                tint_transform,
+                ..
+            } => {
+                let tint = f64::from(component(0).min(max)) / f64::from(max.max(1));
+                let outputs = exponential_function(tint_transform, tint)
+                    .ok_or_else(|| Error::UnsupportedColorSpace("Separation".into()))?;
+                let outputs: Vec<u32> = outputs
+                    .iter()
+                    .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u32)
+                    .collect();
+                alternate.pixel_to_rgb(&outputs, 255)?
+            }
+            ColorSpace::Lab => return Err(Error::UnsupportedColorSpace("Lab".into())),
+            ColorSpace::DeviceN { .. } => return Err(Error::UnsupportedColorSpace("DeviceN".into())),
+            ColorSpace::Pattern { .. } => return Err(Error::UnsupportedColorSpace("Pattern".into())),
+        })
+    }
+}
+
+/// Evaluate a direct exponential interpolation function, of `FunctionType` 2, for `x`.
+fn exponential_function(function: &Object, x: f64) -> Option<Vec<f64>> {
+    let function = function.as_dict().ok()?;
+    if function.get(b"FunctionType").and_then(Object::as_i64).ok()? != 2 {
+        return None;
+    }
+    let numbers = |key: &[u8], default: f64| -> Option<Vec<f64>> {
+        match function.get(key) {
+            Ok(values) => values
+                .as_array()
+                .ok()?
+                .iter()
+                .map(|value| value.as_float().ok().map(f64::from))
+                .collect(),
+            Err(_) => Some(vec![default]),
+        }
+    };
+    let exponent = f64::from(function.get(b"N").and_then(Object::as_float).ok()?);
+    let (c0, c1) = (numbers(b"C0", 0.0)?, numbers(b"C1", 1.0)?);
+    let power = x.powf(exponent);
+    Some(c0.iter().zip(&c1).map(|(c0, c1)| c0 + power * (c1 - c0)).collect())
+}
+
+impl Document {
+    /// Convert the samples of an image to 8-bit RGB, or to RGBA if it has a soft mask, with
+    /// [`ColorSpace::to_rgb`].
+    ///
+    /// Stencil masks are converted as grayscale images, painting black where their samples are
+    /// 0. The soft mask is scaled to the size of the image if it has other dimensions. Fails
+    /// with [`Error::UnsupportedFilter`] for images whose data is left encoded, such as JPEG
+    /// images, which image libraries can decode themselves.
+    pub fn image_to_rgb(&self, image: &PdfImage) -> Result<Vec<u8>> {
+        let samples = |image: &PdfImage| -> Result<(Vec<u8>, usize, usize)> {
+            let filter = match image.content.encoding {
+                ImageEncoding::Raw => None,
+                ImageEncoding::Jpeg => Some("DCTDecode"),
+                ImageEncoding::Jpeg2000 => Some("JPXDecode"),
+                ImageEncoding::Jbig2 => Some("JBIG2Decode"),
+                ImageEncoding::CcittFax => Some("CCITTFaxDecode"),
+            };
+            if let Some(filter) = filter {
+                return Err(Error::UnsupportedFilter(filter.into()));
+            }
+            let color_space = match (&image.content.color_space, image.is_mask) {
+                (Some(color_space), false) => self.color_space(color_space)?,
+                _ => ColorSpace::DeviceGray,
+            };
+            let bits = image.content.bits_per_component.ok_or(Error::DictKey)?;
+            let bits = u8::try_from(bits).map_err(|_| Error::Invalid(format!("{} bits per component", bits)))?;
+            let width = usize::try_from(image.width).map_err(|_| Error::Type)?;
+            let height = usize::try_from(image.height).map_err(|_| Error::Type)?;
+            Ok((
+                color_space.to_rgb(&image.content.data, width, height, bits)?,
+                width,
+                height,
+            ))
+        };
+        let (rgb, width, height) = samples(image)?;
+        let Some(soft_mask) = &image.soft_mask else {
+            return Ok(rgb);
+        };
+        let (alpha, mask_width, mask_height) = samples(soft_mask)?;
+        let mut rgba = Vec::with_capacity(rgb.len() / 3 * 4);
+        for (i, color) in rgb.chunks_exact(3).enumerate() {
+            let (x, y) = (i % width * mask_width / width, i / width * mask_height / height);
+            rgba.extend_from_slice(color);
+            rgba.push(alpha.get((y * mask_width + x) * 3).copied().unwrap_or(255));
+        }
+        Ok(rgba)
+    }
+
+    /// Resolve the color space that the color operators `cs` and `CS` of a page's content
+    /// select with `name`.
+    ///
+    /// The names of the device color spaces and `Pattern` resolve directly, other names are
+    /// looked up in the `ColorSpace` dictionary of the page's resources, which may be
+    /// inherited. Fails with [`Error::DictKey`] if the resources have no such entry.
+    pub fn resolve_color_space(&self, page_id: ObjectId, name: &[u8]) -> Result<ColorSpace> {
+        if let Some(color_space) = family(name) {
+            return Ok(color_space);
+        }
+        let resources = self.get_page_inherited_resources(page_id)?;
+        let color_spaces = resources.get_deref(b"ColorSpace", self).and_then(Object::as_dict)?;
+        self.color_space(color_spaces.get(name)?)
+    }
+
+    /// Parse a color space, such as the `ColorSpace` entry of an image: a name, or an array of
+    /// a color space family and its parameters. References are followed.
+    ///
+    /// Fails with [`Error::UnsupportedColorSpace`] for unknown color space families.
+    pub fn color_space(&self, color_space: &Object) -> Result<ColorSpace> {
+        self.parse_color_space(color_space, 0)
+    }
+
+    fn parse_color_space(&self, color_space: &Object, depth: usize) -> Result<ColorSpace> {
+        if depth > MAX_DEPTH {
+            return Err(Error::ReferenceLimit);
+        }
+        let color_space = self.dereference(color_space)?.1;
+        let (name, params) = match color_space {
+            Object::Name(name) => (name.as_slice(), &[][..]),
+            Object::Array(array) => match array.split_first() {
+                Some((name, params)) => (self.dereference(name)?.1.as_name()?, params),
+                None => return Err(Error::Type),
+            },
+            _ => return Err(Error::Type),
+        };
+        let param = |index: usize| {
+            params
+                .get(index)
+                .ok_or(Error::Type)
+                .and_then(|param| self.dereference(param))
+        };
+        let nested = |index: usize| param(index).and_then(|(_, param)| self.parse_color_space(param, depth + 1));
+        Ok(match name {
+            b"ICCBased" => {
+                let profile = param(0)?.1.as_stream()?;
+                let alternate = match profile.dict.get(b"Alternate") {
+                    Ok(alternate) => Some(Box::new(self.parse_color_space(alternate, depth + 1)?)),
+                    Err(_) => None,
+                };
+                let components = match profile.dict.get_deref(b"N", self).and_then(Object::as_i64) {
+                    Ok(components) => usize::try_from(components).map_err(|_| Error::Type)?,
+                    Err(err) => alternate.as_ref().map(|alternate| alternate.components()).ok_or(err)?,
+                };
+                ColorSpace::IccBased {
+                    components,
+                    profile: Box::new(profile.clone()),
+                    alternate,
+                }
+            }
+            b"Indexed" | b"I" => {
+                let hival = param(1)?.1.as_i64()?;
+                let lookup = match param(2)?.1 {
+                    Object::String(lookup, _) => lookup.clone(),
+                    Object::Stream(stream) => stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()),
+                    _ => return Err(Error::Type),
+                };
+                ColorSpace::Indexed {
+                    base: Box::new(nested(0)?),
+                    hival: u8::try_from(hival).map_err(|_| Error::Type)?,
+                    lookup,
+                }
+            }
+            b"Separation" => ColorSpace::Separation {
+                name: String::from_utf8_lossy(param(0)?.1.as_name()?).into_owned(),
+                alternate: Box::new(nested(1)?),
+                tint_transform: param(2)?.1.clone(),
+            },
+            b"DeviceN" => ColorSpace::DeviceN {
+                names: param(0)?
+                    .1
+                    .as_array()?
+                    .iter()
+                    .map(|name| Ok(String::from_utf8_lossy(name.as_name()?).into_owned()))
+                    .collect::<Result<_>>()?,
+                alternate: Box::new(nested(1)?),
+                tint_transform: param(2)?.1.clone(),
+            },
+            b"Pattern" => ColorSpace::Pattern {
+                base: if params.is_empty() {
+                    None
+                } else {
+                    Some(Box::new(nested(0)?))
+                },
+            },
+            b"CalGray" => ColorSpace::CalGray,
+            b"CalRGB" => ColorSpace::CalRgb,
+            b"Lab" => ColorSpace::Lab,
+            name => family(name).ok_or_else(|| Error::UnsupportedColorSpace(String::from_utf8_lossy(name).into()))?,
+        })
+    }
+}
+
+/// The color spaces without parameters, including the abbreviations of inline images.
+fn family(name: &[u8]) -> Option<ColorSpace> {
+    match name {
+        b"DeviceGray" | b"G" => Some(ColorSpace::DeviceGray),
+        b"DeviceRGB" | b"RGB" => Some(ColorSpace::DeviceRgb),
+        b"DeviceCMYK" | b"CMYK" => Some(ColorSpace::DeviceCmyk),
+        b"Pattern" => Some(ColorSpace::Pattern { base: None }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColorSpace;
+    use crate::creator::tests::create_document;
+    use crate::{Error, Object, Stream, StringFormat};
+
+    #[test]
+    fn resolve_device_color_spaces() {
+        let doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let rgb = doc.resolve_color_space(page_id, b"DeviceRGB").unwrap();
+        assert!(matches!(rgb, ColorSpace::DeviceRgb));
+        assert_eq!(rgb.components(), 3);
+        assert_eq!(doc.resolve_color_space(page_id, b"DeviceCMYK").unwrap().components(), 4);
+        assert!(matches!(doc.resolve_color_space(page_id, b"CS0"), Err(Error::DictKey)));
+        assert!(matches!(
+            doc.color_space(&Object::Name(b"Unknown".to_vec())),
+            Err(Error::UnsupportedColorSpace(name)) if name == "Unknown"
+        ));
+    }
+
+    #[test]
+    fn indexed_image_to_rgb() {
+        let mut doc = create_document();
+        let palette = vec![255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let color_space: Object = vec![
+            "Indexed".into(),
+            "DeviceRGB".into(),
+            2.into(),
+            Object::String(palette, StringFormat::Hexadecimal),
+        ]
+        .into();
+        // Three 4-bit pixels on each row, padded to two bytes, the last index out of range.
+        let samples = vec![0x01, 0x20, 0x21, 0xF0];
+        let rgb = doc
+            .color_space(&color_space)
+            .unwrap()
+            .to_rgb(&samples, 3, 2, 4)
+            .unwrap();
+        assert_eq!(rgb, [255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0, 0, 255]);
+
+        let mask_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 1,
+                "Height" => 1,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![128],
+        ));
+        let image_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 3,
+                "Height" => 2,
+                "ColorSpace" => color_space,
+                "BitsPerComponent" => 4,
+                "SMask" => mask_id,
+            },
+            samples,
+        ));
+        let page_id = doc.page_iter().next().unwrap();
+        let resources = dictionary! { "XObject" => dictionary! { "Im0" => image_id } };
+        doc.get_dictionary_mut(page_id).unwrap().set("Resources", resources);
+        let images = doc.extract_images(page_id).unwrap();
+        let rgba = doc.image_to_rgb(&images[0]).unwrap();
+        assert_eq!(rgba.len(), 3 * 2 * 4);
+        assert_eq!(&rgba[..8], [255, 0, 0, 128, 0, 255, 0, 128]);
+
+        let cmyk = ColorSpace::DeviceCmyk
+            .to_rgb(&[0, 255, 255, 0, 0, 0, 0, 255], 2, 1, 8)
+            .unwrap();
+        assert_eq!(cmyk, [255, 0, 0, 0, 0, 0]);
+        let gray = ColorSpace::DeviceGray.to_rgb(&[0b1010_0000], 3, 1, 1).unwrap();
+        assert_eq!(gray, [255, 255, 255, 0, 0, 0, 255, 255, 255]);
+        assert!(matches!(
+            ColorSpace::DeviceGray.to_rgb(&[0; 4], 3, 2, 8),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            ColorSpace::DeviceGray.to_rgb(&[0; 4], 1 << 40, 1 << 40, 8),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            ColorSpace::Pattern { base: None }.to_rgb(&[], 1 << 30, 1 << 30, 8),
+            Err(Error::UnsupportedColorSpace(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_indexed_color_space() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let profile_id = doc.add_object(Stream::new(
+            dictionary! { "N" => 3, "Alternate" => "DeviceRGB" },
+            b"profile".to_vec(),
+        ));
+        let palette = vec![255, 0, 0, 0, 0, 255];
+        let indexed = vec![
+            "Indexed".into(),
+            vec!["ICCBased".into(), profile_id.into()].into(),
+            1.into(),
+            Object::String(palette.clone(), StringFormat::Hexadecimal),
+        ];
+        let indexed_id = doc.add_object(indexed);
+        let separation = vec![
+            "Separation".into(),
+            "Gold".into(),
+            "DeviceCMYK".into(),
+            dictionary! {
+                "FunctionType" => 2,
+                "Domain" => vec![0.into(), 1.into()],
+                "C0" => vec![0.into(); 4],
+                "C1" => vec![0.into(), 0.into(), 1.into(), 0.into()],
+                "N" => 1,
+            }
+            .into(),
+        ];
+        let resources = dictionary! {
+            "ColorSpace" => dictionary! { "CS0" => indexed_id, "CS1" => separation },
+        };
+        doc.get_dictionary_mut(page_id).unwrap().set("Resources", resources);
+
+        let color_space = doc.resolve_color_space(page_id, b"CS0").unwrap();
+        assert_eq!(color_space.components(), 1);
+        let ColorSpace::Indexed { base, hival, lookup } = color_space else {
+            panic!("expected an indexed color space");
+        };
+        assert_eq!((hival, lookup), (1, palette));
+        let ColorSpace::IccBased {
+            components,
+            profile,
+            alternate,
+        } = *base
+        else {
+            panic!("expected an ICC based color space");
+        };
+        assert_eq!((components, profile.content.as_slice()), (3, b"profile".as_slice()));
+        assert!(matches!(alternate.as_deref(), Some(ColorSpace::DeviceRgb)));
+
+        let separation = doc.resolve_color_space(page_id, b"CS1").unwrap();
+        let tint = separation.to_rgb(&[0, 255], 2, 1, 8).unwrap();
+        assert_eq!(tint, [255, 255, 255, 255, 255, 0]);
+        assert_eq!(separation.components(), 1);
+        assert!(matches!(
+            separation,
+            ColorSpace::Separation { name, alternate, .. } if name == "Gold" && alternate.components() == 4
+        ));
+    }
+}
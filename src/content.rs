@@ -1,8 +1,12 @@
-use super::Object;
+use super::{Object, Stream};
 use crate::writer::Writer;
 use crate::Result;
 use std::io::Write;
 
+/// An operator together with its operands.
+///
+/// Inline images are represented as a `BI` operation whose only operand is a stream
+/// holding the image dictionary and the image data.
 #[derive(Debug, Clone)]
 pub struct Operation {
     pub operator: String,
@@ -18,14 +22,146 @@ pub fn new(operator: &str, operands: Vec<Object>) -> Operation {
     }
 }
 
+/// Kind of operand an operator expects.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[derive(Debug, Clone, Copy)]
+enum OperandKind {
+    Number,
+    Name,
+    String,
+    Array,
+    NameOrDictionary,
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+impl OperandKind {
+    fn matches(self, operand: &Object) -> bool {
+        match self {
+            OperandKind::Number => matches!(operand, Object::Integer(_) | Object::Real(_)),
+            OperandKind::Name => matches!(operand, Object::Name(_)),
+            OperandKind::String => matches!(operand, Object::String(..)),
+            OperandKind::Array => matches!(operand, Object::Array(_)),
+            OperandKind::NameOrDictionary => matches!(operand, Object::Name(_) | Object::Dictionary(_)),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            OperandKind::Number => "number",
+            OperandKind::Name => "name",
+            OperandKind::String => "string",
+            OperandKind::Array => "array",
+            OperandKind::NameOrDictionary => "name or dictionary",
+        }
+    }
+}
+
+/// Result of checking the operands of an operation against the operator table.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[derive(Debug)]
+pub(crate) enum OperandCheck {
+    Valid,
+    UnknownOperator,
+    /// The operands don't match, with a description of the expected operands.
+    Mismatch(String),
+}
+
+/// Operation whose operands don't match its operator, as reported by `Content::parse_validated`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperandMismatch {
+    /// Byte offset of the first operand, or the operator if there are none, in the content.
+    pub offset: usize,
+    pub operator: String,
+    /// Description of the operands the operator expects.
+    pub expected: String,
+}
+
+impl std::fmt::Display for OperandMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at byte {} expects {}", self.operator, self.offset, self.expected)
+    }
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+impl Operation {
+    /// Check the number and types of the operands against the operator table of the PDF
+    /// specification.
+    pub(crate) fn check_operands(&self) -> OperandCheck {
+        use OperandKind::*;
+        let expected: &[OperandKind] = match self.operator.as_str() {
+            "b" | "B" | "b*" | "B*" | "BT" | "ET" | "BX" | "EX" | "EMC" | "f" | "F" | "f*" | "h" | "n" | "q" | "Q"
+            | "s" | "S" | "T*" | "W" | "W*" => &[],
+            "BI" => return OperandCheck::Valid,
+            "w" | "J" | "j" | "M" | "i" | "G" | "g" | "Tc" | "Tw" | "Tz" | "TL" | "Tr" | "Ts" => &[Number],
+            "gs" | "ri" | "CS" | "cs" | "sh" | "Do" | "MP" | "BMC" => &[Name],
+            "d" => &[Array, Number],
+            "m" | "l" | "Td" | "TD" | "d0" => &[Number; 2],
+            "RG" | "rg" => &[Number; 3],
+            "v" | "y" | "re" | "K" | "k" => &[Number; 4],
+            "cm" | "c" | "Tm" | "d1" => &[Number; 6],
+            "Tf" => &[Name, Number],
+            "Tj" | "'" => &[String],
+            "TJ" => &[Array],
+            "\"" => &[Number, Number, String],
+            "DP" | "BDC" => &[Name, NameOrDictionary],
+            "SC" | "sc" | "SCN" | "scn" => {
+                // Color components, patterns can be selected by a name after the components.
+                let pattern = self.operator.ends_with('N') || self.operator.ends_with('n');
+                let numbers = match (pattern, self.operands.last()) {
+                    (true, Some(Object::Name(_))) => &self.operands[..self.operands.len() - 1],
+                    _ => &self.operands[..],
+                };
+                let valid = (pattern || !numbers.is_empty())
+                    && !self.operands.is_empty()
+                    && numbers.len() <= 32
+                    && numbers.iter().all(|operand| Number.matches(operand));
+                return match (valid, pattern) {
+                    (true, _) => OperandCheck::Valid,
+                    (false, false) => OperandCheck::Mismatch("1 to 32 numbers".to_string()),
+                    (false, true) => OperandCheck::Mismatch("up to 32 numbers and a pattern name".to_string()),
+                };
+            }
+            _ => return OperandCheck::UnknownOperator,
+        };
+
+        if self.operands.len() == expected.len()
+            && expected
+                .iter()
+                .zip(&self.operands)
+                .all(|(kind, operand)| kind.matches(operand))
+        {
+            OperandCheck::Valid
+        } else if expected.is_empty() {
+            OperandCheck::Mismatch("no operands".to_string())
+        } else {
+            let kinds: Vec<_> = expected.iter().map(|kind| kind.description()).collect();
+            OperandCheck::Mismatch(kinds.join(", "))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Content<Operations: AsRef<[Operation]> = Vec<Operation>> {
     pub operations: Operations,
 }
 
+/// Options for [`Content::encode_with_options`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodeOptions {
+    /// Round real numbers to at most this many decimal places, dropping trailing zeros.
+    /// When not set, the shortest decimal form that reads back as the same number is written.
+    /// Real numbers are never written in scientific notation, which PDF doesn't allow.
+    pub max_decimal_places: Option<u8>,
+}
+
 impl<Operations: AsRef<[Operation]>> Content<Operations> {
     /// Encode content operations.
     pub fn encode(&self) -> Result<Vec<u8>> {
+        self.encode_with_options(EncodeOptions::default())
+    }
+
+    /// Encode content operations, choosing how real numbers are written.
+    pub fn encode_with_options(&self, options: EncodeOptions) -> Result<Vec<u8>> {
         let mut buffer = Vec::new();
         let mut first_operation = true;
         for operation in self.operations.as_ref() {
@@ -35,12 +171,457 @@ pub fn encode(&self) -> Result<Vec<u8>> {
             } else {
                 buffer.write_all(b"\n")?;
             }
+            if let ("BI", [Object::Stream(image)]) = (operation.operator.as_str(), operation.operands.as_slice()) {
+                Self::encode_inline_image(&mut buffer, image)?;
+                continue;
+            }
             for operand in &operation.operands {
-                Writer::write_object(&mut buffer, operand)?;
+                match options.max_decimal_places {
+                    Some(places) => Writer::write_object(&mut buffer, &round_reals(operand, places))?,
+                    None => Writer::write_object(&mut buffer, operand)?,
+                }
                 buffer.write_all(b" ")?;
             }
             buffer.write_all(operation.operator.as_bytes())?;
         }
         Ok(buffer)
     }
+
+    fn encode_inline_image(buffer: &mut Vec<u8>, image: &Stream) -> Result<()> {
+        buffer.write_all(b"BI")?;
+        for (key, value) in image.dict.iter() {
+            buffer.write_all(b" ")?;
+            Writer::write_object(buffer, &Object::Name(key.clone()))?;
+            buffer.write_all(b" ")?;
+            Writer::write_object(buffer, value)?;
+        }
+        buffer.write_all(b" ID ")?;
+        buffer.write_all(&image.content)?;
+        buffer.write_all(b"\nEI")?;
+        Ok(())
+    }
+}
+
+/// Fluent builder of content streams for the common text and graphics operators.
+///
+/// ```
+/// use lopdf::content::ContentBuilder;
+///
+/// let content = ContentBuilder::new()
+///     .begin_text()
+///     .set_font("F1", 12.0)
+///     .move_text(72.0, 712.0)
+///     .show_text("Hello")
+///     .end_text()
+///     .build();
+/// assert_eq!(content.encode().unwrap(), b"BT\n/F1 12 Tf\n72 712 Td\n(Hello) Tj\nET");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContentBuilder {
+    operations: Vec<Operation>,
+}
+
+impl ContentBuilder {
+    pub fn new() -> ContentBuilder {
+        ContentBuilder::default()
+    }
+
+    /// Append an operation with any operator.
+    pub fn operation(mut self, operator: &str, operands: Vec<Object>) -> ContentBuilder {
+        self.operations.push(Operation::new(operator, operands));
+        self
+    }
+
+    fn numbers<const N: usize>(self, operator: &str, numbers: [f32; N]) -> ContentBuilder {
+        self.operation(operator, numbers.iter().map(|&number| Object::Real(number)).collect())
+    }
+
+    /// Save the graphics state (`q`).
+    pub fn save_state(self) -> ContentBuilder {
+        self.operation("q", vec![])
+    }
+
+    /// Restore the last saved graphics state (`Q`).
+    pub fn restore_state(self) -> ContentBuilder {
+        self.operation("Q", vec![])
+    }
+
+    /// Concatenate a matrix to the current transformation matrix (`cm`).
+    pub fn transform(self, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> ContentBuilder {
+        self.numbers("cm", [a, b, c, d, e, f])
+    }
+
+    /// Move to a point, starting a new subpath (`m`).
+    pub fn move_to(self, x: f32, y: f32) -> ContentBuilder {
+        self.numbers("m", [x, y])
+    }
+
+    /// Append a straight line to the current path (`l`).
+    pub fn line_to(self, x: f32, y: f32) -> ContentBuilder {
+        self.numbers("l", [x, y])
+    }
+
+    /// Append a cubic Bézier curve with two control points to the current path (`c`).
+    pub fn curve_to(self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) -> ContentBuilder {
+        self.numbers("c", [x1, y1, x2, y2, x3, y3])
+    }
+
+    /// Append a rectangle to the current path (`re`).
+    pub fn rectangle(self, x: f32, y: f32, width: f32, height: f32) -> ContentBuilder {
+        self.numbers("re", [x, y, width, height])
+    }
+
+    /// Close the current subpath (`h`).
+    pub fn close_path(self) -> ContentBuilder {
+        self.operation("h", vec![])
+    }
+
+    /// Stroke the current path (`S`).
+    pub fn stroke(self) -> ContentBuilder {
+        self.operation("S", vec![])
+    }
+
+    /// Fill the current path with the nonzero winding number rule (`f`).
+    pub fn fill(self) -> ContentBuilder {
+        self.operation("f", vec![])
+    }
+
+    /// Set the line width (`w`).
+    pub fn set_line_width(self, width: f32) -> ContentBuilder {
+        self.numbers("w", [width])
+    }
+
+    /// Set the fill color in the DeviceRGB color space (`rg`).
+    pub fn set_fill_rgb(self, r: f32, g: f32, b: f32) -> ContentBuilder {
+        self.numbers("rg", [r, g, b])
+    }
+
+    /// Set the stroke color in the DeviceRGB color space (`RG`).
+    pub fn set_stroke_rgb(self, r: f32, g: f32, b: f32) -> ContentBuilder {
+        self.numbers("RG", [r, g, b])
+    }
+
+    /// Begin a text object (`BT`).
+    pub fn begin_text(self) -> ContentBuilder {
+        self.operation("BT", vec![])
+    }
+
+    /// End a text object (`ET`).
+    pub fn end_text(self) -> ContentBuilder {
+        self.operation("ET", vec![])
+    }
+
+    /// Move to the start of the next line, offset from the start of the current line (`Td`).
+    pub fn move_text(self, x: f32, y: f32) -> ContentBuilder {
+        self.numbers("Td", [x, y])
+    }
+
+    /// Select a font resource by name and the font size (`Tf`).
+    pub fn set_font(self, font: &str, size: f32) -> ContentBuilder {
+        self.operation("Tf", vec![Object::Name(font.as_bytes().to_vec()), Object::Real(size)])
+    }
+
+    /// Show a string (`Tj`). The bytes are written unchanged, so they must be encoded in the
+    /// encoding of the current font.
+    pub fn show_text<T: Into<Vec<u8>>>(self, text: T) -> ContentBuilder {
+        self.operation("Tj", vec![Object::string_literal(text)])
+    }
+
+    /// Show strings with individual glyph positioning (`TJ`). The items are strings and numbers,
+    /// which move the next string left by thousandths of the font size.
+    pub fn show_text_adjusted<I: IntoIterator<Item = Object>>(self, items: I) -> ContentBuilder {
+        self.operation("TJ", vec![Object::Array(items.into_iter().collect())])
+    }
+
+    /// Finish building and return the content.
+    pub fn build(self) -> Content {
+        Content {
+            operations: self.operations,
+        }
+    }
+}
+
+/// Round all real numbers in an operand to a number of decimal places.
+fn round_reals(object: &Object, places: u8) -> Object {
+    match object {
+        Object::Real(value) => {
+            let scale = 10f64.powi(places as i32);
+            Object::Real(((*value as f64 * scale).round() / scale) as f32)
+        }
+        Object::Array(array) => Object::Array(array.iter().map(|item| round_reals(item, places)).collect()),
+        Object::Dictionary(dict) => {
+            let mut dict = dict.clone();
+            for (_, value) in dict.iter_mut() {
+                *value = round_reals(value, places);
+            }
+            Object::Dictionary(dict)
+        }
+        _ => object.clone(),
+    }
+}
+
+/// A string shown by one of the text showing operators, together with the text state it was shown in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun<T = Vec<u8>> {
+    /// The shown string, encoded in the font's encoding unless decoded by the caller.
+    pub text: T,
+    /// Name of the font resource selected with `Tf`.
+    pub font: Vec<u8>,
+    /// Font size in user space, that is the `Tf` size scaled by the text and transformation matrices.
+    pub font_size: f32,
+    /// Horizontal position of the start of the run in user space.
+    pub x: f32,
+    /// Vertical position of the baseline of the run in user space.
+    pub y: f32,
+}
+
+/// Affine transformation matrix `[a b c d e f]` as used by the `cm` and `Tm` operators.
+#[derive(Debug, Clone, Copy)]
+struct Matrix([f32; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn from_operands(operands: &[Object]) -> Option<Matrix> {
+        let mut matrix = [0.0; 6];
+        if operands.len() != 6 {
+            return None;
+        }
+        for (value, operand) in matrix.iter_mut().zip(operands) {
+            *value = operand.as_float().ok()?;
+        }
+        Some(Matrix(matrix))
+    }
+
+    fn translation(tx: f32, ty: f32) -> Matrix {
+        Matrix([1.0, 0.0, 0.0, 1.0, tx, ty])
+    }
+
+    /// Matrix product `self × other`, which applies `self` first.
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        Matrix([
+            a * a2 + b * c2,
+            a * b2 + b * d2,
+            c * a2 + d * c2,
+            c * b2 + d * d2,
+            e * a2 + f * c2 + e2,
+            e * b2 + f * d2 + f2,
+        ])
+    }
+}
+
+/// Graphics state parameters relevant for positioning text, saved and restored by `q` and `Q`.
+#[derive(Debug, Clone)]
+struct TextState {
+    ctm: Matrix,
+    font: Vec<u8>,
+    font_size: f32,
+    leading: f32,
+}
+
+impl<Operations: AsRef<[Operation]>> Content<Operations> {
+    /// Collect all strings shown by the `Tj`, `TJ`, `'` and `"` operators with their positions.
+    ///
+    /// The text and line matrices are tracked through `BT`, `Td`, `TD`, `Tm` and `T*`, and the
+    /// current transformation matrix through `cm`, `q` and `Q`. The text matrix is not advanced by
+    /// the width of the shown glyphs, as that requires font metrics, so consecutive runs without a
+    /// positioning operator in between report the same position.
+    pub fn text_runs(&self) -> Vec<TextRun> {
+        let mut runs = vec![];
+        let mut stack = vec![];
+        let mut state = TextState {
+            ctm: Matrix::IDENTITY,
+            font: vec![],
+            font_size: 0.0,
+            leading: 0.0,
+        };
+        let mut text_matrix = Matrix::IDENTITY;
+        let mut line_matrix = Matrix::IDENTITY;
+
+        let float = |operands: &[Object], index: usize| operands.get(index).and_then(|o| o.as_float().ok());
+        let next_line = |line_matrix: &mut Matrix, tx: f32, ty: f32| {
+            *line_matrix = Matrix::translation(tx, ty).multiply(line_matrix);
+            *line_matrix
+        };
+        for operation in self.operations.as_ref() {
+            let operands = operation.operands.as_slice();
+            match operation.operator.as_str() {
+                "q" => stack.push(state.clone()),
+                "Q" => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+                "cm" => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        state.ctm = matrix.multiply(&state.ctm);
+                    }
+                }
+                "BT" => {
+                    text_matrix = Matrix::IDENTITY;
+                    line_matrix = Matrix::IDENTITY;
+                }
+                "Tf" => {
+                    if let Some(font) = operands.first().and_then(|o| o.as_name().ok()) {
+                        state.font = font.to_vec();
+                    }
+                    state.font_size = float(operands, 1).unwrap_or(state.font_size);
+                }
+                "TL" => state.leading = float(operands, 0).unwrap_or(state.leading),
+                "Td" | "TD" => {
+                    if let (Some(tx), Some(ty)) = (float(operands, 0), float(operands, 1)) {
+                        if operation.operator == "TD" {
+                            state.leading = -ty;
+                        }
+                        text_matrix = next_line(&mut line_matrix, tx, ty);
+                    }
+                }
+                "Tm" => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        text_matrix = matrix;
+                        line_matrix = matrix;
+                    }
+                }
+                "T*" => text_matrix = next_line(&mut line_matrix, 0.0, -state.leading),
+                "Tj" | "TJ" | "'" | "\"" => {
+                    if operation.operator != "Tj" && operation.operator != "TJ" {
+                        text_matrix = next_line(&mut line_matrix, 0.0, -state.leading);
+                    }
+                    let mut text = vec![];
+                    for operand in operands {
+                        match operand {
+                            Object::String(bytes, _) => text.extend_from_slice(bytes),
+                            Object::Array(array) => array
+                                .iter()
+                                .filter_map(|item| item.as_str().ok())
+                                .for_each(|bytes| text.extend_from_slice(bytes)),
+                            _ => {}
+                        }
+                    }
+                    let [_, _, c, d, x, y] = text_matrix.multiply(&state.ctm).0;
+                    runs.push(TextRun {
+                        text,
+                        font: state.font.clone(),
+                        font_size: state.font_size * (c * c + d * d).sqrt(),
+                        x,
+                        y,
+                    });
+                }
+                _ => {}
+            }
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_content() {
+        let content = ContentBuilder::new()
+            .save_state()
+            .transform(1.0, 0.0, 0.0, 1.0, 10.0, 20.5)
+            .set_fill_rgb(1.0, 0.0, 0.0)
+            .rectangle(0.0, 0.0, 100.0, 50.0)
+            .fill()
+            .move_to(0.0, 0.0)
+            .line_to(100.0, 50.0)
+            .curve_to(110.0, 60.0, 120.0, 60.0, 130.0, 50.0)
+            .stroke()
+            .restore_state()
+            .begin_text()
+            .set_font("F1", 12.0)
+            .move_text(72.0, 712.0)
+            .show_text("Hello")
+            .show_text_adjusted([Object::string_literal("W"), 120.into(), Object::string_literal("orld")])
+            .end_text()
+            .build();
+        let expected = "q\n1 0 0 1 10 20.5 cm\n1 0 0 rg\n0 0 100 50 re\nf\n0 0 m\n100 50 l\n\
+            110 60 120 60 130 50 c\nS\nQ\nBT\n/F1 12 Tf\n72 712 Td\n(Hello) Tj\n[(W) 120(orld)] TJ\nET";
+        assert_eq!(String::from_utf8(content.encode().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn encode_real_numbers() {
+        let encode = |value: f32, max_decimal_places: Option<u8>| {
+            let content = Content {
+                operations: vec![Operation::new("w", vec![Object::Real(value)])],
+            };
+            let encoded = content
+                .encode_with_options(EncodeOptions { max_decimal_places })
+                .unwrap();
+            String::from_utf8(encoded).unwrap()
+        };
+        assert_eq!(encode(0.1, None), "0.1 w");
+        assert_eq!(encode(2.5, None), "2.5 w");
+        assert_eq!(encode(1e20, None), "100000000000000000000 w");
+        assert_eq!(encode(0.000001, None), "0.000001 w");
+        assert_eq!(encode(0.1 + 0.2, None), "0.3 w");
+        assert_eq!(encode(1.0 / 3.0, Some(3)), "0.333 w");
+        assert_eq!(encode(2.0004, Some(3)), "2 w");
+        assert_eq!(encode(-0.0001, Some(2)), "0 w");
+        assert_eq!(encode(f32::NAN, None), "0 w");
+
+        let content = Content {
+            operations: vec![Operation::new(
+                "d",
+                vec![vec![Object::Real(1.23456)].into(), Object::Real(0.5)],
+            )],
+        };
+        let options = EncodeOptions {
+            max_decimal_places: Some(2),
+        };
+        assert_eq!(content.encode_with_options(options).unwrap(), b"[1.23] 0.5 d");
+    }
+
+    #[test]
+    fn text_runs_track_matrices() {
+        let content = Content {
+            operations: vec![
+                Operation::new("q", vec![]),
+                Operation::new("cm", vec![2.into(), 0.into(), 0.into(), 2.into(), 10.into(), 20.into()]),
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal("Hello")]),
+                Operation::new("TD", vec![0.into(), (-14).into()]),
+                Operation::new(
+                    "TJ",
+                    vec![vec![
+                        Object::string_literal("Wor"),
+                        (-50).into(),
+                        Object::string_literal("ld"),
+                    ]
+                    .into()],
+                ),
+                Operation::new("T*", vec![]),
+                Operation::new("'", vec![Object::string_literal("!")]),
+                Operation::new("ET", vec![]),
+                Operation::new("Q", vec![]),
+                Operation::new("BT", vec![]),
+                Operation::new("Tm", vec![1.into(), 0.into(), 0.into(), 1.into(), 5.into(), 5.into()]),
+                Operation::new("Tj", vec![Object::string_literal("after")]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+
+        let runs: Vec<_> = content
+            .text_runs()
+            .into_iter()
+            .map(|run| (run.text, run.font_size, run.x, run.y))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                (b"Hello".to_vec(), 24.0, 210.0, 1220.0),
+                (b"World".to_vec(), 24.0, 210.0, 1192.0),
+                (b"!".to_vec(), 24.0, 210.0, 1136.0),
+                (b"after".to_vec(), 0.0, 5.0, 5.0),
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use crate::{Dictionary, Document, Object, ObjectId};
+use crate::content::Content;
+use crate::{Dictionary, Document, Object, ObjectId, Stream};
 use crate::{Error, Result};
 
 impl Document {
@@ -45,6 +46,109 @@ pub fn remove_object(&mut self, object_id: &ObjectId) -> Result<()> {
         Ok(())
     }
 
+    /// Add a page at the end of the document, with its content stream and resources.
+    /// The catalog and page tree are created if the document doesn't have them yet.
+    ///
+    /// `media_box` is `[llx, lly, urx, ury]`. Returns the id of the new page.
+    pub fn add_page(&mut self, media_box: [f32; 4], content: Content, resources: Dictionary) -> Result<ObjectId> {
+        let page_count = self.page_iter().count();
+        self.insert_page(page_count, media_box, content, resources)
+    }
+
+    /// Insert a page so it becomes the page at `index`, counting from 0, with its content
+    /// stream and resources. An index equal to the number of pages adds the page at the end.
+    ///
+    /// The page is added to the page tree node of the page it is inserted before, and the
+    /// `Count` of all ancestors is updated. Returns the id of the new page.
+    pub fn insert_page(
+        &mut self, index: usize, media_box: [f32; 4], content: Content, resources: Dictionary,
+    ) -> Result<ObjectId> {
+        let page_ids: Vec<ObjectId> = self.page_iter().collect();
+        if index > page_ids.len() {
+            return Err(Error::PageNumberNotFound(index as u32 + 1));
+        }
+        let root_id = self.get_or_create_page_tree()?;
+
+        // Insert before the page at `index`, or after the last page in its own node.
+        let (parent_id, position) = match page_ids.get(index).or_else(|| page_ids.last()) {
+            Some(&sibling_id) => {
+                let parent_id = self
+                    .get_dictionary(sibling_id)?
+                    .get(b"Parent")
+                    .and_then(Object::as_reference)?;
+                let kids = self
+                    .get_dictionary(parent_id)?
+                    .get(b"Kids")
+                    .and_then(Object::as_array)?;
+                let position = kids
+                    .iter()
+                    .position(|kid| kid.as_reference().ok() == Some(sibling_id))
+                    .ok_or(Error::ObjectNotFound)?;
+                let position = if index == page_ids.len() {
+                    position + 1
+                } else {
+                    position
+                };
+                (parent_id, position)
+            }
+            None => (root_id, 0),
+        };
+
+        let content_id = self.add_object(Stream::new(Dictionary::new(), content.encode()?));
+        let page_id = self.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => parent_id,
+            "MediaBox" => media_box.iter().map(|&value| Object::Real(value)).collect::<Vec<_>>(),
+            "Contents" => content_id,
+            "Resources" => resources,
+        });
+
+        let parent = self.get_dictionary_mut(parent_id)?;
+        if !parent.has(b"Kids") {
+            parent.set("Kids", vec![]);
+        }
+        parent
+            .get_mut(b"Kids")
+            .and_then(Object::as_array_mut)?
+            .insert(position, page_id.into());
+
+        let mut node_id = Some(parent_id);
+        let mut visited = std::collections::BTreeSet::new();
+        while let Some(id) = node_id.filter(|&id| visited.insert(id)) {
+            let node = self.get_dictionary_mut(id)?;
+            let count = node.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+            node.set("Count", count + 1);
+            node_id = node.get(b"Parent").and_then(Object::as_reference).ok();
+        }
+        Ok(page_id)
+    }
+
+    /// Get the id of the root page tree node, creating the catalog and root node if needed.
+    fn get_or_create_page_tree(&mut self) -> Result<ObjectId> {
+        let catalog_id = match self.trailer.get(b"Root").and_then(Object::as_reference) {
+            Ok(id) => id,
+            Err(_) => {
+                let id = self.add_object(dictionary! { "Type" => "Catalog" });
+                self.trailer.set("Root", id);
+                id
+            }
+        };
+        if let Ok(id) = self
+            .get_dictionary(catalog_id)?
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+        {
+            return Ok(id);
+        }
+        let pages_id = self.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![],
+            "Count" => 0,
+        });
+        self.get_dictionary_mut(catalog_id)?.set("Pages", pages_id);
+        Ok(pages_id)
+    }
+
     /// Get the pages resources. (Errors in case of reference)
     ///
     /// Get Object that has the key "Resources".
@@ -131,7 +235,7 @@ pub mod tests {
     use std::path::PathBuf;
 
     use crate::content::*;
-    use crate::{Document, Object, Stream};
+    use crate::{Dictionary, Document, Error, Object, Stream};
 
     /// Create and return a document for testing
     pub fn create_document() -> Document {
@@ -207,4 +311,89 @@ fn save_created_document() {
         save_document(&file_path, &mut doc);
         assert!(file_path.exists());
     }
+
+    #[test]
+    fn add_and_insert_pages() {
+        let page = |text: &str| Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let mut doc = Document::with_version("1.5");
+        let letter = [0.0, 0.0, 612.0, 792.0];
+        let second = doc.add_page(letter, page("second"), Dictionary::new()).unwrap();
+        let first = doc.insert_page(0, letter, page("first"), Dictionary::new()).unwrap();
+        assert_eq!(doc.page_iter().collect::<Vec<_>>(), [first, second]);
+
+        // Nest the pages in an intermediate node, new pages are added next to their siblings.
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let node_id = doc.add_object(dictionary! {
+            "Type" => "Pages",
+            "Parent" => root_id,
+            "Kids" => vec![first.into(), second.into()],
+            "Count" => 2,
+        });
+        for id in [first, second] {
+            doc.get_dictionary_mut(id).unwrap().set("Parent", node_id);
+        }
+        doc.get_dictionary_mut(root_id)
+            .unwrap()
+            .set("Kids", vec![node_id.into()]);
+
+        let middle = doc
+            .insert_page(1, [0.0, 0.0, 100.0, 100.0], page("middle"), Dictionary::new())
+            .unwrap();
+        let last = doc.add_page(letter, page("last"), Dictionary::new()).unwrap();
+        assert_eq!(doc.page_iter().collect::<Vec<_>>(), [first, middle, second, last]);
+        assert_eq!(doc.get_page_media_box(middle).unwrap(), [0.0, 0.0, 100.0, 100.0]);
+        let content = Content::decode(&doc.get_page_content(middle).unwrap()).unwrap();
+        assert_eq!(content.operations[2].operands[0].as_str().unwrap(), b"middle");
+
+        let count = |doc: &Document, id| {
+            let node = doc.get_dictionary(id).unwrap();
+            node.get(b"Count").and_then(Object::as_i64).unwrap()
+        };
+        assert_eq!((count(&doc, root_id), count(&doc, node_id)), (4, 4));
+        assert_eq!(
+            doc.get_dictionary(last)
+                .unwrap()
+                .get(b"Parent")
+                .and_then(Object::as_reference)
+                .unwrap(),
+            node_id
+        );
+        assert!(matches!(
+            doc.insert_page(9, letter, page("none"), Dictionary::new()),
+            Err(Error::PageNumberNotFound(10))
+        ));
+    }
+
+    #[test]
+    fn save_is_deterministic() {
+        let save = || {
+            let mut doc = Document::with_version("1.5");
+            doc.add_object(dictionary! {
+                "Zeta" => 1,
+                "Alpha" => Object::string_literal("second"),
+                "Mid" => dictionary! { "Y" => 1, "X" => 2 },
+            });
+            doc.trailer.set("Root", (1, 0));
+            let mut buffer = Vec::new();
+            doc.save_to(&mut buffer).unwrap();
+            buffer
+        };
+
+        let first = save();
+        assert_eq!(first, save());
+        let text = String::from_utf8_lossy(&first);
+        assert!(text.contains("<</Zeta 1/Alpha(second)/Mid<</Y 1/X 2>>>>"), "{}", text);
+    }
 }
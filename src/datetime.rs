@@ -1,35 +1,161 @@
 use super::Object;
 #[cfg(feature = "chrono_time")]
 use chrono::prelude::*;
+use std::fmt;
 
 use time::{format_description::FormatItem, OffsetDateTime, Time};
 
+/// Date in the PDF date format `D:YYYYMMDDHHmmSSOHH'mm'`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PdfDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Offset from UTC in minutes, `None` if the relationship to UTC is unknown.
+    pub utc_offset: Option<i16>,
+}
+
+impl PdfDate {
+    /// Parse a date string. Every field after the year is optional, as are the `D:` prefix
+    /// and the apostrophes of the UTC offset.
+    pub fn parse(bytes: &[u8]) -> Option<PdfDate> {
+        let mut rest = bytes.strip_prefix(b"D:").unwrap_or(bytes);
+        let year = take_digits(&mut rest, 4)?;
+        let mut date = PdfDate {
+            year,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            utc_offset: None,
+        };
+        let fields = [
+            (&mut date.month, 1..=12u16),
+            (&mut date.day, 1..=31),
+            (&mut date.hour, 0..=23),
+            (&mut date.minute, 0..=59),
+            (&mut date.second, 0..=59),
+        ];
+        for (field, range) in fields {
+            match take_digits(&mut rest, 2) {
+                Some(value) if range.contains(&value) => *field = value as u8,
+                Some(_) => return None,
+                None => break,
+            }
+        }
+
+        let sign = match rest.first() {
+            Some(b'Z') => 0,
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            None => return Some(date),
+            Some(_) => return None,
+        };
+        rest = &rest[1..];
+        let hours = take_digits(&mut rest, 2).unwrap_or(0);
+        rest = rest.strip_prefix(b"'").unwrap_or(rest);
+        let minutes = take_digits(&mut rest, 2).unwrap_or(0);
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        date.utc_offset = Some(sign * (hours * 60 + minutes) as i16);
+        Some(date)
+    }
+
+    /// Format the date as ISO 8601, as used by XMP metadata.
+    pub fn to_iso8601(&self) -> String {
+        let mut text = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+        match self.utc_offset {
+            Some(0) => text.push('Z'),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                text.push_str(&format!("{}{:02}:{:02}", sign, offset.abs() / 60, offset.abs() % 60));
+            }
+            None => {}
+        }
+        text
+    }
+}
+
+fn take_digits(input: &mut &[u8], len: usize) -> Option<u16> {
+    let digits = input
+        .get(..len)
+        .filter(|digits| digits.iter().all(u8::is_ascii_digit))?;
+    *input = &input[len..];
+    Some(digits.iter().fold(0, |value, digit| value * 10 + (digit - b'0') as u16))
+}
+
+impl fmt::Display for PdfDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "D:{:04}{:02}{:02}{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        match self.utc_offset {
+            Some(0) => write!(f, "Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                write!(f, "{}{:02}'{:02}'", sign, offset.abs() / 60, offset.abs() % 60)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<PdfDate> for Object {
+    fn from(date: PdfDate) -> Self {
+        Object::string_literal(date.to_string())
+    }
+}
+
 #[cfg(feature = "chrono_time")]
-impl From<DateTime<Local>> for Object {
-    fn from(date: DateTime<Local>) -> Self {
-        let mut timezone_str = date.format("D:%Y%m%d%H%M%S%:z'").to_string().into_bytes();
-        convert_utc_offset(&mut timezone_str);
-        Object::string_literal(timezone_str)
+impl<Tz: TimeZone> From<DateTime<Tz>> for PdfDate {
+    fn from(date: DateTime<Tz>) -> Self {
+        PdfDate {
+            year: date.year().clamp(0, 9999) as u16,
+            month: date.month() as u8,
+            day: date.day() as u8,
+            hour: date.hour() as u8,
+            minute: date.minute() as u8,
+            second: date.second() as u8,
+            utc_offset: Some((date.offset().fix().local_minus_utc() / 60) as i16),
+        }
     }
 }
 
-// Find the last `:` and turn it into an `'` to account for PDF weirdness
-#[allow(dead_code)]
-fn convert_utc_offset(bytes: &mut [u8]) {
-    let mut index = bytes.len();
-    while let Some(last) = bytes[..index].last_mut() {
-        if *last == b':' {
-            *last = b'\'';
-            break;
+impl From<OffsetDateTime> for PdfDate {
+    fn from(date: OffsetDateTime) -> Self {
+        PdfDate {
+            year: date.year().clamp(0, 9999) as u16,
+            month: date.month().into(),
+            day: date.day(),
+            hour: date.hour(),
+            minute: date.minute(),
+            second: date.second(),
+            utc_offset: Some(date.offset().whole_minutes()),
         }
-        index -= 1;
+    }
+}
+
+#[cfg(feature = "chrono_time")]
+impl From<DateTime<Local>> for Object {
+    fn from(date: DateTime<Local>) -> Self {
+        PdfDate::from(date).into()
     }
 }
 
 #[cfg(feature = "chrono_time")]
 impl From<DateTime<Utc>> for Object {
     fn from(date: DateTime<Utc>) -> Self {
-        Object::string_literal(date.format("D:%Y%m%d%H%M%SZ").to_string())
+        PdfDate::from(date).into()
     }
 }
 
@@ -39,7 +165,7 @@ fn from(date: Time) -> Self {
         Object::string_literal(
             format!(
                 "D:{}",
-                date.format(&FormatItem::Literal("%Y%m%d%H%M%SZ".as_bytes())).unwrap()
+                date.format(&FormatItem::StringLiteral("%Y%m%d%H%M%SZ")).unwrap()
             )
             .into_bytes(),
         )
@@ -48,47 +174,20 @@ fn from(date: Time) -> Self {
 
 impl From<OffsetDateTime> for Object {
     fn from(date: OffsetDateTime) -> Self {
-        Object::string_literal({
-            // D:%Y%m%d%H%M%S:%z'
-            let format = time::format_description::parse(
-                "D:[year][month][day][hour][minute][second][offset_hour sign:mandatory]'[offset_minute]'",
-            )
-            .unwrap();
-            date.format(&format).unwrap()
-        })
+        PdfDate::from(date).into()
     }
 }
 
 impl Object {
-    // Parses the `D`, `:` and `\` out of a `Object::String` to parse the date time
-    fn datetime_string(&self) -> Option<String> {
-        if let Object::String(ref bytes, _) = self {
-            String::from_utf8(
-                bytes
-                    .iter()
-                    .filter(|b| ![b'D', b':', b'\''].contains(b))
-                    .cloned()
-                    .collect(),
-            )
-            .ok()
-        } else {
-            None
-        }
-    }
-
+    /// Parse a date string with [`PdfDate::parse`]. Dates whose relationship to UTC is unknown
+    /// are taken as UTC.
     #[cfg(feature = "chrono_time")]
     pub fn as_datetime(&self) -> Option<DateTime<Local>> {
-        let text = self.datetime_string()?;
-        let from_date = |date: NaiveDate| {
-            FixedOffset::east_opt(0)
-                .unwrap()
-                .from_utc_datetime(&date.and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap()))
-        };
-        DateTime::parse_from_str(&text, "%Y%m%d%H%M%S%#z")
-            .or_else(|_| DateTime::parse_from_str(&text, "%Y%m%d%H%M%#z"))
-            .or_else(|_| NaiveDate::parse_from_str(&text, "%Y%m%d").map(from_date))
-            .map(|date| date.with_timezone(&Local))
-            .ok()
+        let date = PdfDate::parse(self.as_str().ok()?)?;
+        let offset = FixedOffset::east_opt(i32::from(date.utc_offset.unwrap_or(0)) * 60)?;
+        let day = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?;
+        let local = day.and_hms_opt(date.hour.into(), date.minute.into(), date.second.into())?;
+        Some(offset.from_local_datetime(&local).single()?.with_timezone(&Local))
     }
 
     /// WARNING: `tm_wday` (weekday), `tm_yday` (day index in year), `tm_isdst`
@@ -97,12 +196,11 @@ pub fn as_datetime(&self) -> Option<DateTime<Local>> {
     /// however, be calculated manually
     #[cfg(not(feature = "chrono_time"))]
     pub fn as_datetime(&self) -> Option<OffsetDateTime> {
-        let format = time::format_description::parse(
-            "[year][month][day][hour][minute][second][offset_hour sign:mandatory][offset_minute]",
-        )
-        .unwrap();
-        let text = self.datetime_string()?;
-        OffsetDateTime::parse(&text, &format).ok()
+        let date = PdfDate::parse(self.as_str().ok()?)?;
+        let offset = time::UtcOffset::from_whole_seconds(i32::from(date.utc_offset.unwrap_or(0)) * 60).ok()?;
+        let day = time::Date::from_calendar_date(date.year.into(), date.month.try_into().ok()?, date.day).ok()?;
+        let local = day.with_hms(date.hour, date.minute, date.second).ok()?;
+        Some(local.assume_offset(offset))
     }
 }
 
@@ -155,3 +253,33 @@ fn parse_datetime() {
     assert_eq!(time2.time().minute(), time.time().minute());
     assert_eq!(time2.time().second(), time.time().second());
 }
+
+#[test]
+fn parse_dates() {
+    let date = PdfDate::parse(b"D:199812231952-08'00'").unwrap();
+    assert_eq!(
+        date,
+        PdfDate {
+            year: 1998,
+            month: 12,
+            day: 23,
+            hour: 19,
+            minute: 52,
+            second: 0,
+            utc_offset: Some(-480),
+        }
+    );
+    assert_eq!(date.to_string(), "D:19981223195200-08'00'");
+
+    let date = PdfDate::parse(b"D:20040229").unwrap();
+    assert_eq!((date.year, date.month, date.day, date.utc_offset), (2004, 2, 29, None));
+    assert_eq!(date.to_string(), "D:20040229000000");
+
+    let date = PdfDate::parse(b"20230102030405Z").unwrap();
+    assert_eq!(date.to_string(), "D:20230102030405Z");
+    assert_eq!(PdfDate::parse(b"D:20230102030405+0530").unwrap().utc_offset, Some(330));
+
+    assert_eq!(PdfDate::parse(b"D:2023130"), None);
+    assert_eq!(PdfDate::parse(b"D:20"), None);
+    assert_eq!(PdfDate::parse(b"D:20230102X"), None);
+}
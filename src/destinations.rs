@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use super::{Dictionary, Document, Object, Result};
+use super::{Dictionary, Document, Error, Object, ObjectId, Result};
 use indexmap::IndexMap;
 #[derive(Debug, Clone)]
 pub struct Destination {
@@ -22,23 +22,41 @@ pub fn set(&mut self, key: Vec<u8>, value: Object) {
 
     #[allow(dead_code)]
     pub fn title(&self) -> Option<&Object> {
-        self.map.get(&b"Title".to_vec())
+        self.map.get(b"Title".as_slice())
     }
 
     #[allow(dead_code)]
     pub fn page(&self) -> Option<&Object> {
-        self.map.get(&b"Page".to_vec())
+        self.map.get(b"Page".as_slice())
     }
 }
 
 impl Document {
+    /// Collect the destinations of the `Dests` name tree with the given root node.
+    ///
+    /// Fails with [`Error::ReferenceCycle`] if a node is its own ancestor.
     pub fn get_named_destinations(
         &self, tree: &Dictionary, named_destinations: &mut IndexMap<Vec<u8>, Destination>,
+    ) -> Result<()> {
+        self.collect_named_destinations(tree, named_destinations, &mut Vec::new())
+    }
+
+    fn collect_named_destinations(
+        &self, tree: &Dictionary, named_destinations: &mut IndexMap<Vec<u8>, Destination>,
+        ancestors: &mut Vec<ObjectId>,
     ) -> Result<()> {
         if let Ok(kids) = tree.get(b"Kids") {
             for kid in kids.as_array()? {
-                if let Ok(kid) = kid.as_reference().and_then(move |id| self.get_dictionary(id)) {
-                    self.get_named_destinations(kid, named_destinations)?;
+                let Ok(id) = kid.as_reference() else {
+                    continue;
+                };
+                if ancestors.contains(&id) {
+                    return Err(Error::ReferenceCycle(id));
+                }
+                if let Ok(kid) = self.get_dictionary(id) {
+                    ancestors.push(id);
+                    self.collect_named_destinations(kid, named_destinations, ancestors)?;
+                    ancestors.pop();
                 }
             }
         }
@@ -74,3 +92,168 @@ pub fn get_named_destinations(
         Ok(())
     }
 }
+
+impl Document {
+    /// Resolve a destination to the id of its page and its view parameters, such as
+    /// `/XYZ left top zoom`.
+    ///
+    /// `dest` is an explicit destination array, or the name or string naming a destination in
+    /// the `Dests` name tree of the catalog's `Names` dictionary, or in the catalog's `Dests`
+    /// dictionary used before PDF 1.2. Destinations stored as a dictionary holding the array as
+    /// `D` are supported as well. Returns `None` if the destination does not exist or does not
+    /// refer to a page of the document.
+    pub fn resolve_dest(&self, dest: &Object) -> Option<(ObjectId, Vec<Object>)> {
+        let dest = match self.dereference(dest).ok()?.1 {
+            Object::Array(dest) => dest,
+            Object::Name(name) | Object::String(name, _) => {
+                let dest = self.named_destination(name)?;
+                // Named destinations are an array or a dictionary holding it as `D`.
+                match dest {
+                    Object::Dictionary(dict) => dict.get_deref(b"D", self).ok()?,
+                    dest => dest,
+                }
+                .as_array()
+                .ok()?
+            }
+            _ => return None,
+        };
+        let (page, view) = dest.split_first()?;
+        let page_id = match self.dereference(page).ok()? {
+            (Some(page_id), _) if self.get_pages().values().any(|&id| id == page_id) => page_id,
+            // Destinations of other files use page indexes, but some files use them as well.
+            (_, Object::Integer(index)) if *index >= 0 => *self.get_pages().get(&(*index as u32 + 1))?,
+            _ => return None,
+        };
+        let view = view
+            .iter()
+            .map(|param| self.dereference(param).map_or(Object::Null, |(_, param)| param.clone()))
+            .collect();
+        Some((page_id, view))
+    }
+
+    fn named_destination(&self, name: &[u8]) -> Option<&Object> {
+        let catalog = self.catalog().ok()?;
+        let tree = catalog
+            .get_deref(b"Names", self)
+            .and_then(Object::as_dict)
+            .and_then(|names| names.get_deref(b"Dests", self))
+            .and_then(Object::as_dict);
+        if let Some(dest) = tree.ok().and_then(|tree| self.find_in_name_tree(tree, name, 0)) {
+            return Some(dest);
+        }
+        catalog
+            .get_deref(b"Dests", self)
+            .and_then(Object::as_dict)
+            .and_then(|dests| dests.get_deref(name, self))
+            .ok()
+    }
+
+    /// Find the value of `key` in a name tree, descending only into the kids whose `Limits`
+    /// include it.
+    fn find_in_name_tree<'a>(&'a self, node: &'a Dictionary, key: &[u8], depth: usize) -> Option<&'a Object> {
+        // Deeper trees are malformed or cyclic.
+        if depth > 32 {
+            return None;
+        }
+        if let Ok(names) = node.get_deref(b"Names", self).and_then(Object::as_array) {
+            for pair in names.chunks_exact(2) {
+                if self
+                    .dereference(&pair[0])
+                    .is_ok_and(|(_, name)| name.as_str().ok() == Some(key))
+                {
+                    return self.dereference(&pair[1]).ok().map(|(_, value)| value);
+                }
+            }
+        }
+        let kids = node.get_deref(b"Kids", self).and_then(Object::as_array).ok()?;
+        kids.iter().find_map(|kid| {
+            let kid = self.dereference(kid).ok()?.1.as_dict().ok()?;
+            if let Ok(limits) = kid.get_deref(b"Limits", self).and_then(Object::as_array) {
+                let limit = |index: usize| {
+                    let limit = self.dereference(limits.get(index)?).ok()?.1;
+                    limit.as_str().ok()
+                };
+                if let (Some(first), Some(last)) = (limit(0), limit(1)) {
+                    if key < first || key > last {
+                        return None;
+                    }
+                }
+            }
+            self.find_in_name_tree(kid, key, depth + 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creator::tests::create_document;
+    use crate::{Object, ObjectId};
+
+    fn view() -> Vec<Object> {
+        vec!["XYZ".into(), 0.into(), 800.into(), Object::Null]
+    }
+
+    fn dest(page_id: ObjectId) -> Object {
+        [vec![page_id.into()], view()].concat().into()
+    }
+
+    #[test]
+    fn resolve_explicit_destination() {
+        let doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        assert_eq!(doc.resolve_dest(&dest(page_id)), Some((page_id, view())));
+        // Page indexes of remote destinations.
+        let by_index = Object::from(vec![0.into(), "Fit".into()]);
+        assert_eq!(doc.resolve_dest(&by_index), Some((page_id, vec!["Fit".into()])));
+        assert_eq!(doc.resolve_dest(&vec![5.into(), "Fit".into()].into()), None);
+        assert_eq!(doc.resolve_dest(&Object::Integer(1)), None);
+    }
+
+    #[test]
+    fn resolve_destination_in_name_tree() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let dest_id = doc.add_object(dictionary! { "D" => dest(page_id) });
+        let first_leaf = doc.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("a"), Object::string_literal("c")],
+            "Names" => vec![Object::string_literal("a"), Object::Null, Object::string_literal("c"), dest(page_id)],
+        });
+        let second_leaf = doc.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("m"), Object::string_literal("z")],
+            "Names" => vec![Object::string_literal("m"), dest_id.into()],
+        });
+        let middle = doc.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("a"), Object::string_literal("z")],
+            "Kids" => vec![first_leaf.into(), second_leaf.into()],
+        });
+        let names = dictionary! { "Dests" => dictionary! { "Kids" => vec![middle.into()] } };
+        doc.catalog_mut().unwrap().set("Names", names);
+
+        assert_eq!(doc.resolve_dest(&Object::string_literal("c")), Some((page_id, view())));
+        assert_eq!(doc.resolve_dest(&Object::Name(b"m".to_vec())), Some((page_id, view())));
+        assert_eq!(doc.resolve_dest(&Object::string_literal("a")), None);
+        assert_eq!(doc.resolve_dest(&Object::string_literal("b")), None);
+    }
+
+    #[test]
+    fn resolve_destination_in_dests_dictionary() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let dests = dictionary! {
+            "intro" => dest(page_id),
+            "chapter" => dictionary! { "D" => dest(page_id) },
+        };
+        let dests_id = doc.add_object(dests);
+        doc.catalog_mut().unwrap().set("Dests", dests_id);
+
+        assert_eq!(
+            doc.resolve_dest(&Object::Name(b"intro".to_vec())),
+            Some((page_id, view()))
+        );
+        assert_eq!(
+            doc.resolve_dest(&Object::string_literal("chapter")),
+            Some((page_id, view()))
+        );
+        assert_eq!(doc.resolve_dest(&Object::Name(b"missing".to_vec())), None);
+    }
+}
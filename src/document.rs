@@ -1,12 +1,13 @@
 use super::encodings::{self, bytes_to_string, string_to_bytes};
 use super::{Bookmark, Dictionary, Object, ObjectId};
 use crate::encryption;
+use crate::xobject::PdfImage;
 use crate::xref::{Xref, XrefType};
 use crate::{Error, Result, Stream};
 use encoding_rs::UTF_16BE;
-use log::info;
+use log::{info, warn};
 use std::cmp::max;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::Write;
 use std::str;
 
@@ -46,6 +47,16 @@ pub struct Document {
     /// It is used to support incremental updates in PDFs.
     /// Default value is `0`.
     pub xref_start: usize,
+
+    /// Whether the cross-reference table could not be read and was rebuilt by scanning the file
+    /// for objects while reading.
+    /// Default value is `false`.
+    pub xref_rebuilt: bool,
+
+    /// The number of bytes in front of the `%PDF-` header of the file, which are skipped while
+    /// reading. Offsets in the file, such as `xref_start`, are relative to the header.
+    /// Default value is `0`.
+    pub header_offset: usize,
 }
 
 impl Document {
@@ -61,6 +72,8 @@ pub fn new() -> Self {
             bookmarks: Vec::new(),
             bookmark_table: HashMap::new(),
             xref_start: 0,
+            xref_rebuilt: false,
+            header_offset: 0,
         }
     }
 
@@ -78,6 +91,8 @@ pub fn new_from_prev(prev: &Document) -> Self {
             bookmarks: Vec::new(),
             bookmark_table: HashMap::new(),
             xref_start: 0,
+            xref_rebuilt: false,
+            header_offset: 0,
         }
     }
 
@@ -127,18 +142,24 @@ pub fn adjust_zero_pages(&mut self) {
     /// The object id will be None if the object was not a
     /// reference. Otherwise, it will be the last object id in the
     /// reference chain.
+    ///
+    /// Fails with [`Error::ReferenceCycle`] if the chain leads back to an object it passed
+    /// before, and with [`Error::ReferenceLimit`] if it is too long.
     pub fn dereference<'a>(&'a self, mut object: &'a Object) -> Result<(Option<ObjectId>, &'a Object)> {
-        let mut nb_deref = 0;
+        let mut visited = Vec::new();
         let mut id = None;
 
         while let Ok(ref_id) = object.as_reference() {
-            id = Some(ref_id);
-            object = self.objects.get(&ref_id).ok_or(Error::ObjectNotFound)?;
-
-            nb_deref += 1;
-            if nb_deref > Self::DEREF_LIMIT {
+            if visited.contains(&ref_id) {
+                return Err(Error::ReferenceCycle(ref_id));
+            }
+            if visited.len() >= Self::DEREF_LIMIT {
                 return Err(Error::ReferenceLimit);
             }
+            visited.push(ref_id);
+
+            id = Some(ref_id);
+            object = self.objects.get(&ref_id).ok_or(Error::ObjectNotFound)?;
         }
 
         Ok((id, object))
@@ -154,7 +175,69 @@ pub fn get_object(&self, id: ObjectId) -> Result<&Object> {
     /// with the given `ObjectId`.
     /// `true` if the object exists, `false` if it does not exist.
     pub fn has_object(&self, id: ObjectId) -> bool {
-        self.objects.get(&id).is_some()
+        self.objects.contains_key(&id)
+    }
+
+    /// Get the byte offset of the object `id` in the file the document was loaded from, from the
+    /// `reference_table`. The offset counts from the start of the file, including any bytes in
+    /// front of the header, so it can be used to compute byte ranges of the raw file.
+    ///
+    /// Returns `None` if the `reference_table` has no entry for this object and generation, or if
+    /// the object is stored in an object stream.
+    pub fn object_offset(&self, id: ObjectId) -> Option<usize> {
+        match self.reference_table.get(id.0) {
+            Some(&crate::xref::XrefEntry::Normal { offset, generation }) if generation == id.1 => {
+                Some(offset as usize + self.header_offset)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the byte offset of the last cross-reference section in the file the document was
+    /// loaded from, as located by the last `startxref` keyword, counted from the start of the file
+    /// like [`Document::object_offset`].
+    ///
+    /// Returns `None` if the document was not loaded from a file or its cross-reference table was
+    /// rebuilt.
+    pub fn startxref_offset(&self) -> Option<usize> {
+        (self.xref_start > 0 && !self.xref_rebuilt).then(|| self.xref_start + self.header_offset)
+    }
+
+    /// Iterate over the objects of the document in ascending order of their ids.
+    pub fn iter_objects_by_id(&self) -> impl Iterator<Item = (ObjectId, &Object)> + '_ {
+        self.objects.iter().map(|(&id, object)| (id, object))
+    }
+
+    /// Iterate over the objects of the document in the order they are stored in the file it was
+    /// loaded from, according to the `reference_table`.
+    ///
+    /// Objects stored in an object stream come at the offset of the object stream, in the order
+    /// of their index in it. Objects without an entry in the `reference_table`, such as the
+    /// objects added after loading, come last in ascending order of their ids.
+    pub fn iter_objects_by_offset(&self) -> impl Iterator<Item = (ObjectId, &Object)> + '_ {
+        use crate::xref::XrefEntry;
+
+        let offset = |number: u32| match self.reference_table.get(number) {
+            Some(&XrefEntry::Normal { offset, .. }) => Some(offset),
+            _ => None,
+        };
+        let mut objects: Vec<_> = self
+            .objects
+            .iter()
+            .map(|(&id, object)| {
+                let position = match self.reference_table.get(id.0) {
+                    Some(&XrefEntry::Normal { offset, generation }) if generation == id.1 => Some((offset, 0)),
+                    Some(&XrefEntry::Compressed { container, index }) if id.1 == 0 => {
+                        offset(container).map(|offset| (offset, u32::from(index) + 1))
+                    }
+                    _ => None,
+                };
+                // Objects without a position sort last, as `None` is ordered before `Some`.
+                ((position.is_none(), position), id, object)
+            })
+            .collect();
+        objects.sort_by_key(|&(position, id, _)| (position, id));
+        objects.into_iter().map(|(_, id, object)| (id, object))
     }
 
     /// Get mutable reference to object by object id, will iteratively dereference a referenced object.
@@ -198,53 +281,45 @@ pub fn get_dictionary_mut(&mut self, id: ObjectId) -> Result<&mut Dictionary> {
 
     /// Get dictionary in dictionary by key.
     pub fn get_dict_in_dict<'a>(&'a self, node: &'a Dictionary, key: &[u8]) -> Result<&'a Dictionary> {
-        match node.get(key)? {
-            Object::Reference(object_id) => {
-                self.get_dictionary(*object_id)
-            }
-            Object::Dictionary(dic) => {
-                Ok(dic)
-            }
-            _ => { Err(Error::Type) }
-        }
+        node.get_deref(key, self).and_then(Object::as_dict)
     }
 
     /// Traverse objects from trailer recursively, return all referenced object IDs.
     pub fn traverse_objects<A: Fn(&mut Object)>(&mut self, action: A) -> Vec<ObjectId> {
-        fn traverse_array<A: Fn(&mut Object)>(array: &mut [Object], action: &A, refs: &mut Vec<ObjectId>) {
+        /// Referenced ids in the order they were found, and the same ids for quick lookup.
+        type Refs = (Vec<ObjectId>, BTreeSet<ObjectId>);
+        fn traverse_array<A: Fn(&mut Object)>(array: &mut [Object], action: &A, refs: &mut Refs) {
             for item in array.iter_mut() {
                 traverse_object(item, action, refs);
             }
         }
-        fn traverse_dictionary<A: Fn(&mut Object)>(dict: &mut Dictionary, action: &A, refs: &mut Vec<ObjectId>) {
+        fn traverse_dictionary<A: Fn(&mut Object)>(dict: &mut Dictionary, action: &A, refs: &mut Refs) {
             for (_, v) in dict.iter_mut() {
                 traverse_object(v, action, refs);
             }
         }
-        fn traverse_object<A: Fn(&mut Object)>(object: &mut Object, action: &A, refs: &mut Vec<ObjectId>) {
+        fn traverse_object<A: Fn(&mut Object)>(object: &mut Object, action: &A, refs: &mut Refs) {
             action(object);
             match *object {
                 Object::Array(ref mut array) => traverse_array(array, action, refs),
                 Object::Dictionary(ref mut dict) => traverse_dictionary(dict, action, refs),
                 Object::Stream(ref mut stream) => traverse_dictionary(&mut stream.dict, action, refs),
-                Object::Reference(id) => {
-                    if !refs.contains(&id) {
-                        refs.push(id);
-                    }
+                Object::Reference(id) if refs.1.insert(id) => {
+                    refs.0.push(id);
                 }
                 _ => {}
             }
         }
-        let mut refs = vec![];
+        let mut refs = (vec![], BTreeSet::new());
         traverse_dictionary(&mut self.trailer, &action, &mut refs);
         let mut index = 0;
-        while index < refs.len() {
-            if let Some(object) = self.objects.get_mut(&refs[index]) {
+        while index < refs.0.len() {
+            if let Some(object) = self.objects.get_mut(&refs.0[index]) {
                 traverse_object(object, &action, &mut refs);
             }
             index += 1;
         }
-        refs
+        refs.0
     }
 
     /// Return dictionary with encryption information
@@ -260,61 +335,121 @@ pub fn is_encrypted(&self) -> bool {
         self.get_encrypted().is_ok()
     }
 
-    /// Replaces all encrypted Strings and Streams with their decrypted contents
+    /// Replaces all encrypted Strings and Streams with their decrypted contents.
+    ///
+    /// The password can be either the user or the owner password; most documents that only
+    /// restrict permissions use an empty user password. Objects stored in encrypted object
+    /// streams are added to the document once their stream is decrypted.
     pub fn decrypt<P: AsRef<[u8]>>(&mut self, password: P) -> Result<()> {
         // Find the ID of the encryption dict; we'll want to skip it when decrypting
         let encryption_obj_id = self.trailer.get(b"Encrypt").and_then(Object::as_reference)?;
+        let state = encryption::EncryptionState::decode(self, password)?;
 
-        // Since PDF 1.5, metadata may or may not be encrypted; defaults to true
-        let metadata_is_encrypted = self
-            .get_object(encryption_obj_id)?
-            .as_dict()?
-            .get(b"EncryptMetadata")
-            .and_then(|o| o.as_bool())
-            .unwrap_or(true);
-
-        let key = encryption::get_encryption_key(self, &password, true)?;
         for (&id, obj) in self.objects.iter_mut() {
-            // The encryption dictionary is not encrypted, leave it alone
-            if id == encryption_obj_id {
+            // The encryption dictionary and cross-reference streams are not encrypted, leave them alone
+            if id == encryption_obj_id || obj.type_name().unwrap_or("") == "XRef" {
                 continue;
             }
-
-            // If a Metadata stream but metadata isn't encrypted, leave it alone
-            if obj.type_name().unwrap_or("") == "Metadata" && !metadata_is_encrypted {
-                continue;
+            if let Err(err) = state.decrypt_object(id, obj) {
+                warn!("Object {:?} could not be decrypted: {}", id, err);
             }
+        }
 
-            let decrypted = match encryption::decrypt_object(&key, id, &*obj) {
-                Ok(content) => content,
-                Err(encryption::DecryptionError::NotDecryptable) => {
-                    continue;
-                }
-                Err(_err) => {
-                    return Err(_err.into());
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        {
+            let mut compressed_objects = BTreeMap::new();
+            for obj in self.objects.values_mut() {
+                if let Object::Stream(stream) = obj {
+                    if stream.dict.type_is(b"ObjStm") {
+                        match crate::object_stream::ObjectStream::new(stream) {
+                            Ok(object_stream) => compressed_objects.extend(object_stream.objects),
+                            Err(err) => warn!("Object stream could not be parsed: {:?}", err),
+                        }
+                    }
                 }
-            };
+            }
+            for (id, obj) in compressed_objects {
+                self.objects.entry(id).or_insert(obj);
+            }
+        }
 
-            // Only strings and streams are encrypted
-            match obj {
-                Object::Stream(stream) => stream.set_content(decrypted),
-                Object::String(ref mut content, _) => *content = decrypted,
-                _ => {}
+        self.trailer.remove(b"Encrypt");
+        Ok(())
+    }
+
+    /// Remove the encryption of a document that can be opened without a password, which is
+    /// commonly used only to restrict permissions such as printing or copying text.
+    ///
+    /// All strings and streams are decrypted in place and the `Encrypt` entry is removed from the
+    /// trailer. Documents that are not encrypted are left as they are. If the document needs a
+    /// user password to be opened, it is left unchanged and
+    /// [`DecryptionError::PasswordRequired`](encryption::DecryptionError::PasswordRequired) is
+    /// returned.
+    pub fn decrypt_permissions(&mut self) -> Result<()> {
+        if !self.trailer.has(b"Encrypt") {
+            return Ok(());
+        }
+        match self.decrypt("") {
+            Err(Error::Decryption(encryption::DecryptionError::IncorrectPassword)) => {
+                Err(Error::Decryption(encryption::DecryptionError::PasswordRequired))
             }
+            result => result,
         }
+    }
 
-        if let Ok(info_obj_id) = self.trailer.get(b"Info").and_then(Object::as_reference) {
-            if let Ok(info_dict) = self.get_object_mut(info_obj_id).and_then(Object::as_dict_mut) {
-                for (_, info_obj) in info_dict.iter_mut() {
-                    if let Ok(content) = encryption::decrypt_object(&key, info_obj_id, &*info_obj) {
-                        info_obj.as_str_mut().unwrap().clear();
-                        info_obj.as_str_mut().unwrap().extend(content);
-                    };
-                }
+    /// Encrypt all strings and streams with the standard security handler and add the encryption
+    /// dictionary to the trailer, creating the file identifier if the document has none.
+    ///
+    /// The version of the document is raised to the first version supporting the method.
+    /// Documents that are already encrypted must be decrypted first. Encrypted documents are saved
+    /// without object streams, whatever the [`SaveOptions`](crate::SaveOptions).
+    #[cfg(feature = "encrypt")]
+    pub fn encrypt(&mut self, options: &encryption::EncryptOptions) -> Result<()> {
+        if self.trailer.has(b"Encrypt") {
+            return Err(Error::AlreadyEncrypted);
+        }
+
+        let file_id_0 = match self
+            .trailer
+            .get(b"ID")
+            .and_then(Object::as_array)
+            .and_then(|id| id.first().ok_or(Error::Type))
+            .and_then(Object::as_str)
+        {
+            Ok(file_id_0) => file_id_0.to_vec(),
+            Err(_) => {
+                let file_id = encryption::random_bytes(16)?;
+                self.trailer.set(
+                    "ID",
+                    vec![
+                        Object::string_literal(file_id.clone()),
+                        Object::string_literal(file_id.clone()),
+                    ],
+                );
+                file_id
+            }
+        };
+
+        let (state, encryption_dict) = encryption::EncryptionState::encode(options, &file_id_0)?;
+        for (&id, obj) in self.objects.iter_mut() {
+            // Object streams and cross-reference streams are not written, but recreated on save
+            if ["ObjStm", "XRef"].contains(&obj.type_name().unwrap_or("")) {
+                continue;
             }
+            state.encrypt_object(id, obj)?;
         }
 
-        self.trailer.remove(b"Encrypt");
+        let required_version = match options.method {
+            encryption::CryptMethod::Identity | encryption::CryptMethod::Rc4 => "1.4",
+            encryption::CryptMethod::Aes128 => "1.6",
+            encryption::CryptMethod::Aes256 => "2.0",
+        };
+        if self.version.as_str() < required_version {
+            self.version = required_version.to_string();
+        }
+
+        let encryption_id = self.add_object(encryption_dict);
+        self.trailer.set("Encrypt", encryption_id);
         Ok(())
     }
 
@@ -408,11 +543,17 @@ pub fn add_page_contents(&mut self, page_id: ObjectId, content: Vec<u8>) -> Resu
     }
 
     /// Get content of a page.
+    ///
+    /// When `Contents` is an array, the content streams are concatenated in order, separated by
+    /// a newline, since a content stream may end without whitespace after its last token.
     pub fn get_page_content(&self, page_id: ObjectId) -> Result<Vec<u8>> {
         let mut content = Vec::new();
         let content_streams = self.get_page_contents(page_id);
         for object_id in content_streams {
             if let Ok(content_stream) = self.get_object(object_id).and_then(Object::as_stream) {
+                if !content.is_empty() {
+                    content.push(b'\n');
+                }
                 match content_stream.decompressed_content() {
                     Ok(data) => content.write_all(&data)?,
                     Err(_) => content.write_all(&content_stream.content)?,
@@ -424,28 +565,107 @@ pub fn get_page_content(&self, page_id: ObjectId) -> Result<Vec<u8>> {
 
     /// Get resources used by a page.
     pub fn get_page_resources(&self, page_id: ObjectId) -> (Option<&Dictionary>, Vec<ObjectId>) {
-        fn collect_resources(page_node: &Dictionary, resource_ids: &mut Vec<ObjectId>, doc: &Document) {
-            if let Ok(resources_id) = page_node.get(b"Resources").and_then(Object::as_reference) {
-                resource_ids.push(resources_id);
-            }
-            if let Ok(page_tree) = page_node
-                .get(b"Parent")
-                .and_then(Object::as_reference)
-                .and_then(|id| doc.get_dictionary(id))
-            {
-                collect_resources(page_tree, resource_ids, doc);
-            }
-        }
-
         let mut resource_dict = None;
         let mut resource_ids = Vec::new();
         if let Ok(page) = self.get_dictionary(page_id) {
             resource_dict = page.get(b"Resources").and_then(Object::as_dict).ok();
-            collect_resources(page, &mut resource_ids, self);
+            // Walk up the page tree, stopping at an ancestor that was already visited.
+            let mut visited = BTreeSet::from([page_id]);
+            let mut node = page;
+            loop {
+                if let Ok(resources_id) = node.get(b"Resources").and_then(Object::as_reference) {
+                    resource_ids.push(resources_id);
+                }
+                match node.get(b"Parent").and_then(Object::as_reference) {
+                    Ok(parent_id) if visited.insert(parent_id) => match self.get_dictionary(parent_id) {
+                        Ok(parent) => node = parent,
+                        Err(_) => break,
+                    },
+                    _ => break,
+                }
+            }
         }
         (resource_dict, resource_ids)
     }
 
+    /// Get an attribute of a page, looking it up in the ancestors of the page if the page
+    /// doesn't have it itself. References are followed.
+    ///
+    /// Any attribute can be looked up, but only `Resources`, `MediaBox`, `CropBox` and `Rotate`
+    /// are inheritable according to the PDF specification.
+    pub fn get_page_attribute(&self, page_id: ObjectId, key: &[u8]) -> Result<&Object> {
+        let mut node = self.get_dictionary(page_id)?;
+        let mut visited = BTreeSet::from([page_id]);
+        loop {
+            if let Ok(value) = node.get(key) {
+                return self.dereference(value).map(|(_, value)| value);
+            }
+            let parent_id = node.get(b"Parent").and_then(Object::as_reference)?;
+            if !visited.insert(parent_id) {
+                return Err(Error::ReferenceCycle(parent_id));
+            }
+            node = self.get_dictionary(parent_id)?;
+        }
+    }
+
+    /// Get the media box of a page as `[llx, lly, urx, ury]`, inherited if needed.
+    ///
+    /// A page without a media box, which is required, is given the US Letter size.
+    pub fn get_page_media_box(&self, page_id: ObjectId) -> Result<[f32; 4]> {
+        match self.get_page_attribute(page_id, b"MediaBox") {
+            Ok(media_box) => rectangle(media_box),
+            Err(Error::DictKey) => {
+                warn!("Page {:?} has no MediaBox, using US Letter size", page_id);
+                Ok([0.0, 0.0, 612.0, 792.0])
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the crop box of a page as `[llx, lly, urx, ury]`, inherited if needed.
+    ///
+    /// It defaults to the media box and is clipped to the media box.
+    pub fn get_page_crop_box(&self, page_id: ObjectId) -> Result<[f32; 4]> {
+        let media_box = self.get_page_media_box(page_id)?;
+        let crop_box = match self.get_page_attribute(page_id, b"CropBox") {
+            Ok(crop_box) => rectangle(crop_box)?,
+            Err(Error::DictKey) => return Ok(media_box),
+            Err(err) => return Err(err),
+        };
+        Ok([
+            crop_box[0].max(media_box[0]),
+            crop_box[1].max(media_box[1]),
+            crop_box[2].min(media_box[2]),
+            crop_box[3].min(media_box[3]),
+        ])
+    }
+
+    /// Get the clockwise rotation of a page in degrees, inherited if needed, normalized to 0,
+    /// 90, 180 or 270. It defaults to 0.
+    pub fn get_page_rotation(&self, page_id: ObjectId) -> Result<i64> {
+        match self.get_page_attribute(page_id, b"Rotate") {
+            Ok(rotate) => {
+                let rotate = rotate.as_i64()?;
+                if rotate % 90 != 0 {
+                    return Err(Error::Type);
+                }
+                Ok(rotate.rem_euclid(360))
+            }
+            Err(Error::DictKey) => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the resource dictionary of a page, inherited if needed. A page without resources
+    /// gets an empty dictionary.
+    pub fn get_page_inherited_resources(&self, page_id: ObjectId) -> Result<Dictionary> {
+        match self.get_page_attribute(page_id, b"Resources") {
+            Ok(resources) => resources.as_dict().cloned(),
+            Err(Error::DictKey) => Ok(Dictionary::new()),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Get fonts used by a page.
     pub fn get_page_fonts(&self, page_id: ObjectId) -> BTreeMap<Vec<u8>, &Dictionary> {
         fn collect_fonts_from_resources<'a>(
@@ -491,6 +711,84 @@ fn collect_fonts_from_resources<'a>(
         fonts
     }
 
+    /// Get the images drawn by a page, from the image XObjects of its resources and of the
+    /// form XObjects it uses. Images whose data can't be decoded are skipped.
+    pub fn extract_images(&self, page_id: ObjectId) -> Result<Vec<PdfImage>> {
+        self.get_dictionary(page_id)?;
+
+        let mut images = Vec::new();
+        let mut visited = BTreeSet::new();
+        let (resource_dict, resource_ids) = self.get_page_resources(page_id);
+        if let Some(resources) = resource_dict {
+            self.collect_images(resources, &mut visited, &mut images);
+        }
+        for resource_id in resource_ids {
+            if let Ok(resources) = self.get_dictionary(resource_id) {
+                self.collect_images(resources, &mut visited, &mut images);
+            }
+        }
+        Ok(images)
+    }
+
+    fn collect_images(&self, resources: &Dictionary, visited: &mut BTreeSet<ObjectId>, images: &mut Vec<PdfImage>) {
+        let Ok(xobjects) = self.get_dict_in_dict(resources, b"XObject") else {
+            return;
+        };
+        for (_, xobject) in xobjects.iter() {
+            let Ok(id) = xobject.as_reference() else {
+                continue;
+            };
+            if !visited.insert(id) {
+                continue;
+            }
+            let Ok(stream) = self.get_object(id).and_then(Object::as_stream) else {
+                continue;
+            };
+            match stream.dict.get(b"Subtype").and_then(Object::as_name_str) {
+                Ok("Image") => match self.get_image(id, stream, true) {
+                    Ok(image) => images.push(image),
+                    Err(err) => warn!("Skipping image {:?}: {}", id, err),
+                },
+                Ok("Form") => {
+                    if let Ok(form_resources) = self.get_dict_in_dict(&stream.dict, b"Resources") {
+                        self.collect_images(form_resources, visited, images);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_image(&self, id: ObjectId, stream: &Stream, with_masks: bool) -> Result<PdfImage> {
+        let mut content = stream.image_bytes()?;
+        if let Some(color_space) = &content.color_space {
+            content.color_space = Some(self.dereference(color_space)?.1.clone());
+        }
+        let get_integer = |key: &[u8]| {
+            stream
+                .dict
+                .get(key)
+                .and_then(|value| self.dereference(value))
+                .and_then(|(_, value)| value.as_i64())
+        };
+        // Masks of a mask image are ignored.
+        let get_mask = |key: &[u8]| {
+            let mask_id = stream.dict.get(key).and_then(Object::as_reference).ok()?;
+            let mask = self.get_object(mask_id).and_then(Object::as_stream).ok()?;
+            self.get_image(mask_id, mask, false).ok().map(Box::new)
+        };
+
+        Ok(PdfImage {
+            id,
+            width: get_integer(b"Width")?,
+            height: get_integer(b"Height")?,
+            is_mask: stream.dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false),
+            content,
+            mask: if with_masks { get_mask(b"Mask") } else { None },
+            soft_mask: if with_masks { get_mask(b"SMask") } else { None },
+        })
+    }
+
     /// Get the PDF annotations of a page. The /Subtype of each annotation dictionary defines the
     /// annotation type (Text, Link, Highlight, Underline, Ink, Popup, Widget, etc.). The /Rect of
     /// an annotation dictionary defines its location on the page.
@@ -551,6 +849,24 @@ pub fn encode_text(encoding: Option<&str>, text: &str) -> Vec<u8> {
     }
 }
 
+/// Normalize a rectangle array to `[llx, lly, urx, ury]`.
+pub(crate) fn rectangle(object: &Object) -> Result<[f32; 4]> {
+    let array = object.as_array()?;
+    if array.len() != 4 {
+        return Err(Error::Type);
+    }
+    let mut values = [0.0; 4];
+    for (value, item) in values.iter_mut().zip(array) {
+        *value = item.as_float()?;
+    }
+    Ok([
+        values[0].min(values[2]),
+        values[1].min(values[3]),
+        values[0].max(values[2]),
+        values[1].max(values[3]),
+    ])
+}
+
 impl Default for Document {
     fn default() -> Self {
         Self::new()
@@ -617,14 +933,12 @@ fn next(&mut self) -> Option<Self::Item> {
                             "Page" => {
                                 return Some(kid_id);
                             }
-                            "Pages" => {
-                                if self.stack.len() < Self::PAGE_TREE_DEPTH_LIMIT {
-                                    let kids = self.kids.unwrap();
-                                    if !kids.is_empty() {
-                                        self.stack.push(kids);
-                                    }
-                                    self.kids = Self::kids(self.doc, kid_id);
+                            "Pages" if self.stack.len() < Self::PAGE_TREE_DEPTH_LIMIT => {
+                                let kids = self.kids.unwrap();
+                                if !kids.is_empty() {
+                                    self.stack.push(kids);
                                 }
+                                self.kids = Self::kids(self.doc, kid_id);
                             }
                             _ => {}
                         }
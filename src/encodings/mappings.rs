@@ -1555,3 +1555,263 @@
     Some(Glyph::bracerightbt),
     None,
 ];
+
+/// Encoding of text strings outside of content streams, such as document metadata.
+pub const PDF_DOC_ENCODING: [Option<u16>; 256] = [
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(0x0009),
+    Some(0x000a),
+    None,
+    None,
+    Some(0x000d),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(Glyph::breve),
+    Some(Glyph::caron),
+    Some(Glyph::circumflex),
+    Some(Glyph::dotaccent),
+    Some(Glyph::hungarumlaut),
+    Some(Glyph::ogonek),
+    Some(Glyph::ring),
+    Some(Glyph::tilde),
+    Some(Glyph::space),
+    Some(Glyph::exclam),
+    Some(Glyph::quotedbl),
+    Some(Glyph::numbersign),
+    Some(Glyph::dollar),
+    Some(Glyph::percent),
+    Some(Glyph::ampersand),
+    Some(Glyph::quotesingle),
+    Some(Glyph::parenleft),
+    Some(Glyph::parenright),
+    Some(Glyph::asterisk),
+    Some(Glyph::plus),
+    Some(Glyph::comma),
+    Some(Glyph::hyphen),
+    Some(Glyph::period),
+    Some(Glyph::slash),
+    Some(Glyph::zero),
+    Some(Glyph::one),
+    Some(Glyph::two),
+    Some(Glyph::three),
+    Some(Glyph::four),
+    Some(Glyph::five),
+    Some(Glyph::six),
+    Some(Glyph::seven),
+    Some(Glyph::eight),
+    Some(Glyph::nine),
+    Some(Glyph::colon),
+    Some(Glyph::semicolon),
+    Some(Glyph::less),
+    Some(Glyph::equal),
+    Some(Glyph::greater),
+    Some(Glyph::question),
+    Some(Glyph::at),
+    Some(Glyph::A),
+    Some(Glyph::B),
+    Some(Glyph::C),
+    Some(Glyph::D),
+    Some(Glyph::E),
+    Some(Glyph::F),
+    Some(Glyph::G),
+    Some(Glyph::H),
+    Some(Glyph::I),
+    Some(Glyph::J),
+    Some(Glyph::K),
+    Some(Glyph::L),
+    Some(Glyph::M),
+    Some(Glyph::N),
+    Some(Glyph::O),
+    Some(Glyph::P),
+    Some(Glyph::Q),
+    Some(Glyph::R),
+    Some(Glyph::S),
+    Some(Glyph::T),
+    Some(Glyph::U),
+    Some(Glyph::V),
+    Some(Glyph::W),
+    Some(Glyph::X),
+    Some(Glyph::Y),
+    Some(Glyph::Z),
+    Some(Glyph::bracketleft),
+    Some(Glyph::backslash),
+    Some(Glyph::bracketright),
+    Some(Glyph::asciicircum),
+    Some(Glyph::underscore),
+    Some(Glyph::grave),
+    Some(Glyph::a),
+    Some(Glyph::b),
+    Some(Glyph::c),
+    Some(Glyph::d),
+    Some(Glyph::e),
+    Some(Glyph::f),
+    Some(Glyph::g),
+    Some(Glyph::h),
+    Some(Glyph::i),
+    Some(Glyph::j),
+    Some(Glyph::k),
+    Some(Glyph::l),
+    Some(Glyph::m),
+    Some(Glyph::n),
+    Some(Glyph::o),
+    Some(Glyph::p),
+    Some(Glyph::q),
+    Some(Glyph::r),
+    Some(Glyph::s),
+    Some(Glyph::t),
+    Some(Glyph::u),
+    Some(Glyph::v),
+    Some(Glyph::w),
+    Some(Glyph::x),
+    Some(Glyph::y),
+    Some(Glyph::z),
+    Some(Glyph::braceleft),
+    Some(Glyph::bar),
+    Some(Glyph::braceright),
+    Some(Glyph::asciitilde),
+    None,
+    Some(Glyph::bullet),
+    Some(Glyph::dagger),
+    Some(Glyph::daggerdbl),
+    Some(Glyph::ellipsis),
+    Some(Glyph::emdash),
+    Some(Glyph::endash),
+    Some(Glyph::florin),
+    Some(Glyph::fraction),
+    Some(Glyph::guilsinglleft),
+    Some(Glyph::guilsinglright),
+    Some(Glyph::minus),
+    Some(Glyph::perthousand),
+    Some(Glyph::quotedblbase),
+    Some(Glyph::quotedblleft),
+    Some(Glyph::quotedblright),
+    Some(Glyph::quoteleft),
+    Some(Glyph::quoteright),
+    Some(Glyph::quotesinglbase),
+    Some(Glyph::trademark),
+    Some(Glyph::fi),
+    Some(Glyph::fl),
+    Some(Glyph::Lslash),
+    Some(Glyph::OE),
+    Some(Glyph::Scaron),
+    Some(Glyph::Ydieresis),
+    Some(Glyph::Zcaron),
+    Some(Glyph::dotlessi),
+    Some(Glyph::lslash),
+    Some(Glyph::oe),
+    Some(Glyph::scaron),
+    Some(Glyph::zcaron),
+    None,
+    Some(Glyph::Euro),
+    Some(Glyph::exclamdown),
+    Some(Glyph::cent),
+    Some(Glyph::sterling),
+    Some(Glyph::currency),
+    Some(Glyph::yen),
+    Some(Glyph::brokenbar),
+    Some(Glyph::section),
+    Some(Glyph::dieresis),
+    Some(Glyph::copyright),
+    Some(Glyph::ordfeminine),
+    Some(Glyph::guillemotleft),
+    Some(Glyph::logicalnot),
+    None,
+    Some(Glyph::registered),
+    Some(Glyph::macron),
+    Some(Glyph::degree),
+    Some(Glyph::plusminus),
+    Some(Glyph::twosuperior),
+    Some(Glyph::threesuperior),
+    Some(Glyph::acute),
+    Some(Glyph::mu),
+    Some(Glyph::paragraph),
+    Some(Glyph::periodcentered),
+    Some(Glyph::cedilla),
+    Some(Glyph::onesuperior),
+    Some(Glyph::ordmasculine),
+    Some(Glyph::guillemotright),
+    Some(Glyph::onequarter),
+    Some(Glyph::onehalf),
+    Some(Glyph::threequarters),
+    Some(Glyph::questiondown),
+    Some(Glyph::Agrave),
+    Some(Glyph::Aacute),
+    Some(Glyph::Acircumflex),
+    Some(Glyph::Atilde),
+    Some(Glyph::Adieresis),
+    Some(Glyph::Aring),
+    Some(Glyph::AE),
+    Some(Glyph::Ccedilla),
+    Some(Glyph::Egrave),
+    Some(Glyph::Eacute),
+    Some(Glyph::Ecircumflex),
+    Some(Glyph::Edieresis),
+    Some(Glyph::Igrave),
+    Some(Glyph::Iacute),
+    Some(Glyph::Icircumflex),
+    Some(Glyph::Idieresis),
+    Some(Glyph::Eth),
+    Some(Glyph::Ntilde),
+    Some(Glyph::Ograve),
+    Some(Glyph::Oacute),
+    Some(Glyph::Ocircumflex),
+    Some(Glyph::Otilde),
+    Some(Glyph::Odieresis),
+    Some(Glyph::multiply),
+    Some(Glyph::Oslash),
+    Some(Glyph::Ugrave),
+    Some(Glyph::Uacute),
+    Some(Glyph::Ucircumflex),
+    Some(Glyph::Udieresis),
+    Some(Glyph::Yacute),
+    Some(Glyph::Thorn),
+    Some(Glyph::germandbls),
+    Some(Glyph::agrave),
+    Some(Glyph::aacute),
+    Some(Glyph::acircumflex),
+    Some(Glyph::atilde),
+    Some(Glyph::adieresis),
+    Some(Glyph::aring),
+    Some(Glyph::ae),
+    Some(Glyph::ccedilla),
+    Some(Glyph::egrave),
+    Some(Glyph::eacute),
+    Some(Glyph::ecircumflex),
+    Some(Glyph::edieresis),
+    Some(Glyph::igrave),
+    Some(Glyph::iacute),
+    Some(Glyph::icircumflex),
+    Some(Glyph::idieresis),
+    Some(Glyph::eth),
+    Some(Glyph::ntilde),
+    Some(Glyph::ograve),
+    Some(Glyph::oacute),
+    Some(Glyph::ocircumflex),
+    Some(Glyph::otilde),
+    Some(Glyph::odieresis),
+    Some(Glyph::divide),
+    Some(Glyph::oslash),
+    Some(Glyph::ugrave),
+    Some(Glyph::uacute),
+    Some(Glyph::ucircumflex),
+    Some(Glyph::udieresis),
+    Some(Glyph::yacute),
+    Some(Glyph::thorn),
+    Some(Glyph::ydieresis),
+];
@@ -17,3 +17,51 @@
         .map(|byte| byte as u8)
         .collect()
 }
+
+/// Decode a text string, which is UTF-16BE if it starts with the byte order mark `FE FF`,
+/// UTF-8 if it starts with `EF BB BF` and PDFDocEncoding otherwise.
+///
+/// Bytes that can't be decoded are replaced by U+FFFD.
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(b"\xFE\xFF") {
+        let code_points: Vec<u16> = utf16
+            .chunks(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit.get(1).copied().unwrap_or(0)]))
+            .collect();
+        String::from_utf16_lossy(&code_points)
+    } else if let Some(utf8) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes
+            .iter()
+            .map(|&byte| {
+                PDF_DOC_ENCODING[byte as usize]
+                    .and_then(|code| char::from_u32(code as u32))
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+            })
+            .collect()
+    }
+}
+
+/// Encode a text string as PDFDocEncoding, or UTF-16BE with a byte order mark if the text
+/// contains characters PDFDocEncoding can't represent.
+pub fn encode_text_string(text: &str) -> Vec<u8> {
+    let pdf_doc: Option<Vec<u8>> = text
+        .encode_utf16()
+        .map(|ch| {
+            PDF_DOC_ENCODING
+                .iter()
+                .position(|&code| code == Some(ch))
+                .map(|byte| byte as u8)
+        })
+        .collect();
+    match pdf_doc {
+        // Text starting with þÿ would be read back as UTF-16BE.
+        Some(bytes) if !bytes.starts_with(b"\xFE\xFF") && !bytes.starts_with(b"\xEF\xBB\xBF") => bytes,
+        _ => b"\xFE\xFF"
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect(),
+    }
+}
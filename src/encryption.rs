@@ -1,6 +1,12 @@
 use crate::rc4::Rc4;
-use crate::{Document, Object, ObjectId};
+use crate::{Dictionary, Document, Object, ObjectId, Stream};
+use aes::cipher::block_padding::NoPadding;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+#[cfg(feature = "encrypt")]
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes256};
 use md5::{Digest as _, Md5};
+use sha2::{Sha256, Sha384, Sha512};
 use std::fmt;
 
 #[derive(Debug)]
@@ -18,6 +24,8 @@ pub enum DecryptionError {
 
     NotDecryptable,
     IncorrectPassword,
+    PasswordRequired,
+    InvalidCipherText,
 
     UnsupportedEncryption,
 }
@@ -37,6 +45,8 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             DecryptionError::MissingFileID => write!(f, "missing the file /ID elements"),
             DecryptionError::NotDecryptable => write!(f, "the object is not capable of being decrypted"),
             DecryptionError::IncorrectPassword => write!(f, "the supplied password is incorrect"),
+            DecryptionError::PasswordRequired => write!(f, "the document can only be opened with a user password"),
+            DecryptionError::InvalidCipherText => write!(f, "the encrypted data has an invalid length"),
             DecryptionError::UnsupportedEncryption => {
                 write!(f, "the document uses an encryption scheme that is not supported")
             }
@@ -52,8 +62,425 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 const DEFAULT_KEY_LEN: Object = Object::Integer(40);
 const DEFAULT_ALGORITHM: Object = Object::Integer(0);
 
-/// Generates the encryption key for the document and, if `check_password` is
-///  true, verifies that the key is correct.
+/// Method a crypt filter uses to encrypt strings or streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptMethod {
+    /// The data is not encrypted.
+    Identity,
+    /// RC4 with a key derived for each object.
+    Rc4,
+    /// AES-128 in CBC mode with a key derived for each object (`AESV2`).
+    Aes128,
+    /// AES-256 in CBC mode with the file encryption key (`AESV3`).
+    Aes256,
+}
+
+/// The file encryption key of a document and the methods its strings and streams are encrypted
+/// with, as described by its encryption dictionary.
+#[derive(Debug, Clone)]
+pub struct EncryptionState {
+    /// The file encryption key.
+    pub key: Vec<u8>,
+    /// Method used to encrypt strings.
+    pub string_method: CryptMethod,
+    /// Method used to encrypt streams.
+    pub stream_method: CryptMethod,
+    /// Whether metadata streams are encrypted.
+    pub encrypt_metadata: bool,
+}
+
+/// Options for [`Document::encrypt`] and [`Document::save_encrypted`].
+///
+/// The default options encrypt with AES-128 and empty passwords, so the document can be opened by
+/// anyone and all operations are permitted.
+#[cfg(feature = "encrypt")]
+#[derive(Debug, Clone)]
+pub struct EncryptOptions {
+    /// Password needed to open the document. Anyone can open it if the password is empty.
+    pub user_password: String,
+    /// Password that grants all permissions. When empty, the user password is used.
+    pub owner_password: String,
+    /// The `P` entry of the encryption dictionary, whose bits set the operations permitted when
+    /// the document is opened with the user password, such as printing (bit 3), modifying the
+    /// contents (bit 4) and copying text (bit 5). `-4` permits all operations.
+    pub permissions: i32,
+    /// Method used to encrypt strings and streams. [`CryptMethod::Identity`] is not supported.
+    pub method: CryptMethod,
+}
+
+#[cfg(feature = "encrypt")]
+impl Default for EncryptOptions {
+    fn default() -> Self {
+        EncryptOptions {
+            user_password: String::new(),
+            owner_password: String::new(),
+            permissions: -4,
+            method: CryptMethod::Aes128,
+        }
+    }
+}
+
+impl EncryptionState {
+    /// Create the encryption dictionary of the standard security handler for `options` and the
+    /// first element of the file identifier, and the state to encrypt the document with.
+    ///
+    /// RC4 uses revision 3 with a 128 bit key, AES-128 revision 4 and AES-256 revision 6.
+    #[cfg(feature = "encrypt")]
+    pub fn encode(options: &EncryptOptions, file_id_0: &[u8]) -> crate::Result<(Self, Dictionary)> {
+        let user_password = options.user_password.as_bytes();
+        let owner_password = match options.owner_password.is_empty() {
+            true => user_password,
+            false => options.owner_password.as_bytes(),
+        };
+
+        let mut encryption_dict = Dictionary::new();
+        encryption_dict.set("Filter", Object::Name(b"Standard".to_vec()));
+        encryption_dict.set("P", options.permissions as i64);
+        let (algorithm, revision) = match options.method {
+            CryptMethod::Identity => return Err(DecryptionError::UnsupportedEncryption.into()),
+            CryptMethod::Rc4 => (2, 3),
+            CryptMethod::Aes128 => (4, 4),
+            CryptMethod::Aes256 => (5, 6),
+        };
+        encryption_dict.set("V", algorithm);
+        encryption_dict.set("R", revision);
+        if algorithm >= 4 {
+            let (method, key_len): (&[u8], i64) = match algorithm {
+                4 => (b"AESV2", 16),
+                _ => (b"AESV3", 32),
+            };
+            let mut crypt_filter = Dictionary::new();
+            crypt_filter.set("CFM", Object::Name(method.to_vec()));
+            crypt_filter.set("AuthEvent", Object::Name(b"DocOpen".to_vec()));
+            crypt_filter.set("Length", key_len);
+            let mut crypt_filters = Dictionary::new();
+            crypt_filters.set("StdCF", crypt_filter);
+            encryption_dict.set("CF", crypt_filters);
+            encryption_dict.set("StmF", Object::Name(b"StdCF".to_vec()));
+            encryption_dict.set("StrF", Object::Name(b"StdCF".to_vec()));
+        }
+
+        let key = if revision == 6 {
+            encryption_dict.set("Length", 256);
+            // Algorithms 8, 9 and 10 of ISO 32000-2
+            let password_len = |password: &[u8]| std::cmp::min(password.len(), 127);
+            let user_password = &user_password[..password_len(user_password)];
+            let owner_password = &owner_password[..password_len(owner_password)];
+            let key = random_bytes(32)?;
+
+            let user_salts = random_bytes(16)?;
+            let mut hashed_user_password = hardened_hash(user_password, &user_salts[..8], &[], revision).to_vec();
+            hashed_user_password.extend_from_slice(&user_salts);
+            let user_key = hardened_hash(user_password, &user_salts[8..], &[], revision);
+
+            let owner_salts = random_bytes(16)?;
+            let mut hashed_owner_password =
+                hardened_hash(owner_password, &owner_salts[..8], &hashed_user_password, revision).to_vec();
+            hashed_owner_password.extend_from_slice(&owner_salts);
+            let owner_key = hardened_hash(owner_password, &owner_salts[8..], &hashed_user_password, revision);
+
+            let mut perms = [0; 16];
+            perms[..4].copy_from_slice(&options.permissions.to_le_bytes());
+            perms[4..8].fill(0xFF);
+            perms[8..12].copy_from_slice(b"Tadb");
+            perms[12..].copy_from_slice(&random_bytes(4)?);
+            Aes256::new(key.as_slice().into()).encrypt_block((&mut perms).into());
+
+            encryption_dict.set("UE", Object::string_literal(aes_cbc_encrypt(&user_key, [0; 16], &key)?));
+            encryption_dict.set(
+                "OE",
+                Object::string_literal(aes_cbc_encrypt(&owner_key, [0; 16], &key)?),
+            );
+            encryption_dict.set("U", Object::string_literal(hashed_user_password));
+            encryption_dict.set("O", Object::string_literal(hashed_owner_password));
+            encryption_dict.set("Perms", Object::string_literal(perms.to_vec()));
+            key
+        } else {
+            encryption_dict.set("Length", 128);
+            let hashed_owner_password = compute_owner_password(owner_password, user_password, revision, 16);
+            encryption_dict.set("O", Object::string_literal(hashed_owner_password));
+            let key = compute_encryption_key(&encryption_dict, user_password, revision, 16, file_id_0)?;
+            let hashed_user_password = compute_user_password(&key, revision, file_id_0);
+            encryption_dict.set("U", Object::string_literal(hashed_user_password));
+            key
+        };
+
+        let state = EncryptionState {
+            key,
+            string_method: options.method,
+            stream_method: options.method,
+            encrypt_metadata: true,
+        };
+        Ok((state, encryption_dict))
+    }
+
+    /// Read the encryption dictionary of `doc` and derive the file encryption key from
+    /// `password`, which can be either the user or the owner password.
+    pub fn decode<P: AsRef<[u8]>>(doc: &Document, password: P) -> Result<Self, DecryptionError> {
+        let encryption_dict = doc
+            .get_encrypted()
+            .map_err(|_| DecryptionError::MissingEncryptDictionary)?;
+        if encryption_dict
+            .get(b"Filter")
+            .and_then(Object::as_name)
+            .unwrap_or(b"Standard")
+            != b"Standard"
+        {
+            return Err(DecryptionError::UnsupportedEncryption);
+        }
+
+        let algorithm = encryption_dict
+            .get(b"V")
+            .unwrap_or(&DEFAULT_ALGORITHM)
+            .as_i64()
+            .map_err(|_| DecryptionError::InvalidType)?;
+        let (string_method, stream_method) = match algorithm {
+            1 | 2 => (CryptMethod::Rc4, CryptMethod::Rc4),
+            4 | 5 => (
+                crypt_filter_method(encryption_dict, b"StrF")?,
+                crypt_filter_method(encryption_dict, b"StmF")?,
+            ),
+            _ => return Err(DecryptionError::UnsupportedEncryption),
+        };
+
+        Ok(Self {
+            key: get_encryption_key(doc, password, true)?,
+            string_method,
+            stream_method,
+            encrypt_metadata: encrypt_metadata(encryption_dict),
+        })
+    }
+
+    /// Derive the key of a single object for methods that use one.
+    fn object_key(&self, id: ObjectId, method: CryptMethod) -> Vec<u8> {
+        if method == CryptMethod::Aes256 {
+            return self.key.clone();
+        }
+
+        let mut builder = Vec::<u8>::with_capacity(self.key.len() + 9);
+        builder.extend_from_slice(&self.key);
+        // Extend the key with the lower 3 bytes of the object number
+        builder.extend_from_slice(&id.0.to_le_bytes()[..3]);
+        // and the lower 2 bytes of the generation number
+        builder.extend_from_slice(&id.1.to_le_bytes()[..2]);
+        if method == CryptMethod::Aes128 {
+            builder.extend_from_slice(b"sAlT");
+        }
+
+        let key_len = std::cmp::min(self.key.len() + 5, 16);
+        Md5::digest(builder)[..key_len].to_vec()
+    }
+
+    /// Decrypt the data of a string or stream of the object `id`, encrypted with `method`.
+    pub fn decrypt_bytes(&self, id: ObjectId, method: CryptMethod, data: &[u8]) -> Result<Vec<u8>, DecryptionError> {
+        match method {
+            CryptMethod::Identity => Ok(data.to_vec()),
+            CryptMethod::Rc4 => Ok(Rc4::new(self.object_key(id, method)).decrypt(data)),
+            CryptMethod::Aes128 | CryptMethod::Aes256 => {
+                if data.is_empty() {
+                    return Ok(Vec::new());
+                }
+                // The data starts with the initialization vector and is padded to whole blocks.
+                if data.len() < 32 || !data.len().is_multiple_of(16) {
+                    return Err(DecryptionError::InvalidCipherText);
+                }
+                let (iv, data) = data.split_at(16);
+                let mut plain = aes_cbc_decrypt(&self.object_key(id, method), iv.try_into().unwrap(), data)?;
+                let padding = plain[plain.len() - 1] as usize;
+                let padded = plain[plain.len() - padding.clamp(1, 16)..]
+                    .iter()
+                    .all(|&b| b as usize == padding);
+                if (1..=16).contains(&padding) && padded {
+                    plain.truncate(plain.len() - padding);
+                }
+                Ok(plain)
+            }
+        }
+    }
+
+    /// Encrypt the data of a string or stream of the object `id` with `method`.
+    #[cfg(feature = "encrypt")]
+    pub fn encrypt_bytes(&self, id: ObjectId, method: CryptMethod, data: &[u8]) -> crate::Result<Vec<u8>> {
+        match method {
+            CryptMethod::Identity => Ok(data.to_vec()),
+            CryptMethod::Rc4 => Ok(Rc4::new(self.object_key(id, method)).encrypt(data)),
+            CryptMethod::Aes128 | CryptMethod::Aes256 => {
+                // Pad to whole blocks as described in RFC 8018, a full block is added if needed
+                let padding = 16 - data.len() % 16;
+                let mut padded = Vec::with_capacity(data.len() + padding);
+                padded.extend_from_slice(data);
+                padded.resize(data.len() + padding, padding as u8);
+
+                let iv: [u8; 16] = random_bytes(16)?.try_into().unwrap();
+                let mut encrypted = iv.to_vec();
+                encrypted.extend(aes_cbc_encrypt(&self.object_key(id, method), iv, &padded)?);
+                Ok(encrypted)
+            }
+        }
+    }
+
+    /// Encrypt all strings of an object, and the content if it is a stream.
+    ///
+    /// Like [`EncryptionState::decrypt_object`], streams that use a `Crypt` filter and metadata
+    /// streams of documents that don't encrypt metadata are left as they are.
+    #[cfg(feature = "encrypt")]
+    pub fn encrypt_object(&self, id: ObjectId, obj: &mut Object) -> crate::Result<()> {
+        match obj {
+            Object::String(content, _) => *content = self.encrypt_bytes(id, self.string_method, content)?,
+            Object::Array(array) => {
+                for item in array {
+                    self.encrypt_object(id, item)?;
+                }
+            }
+            Object::Dictionary(dict) => {
+                for (_, value) in dict.iter_mut() {
+                    self.encrypt_object(id, value)?;
+                }
+            }
+            Object::Stream(stream) => {
+                for (_, value) in stream.dict.iter_mut() {
+                    self.encrypt_object(id, value)?;
+                }
+                if self.is_stream_encrypted(stream) {
+                    let content = self.encrypt_bytes(id, self.stream_method, &stream.content)?;
+                    stream.set_content(content);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn is_stream_encrypted(&self, stream: &Stream) -> bool {
+        let has_crypt_filter = stream
+            .filters()
+            .is_ok_and(|filters| filters.iter().any(|filter| filter == "Crypt"));
+        let is_plain_metadata = !self.encrypt_metadata && stream.dict.type_is(b"Metadata");
+        !has_crypt_filter && !is_plain_metadata
+    }
+
+    /// Decrypt all strings of an object, and the content if it is a stream.
+    ///
+    /// Streams that use a `Crypt` filter and metadata streams of documents that don't encrypt
+    /// metadata are left as they are, apart from the strings in their dictionary.
+    pub fn decrypt_object(&self, id: ObjectId, obj: &mut Object) -> Result<(), DecryptionError> {
+        match obj {
+            Object::String(content, _) => *content = self.decrypt_bytes(id, self.string_method, content)?,
+            Object::Array(array) => {
+                for item in array {
+                    self.decrypt_object(id, item)?;
+                }
+            }
+            Object::Dictionary(dict) => {
+                for (_, value) in dict.iter_mut() {
+                    self.decrypt_object(id, value)?;
+                }
+            }
+            Object::Stream(stream) => {
+                for (_, value) in stream.dict.iter_mut() {
+                    self.decrypt_object(id, value)?;
+                }
+                if self.is_stream_encrypted(stream) {
+                    let content = self.decrypt_bytes(id, self.stream_method, &stream.content)?;
+                    stream.set_content(content);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Generate bytes for keys, salts and initialization vectors with the cryptographically secure
+/// random number generator of the operating system.
+#[cfg(feature = "encrypt")]
+pub(crate) fn random_bytes(len: usize) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Encrypt `data`, whose length is a multiple of the block size, with AES in CBC mode. The key
+/// length selects AES-128 or AES-256.
+#[cfg(feature = "encrypt")]
+fn aes_cbc_encrypt(key: &[u8], iv: [u8; 16], data: &[u8]) -> Result<Vec<u8>, DecryptionError> {
+    match key.len() {
+        16 => Ok(cbc::Encryptor::<Aes128>::new(key.into(), &iv.into()).encrypt_padded_vec_mut::<NoPadding>(data)),
+        32 => Ok(cbc::Encryptor::<Aes256>::new(key.into(), &iv.into()).encrypt_padded_vec_mut::<NoPadding>(data)),
+        _ => Err(DecryptionError::InvalidKeyLength),
+    }
+}
+
+/// Decrypt `data`, whose length is a multiple of the block size, with AES in CBC mode. The key
+/// length selects AES-128 or AES-256.
+fn aes_cbc_decrypt(key: &[u8], iv: [u8; 16], data: &[u8]) -> Result<Vec<u8>, DecryptionError> {
+    let plain = match key.len() {
+        16 => cbc::Decryptor::<Aes128>::new(key.into(), &iv.into()).decrypt_padded_vec_mut::<NoPadding>(data),
+        32 => cbc::Decryptor::<Aes256>::new(key.into(), &iv.into()).decrypt_padded_vec_mut::<NoPadding>(data),
+        _ => return Err(DecryptionError::InvalidKeyLength),
+    };
+    plain.map_err(|_| DecryptionError::InvalidCipherText)
+}
+
+/// Find the method of the crypt filter named by `key` in a version 4 or 5 encryption dictionary.
+fn crypt_filter_method(encryption_dict: &Dictionary, key: &[u8]) -> Result<CryptMethod, DecryptionError> {
+    let name = encryption_dict
+        .get(key)
+        .and_then(Object::as_name)
+        .unwrap_or(b"Identity");
+    if name == b"Identity" {
+        return Ok(CryptMethod::Identity);
+    }
+    let method = encryption_dict
+        .get(b"CF")
+        .and_then(Object::as_dict)
+        .and_then(|filters| filters.get(name))
+        .and_then(Object::as_dict)
+        .and_then(|filter| filter.get(b"CFM"))
+        .and_then(Object::as_name)
+        .map_err(|_| DecryptionError::UnsupportedEncryption)?;
+    match method {
+        b"None" => Ok(CryptMethod::Identity),
+        b"V2" => Ok(CryptMethod::Rc4),
+        b"AESV2" => Ok(CryptMethod::Aes128),
+        b"AESV3" => Ok(CryptMethod::Aes256),
+        _ => Err(DecryptionError::UnsupportedEncryption),
+    }
+}
+
+fn encrypt_metadata(encryption_dict: &Dictionary) -> bool {
+    encryption_dict
+        .get(b"EncryptMetadata")
+        .and_then(Object::as_bool)
+        .unwrap_or(true)
+}
+
+/// Get a string of the encryption dictionary that holds at least `len` bytes.
+fn get_bytes<'a>(
+    encryption_dict: &'a Dictionary, key: &[u8], len: usize, missing: DecryptionError,
+) -> Result<&'a [u8], DecryptionError> {
+    let bytes = encryption_dict
+        .get(key)
+        .map_err(|_| missing)?
+        .as_str()
+        .map_err(|_| DecryptionError::InvalidType)?;
+    bytes.get(..len).ok_or(DecryptionError::InvalidType)
+}
+
+/// Pad or truncate a password to 32 bytes.
+fn pad_password(password: &[u8]) -> Vec<u8> {
+    let password_len = std::cmp::min(password.len(), 32);
+    let mut padded = Vec::with_capacity(32);
+    padded.extend_from_slice(&password[0..password_len]);
+    padded.extend_from_slice(&PAD_BYTES[0..32 - password_len]);
+    padded
+}
+
+/// Generates the encryption key for the document from the user or owner password and, if
+/// `check_password` is true, verifies that the key is correct.
+///
+/// The passwords of AES-256 encryption (revisions 5 and 6) are always verified, since the key can
+/// only be found with the correct password.
 pub fn get_encryption_key<P>(doc: &Document, password: P, check_password: bool) -> Result<Vec<u8>, DecryptionError>
 where
     P: AsRef<[u8]>,
@@ -64,27 +491,13 @@ pub fn get_encryption_key<P>(doc: &Document, password: P, check_password: bool)
         .get_encrypted()
         .map_err(|_| DecryptionError::MissingEncryptDictionary)?;
 
-    // Very early versions of PDF assume a key length of 40 bits
-    let key_len = encryption_dict
-        .get(b"Length")
-        .unwrap_or(&DEFAULT_KEY_LEN)
-        .as_i64()
-        .map_err(|_| DecryptionError::InvalidType)? as usize
-        / 8; // Length is in bits, convert to bytes
-
-    // MD5 produces 128bit digests, so key_len must not be greater
-    if key_len > 128 / 8 {
-        return Err(DecryptionError::InvalidKeyLength);
-    }
-
     // Make sure we support the encryption algorithm
     let algorithm = encryption_dict
         .get(b"V")
         .unwrap_or(&DEFAULT_ALGORITHM)
         .as_i64()
         .map_err(|_| DecryptionError::InvalidType)?;
-    // Currently only support V = 1 or 2
-    if !(1..=2).contains(&algorithm) {
+    if ![1, 2, 4, 5].contains(&algorithm) {
         return Err(DecryptionError::UnsupportedEncryption);
     }
 
@@ -94,18 +507,108 @@ pub fn get_encryption_key<P>(doc: &Document, password: P, check_password: bool)
         .map_err(|_| DecryptionError::MissingRevision)?
         .as_i64()
         .map_err(|_| DecryptionError::InvalidType)?;
-    if !(2..=3).contains(&revision) {
+    if !(2..=6).contains(&revision) {
         return Err(DecryptionError::UnsupportedEncryption);
     }
+    if revision >= 5 {
+        return get_aes256_key(encryption_dict, password, revision);
+    }
 
-    // Algorithm 3.2
+    // Very early versions of PDF assume a key length of 40 bits, crypt filters use 128 bits
+    let default_key_len = if algorithm == 4 {
+        Object::Integer(128)
+    } else {
+        DEFAULT_KEY_LEN
+    };
+    let key_len = encryption_dict
+        .get(b"Length")
+        .unwrap_or(&default_key_len)
+        .as_i64()
+        .map_err(|_| DecryptionError::InvalidType)? as usize
+        / 8; // Length is in bits, convert to bytes
 
+    // MD5 produces 128bit digests, so key_len must not be greater
+    if !(5..=128 / 8).contains(&key_len) {
+        return Err(DecryptionError::InvalidKeyLength);
+    }
+
+    let file_id_0 = doc
+        .trailer
+        .get(b"ID")
+        .map_err(|_| DecryptionError::MissingFileID)?
+        .as_array()
+        .map_err(|_| DecryptionError::InvalidType)?
+        .first()
+        .ok_or(DecryptionError::InvalidType)?
+        .as_str()
+        .map_err(|_| DecryptionError::InvalidType)?;
+
+    let key = compute_encryption_key(encryption_dict, password, revision, key_len, file_id_0)?;
+    if !check_password || check_user_password(encryption_dict, &key, revision, file_id_0) {
+        return Ok(key);
+    }
+
+    // Algorithm 3.7: the password may be the owner password, which decrypts the user password
+    let owner_key = compute_owner_key(password, revision, key_len);
+    let hashed_owner_password = get_bytes(encryption_dict, b"O", 32, DecryptionError::MissingOwnerPassword)?;
+    let user_password = if revision == 2 {
+        Rc4::new(&owner_key).decrypt(hashed_owner_password)
+    } else {
+        let mut user_password = hashed_owner_password.to_vec();
+        let mut temp_key = vec![0; key_len];
+        for i in (0..=19).rev() {
+            for (in_byte, out_byte) in owner_key.iter().zip(temp_key.iter_mut()) {
+                *out_byte = in_byte ^ (i as u8);
+            }
+            user_password = Rc4::new(&temp_key).decrypt(user_password);
+        }
+        user_password
+    };
+
+    let key = compute_encryption_key(encryption_dict, &user_password, revision, key_len, file_id_0)?;
+    if check_user_password(encryption_dict, &key, revision, file_id_0) {
+        Ok(key)
+    } else {
+        Err(DecryptionError::IncorrectPassword)
+    }
+}
+
+/// Algorithm 3.3 steps a to d: compute the RC4 key that encrypts the user password in `O`.
+fn compute_owner_key(owner_password: &[u8], revision: i64, key_len: usize) -> Vec<u8> {
+    let mut hash = Md5::digest(pad_password(owner_password));
+    if revision >= 3 {
+        for _ in 0..50 {
+            hash = Md5::digest(hash);
+        }
+    }
+    hash[..key_len].to_vec()
+}
+
+/// Algorithm 3.3: compute the `O` entry from the owner and user passwords.
+#[cfg(feature = "encrypt")]
+fn compute_owner_password(owner_password: &[u8], user_password: &[u8], revision: i64, key_len: usize) -> Vec<u8> {
+    let owner_key = compute_owner_key(owner_password, revision, key_len);
+    let mut hashed_owner_password = Rc4::new(&owner_key).encrypt(pad_password(user_password));
+    if revision >= 3 {
+        let mut temp_key = vec![0; key_len];
+        for i in 1..=19 {
+            for (in_byte, out_byte) in owner_key.iter().zip(temp_key.iter_mut()) {
+                *out_byte = in_byte ^ (i as u8);
+            }
+            hashed_owner_password = Rc4::new(&temp_key).encrypt(hashed_owner_password);
+        }
+    }
+    hashed_owner_password
+}
+
+/// Algorithm 3.2: compute the file encryption key from the user password.
+fn compute_encryption_key(
+    encryption_dict: &Dictionary, password: &[u8], revision: i64, key_len: usize, file_id_0: &[u8],
+) -> Result<Vec<u8>, DecryptionError> {
     // 3.2.1 Start building up the key, starting with the user password plaintext,
     //  padding as needed to 32 bytes
     let mut key = Vec::with_capacity(128);
-    let password_len = std::cmp::min(password.len(), 32);
-    key.extend_from_slice(&password[0..password_len]);
-    key.extend_from_slice(&PAD_BYTES[0..32 - password_len]);
+    key.extend_from_slice(&pad_password(password));
 
     // 3.2.3 Append hashed owner password
     let hashed_owner_password = encryption_dict
@@ -126,20 +629,10 @@ pub fn get_encryption_key<P>(doc: &Document, password: P, check_password: bool)
     key.extend_from_slice(&permissions.to_le_bytes());
 
     // 3.2.5 Append the first element of the file identifier
-    let file_id_0 = doc
-        .trailer
-        .get(b"ID")
-        .map_err(|_| DecryptionError::MissingFileID)?
-        .as_array()
-        .map_err(|_| DecryptionError::InvalidType)?
-        .first()
-        .ok_or(DecryptionError::InvalidType)?
-        .as_str()
-        .map_err(|_| DecryptionError::InvalidType)?;
     key.extend_from_slice(file_id_0);
 
-    // 3.2.6 Revision >=4
-    if revision >= 4 {
+    // 3.2.6 Revision >=4, if metadata is not encrypted
+    if revision >= 4 && !encrypt_metadata(encryption_dict) {
         key.extend_from_slice(&[0xFF_u8, 0xFF, 0xFF, 0xFF]);
     }
 
@@ -151,19 +644,72 @@ pub fn get_encryption_key<P>(doc: &Document, password: P, check_password: bool)
         key.truncate(key_len); // only keep the first key_len bytes
         key.copy_from_slice(&digest[..key_len]);
     }
+    Ok(key)
+}
 
-    // Check that the password is correct
-    if check_password {
-        let check = compute_user_password(&key, revision, file_id_0);
-        if let Ok(Object::String(expected, _)) = encryption_dict.get(b"U") {
-            // Only first 16 bytes are significant, the rest are arbitrary padding
-            if expected[..16] != check[..16] {
-                return Err(DecryptionError::IncorrectPassword);
-            }
+/// Algorithm 3.6: check that a key computed from a password matches the `U` entry.
+fn check_user_password(encryption_dict: &Dictionary, key: &[u8], revision: i64, file_id_0: &[u8]) -> bool {
+    let check = compute_user_password(key, revision, file_id_0);
+    match encryption_dict.get(b"U") {
+        Ok(Object::String(expected, _)) => {
+            // Only first 16 bytes are significant for revision 3 and later, the rest are arbitrary padding
+            let len = if revision == 2 { 32 } else { 16 };
+            expected.get(..len) == check.get(..len)
         }
+        _ => true,
     }
+}
 
-    Ok(key)
+/// Algorithms 2.A and 2.B of ISO 32000-2: find the key of AES-256 encryption from the user or
+/// owner password.
+fn get_aes256_key(encryption_dict: &Dictionary, password: &[u8], revision: i64) -> Result<Vec<u8>, DecryptionError> {
+    // Passwords are limited to 127 bytes of UTF-8
+    let password = &password[..std::cmp::min(password.len(), 127)];
+    let hashed_user_password = get_bytes(encryption_dict, b"U", 48, DecryptionError::InvalidType)?;
+    let hashed_owner_password = get_bytes(encryption_dict, b"O", 48, DecryptionError::MissingOwnerPassword)?;
+
+    let (salt, user_data, encrypted_key) =
+        if hardened_hash(password, &hashed_owner_password[32..40], hashed_user_password, revision)
+            == hashed_owner_password[..32]
+        {
+            let encrypted_key = get_bytes(encryption_dict, b"OE", 32, DecryptionError::InvalidType)?;
+            (&hashed_owner_password[40..48], hashed_user_password, encrypted_key)
+        } else if hardened_hash(password, &hashed_user_password[32..40], &[], revision) == hashed_user_password[..32] {
+            let encrypted_key = get_bytes(encryption_dict, b"UE", 32, DecryptionError::InvalidType)?;
+            (&hashed_user_password[40..48], &[][..], encrypted_key)
+        } else {
+            return Err(DecryptionError::IncorrectPassword);
+        };
+
+    let intermediate_key = hardened_hash(password, salt, user_data, revision);
+    aes_cbc_decrypt(&intermediate_key, [0; 16], encrypted_key)
+}
+
+/// Hash a password with a salt and the user data, using SHA-256 for revision 5 and the iterated
+/// hash of algorithm 2.B for revision 6.
+fn hardened_hash(password: &[u8], salt: &[u8], user_data: &[u8], revision: i64) -> [u8; 32] {
+    let mut hash = Sha256::digest([password, salt, user_data].concat()).to_vec();
+    if revision == 5 {
+        return hash.try_into().unwrap();
+    }
+
+    let mut round = 0;
+    loop {
+        let block = [password, &hash, user_data].concat().repeat(64);
+        let encrypted = cbc::Encryptor::<Aes128>::new(hash[..16].into(), hash[16..32].into())
+            .encrypt_padded_vec_mut::<NoPadding>(&block);
+        let remainder = encrypted[..16].iter().map(|&byte| byte as u32).sum::<u32>() % 3;
+        hash = match remainder {
+            0 => Sha256::digest(&encrypted).to_vec(),
+            1 => Sha384::digest(&encrypted).to_vec(),
+            _ => Sha512::digest(&encrypted).to_vec(),
+        };
+        round += 1;
+        if round >= 64 && encrypted[encrypted.len() - 1] as u32 <= round - 32 {
+            break;
+        }
+    }
+    hash[..32].try_into().unwrap()
 }
 
 fn compute_user_password<K, ID>(key: K, revision: i64, file_id_0: ID) -> Vec<u8>
@@ -266,4 +812,161 @@ fn rc4_works() {
             assert_eq!(plain.as_bytes(), &decrypted[..]);
         }
     }
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn encrypted_document(encryption_dict: Dictionary) -> Document {
+        let mut doc = Document::with_version("1.7");
+        let encrypt_id = doc.add_object(encryption_dict);
+        let file_id: Vec<u8> = (0x10..0x20).collect();
+        doc.trailer.set("Encrypt", encrypt_id);
+        doc.trailer.set(
+            "ID",
+            vec![Object::string_literal(file_id.clone()), Object::string_literal(file_id)],
+        );
+        doc
+    }
+
+    #[test]
+    fn rc4_keys_from_user_and_owner_password() {
+        let doc = encrypted_document(dictionary! {
+            "Filter" => "Standard",
+            "V" => 2,
+            "R" => 3,
+            "Length" => 128,
+            "P" => -3904,
+            "O" => Object::string_literal(hex("0BA3835F88F90388E74E54584125CE142BE0DE24C6B0D37746E075B891756671")),
+            "U" => Object::string_literal(hex("0134EA83382F5F5DAAED65096A5FDBBA00000000000000000000000000000000")),
+        });
+        let key = hex("AAB248F700CAAC15BAB4D2839F7B0761");
+        assert_eq!(get_encryption_key(&doc, "user", true).unwrap(), key);
+        assert_eq!(get_encryption_key(&doc, "owner", true).unwrap(), key);
+        assert!(matches!(
+            get_encryption_key(&doc, "wrong", true),
+            Err(DecryptionError::IncorrectPassword)
+        ));
+
+        let state = EncryptionState::decode(&doc, "owner").unwrap();
+        assert_eq!(state.string_method, CryptMethod::Rc4);
+        let mut string = Object::string_literal(hex("5AE9C8B885"));
+        state.decrypt_object((4, 0), &mut string).unwrap();
+        assert_eq!(string.as_str().unwrap(), b"Hello");
+    }
+
+    #[test]
+    fn rc4_40_bit_key_from_empty_password() {
+        let doc = encrypted_document(dictionary! {
+            "Filter" => "Standard",
+            "V" => 1,
+            "R" => 2,
+            "P" => -4,
+            "O" => Object::string_literal(hex("2055C756C72E1AD702608E8196ACAD447AD32D17CFF583235F6DD15FED7DAB67")),
+            "U" => Object::string_literal(hex("C35480346C0E35764A3AC68C581C06799DE3D05DD9A9A554CAF67536DEE76CC5")),
+        });
+        assert_eq!(get_encryption_key(&doc, "", true).unwrap(), hex("5EFCAA8ECA"));
+    }
+
+    #[test]
+    fn aes_128_decryption() {
+        let doc = encrypted_document(dictionary! {
+            "Filter" => "Standard",
+            "V" => 4,
+            "R" => 4,
+            "Length" => 128,
+            "P" => -1028,
+            "CF" => dictionary! {
+                "StdCF" => dictionary! { "CFM" => "AESV2", "AuthEvent" => "DocOpen", "Length" => 16 },
+            },
+            "StmF" => "StdCF",
+            "StrF" => "StdCF",
+            "O" => Object::string_literal(hex("5588040988E54A8AE991AC9D80AAC232A494D494B4BCC37A4DCC6D07206C9192")),
+            "U" => Object::string_literal(hex("D33A28EB05426784E4F6333FFBEAF06800000000000000000000000000000000")),
+        });
+        let state = EncryptionState::decode(&doc, "").unwrap();
+        assert_eq!(state.key, hex("3380ECC90002CE04107553C3527001A0"));
+        assert_eq!(EncryptionState::decode(&doc, "secret").unwrap().key, state.key);
+        assert_eq!(state.stream_method, CryptMethod::Aes128);
+
+        let mut string =
+            Object::string_literal(hex("000102030405060708090A0B0C0D0E0F853EB9302237FD8CF077F24D8D6B8562"));
+        state.decrypt_object((5, 0), &mut string).unwrap();
+        assert_eq!(string.as_str().unwrap(), b"Hello");
+
+        let mut stream = Object::Stream(Stream::new(
+            dictionary! {},
+            hex(concat!(
+                "000102030405060708090A0B0C0D0E0FC13E1A7F671AADE70A0D996A4048A23C",
+                "15E79A3F50708778131CB72153833665"
+            )),
+        ));
+        state.decrypt_object((6, 0), &mut stream).unwrap();
+        assert_eq!(stream.as_stream().unwrap().content, b"BT /F1 12 Tf (Secret) Tj ET");
+
+        let mut truncated = Object::string_literal(vec![0; 20]);
+        assert!(matches!(
+            state.decrypt_object((5, 0), &mut truncated),
+            Err(DecryptionError::InvalidCipherText)
+        ));
+    }
+
+    #[test]
+    fn aes_256_keys_from_user_and_owner_password() {
+        let file_key: Vec<u8> = (0xA0..0xC0).collect();
+        let mut encryption_dict = dictionary! {
+            "Filter" => "Standard",
+            "V" => 5,
+            "R" => 6,
+            "Length" => 256,
+            "P" => -1028,
+            "CF" => dictionary! {
+                "StdCF" => dictionary! { "CFM" => "AESV3", "AuthEvent" => "DocOpen", "Length" => 32 },
+            },
+            "StmF" => "StdCF",
+            "StrF" => "StdCF",
+            "U" => Object::string_literal(hex(concat!(
+                "273444D38B9BFA2EF7F9EC30B363C84A94E674ABB67E0E8C8CA3479A96551090",
+                "01010101010101010202020202020202"
+            ))),
+            "UE" => Object::string_literal(hex("DFE88C89A8191A7D647AFCDEDF29536CFF3E1AD1AE8F978B0E7F86785DBC8C8F")),
+            "O" => Object::string_literal(hex(concat!(
+                "9F982BE98253F0645861CAC62FB244EAC286CA130F3246B202B4AADCF58D8D0B",
+                "03030303030303030404040404040404"
+            ))),
+            "OE" => Object::string_literal(hex("EAF2326988A97B3C97E715C664E73CB32BD201E2E969BA87729DE2E1BF2BD7A0")),
+        };
+        let doc = encrypted_document(encryption_dict.clone());
+        assert_eq!(get_encryption_key(&doc, "user", true).unwrap(), file_key);
+        assert_eq!(get_encryption_key(&doc, "owner", true).unwrap(), file_key);
+        assert!(matches!(
+            get_encryption_key(&doc, "", true),
+            Err(DecryptionError::IncorrectPassword)
+        ));
+
+        let state = EncryptionState::decode(&doc, "user").unwrap();
+        let mut string =
+            Object::string_literal(hex("000102030405060708090A0B0C0D0E0F0288904806A0EF13E186F79514D45925"));
+        state.decrypt_object((7, 0), &mut string).unwrap();
+        assert_eq!(string.as_str().unwrap(), b"Hello");
+
+        // Revision 5 uses a single SHA-256 hash of the password
+        encryption_dict.set("R", 5);
+        encryption_dict.set(
+            "U",
+            Object::string_literal(hex(concat!(
+                "11F9C78082A5DC812A13967141CD9C043E9D2ACCCB1CC056164AFF673C7AA208",
+                "01010101010101010202020202020202"
+            ))),
+        );
+        encryption_dict.set(
+            "UE",
+            Object::string_literal(hex("B2EF94043079C72E3623DFAB97D154CB18137449598FC6FBCBDD0C863114D4CD")),
+        );
+        let doc = encrypted_document(encryption_dict);
+        assert_eq!(get_encryption_key(&doc, "user", true).unwrap(), file_key);
+    }
 }
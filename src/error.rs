@@ -1,5 +1,6 @@
 use std::fmt;
 use crate::encryption;
+use crate::ObjectId;
 
 #[derive(Debug)]
 pub enum Error {
@@ -19,19 +20,50 @@ pub enum Error {
     Offset(usize),
     /// Page number was not found in document.
     PageNumberNotFound(u32),
+    /// Form field with this fully qualified name was not found in document.
+    FormFieldNotFound(String),
+    /// Optional content group with this name was not found in document.
+    LayerNotFound(String),
     /// Invalid object while parsing at offset.
-    Parse { offset: usize },
+    Parse {
+        offset: usize,
+        /// Id of the indirect object being parsed, if its header could be read.
+        id: Option<ObjectId>,
+        /// Up to 16 bytes of the input around `offset`, starting at most 8 bytes before it.
+        context: Vec<u8>,
+    },
+    /// Object number greater than `u32::MAX` or generation number greater than 65535 of an
+    /// indirect object or a reference at offset.
+    ObjectIdRange {
+        offset: usize,
+        /// Id of the indirect object being parsed, if its header could be read.
+        id: Option<ObjectId>,
+    },
+    /// Operations of a content stream whose operands don't match their operator.
+    InvalidOperands(Vec<crate::content::OperandMismatch>),
     /// Dereferencing object reached the limit.
     /// This might indicate a reference loop.
     ReferenceLimit,
+    /// Dereferencing object led back to an object that was already visited.
+    ReferenceCycle(ObjectId),
     /// Brackets limit reached.
     /// To many brackets nested.
     // TODO: This does not seem to be used.
     BracketLimit,
     /// The file trailer was invalid.
     Trailer,
-    /// The object does not have the expected type.
+    /// The object does not have the expected type. The `Object` accessors report
+    /// [`Error::ObjectType`] instead.
     Type,
+    /// The object is of another kind than the accessor expects, for example `as_i64` on a Name.
+    ObjectType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The stream is encoded with a filter that is not supported.
+    UnsupportedFilter(String),
+    /// The color space family is not supported.
+    UnsupportedColorSpace(String),
     /// Decoding byte vector to UTF8 String failed.
     UTF8,
     /// Syntax error while parsing the file.
@@ -42,11 +74,15 @@ pub enum Error {
     Invalid(String),
     /// PDF document has no Outlines.
     NoOutlines,
+    /// The font file is invalid or not supported.
+    InvalidFont(String),
     /// Error when handling images.
     #[cfg(feature = "embed_image")]
     Image(image::ImageError),
     /// Error when decrypting the contents of the file
     Decryption(encryption::DecryptionError),
+    /// The document is already encrypted.
+    AlreadyEncrypted,
 }
 
 impl fmt::Display for Error {
@@ -60,25 +96,95 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             Error::ObjectNotFound => write!(f, "A required object was not found"),
             Error::Offset(o) => write!(f, "Invalid file offset: {}", o),
             Error::PageNumberNotFound(p) => write!(f, "Page number {} could not be found", p),
-            Error::Parse { offset, .. } => write!(f, "Invalid object at byte {}", offset),
+            Error::FormFieldNotFound(name) => write!(f, "Form field {} could not be found", name),
+            Error::LayerNotFound(name) => write!(f, "Layer {} could not be found", name),
+            Error::Parse { offset, id, context } => {
+                write!(f, "Invalid object")?;
+                if let Some(id) = id {
+                    write!(f, " {} {}", id.0, id.1)?;
+                }
+                write!(f, " at byte {}", offset)?;
+                if !context.is_empty() {
+                    // The offset is marked by a space between the bytes before and after it.
+                    let (before, after) = context.split_at((*offset).min(CONTEXT_BEFORE).min(context.len()));
+                    write!(f, " near ")?;
+                    for b in before {
+                        write!(f, "{:02x}", b)?;
+                    }
+                    write!(f, " ")?;
+                    for b in after {
+                        write!(f, "{:02x}", b)?;
+                    }
+                }
+                Ok(())
+            }
+            Error::ObjectIdRange { offset, id } => {
+                write!(f, "Object or generation number out of range at byte {}", offset)?;
+                if let Some(id) = id {
+                    write!(f, " in object {} {}", id.0, id.1)?;
+                }
+                Ok(())
+            }
+            Error::InvalidOperands(mismatches) => {
+                write!(f, "Invalid operands in content:")?;
+                for mismatch in mismatches {
+                    write!(f, " {};", mismatch)?;
+                }
+                Ok(())
+            }
             Error::ReferenceLimit => write!(f, "Could not dereference an object; possible reference loop"),
+            Error::ReferenceCycle(id) => write!(f, "Reference cycle through object {} {} R", id.0, id.1),
             Error::BracketLimit => write!(f, "Too deep embedding of ()'s."),
             Error::Trailer => write!(f, "Invalid file trailer"),
             Error::Type => write!(f, "An object does not have the expected type"),
+            Error::ObjectType { expected, found } => {
+                write!(f, "Expected an object of type {}, found {}", expected, found)
+            }
+            Error::UnsupportedFilter(filter) => write!(f, "Unsupported stream filter: {}", filter),
+            Error::UnsupportedColorSpace(name) => write!(f, "Color space {} is not supported", name),
             Error::UTF8 => write!(f, "UTF-8 error"),
             Error::Syntax(msg) => write!(f, "Syntax error: {}", msg),
             Error::Xref(e) => write!(f, "Invalid cross-reference table ({})", e),
             Error::Invalid(msg) => write!(f, "Invalid command: {}", msg),
             Error::NoOutlines => write!(f, "PDF document has no Outlines"),
+            Error::InvalidFont(msg) => write!(f, "Invalid font: {}", msg),
             #[cfg(feature = "embed_image")]
             Error::Image(e) => e.fmt(f),
             Error::Decryption(d) => d.fmt(f),
+            Error::AlreadyEncrypted => write!(f, "The document is already encrypted"),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// Number of bytes before the offset of an [`Error::Parse`] kept as its context.
+const CONTEXT_BEFORE: usize = 8;
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+impl Error {
+    /// Parse error at `offset` of `input`, with the bytes around it as context.
+    pub(crate) fn parse(input: &[u8], offset: usize, id: Option<ObjectId>) -> Error {
+        let start = offset.saturating_sub(CONTEXT_BEFORE).min(input.len());
+        let end = offset.saturating_add(CONTEXT_BEFORE).min(input.len());
+        Error::Parse {
+            offset,
+            id,
+            context: input[start..end].to_vec(),
+        }
+    }
+
+    /// Error of the indirect object whose header or body at `offset` of `input` failed to
+    /// parse, an [`Error::ObjectIdRange`] if it has an out-of-range object id, otherwise an
+    /// [`Error::Parse`].
+    pub(crate) fn parse_object(input: &[u8], offset: usize, id: Option<ObjectId>) -> Error {
+        match crate::parser_aux::find_object_id_overflow(input, offset) {
+            Some(offset) => Error::ObjectIdRange { offset, id },
+            None => Error::parse(input, offset, id),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum XrefError {
     /// Could not parse cross reference table.
@@ -0,0 +1,119 @@
+//! The base-85 encoding of the `ASCII85Decode` filter.
+//!
+//! Every group of four bytes is written as five characters from `!` to `u`, a group of four zero
+//! bytes may be abbreviated to `z`, and the data ends with the `~>` end-of-data marker.
+use std::io::{Error, ErrorKind, Result};
+
+/// Number of characters written per line.
+const LINE_LENGTH: usize = 75;
+
+pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+    let input = input.strip_prefix(b"<~").unwrap_or(input);
+    let mut output = Vec::with_capacity(input.len() * 4 / 5);
+    let mut group = [0_u8; 5];
+    let mut len = 0;
+
+    for &byte in input {
+        match byte {
+            b'~' => break,
+            b'z' if len == 0 => output.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[len] = byte - b'!';
+                len += 1;
+                if len == 5 {
+                    output.extend_from_slice(&decode_group(&group)?);
+                    len = 0;
+                }
+            }
+            _ if byte.is_ascii_whitespace() || byte == 0 => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid ASCII85 character ({:#04x})", byte),
+                ));
+            }
+        }
+    }
+
+    match len {
+        0 => {}
+        1 => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "ASCII85 data ends with a single character",
+            ))
+        }
+        _ => {
+            // A final partial group is padded with the highest digit and truncated after decoding.
+            group[len..].fill(b'u' - b'!');
+            output.extend_from_slice(&decode_group(&group)?[..len - 1]);
+        }
+    }
+    Ok(output)
+}
+
+fn decode_group(group: &[u8; 5]) -> Result<[u8; 4]> {
+    let value = group.iter().fold(0_u64, |value, &digit| value * 85 + u64::from(digit));
+    u32::try_from(value)
+        .map(u32::to_be_bytes)
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "ASCII85 group exceeds 32 bits"))
+}
+
+pub fn encode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 4 + input.len() / 60 + 3);
+    let mut line_start = 0;
+
+    for group in input.chunks(4) {
+        if group.len() == 4 && group == [0; 4] {
+            output.push(b'z');
+        } else {
+            let mut bytes = [0; 4];
+            bytes[..group.len()].copy_from_slice(group);
+            let mut value = u32::from_be_bytes(bytes);
+            let mut digits = [0; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = (value % 85) as u8 + b'!';
+                value /= 85;
+            }
+            // A final partial group of n bytes is written as its first n + 1 characters.
+            output.extend_from_slice(&digits[..group.len() + 1]);
+        }
+        if output.len() - line_start >= LINE_LENGTH {
+            output.push(b'\n');
+            line_start = output.len();
+        }
+    }
+    output.extend_from_slice(b"~>");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_zero_group_shortcut() {
+        assert_eq!(decode(b"z87cURz~>").unwrap(), b"\0\0\0\0Hell\0\0\0\0");
+        // `z` is only valid between groups.
+        assert!(decode(b"87z~>").is_err());
+    }
+
+    #[test]
+    fn decode_with_whitespace_and_partial_group() {
+        assert_eq!(decode(b"<~87c\nUR DZ ~>").unwrap(), b"Hello");
+        assert_eq!(decode(b"87cURD]j7BEbo7~>garbage").unwrap(), b"Hello world");
+        assert!(decode(b"s8W-\"~>").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut data = vec![0; 8];
+        data.extend((0..=255).cycle().take(301));
+        let encoded = encode(&data);
+        assert!(encoded.starts_with(b"zz!!*-'"));
+        assert!(encoded.ends_with(b"~>"));
+        assert!(encoded.split(|&b| b == b'\n').all(|line| line.len() <= LINE_LENGTH + 4));
+        assert_eq!(decode(&encoded).unwrap(), data);
+        assert_eq!(encode(b""), b"~>");
+    }
+}
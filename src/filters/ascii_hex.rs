@@ -0,0 +1,73 @@
+//! The hexadecimal encoding of the `ASCIIHexDecode` filter, which ends with the `>` end-of-data marker.
+use std::io::{Error, ErrorKind, Result};
+
+/// Number of hexadecimal digits written per line.
+const LINE_LENGTH: usize = 64;
+
+pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() / 2);
+    let mut high = None;
+
+    for &byte in input {
+        let digit = match byte {
+            b'>' => break,
+            b'0'..=b'9' => byte - b'0',
+            b'a'..=b'f' => byte - b'a' + 10,
+            b'A'..=b'F' => byte - b'A' + 10,
+            _ if byte.is_ascii_whitespace() || byte == 0 => continue,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid ASCIIHex character ({:#04x})", byte),
+                ));
+            }
+        };
+        match high.take() {
+            Some(high) => output.push(high << 4 | digit),
+            None => high = Some(digit),
+        }
+    }
+
+    // An odd final digit is followed by an implicit zero.
+    if let Some(high) = high {
+        output.push(high << 4);
+    }
+    Ok(output)
+}
+
+pub fn encode(input: &[u8]) -> Vec<u8> {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut output = Vec::with_capacity(input.len() * 2 + input.len() / (LINE_LENGTH / 2) + 1);
+    for (i, byte) in input.iter().enumerate() {
+        if i > 0 && i % (LINE_LENGTH / 2) == 0 {
+            output.push(b'\n');
+        }
+        output.push(DIGITS[(byte >> 4) as usize]);
+        output.push(DIGITS[(byte & 0xF) as usize]);
+    }
+    output.push(b'>');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_with_whitespace_and_end_marker() {
+        assert_eq!(decode(b"48 65\n6c6C\r\n6F>20").unwrap(), b"Hello");
+        assert_eq!(decode(b"7>").unwrap(), [0x70]);
+        assert!(decode(b"4G>").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let encoded = encode(&data);
+        assert!(encoded.starts_with(b"000102"));
+        assert!(encoded.ends_with(b"FEFF>"));
+        assert!(encoded.split(|&b| b == b'\n').all(|line| line.len() <= LINE_LENGTH + 1));
+        assert_eq!(decode(&encoded).unwrap(), data);
+    }
+}
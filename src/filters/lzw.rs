@@ -0,0 +1,60 @@
+//! The LZW compression of the `LZWDecode` filter.
+//!
+//! Codes start at 9 bits and grow up to 12 bits. With `early_change` set, which is the default of
+//! the `EarlyChange` parameter, the code length grows one code earlier than strictly necessary.
+use log::warn;
+use weezl::{decode, encode, BitOrder};
+
+const MIN_CODE_SIZE: u8 = 8;
+
+pub fn decode(input: &[u8], early_change: bool) -> Vec<u8> {
+    let mut decoder = if early_change {
+        decode::Decoder::with_tiff_size_switch(BitOrder::Msb, MIN_CODE_SIZE)
+    } else {
+        decode::Decoder::new(BitOrder::Msb, MIN_CODE_SIZE)
+    };
+
+    let mut output = vec![];
+    let result = decoder.into_stream(&mut output).decode_all(input);
+    if let Err(err) = result.status {
+        warn!("{}", err);
+    }
+    output
+}
+
+pub fn encode(input: &[u8], early_change: bool) -> Vec<u8> {
+    let mut encoder = if early_change {
+        encode::Encoder::with_tiff_size_switch(BitOrder::Msb, MIN_CODE_SIZE)
+    } else {
+        encode::Encoder::new(BitOrder::Msb, MIN_CODE_SIZE)
+    };
+
+    let mut output = vec![];
+    let result = encoder.into_stream(&mut output).encode_all(input);
+    if let Err(err) = result.status {
+        warn!("{}", err);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_specification_example() {
+        // Example of section 7.4.4.2 of the PDF specification, with codes 256 45 45 45 45 65 45 45 45 66 257.
+        let encoded = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(decode(&encoded, true), b"-----A---B");
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..5000u32).map(|i| (i * i % 251) as u8).collect();
+        for early_change in [true, false] {
+            let encoded = encode(&data, early_change);
+            assert!(encoded.len() < data.len());
+            assert_eq!(decode(&encoded, early_change), data);
+        }
+    }
+}
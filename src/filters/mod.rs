@@ -1 +1,6 @@
+pub mod ascii85;
+pub mod ascii_hex;
+pub mod lzw;
 pub mod png;
+pub mod run_length;
+pub mod tiff;
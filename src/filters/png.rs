@@ -46,6 +46,10 @@ fn paeth_predict(left: u8, above: u8, upperleft: u8) -> u8 {
     }
 }
 
+fn average(left: u8, above: u8) -> u8 {
+    ((u16::from(left) + u16::from(above)) / 2) as u8
+}
+
 pub fn decode_row(filter: FilterType, bpp: usize, previous: &[u8], current: &mut [u8]) {
     use self::FilterType::*;
     let len = current.len();
@@ -69,7 +73,7 @@ pub fn decode_row(filter: FilterType, bpp: usize, previous: &[u8], current: &mut
             }
 
             for i in bpp..len {
-                current[i] = current[i].wrapping_add((i16::from(current[i - bpp]) + i16::from(previous[i]) / 2) as u8);
+                current[i] = current[i].wrapping_add(average(current[i - bpp], previous[i]));
             }
         }
         Paeth => {
@@ -85,7 +89,12 @@ pub fn decode_row(filter: FilterType, bpp: usize, previous: &[u8], current: &mut
 }
 
 pub fn decode_frame(content: &[u8], bytes_per_pixel: usize, pixels_per_row: usize) -> Result<Vec<u8>> {
-    let bytes_per_row = bytes_per_pixel * pixels_per_row;
+    decode_rows(content, bytes_per_pixel, bytes_per_pixel * pixels_per_row)
+}
+
+/// Like [`decode_frame`], for rows whose length is not a whole number of pixels,
+/// as happens with components of less than 8 bits.
+pub fn decode_rows(content: &[u8], bytes_per_pixel: usize, bytes_per_row: usize) -> Result<Vec<u8>> {
     let mut previous = vec![0_u8; bytes_per_row];
     let mut current = vec![0_u8; bytes_per_row];
     let mut decoded = Vec::new();
@@ -128,7 +137,7 @@ pub fn encode_row(method: FilterType, bpp: usize, previous: &[u8], current: &mut
         }
         Avg => {
             for i in (bpp..len).rev() {
-                current[i] = current[i].wrapping_sub(current[i - bpp].wrapping_add(previous[i]) / 2);
+                current[i] = current[i].wrapping_sub(average(current[i - bpp], previous[i]));
             }
 
             for i in 0..bpp {
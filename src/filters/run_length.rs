@@ -0,0 +1,104 @@
+//! The byte-oriented run-length encoding of the `RunLengthDecode` filter.
+//!
+//! Each run starts with a length byte. A length of 0 to 127 is followed by that many plus one
+//! literal bytes, a length of 129 to 255 by a single byte that is repeated 257 minus length times,
+//! and a length of 128 marks the end of the data.
+use log::warn;
+
+const END_OF_DATA: u8 = 128;
+
+/// Longest run that fits into a single length byte.
+const MAX_RUN: usize = 128;
+
+pub fn decode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() * 2);
+    let mut pos = 0;
+    while let Some(&length) = input.get(pos) {
+        pos += 1;
+        match length {
+            END_OF_DATA => return output,
+            0..=127 => {
+                let end = pos + length as usize + 1;
+                if end > input.len() {
+                    warn!(
+                        "RunLengthDecode literal run of {} bytes is truncated",
+                        length as usize + 1
+                    );
+                }
+                output.extend_from_slice(&input[pos..end.min(input.len())]);
+                pos = end;
+            }
+            _ => match input.get(pos) {
+                Some(&byte) => {
+                    output.resize(output.len() + 257 - length as usize, byte);
+                    pos += 1;
+                }
+                None => warn!("RunLengthDecode repeat run is missing its byte"),
+            },
+        }
+    }
+    warn!("RunLengthDecode data has no end-of-data marker");
+    output
+}
+
+pub fn encode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() + input.len() / MAX_RUN + 2);
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos < input.len() {
+        let byte = input[pos];
+        let run = input[pos..].iter().take(MAX_RUN).take_while(|&&b| b == byte).count();
+        // A run of two bytes only pays off when it doesn't interrupt a literal run.
+        if run > 2 || (run == 2 && literal_start == pos) {
+            encode_literals(&mut output, &input[literal_start..pos]);
+            output.push((257 - run) as u8);
+            output.push(byte);
+            pos += run;
+            literal_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    encode_literals(&mut output, &input[literal_start..]);
+    output.push(END_OF_DATA);
+    output
+}
+
+fn encode_literals(output: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_RUN) {
+        output.push((chunk.len() - 1) as u8);
+        output.extend_from_slice(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_stops_at_end_of_data() {
+        let encoded = [2, b'a', b'b', b'c', 254, b'x', END_OF_DATA, 0, b'z'];
+        assert_eq!(decode(&encoded), b"abcxxx");
+    }
+
+    #[test]
+    fn decode_final_partial_run() {
+        // The data ends in the middle of a literal run, without an end-of-data marker.
+        assert_eq!(decode(&[255, b'-', 4, b'a', b'b']), b"--ab");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut data = b"abcccccd".to_vec();
+        data.extend([0; 300]);
+        data.extend((0..=255).cycle().take(400));
+        data.extend(b"ee");
+
+        let encoded = encode(&data);
+        assert_eq!(encoded.last(), Some(&END_OF_DATA));
+        assert!(encoded.len() < data.len());
+        assert_eq!(decode(&encoded), data);
+        assert_eq!(encode(b""), [END_OF_DATA]);
+        assert_eq!(encode(b"ee"), [255, b'e', END_OF_DATA]);
+    }
+}
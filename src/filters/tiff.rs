@@ -0,0 +1,79 @@
+//! Horizontal differencing of TIFF predictor 2, where each component is stored as the difference
+//! to the same component of the pixel to its left.
+
+/// Reverse the prediction of each row, with components of 1, 2, 4, 8 or 16 bits.
+pub fn decode_frame(content: &[u8], bits_per_component: usize, colors: usize, columns: usize) -> Vec<u8> {
+    let mut decoded = content.to_vec();
+    let bytes_per_row = (bits_per_component * colors * columns).div_ceil(8);
+    if bytes_per_row == 0 {
+        return decoded;
+    }
+    for row in decoded.chunks_mut(bytes_per_row) {
+        match bits_per_component {
+            8 => {
+                for i in colors..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - colors]);
+                }
+            }
+            16 => {
+                for i in (colors * 2..row.len() - row.len() % 2).step_by(2) {
+                    let left = u16::from_be_bytes([row[i - colors * 2], row[i - colors * 2 + 1]]);
+                    let value = u16::from_be_bytes([row[i], row[i + 1]]).wrapping_add(left);
+                    row[i..i + 2].copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            1 | 2 | 4 => {
+                let samples = (row.len() * 8 / bits_per_component).min(colors * columns);
+                let mask = (1 << bits_per_component) - 1;
+                for i in colors..samples {
+                    let left = sample(row, bits_per_component, i - colors);
+                    let value = (sample(row, bits_per_component, i) + left) & mask;
+                    set_sample(row, bits_per_component, i, value);
+                }
+            }
+            _ => {}
+        }
+    }
+    decoded
+}
+
+fn sample(row: &[u8], bits: usize, index: usize) -> u8 {
+    let bit = index * bits;
+    let shift = 8 - bits - bit % 8;
+    (row[bit / 8] >> shift) & ((1 << bits) - 1)
+}
+
+fn set_sample(row: &mut [u8], bits: usize, index: usize, value: u8) {
+    let bit = index * bits;
+    let shift = 8 - bits - bit % 8;
+    let mask = ((1 << bits) - 1) << shift;
+    row[bit / 8] = (row[bit / 8] & !mask) | (value << shift);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_8_and_16_bits() {
+        assert_eq!(
+            decode_frame(&[10, 20, 30, 5, 5, 251, 1, 2, 3, 1, 1, 1], 8, 3, 2),
+            [10, 20, 30, 15, 25, 25, 1, 2, 3, 2, 3, 4]
+        );
+        assert_eq!(
+            decode_frame(&[0x01, 0xFF, 0x00, 0x02], 16, 1, 2),
+            [0x01, 0xFF, 0x02, 0x01]
+        );
+    }
+
+    #[test]
+    fn decode_sub_byte_components() {
+        // Four 2-bit samples per byte: 1 1 1 1 decodes to 1 2 3 0.
+        assert_eq!(decode_frame(&[0b01010101], 2, 1, 4), [0b01101100]);
+        // Rows are decoded separately, each starting at a byte boundary.
+        assert_eq!(
+            decode_frame(&[0b11100000, 0b01100000], 1, 1, 3),
+            [0b10100000, 0b01000000]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{Dictionary, Document, Object, ObjectId};
+
+/// Font program embedded in a document.
+#[derive(Debug, Clone)]
+pub struct EmbeddedFont {
+    /// Id of the font dictionary.
+    pub font_id: ObjectId,
+    pub base_font: String,
+    /// Subtype of the font dictionary, or of the descendant font of a `Type0` font.
+    pub subtype: String,
+    /// Format of the font program: `Type1` for `FontFile`, `TrueType` for `FontFile2`, and for
+    /// `FontFile3` the subtype of the stream, such as `Type1C`, `CIDFontType0C` or `OpenType`.
+    pub format: String,
+    /// Decoded font program.
+    pub data: Vec<u8>,
+}
+
+impl Document {
+    /// Get the font programs embedded in the document, one for each font dictionary that embeds
+    /// one. Descendant fonts are reported through their `Type0` font.
+    pub fn embedded_fonts(&self) -> Vec<EmbeddedFont> {
+        let mut fonts = vec![];
+        for (&font_id, object) in &self.objects {
+            let Ok(font) = object.as_dict() else {
+                continue;
+            };
+            if font.get(b"Type").and_then(Object::as_name_str).ok() != Some("Font") {
+                continue;
+            }
+            let descriptor_font = match font.get(b"Subtype").and_then(Object::as_name_str) {
+                Ok("CIDFontType0") | Ok("CIDFontType2") => continue,
+                Ok("Type0") => {
+                    let descendant = font
+                        .get_deref(b"DescendantFonts", self)
+                        .and_then(Object::as_array)
+                        .ok()
+                        .and_then(|descendants| descendants.first())
+                        .and_then(|descendant| self.dereference(descendant).ok())
+                        .and_then(|(_, descendant)| descendant.as_dict().ok());
+                    match descendant {
+                        Some(descendant) => descendant,
+                        None => continue,
+                    }
+                }
+                _ => font,
+            };
+            if let Some(embedded) = self.embedded_font(font_id, font, descriptor_font) {
+                fonts.push(embedded);
+            }
+        }
+        fonts
+    }
+
+    fn embedded_font(
+        &self, font_id: ObjectId, font: &Dictionary, descriptor_font: &Dictionary,
+    ) -> Option<EmbeddedFont> {
+        let descriptor = descriptor_font
+            .get_deref(b"FontDescriptor", self)
+            .and_then(Object::as_dict)
+            .ok()?;
+        let (stream, format) = [
+            (b"FontFile".as_slice(), "Type1"),
+            (b"FontFile2", "TrueType"),
+            (b"FontFile3", ""),
+        ]
+        .into_iter()
+        .find_map(|(key, format)| {
+            let stream = descriptor.get_deref(key, self).and_then(Object::as_stream).ok()?;
+            let format = match format {
+                "" => stream
+                    .dict
+                    .get(b"Subtype")
+                    .and_then(Object::as_name_str)
+                    .unwrap_or("Unknown"),
+                format => format,
+            };
+            Some((stream, format))
+        })?;
+        Some(EmbeddedFont {
+            font_id,
+            base_font: font
+                .get(b"BaseFont")
+                .and_then(Object::as_name_str)
+                .unwrap_or_default()
+                .to_string(),
+            subtype: descriptor_font
+                .get(b"Subtype")
+                .and_then(Object::as_name_str)
+                .unwrap_or_default()
+                .to_string(),
+            format: format.to_string(),
+            data: stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::truetype::tests::test_font;
+    use crate::{Document, Stream};
+
+    #[test]
+    fn embedded_truetype_font() {
+        let data = test_font();
+        let mut doc = Document::with_version("1.5");
+        let font_id = doc.add_truetype_font(&data).unwrap();
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog" });
+        doc.trailer.set("Root", catalog_id);
+        // A standard font embeds no program and is not reported.
+        doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        doc.compress();
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let doc = Document::load_mem(&buffer).unwrap();
+        let fonts = doc.embedded_fonts();
+        assert_eq!(fonts.len(), 1);
+        assert_eq!(fonts[0].font_id, font_id);
+        assert_eq!(fonts[0].base_font, "TestSans-Bold");
+        assert_eq!(fonts[0].subtype, "TrueType");
+        assert_eq!(fonts[0].format, "TrueType");
+        assert_eq!(fonts[0].data, data);
+    }
+
+    #[test]
+    fn embedded_cff_font_of_type0_font() {
+        let mut doc = Document::with_version("1.5");
+        let font_file_id = doc.add_object(Stream::new(
+            dictionary! { "Subtype" => "CIDFontType0C" },
+            b"CFF".to_vec(),
+        ));
+        let descriptor_id = doc.add_object(dictionary! { "Type" => "FontDescriptor", "FontFile3" => font_file_id });
+        let descendant_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType0",
+            "BaseFont" => "Example",
+            "FontDescriptor" => descriptor_id,
+        });
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "Example-Identity-H",
+            "DescendantFonts" => vec![descendant_id.into()],
+        });
+
+        let fonts = doc.embedded_fonts();
+        assert_eq!(fonts.len(), 1);
+        assert_eq!(fonts[0].font_id, font_id);
+        assert_eq!(fonts[0].base_font, "Example-Identity-H");
+        assert_eq!(
+            (fonts[0].subtype.as_str(), fonts[0].format.as_str()),
+            ("CIDFontType0", "CIDFontType0C")
+        );
+        assert_eq!(fonts[0].data, b"CFF");
+    }
+}
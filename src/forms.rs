@@ -0,0 +1,486 @@
+use log::warn;
+
+use crate::document::rectangle;
+use crate::{Document, Error, Object, ObjectId, Result};
+
+/// Space between the border of a text field and its text.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+const TEXT_FIELD_PADDING: f32 = 2.0;
+
+/// Font size of text fields whose default appearance has an automatic font size of 0.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+const AUTO_FONT_SIZE: f32 = 12.0;
+
+/// Terminal field of an interactive form, see [`Document::form_fields`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    /// Id of the field dictionary.
+    pub id: ObjectId,
+    /// Fully qualified name, joining the partial names of the field and its ancestors with `.`.
+    pub name: String,
+    /// Field type `FT`, which may be inherited: `Tx`, `Btn`, `Ch` or `Sig`.
+    pub field_type: Option<String>,
+    /// Value `V`, which may be inherited, decoded as a text string, or the name of the state of a
+    /// button. For a choice field with several selected options, the first one.
+    pub value: Option<String>,
+    /// Locations of the widget annotations of the field, as `[llx, lly, urx, ury]`.
+    pub widget_rects: Vec<[f32; 4]>,
+}
+
+impl Document {
+    /// Get the terminal fields of the interactive form, those that hold a value, in the order
+    /// of the `Fields` tree of the catalog's `AcroForm` dictionary.
+    ///
+    /// Returns no fields if the document has no interactive form.
+    pub fn form_fields(&self) -> Result<Vec<FormField>> {
+        let fields = match self
+            .catalog()?
+            .get_deref(b"AcroForm", self)
+            .and_then(Object::as_dict)
+            .and_then(|form| form.get_deref(b"Fields", self))
+        {
+            Ok(fields) => fields.as_array()?,
+            Err(_) => return Ok(vec![]),
+        };
+        let mut form_fields = vec![];
+        let mut visited = vec![];
+        for field in fields {
+            self.collect_form_fields(field, &mut form_fields, &mut visited);
+        }
+        Ok(form_fields)
+    }
+
+    /// Set the value of the terminal form field with the fully qualified `name`.
+    ///
+    /// For text and choice fields, `V` is set to `value`. For check boxes and radio buttons,
+    /// `value` is the name of the state to select, such as `Yes` or `Off`: it becomes `V`, and the
+    /// appearance state `AS` of each widget becomes `value` if the widget has an appearance for it
+    /// and `Off` otherwise. The `NeedAppearances` entry of the `AcroForm` dictionary is set so
+    /// viewers regenerate the appearances of the fields.
+    pub fn set_form_value(&mut self, name: &str, value: &str) -> Result<()> {
+        let field = self.form_field(name)?;
+        match field.field_type.as_deref() {
+            Some("Tx") | Some("Ch") => {
+                self.get_dictionary_mut(field.id)?.set("V", Object::text_string(value));
+            }
+            Some("Btn") => {
+                let state = Object::Name(value.as_bytes().to_vec());
+                for widget_id in self.field_widgets(field.id)? {
+                    let widget = self.get_dictionary(widget_id)?;
+                    let has_appearance = widget
+                        .get_deref(b"AP", self)
+                        .and_then(Object::as_dict)
+                        .and_then(|appearances| appearances.get_deref(b"N", self))
+                        .and_then(Object::as_dict)
+                        .is_ok_and(|normal| normal.has(value.as_bytes()));
+                    let appearance_state = if has_appearance { state.clone() } else { "Off".into() };
+                    self.get_dictionary_mut(widget_id)?.set("AS", appearance_state);
+                }
+                self.get_dictionary_mut(field.id)?.set("V", state);
+            }
+            field_type => {
+                return Err(Error::Invalid(format!(
+                    "Cannot set the value of form field {} of type {}",
+                    name,
+                    field_type.unwrap_or("unknown")
+                )))
+            }
+        }
+
+        let catalog = self.catalog_mut()?;
+        let form = match catalog.get(b"AcroForm").and_then(Object::as_reference) {
+            Ok(form_id) => self.get_dictionary_mut(form_id)?,
+            Err(_) => catalog.get_mut(b"AcroForm").and_then(Object::as_dict_mut)?,
+        };
+        form.set("NeedAppearances", true);
+        Ok(())
+    }
+
+    /// Generate the normal appearance stream of each widget of the text field with the fully
+    /// qualified `name`, showing its value on a single line, so it is displayed by viewers that
+    /// ignore `NeedAppearances`.
+    ///
+    /// The text is drawn with the default appearance `DA` of the field or of the `AcroForm`
+    /// dictionary, using a font from the default resources `DR`. An automatic font size of 0
+    /// becomes 12, or less for fields that are not high enough. The text is aligned according to
+    /// the quadding `Q` when the font has `Widths`, and to the left otherwise.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    pub fn generate_text_field_appearance(&mut self, name: &str) -> Result<()> {
+        use crate::content::{Content, ContentBuilder};
+
+        let field = self.form_field(name)?;
+        if field.field_type.as_deref() != Some("Tx") {
+            return Err(Error::Invalid(format!("Form field {} is not a text field", name)));
+        }
+        let form = self.catalog()?.get_deref(b"AcroForm", self).and_then(Object::as_dict)?;
+        let dict = self.get_dictionary(field.id)?;
+        let attribute = |key: &[u8]| {
+            self.inherited_field_attribute(dict, key)
+                .or_else(|| form.get_deref(key, self).ok())
+        };
+        let default_appearance = attribute(b"DA")
+            .and_then(|appearance| appearance.as_str().ok())
+            .ok_or_else(|| Error::Invalid(format!("Form field {} has no default appearance", name)))?;
+        let appearance = Content::decode(default_appearance)?;
+        let quadding = attribute(b"Q").and_then(|quadding| quadding.as_i64().ok()).unwrap_or(0);
+        let resources = form.get(b"DR").ok().cloned();
+
+        let font_operation = appearance
+            .operations
+            .iter()
+            .rfind(|operation| operation.operator == "Tf");
+        let (font_name, font_size) = match font_operation.map(|operation| operation.operands.as_slice()) {
+            Some([Object::Name(font_name), size]) => (font_name.clone(), size.as_float()?),
+            _ => return Err(Error::Invalid(format!("Form field {} has no font", name))),
+        };
+        let font = form
+            .get_deref(b"DR", self)
+            .and_then(Object::as_dict)
+            .and_then(|resources| self.get_dict_in_dict(resources, b"Font"))
+            .and_then(|fonts| self.get_dict_in_dict(fonts, &font_name))
+            .ok();
+        let encoding = font.map(|font| font.get_font_encoding());
+        let text = Document::encode_text(encoding, field.value.as_deref().unwrap_or(""));
+        // Width of the text for a font size of 1, if the font has widths.
+        let text_width = font.and_then(|font| {
+            let first_char = font.get(b"FirstChar").and_then(Object::as_i64).ok()?;
+            let widths = font.get_deref(b"Widths", self).and_then(Object::as_array).ok()?;
+            let width = |code: u8| {
+                let index = usize::try_from(i64::from(code) - first_char).ok()?;
+                self.dereference(widths.get(index)?).ok()?.1.as_float().ok()
+            };
+            Some(text.iter().map(|&code| width(code).unwrap_or(0.0)).sum::<f32>() / 1000.0)
+        });
+
+        for widget_id in self.field_widgets(field.id)? {
+            let [llx, lly, urx, ury] = self
+                .get_dictionary(widget_id)?
+                .get_deref(b"Rect", self)
+                .and_then(rectangle)?;
+            let (width, height) = (urx - llx, ury - lly);
+            let size = match font_size {
+                size if size > 0.0 => size,
+                _ => AUTO_FONT_SIZE.min(height - 2.0 * TEXT_FIELD_PADDING).max(1.0),
+            };
+            let x = match (quadding, text_width) {
+                (1, Some(text_width)) => (width - text_width * size) / 2.0,
+                (2, Some(text_width)) => width - TEXT_FIELD_PADDING - text_width * size,
+                _ => TEXT_FIELD_PADDING,
+            };
+            // Center the height of capital letters, about 0.7 of the font size, vertically.
+            let y = (height - 0.7 * size) / 2.0;
+
+            let mut content = ContentBuilder::new()
+                .operation("BMC", vec!["Tx".into()])
+                .save_state()
+                .rectangle(1.0, 1.0, width - 2.0, height - 2.0)
+                .operation("W", vec![])
+                .operation("n", vec![])
+                .begin_text();
+            for operation in &appearance.operations {
+                content = match operation.operator.as_str() {
+                    "Tf" => content.set_font(&String::from_utf8_lossy(&font_name), size),
+                    operator => content.operation(operator, operation.operands.clone()),
+                };
+            }
+            let content = content
+                .move_text(x, y)
+                .show_text(text.clone())
+                .end_text()
+                .restore_state()
+                .operation("EMC", vec![])
+                .build();
+
+            let mut stream_dict = dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            };
+            if let Some(resources) = &resources {
+                stream_dict.set("Resources", resources.clone());
+            }
+            let stream_id = self.add_object(crate::Stream::new(stream_dict, content.encode()?));
+            self.get_dictionary_mut(widget_id)?
+                .set("AP", dictionary! { "N" => stream_id });
+        }
+        Ok(())
+    }
+
+    /// Get the terminal form field with the fully qualified `name`.
+    fn form_field(&self, name: &str) -> Result<FormField> {
+        self.form_fields()?
+            .into_iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| Error::FormFieldNotFound(name.to_string()))
+    }
+
+    /// Get the ids of the widget annotations of a terminal field, which is its own widget if it
+    /// has no kids.
+    fn field_widgets(&self, field_id: ObjectId) -> Result<Vec<ObjectId>> {
+        let field = self.get_dictionary(field_id)?;
+        match field.get_deref(b"Kids", self).and_then(Object::as_array) {
+            Ok(kids) => Ok(kids.iter().filter_map(|kid| kid.as_reference().ok()).collect()),
+            Err(_) => Ok(vec![field_id]),
+        }
+    }
+
+    fn collect_form_fields(&self, field: &Object, form_fields: &mut Vec<FormField>, visited: &mut Vec<ObjectId>) {
+        let Ok(field_id) = field.as_reference() else {
+            warn!("Skipping form field {:?}, which is not an indirect object", field);
+            return;
+        };
+        if visited.contains(&field_id) {
+            warn!("Form field {:?} is referenced twice", field_id);
+            return;
+        }
+        visited.push(field_id);
+        let Ok(dict) = self.get_dictionary(field_id) else {
+            return;
+        };
+
+        // Kids are either fields, which have a partial name, or widget annotations of this field.
+        let kids = dict
+            .get_deref(b"Kids", self)
+            .and_then(Object::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        let (fields, widgets): (Vec<_>, Vec<_>) = kids.iter().partition(|kid| {
+            self.dereference(kid)
+                .and_then(|(_, kid)| kid.as_dict())
+                .is_ok_and(|kid| kid.has(b"T"))
+        });
+        if !fields.is_empty() {
+            for kid in fields {
+                self.collect_form_fields(kid, form_fields, visited);
+            }
+            return;
+        }
+
+        let mut widget_rects: Vec<_> = widgets
+            .into_iter()
+            .filter_map(|widget| self.dereference(widget).ok()?.1.as_dict().ok())
+            .filter_map(|widget| widget.get_deref(b"Rect", self).and_then(rectangle).ok())
+            .collect();
+        // A field with a single widget annotation may be merged with it.
+        if kids.is_empty() {
+            widget_rects.extend(dict.get_deref(b"Rect", self).and_then(rectangle));
+        }
+        form_fields.push(FormField {
+            id: field_id,
+            name: self.field_name(dict).unwrap_or_default(),
+            field_type: self
+                .inherited_field_attribute(dict, b"FT")
+                .and_then(|field_type| field_type.as_name_str().ok())
+                .map(str::to_string),
+            value: self
+                .inherited_field_attribute(dict, b"V")
+                .and_then(|value| self.field_value(value)),
+            widget_rects,
+        });
+    }
+
+    fn field_value(&self, value: &Object) -> Option<String> {
+        match value {
+            Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+            Object::Array(values) => {
+                let value = self.dereference(values.first()?).ok()?.1;
+                value.as_text_string().ok()
+            }
+            value => value.as_text_string().ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creator::tests::create_document;
+    use crate::Dictionary;
+
+    #[test]
+    fn read_form_fields() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let rect = |x: i64| Object::from(vec![x.into(), 0.into(), (x + 100).into(), 20.into()]);
+
+        let person_id = doc.new_object_id();
+        let name_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("name"),
+            "Parent" => person_id,
+            "V" => Object::text_string("Zoë"),
+            "Subtype" => "Widget",
+            "Rect" => rect(0),
+            "P" => page_id,
+        });
+        let city_id = doc.new_object_id();
+        let widgets: Vec<Object> = [100, 200]
+            .into_iter()
+            .map(|x| {
+                let widget = dictionary! { "Subtype" => "Widget", "Rect" => rect(x), "Parent" => city_id };
+                doc.add_object(widget).into()
+            })
+            .collect();
+        doc.objects.insert(
+            city_id,
+            dictionary! { "T" => Object::string_literal("city"), "Parent" => person_id, "Kids" => widgets }.into(),
+        );
+        doc.objects.insert(
+            person_id,
+            dictionary! {
+                "T" => Object::string_literal("person"),
+                "FT" => "Tx",
+                "V" => Object::string_literal("Paris"),
+                "Kids" => vec![name_id.into(), city_id.into()],
+            }
+            .into(),
+        );
+        let agree_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("agree"),
+            "FT" => "Btn",
+            "V" => "Yes",
+            "Rect" => rect(300),
+        });
+        let form = dictionary! { "Fields" => vec![person_id.into(), agree_id.into()] };
+        doc.catalog_mut().unwrap().set("AcroForm", form);
+
+        let fields = doc.form_fields().unwrap();
+        let summary: Vec<_> = fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.field_type.as_deref(), field.value.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("person.name", Some("Tx"), Some("Zoë")),
+                ("person.city", Some("Tx"), Some("Paris")),
+                ("agree", Some("Btn"), Some("Yes")),
+            ]
+        );
+        assert_eq!(fields[0].id, name_id);
+        assert_eq!(fields[0].widget_rects, [[0.0, 0.0, 100.0, 20.0]]);
+        assert_eq!(
+            fields[1].widget_rects,
+            [[100.0, 0.0, 200.0, 20.0], [200.0, 0.0, 300.0, 20.0]]
+        );
+        assert_eq!(fields[2].widget_rects, [[300.0, 0.0, 400.0, 20.0]]);
+
+        assert!(create_document().form_fields().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fill_form_fields() {
+        let mut doc = create_document();
+        let rect = || Object::from(vec![0.into(), 0.into(), 100.into(), 20.into()]);
+        let name = dictionary! { "T" => Object::string_literal("name"), "FT" => "Tx", "Rect" => rect() };
+        let name_id = doc.add_object(name);
+        let color_id = doc.new_object_id();
+        let mut radio_button = |state: &str, selected: &str| {
+            let mut normal = Dictionary::new();
+            normal.set(state, doc.add_object(crate::Stream::new(dictionary! {}, vec![])));
+            normal.set("Off", doc.add_object(crate::Stream::new(dictionary! {}, vec![])));
+            doc.add_object(dictionary! {
+                "Subtype" => "Widget",
+                "Parent" => color_id,
+                "AP" => dictionary! { "N" => normal },
+                "AS" => selected,
+            })
+        };
+        let red_id = radio_button("Red", "Red");
+        let blue_id = radio_button("Blue", "Off");
+        let color = dictionary! {
+            "T" => Object::string_literal("color"),
+            "FT" => "Btn",
+            "V" => "Red",
+            "Kids" => vec![red_id.into(), blue_id.into()],
+        };
+        doc.objects.insert(color_id, color.into());
+        let form = dictionary! { "Fields" => vec![name_id.into(), color_id.into()] };
+        let form_id = doc.add_object(form);
+        doc.catalog_mut().unwrap().set("AcroForm", form_id);
+
+        doc.set_form_value("name", "Ada Lovelace").unwrap();
+        doc.set_form_value("color", "Blue").unwrap();
+        let fields = doc.form_fields().unwrap();
+        assert_eq!(fields[0].value.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(fields[1].value.as_deref(), Some("Blue"));
+        let appearance_state = |id| {
+            doc.get_dictionary(id)
+                .unwrap()
+                .get(b"AS")
+                .unwrap()
+                .as_name_str()
+                .unwrap()
+        };
+        assert_eq!((appearance_state(red_id), appearance_state(blue_id)), ("Off", "Blue"));
+        let form = doc.get_dictionary(form_id).unwrap();
+        assert!(form.get(b"NeedAppearances").and_then(Object::as_bool).unwrap());
+
+        let missing = doc.set_form_value("missing", "value");
+        assert!(matches!(missing, Err(Error::FormFieldNotFound(name)) if name == "missing"));
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn generate_text_field_appearance() {
+        use crate::content::Content;
+
+        let mut doc = create_document();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+            "FirstChar" => 32,
+            "Widths" => vec![500.into(); 224],
+        });
+        let rect = vec![100.into(), 700.into(), 300.into(), 716.into()];
+        let name_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("name"),
+            "FT" => "Tx",
+            "Subtype" => "Widget",
+            "Rect" => rect,
+            "Q" => 1,
+        });
+        let form = dictionary! {
+            "Fields" => vec![name_id.into()],
+            "DA" => Object::string_literal("/Helv 0 Tf 0 0 1 rg"),
+            "DR" => dictionary! { "Font" => dictionary! { "Helv" => font_id } },
+        };
+        doc.catalog_mut().unwrap().set("AcroForm", form);
+
+        doc.set_form_value("name", "Zoë").unwrap();
+        doc.generate_text_field_appearance("name").unwrap();
+        let widget = doc.get_dictionary(name_id).unwrap();
+        let appearance = doc.get_dict_in_dict(widget, b"AP").unwrap();
+        let stream_id = appearance.get(b"N").and_then(Object::as_reference).unwrap();
+        let stream = doc.get_object(stream_id).and_then(Object::as_stream).unwrap();
+        let bbox = stream.dict.get(b"BBox").and_then(Object::as_array).unwrap();
+        let bbox: Vec<_> = bbox.iter().map(|number| number.as_float().unwrap()).collect();
+        assert_eq!(bbox, [0.0, 0.0, 200.0, 16.0]);
+        assert!(stream
+            .dict
+            .get(b"Resources")
+            .and_then(Object::as_dict)
+            .unwrap()
+            .has(b"Font"));
+
+        let content = Content::decode(&stream.content).unwrap();
+        let operation = |operator: &str| {
+            let operation = content
+                .operations
+                .iter()
+                .find(|operation| operation.operator == operator)
+                .unwrap();
+            operation.operands.clone()
+        };
+        assert_eq!(operation("Tj"), [Object::string_literal(b"Zo\xEB".to_vec())]);
+        // The automatic font size fits the field, and the text of width 1.5 is centered.
+        assert_eq!(operation("Tf"), ["Helv".into(), 12.into()]);
+        assert_eq!(operation("Td")[0].as_float().unwrap(), (200.0 - 1.5 * 12.0) / 2.0);
+        assert_eq!(operation("rg"), [0.into(), 0.into(), 1.into()]);
+
+        assert!(matches!(
+            doc.generate_text_field_appearance("missing"),
+            Err(Error::FormFieldNotFound(_))
+        ));
+    }
+}
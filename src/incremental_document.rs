@@ -1,3 +1,4 @@
+use crate::xref::XrefEntry;
 use crate::{Dictionary, Document, Error, Object, ObjectId, Result};
 
 #[derive(Debug, Clone)]
@@ -27,6 +28,7 @@ pub fn new() -> Self {
     ///
     /// The function expects the bytes and previous document to match.
     /// If they do not match exactly this might result in broken PDFs.
+    /// Bytes in front of the header of the file, see [`Document::header_offset`], are kept.
     pub fn create_from(prev_bytes: Vec<u8>, prev_documents: Document) -> Self {
         Self {
             bytes_documents: prev_bytes,
@@ -35,6 +37,44 @@ pub fn create_from(prev_bytes: Vec<u8>, prev_documents: Document) -> Self {
         }
     }
 
+    /// Create an incremental update that changes the file `prev_bytes`, loaded as `prev_documents`,
+    /// into `document`.
+    ///
+    /// The objects of `document` are compared with the objects of `prev_documents`, only the ones
+    /// that were added or changed are put in the new document. If the cross-reference table of
+    /// `prev_bytes` had to be rebuilt, all objects are put in the new document instead. Objects
+    /// that `document` no longer has get free entries in the `reference_table` of the new
+    /// document, with their generation number increased, so that they are deleted by the update.
+    pub fn create_from_changes(prev_bytes: Vec<u8>, prev_documents: Document, document: &Document) -> Self {
+        let mut incremental = Self::create_from(prev_bytes, prev_documents);
+        let prev_documents = &incremental.prev_documents;
+        let new_document = &mut incremental.new_document;
+
+        for (id, object) in &document.objects {
+            if prev_documents.xref_rebuilt || prev_documents.objects.get(id) != Some(object) {
+                new_document.objects.insert(*id, object.clone());
+            }
+        }
+        for &(id, generation) in prev_documents.objects.keys() {
+            if !document.objects.keys().any(|&(number, _)| number == id) {
+                let entry = match generation.checked_add(1) {
+                    Some(generation) if generation < u16::MAX => XrefEntry::Free { generation },
+                    _ => XrefEntry::UnusableFree,
+                };
+                new_document.reference_table.insert(id, entry);
+            }
+        }
+        let prev = new_document.trailer.get(b"Prev").cloned();
+        new_document.trailer = document.trailer.clone();
+        match prev {
+            Ok(prev) if !prev_documents.xref_rebuilt => new_document.trailer.set("Prev", prev),
+            _ => {}
+        }
+        new_document.version = document.version.clone();
+        new_document.max_id = new_document.max_id.max(document.max_id);
+        incremental
+    }
+
     /// Get the structure of the previous documents (all prev incremental updates combined.)
     pub fn get_prev_documents(&self) -> &Document {
         &self.prev_documents
@@ -0,0 +1,275 @@
+//! Typed access to the document information dictionary.
+
+use crate::{Dictionary, Document, Object, ObjectId, PdfDate, Result, Stream};
+
+/// Entries of the document information dictionary.
+///
+/// Text entries are decoded from and encoded to text strings, see [`Object::as_text_string`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<PdfDate>,
+    pub modification_date: Option<PdfDate>,
+}
+
+impl DocumentInfo {
+    /// Build an XMP packet with the same metadata, using the Dublin Core, XMP and Adobe PDF
+    /// schemas.
+    pub fn to_xmp(&self) -> String {
+        let mut properties = String::new();
+        let mut property = |name: &str, value: &Option<String>, container: Option<&str>| {
+            let Some(value) = value else {
+                return;
+            };
+            let value = escape_xml(value);
+            let value = match container {
+                Some("Alt") => format!("<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>", value),
+                Some(container) => format!("<rdf:{0}><rdf:li>{1}</rdf:li></rdf:{0}>", container, value),
+                None => value,
+            };
+            properties.push_str(&format!("   <{0}>{1}</{0}>\n", name, value));
+        };
+        property("dc:title", &self.title, Some("Alt"));
+        property("dc:creator", &self.author, Some("Seq"));
+        property("dc:description", &self.subject, Some("Alt"));
+        property("pdf:Keywords", &self.keywords, None);
+        property("pdf:Producer", &self.producer, None);
+        property("xmp:CreatorTool", &self.creator, None);
+        let iso8601 = |date: &Option<PdfDate>| date.as_ref().map(PdfDate::to_iso8601);
+        property("xmp:CreateDate", &iso8601(&self.creation_date), None);
+        property("xmp:ModifyDate", &iso8601(&self.modification_date), None);
+
+        format!(
+            "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">
+{}  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>",
+            properties
+        )
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+impl Document {
+    /// Get the entries of the document information dictionary.
+    ///
+    /// Entries that are missing or can't be decoded are `None`.
+    pub fn info(&self) -> DocumentInfo {
+        let Some(dict) = self.info_dictionary() else {
+            return DocumentInfo::default();
+        };
+        let text = |key: &[u8]| dict.get_deref(key, self).and_then(Object::as_text_string).ok();
+        let date = |key: &[u8]| {
+            dict.get_deref(key, self)
+                .and_then(Object::as_str)
+                .ok()
+                .and_then(PdfDate::parse)
+        };
+        DocumentInfo {
+            title: text(b"Title"),
+            author: text(b"Author"),
+            subject: text(b"Subject"),
+            keywords: text(b"Keywords"),
+            creator: text(b"Creator"),
+            producer: text(b"Producer"),
+            creation_date: date(b"CreationDate"),
+            modification_date: date(b"ModDate"),
+        }
+    }
+
+    /// Set the entries of the document information dictionary, creating it if needed.
+    ///
+    /// Entries that are `None` are removed, other entries of the dictionary are kept.
+    pub fn set_info(&mut self, info: &DocumentInfo) {
+        let info_id = match self.trailer.get(b"Info") {
+            Ok(Object::Reference(id)) if self.get_dictionary(*id).is_ok() => Some(*id),
+            Ok(Object::Dictionary(_)) => None,
+            _ => {
+                let id = self.add_object(Dictionary::new());
+                self.trailer.set("Info", id);
+                Some(id)
+            }
+        };
+        let dict = match info_id {
+            Some(id) => self.get_dictionary_mut(id),
+            None => self.trailer.get_mut(b"Info").and_then(Object::as_dict_mut),
+        };
+        let Ok(dict) = dict else {
+            return;
+        };
+
+        let texts = [
+            ("Title", &info.title),
+            ("Author", &info.author),
+            ("Subject", &info.subject),
+            ("Keywords", &info.keywords),
+            ("Creator", &info.creator),
+            ("Producer", &info.producer),
+        ];
+        for (key, value) in texts {
+            match value {
+                Some(text) => dict.set(key, Object::text_string(text)),
+                None => {
+                    dict.remove(key.as_bytes());
+                }
+            }
+        }
+        for (key, value) in [
+            ("CreationDate", info.creation_date),
+            ("ModDate", info.modification_date),
+        ] {
+            match value {
+                Some(date) => dict.set(key, date),
+                None => {
+                    dict.remove(key.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Set the entries of the document information dictionary and replace the XMP metadata of
+    /// the catalog with the same entries, keeping both in sync.
+    pub fn set_info_with_xmp(&mut self, info: &DocumentInfo) -> Result<ObjectId> {
+        self.set_info(info);
+        self.set_xmp_metadata(&info.to_xmp())
+    }
+
+    /// Get the XMP metadata packet of the catalog's `Metadata` stream.
+    pub fn xmp_metadata(&self) -> Option<String> {
+        let metadata = self.catalog().ok()?.get(b"Metadata").ok()?;
+        let stream = self.dereference(metadata).ok()?.1.as_stream().ok()?;
+        let content = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+        Some(String::from_utf8_lossy(&content).into_owned())
+    }
+
+    /// Set the XMP metadata packet, replacing the content of the catalog's existing `Metadata`
+    /// stream or adding a new stream to the catalog. Returns the id of the stream.
+    ///
+    /// The stream is left uncompressed so the packet can be found by tools that scan files for
+    /// XMP, as PDF/A expects.
+    pub fn set_xmp_metadata(&mut self, xml: &str) -> Result<ObjectId> {
+        let existing = self.catalog()?.get(b"Metadata").and_then(Object::as_reference).ok();
+        let dict = dictionary! {
+            "Type" => "Metadata",
+            "Subtype" => "XML",
+        };
+        let stream = Stream::new(dict, xml.as_bytes().to_vec()).with_compression(false);
+        let id = match existing {
+            Some(id) if self.get_object(id).and_then(Object::as_stream).is_ok() => {
+                self.objects.insert(id, Object::Stream(stream));
+                id
+            }
+            _ => {
+                let id = self.add_object(stream);
+                self.catalog_mut()?.set("Metadata", id);
+                id
+            }
+        };
+        Ok(id)
+    }
+
+    fn info_dictionary(&self) -> Option<&Dictionary> {
+        let info = self.trailer.get(b"Info").ok()?;
+        self.dereference(info).ok()?.1.as_dict().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_write_info() {
+        let mut doc = Document::with_version("1.5");
+        assert_eq!(doc.info(), DocumentInfo::default());
+
+        let info = DocumentInfo {
+            title: Some("R\u{E9}sum\u{E9}".to_string()),
+            author: Some("\u{5F20}\u{4F1F}".to_string()),
+            producer: Some("lopdf".to_string()),
+            creation_date: PdfDate::parse(b"D:20240101120000+01'00'"),
+            ..DocumentInfo::default()
+        };
+        doc.set_info(&info);
+        assert_eq!(doc.info(), info);
+
+        let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+        let dict = doc.get_dictionary(info_id).unwrap();
+        assert_eq!(dict.get(b"Title").and_then(Object::as_str).unwrap(), b"R\xE9sum\xE9");
+        assert_eq!(
+            dict.get(b"CreationDate").and_then(Object::as_str).unwrap(),
+            b"D:20240101120000+01'00'"
+        );
+
+        doc.get_dictionary_mut(info_id).unwrap().set("Trapped", "False");
+        doc.set_info(&DocumentInfo {
+            title: Some("Other".to_string()),
+            ..DocumentInfo::default()
+        });
+        let dict = doc.get_dictionary(info_id).unwrap();
+        assert!(!dict.has(b"Producer"));
+        assert!(dict.has(b"Trapped"));
+        assert_eq!(doc.info().title.as_deref(), Some("Other"));
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn read_and_write_xmp_metadata() {
+        let mut doc = Document::with_version("1.5");
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog" });
+        doc.trailer.set("Root", catalog_id);
+        assert_eq!(doc.xmp_metadata(), None);
+
+        let id = doc.set_xmp_metadata("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>").unwrap();
+        let catalog = doc.get_dictionary(catalog_id).unwrap();
+        assert_eq!(catalog.get(b"Metadata").and_then(Object::as_reference).unwrap(), id);
+        let stream = doc.get_object(id).and_then(Object::as_stream).unwrap();
+        assert_eq!(
+            stream.dict.get(b"Subtype").and_then(Object::as_name_str).unwrap(),
+            "XML"
+        );
+        assert!(!stream.allows_compression);
+
+        let info = DocumentInfo {
+            title: Some("Q&A <draft>".to_string()),
+            author: Some("Jane Doe".to_string()),
+            creation_date: PdfDate::parse(b"D:20240101120000+01'00'"),
+            ..DocumentInfo::default()
+        };
+        assert_eq!(doc.set_info_with_xmp(&info).unwrap(), id);
+        assert_eq!(doc.info(), info);
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        let doc = Document::load_mem(&buffer).unwrap();
+        let xmp = doc.xmp_metadata().unwrap();
+        assert!(xmp.starts_with("<?xpacket begin=\"\u{FEFF}\""));
+        assert!(xmp.contains("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Q&amp;A &lt;draft&gt;</rdf:li>"));
+        assert!(xmp.contains("<dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator>"));
+        assert!(xmp.contains("<xmp:CreateDate>2024-01-01T12:00:00+01:00</xmp:CreateDate>"));
+        assert!(!xmp.contains("pdf:Producer"));
+    }
+}
@@ -0,0 +1,454 @@
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use std::collections::BTreeSet;
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use crate::content::Operation;
+use crate::{Dictionary, Document, Error, Object, ObjectId, Result};
+
+/// Optional content group of a document, a layer that viewers can show or hide, see
+/// [`Document::layers`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// Id of the optional content group dictionary.
+    pub id: ObjectId,
+    /// Name of the group, from its `Name` entry.
+    pub name: String,
+    /// Whether the group is shown when the document is opened, according to the default
+    /// configuration `D` of the catalog's `OCProperties`.
+    pub visible: bool,
+}
+
+impl Document {
+    /// Get the optional content groups listed in the `OCGs` array of the catalog's
+    /// `OCProperties`, in that order. Empty if the document has no optional content.
+    ///
+    /// A group is visible unless the default configuration lists it in its `OFF` array, or it
+    /// has a `BaseState` of `OFF` and doesn't list the group in its `ON` array.
+    pub fn layers(&self) -> Result<Vec<Layer>> {
+        let Ok(properties) = self
+            .catalog()?
+            .get_deref(b"OCProperties", self)
+            .and_then(Object::as_dict)
+        else {
+            return Ok(vec![]);
+        };
+        let config = properties.get_deref(b"D", self).and_then(Object::as_dict).ok();
+        let listed = |key: &[u8], id: ObjectId| {
+            config
+                .and_then(|config| config.get_deref(key, self).and_then(Object::as_array).ok())
+                .is_some_and(|ids| ids.iter().any(|item| item.as_reference().ok() == Some(id)))
+        };
+        let base_off = config
+            .and_then(|config| config.get_deref(b"BaseState", self).and_then(Object::as_name).ok())
+            .is_some_and(|state| state == b"OFF");
+        let groups = properties.get_deref(b"OCGs", self).and_then(Object::as_array)?;
+        let layers = groups.iter().filter_map(|group| {
+            let id = group.as_reference().ok()?;
+            let group = self.get_dictionary(id).ok()?;
+            Some(Layer {
+                id,
+                name: group
+                    .get_deref(b"Name", self)
+                    .and_then(Object::as_text_string)
+                    .unwrap_or_default(),
+                visible: if base_off {
+                    listed(b"ON", id)
+                } else {
+                    !listed(b"OFF", id)
+                },
+            })
+        });
+        Ok(layers.collect())
+    }
+
+    /// Show or hide the optional content groups with the given name when the document is
+    /// opened, by moving them between the `ON` and `OFF` arrays of the default configuration
+    /// of the catalog's `OCProperties`. The default configuration is created if it is missing.
+    ///
+    /// Fails with [`Error::LayerNotFound`] if no group has that name.
+    pub fn set_layer_visible(&mut self, name: &str, visible: bool) -> Result<()> {
+        let ids: Vec<ObjectId> = self
+            .layers()?
+            .into_iter()
+            .filter(|layer| layer.name == name)
+            .map(|layer| layer.id)
+            .collect();
+        if ids.is_empty() {
+            return Err(Error::LayerNotFound(name.to_string()));
+        }
+
+        let config = match self.oc_properties_mut()?.get(b"D").and_then(Object::as_reference) {
+            Ok(id) => self.get_dictionary_mut(id)?,
+            Err(_) => {
+                let properties = self.oc_properties_mut()?;
+                if !properties.has(b"D") {
+                    properties.set("D", Dictionary::new());
+                }
+                properties.get_mut(b"D").and_then(Object::as_dict_mut)?
+            }
+        };
+        let base_off = config
+            .get(b"BaseState")
+            .and_then(Object::as_name)
+            .is_ok_and(|state| state == b"OFF");
+        let (add, remove): (&[u8], &[u8]) = if visible { (b"ON", b"OFF") } else { (b"OFF", b"ON") };
+        if let Ok(array) = config.get_mut(remove).and_then(Object::as_array_mut) {
+            array.retain(|item| !item.as_reference().is_ok_and(|id| ids.contains(&id)));
+        }
+        // Groups in the base state need not be listed.
+        if base_off != visible {
+            return Ok(());
+        }
+        if !config.has(add) {
+            config.set(add, Vec::<Object>::new());
+        }
+        let array = config.get_mut(add).and_then(Object::as_array_mut)?;
+        for id in ids {
+            if !array.iter().any(|item| item.as_reference().ok() == Some(id)) {
+                array.push(id.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Bake the visibility of the layers into the document: content of hidden optional content
+    /// groups is removed, and the optional content markers of the remaining content, the `OC`
+    /// entries of objects and the catalog's `OCProperties` are stripped.
+    ///
+    /// Removed are the marked-content sections `/OC /Name BDC ... EMC` of hidden groups in the
+    /// content streams of pages and form XObjects, `Do` operators painting hidden XObjects,
+    /// and hidden annotations from the `Annots` of pages. Visibility also follows optional
+    /// content membership dictionaries, by their `P` policy. The groups themselves are left in
+    /// the document, [`Document::prune_objects`] removes them.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    pub fn flatten_layers(&mut self) -> Result<()> {
+        use crate::content::Content;
+
+        let hidden: BTreeSet<ObjectId> = self
+            .layers()?
+            .into_iter()
+            .filter(|layer| !layer.visible)
+            .map(|layer| layer.id)
+            .collect();
+
+        for page_id in self.page_iter().collect::<Vec<_>>() {
+            let resources = self.get_page_inherited_resources(page_id)?;
+            let content = self.get_and_decode_page_content(page_id)?;
+            if let Some(operations) = self.strip_optional_content(&content.operations, &resources, &hidden) {
+                self.set_page_content(page_id, Content { operations })?;
+            }
+            let page = self.get_dictionary(page_id)?;
+            let Ok(annots) = page.get_deref(b"Annots", self).and_then(Object::as_array) else {
+                continue;
+            };
+            let visible: Vec<Object> = annots
+                .iter()
+                .filter(|annot| {
+                    let oc = self
+                        .dereference(annot)
+                        .and_then(|(_, annot)| annot.as_dict()?.get(b"OC"));
+                    !oc.is_ok_and(|oc| self.is_hidden(oc, &hidden))
+                })
+                .cloned()
+                .collect();
+            if visible.len() < annots.len() {
+                self.get_dictionary_mut(page_id)?.set("Annots", visible);
+            }
+        }
+
+        let forms: Vec<ObjectId> = self
+            .objects
+            .iter()
+            .filter(|(_, object)| {
+                let subtype = object.as_stream().and_then(|stream| stream.dict.get(b"Subtype"));
+                subtype
+                    .and_then(Object::as_name)
+                    .is_ok_and(|subtype| subtype == b"Form")
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        for id in forms {
+            let stream = self.get_object(id).and_then(Object::as_stream)?;
+            let resources = match stream.dict.get_deref(b"Resources", self).and_then(Object::as_dict) {
+                Ok(resources) => resources.clone(),
+                Err(_) => Dictionary::new(),
+            };
+            let Ok(content) = stream
+                .decompressed_content()
+                .and_then(|content| Content::decode(&content))
+            else {
+                continue;
+            };
+            if let Some(operations) = self.strip_optional_content(&content.operations, &resources, &hidden) {
+                let content = Content { operations }.encode()?;
+                let stream = self.get_object_mut(id).and_then(Object::as_stream_mut)?;
+                stream.set_plain_content(content);
+                // Ignore any compression error, the stream is stored uncompressed then.
+                let _ = stream.compress();
+            }
+        }
+
+        for object in self.objects.values_mut() {
+            match object {
+                Object::Dictionary(dict) => dict.remove(b"OC"),
+                Object::Stream(stream) => stream.dict.remove(b"OC"),
+                _ => None,
+            };
+        }
+        self.catalog_mut()?.remove(b"OCProperties");
+        Ok(())
+    }
+
+    /// Whether the optional content group or membership dictionary `oc` is hidden, given the
+    /// hidden groups.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    fn is_hidden(&self, oc: &Object, hidden: &BTreeSet<ObjectId>) -> bool {
+        let Ok((id, Object::Dictionary(oc))) = self.dereference(oc) else {
+            return false;
+        };
+        if !oc.type_is(b"OCMD") {
+            return id.is_some_and(|id| hidden.contains(&id));
+        }
+        let members: Vec<bool> = match oc.get_deref(b"OCGs", self) {
+            Ok(Object::Array(groups)) => groups.iter().filter_map(|group| group.as_reference().ok()).collect(),
+            Ok(_) => oc.get(b"OCGs").and_then(Object::as_reference).into_iter().collect(),
+            Err(_) => vec![],
+        }
+        .into_iter()
+        .map(|id: ObjectId| !hidden.contains(&id))
+        .collect();
+        if members.is_empty() {
+            return false;
+        }
+        let policy = oc.get_deref(b"P", self).and_then(Object::as_name).unwrap_or(b"AnyOn");
+        let visible = match policy {
+            b"AllOn" => members.iter().all(|&on| on),
+            b"AnyOff" => members.iter().any(|&on| !on),
+            b"AllOff" => members.iter().all(|&on| !on),
+            _ => members.iter().any(|&on| on),
+        };
+        !visible
+    }
+
+    /// Remove the hidden optional content from the operations of a content stream with the
+    /// given resources, and the optional content markers of the visible content. `None` if
+    /// the content has no optional content.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    fn strip_optional_content(
+        &self, operations: &[Operation], resources: &Dictionary, hidden: &BTreeSet<ObjectId>,
+    ) -> Option<Vec<Operation>> {
+        /// Marked-content section opened by `BMC` or `BDC`, for the `EMC` closing it.
+        enum Section {
+            /// Other marked content, whose operators are kept.
+            Kept,
+            /// Optional content or other marked content inside hidden content.
+            Removed,
+            Hidden,
+        }
+
+        let resource = |category: &[u8], name: &Object| {
+            let name = name.as_name().ok()?;
+            let entries = resources.get_deref(category, self).and_then(Object::as_dict).ok()?;
+            entries.get(name).ok()
+        };
+        let mut sections = vec![];
+        let mut hidden_depth = 0;
+        let mut changed = false;
+        let mut kept = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let keep = match operation.operator.as_str() {
+                "BMC" | "BDC" if hidden_depth > 0 => {
+                    sections.push(Section::Removed);
+                    false
+                }
+                "BDC" if operation.operands.first().and_then(|tag| tag.as_name().ok()) == Some(b"OC") => {
+                    let oc = match operation.operands.get(1) {
+                        Some(name @ Object::Name(_)) => resource(b"Properties", name),
+                        oc => oc,
+                    };
+                    if oc.is_some_and(|oc| self.is_hidden(oc, hidden)) {
+                        hidden_depth += 1;
+                        sections.push(Section::Hidden);
+                    } else {
+                        sections.push(Section::Removed);
+                    }
+                    false
+                }
+                "BMC" | "BDC" => {
+                    sections.push(Section::Kept);
+                    true
+                }
+                "EMC" => match sections.pop() {
+                    Some(Section::Kept) => true,
+                    Some(Section::Removed) => false,
+                    Some(Section::Hidden) => {
+                        hidden_depth -= 1;
+                        false
+                    }
+                    None => hidden_depth == 0,
+                },
+                "Do" if hidden_depth == 0 => {
+                    let xobject = operation.operands.first().and_then(|name| resource(b"XObject", name));
+                    let oc = xobject
+                        .and_then(|xobject| self.dereference(xobject).ok())
+                        .and_then(|(_, xobject)| xobject.as_stream().ok())
+                        .and_then(|xobject| xobject.dict.get(b"OC").ok());
+                    !oc.is_some_and(|oc| self.is_hidden(oc, hidden))
+                }
+                _ => hidden_depth == 0,
+            };
+            if keep {
+                kept.push(operation.clone());
+            } else {
+                changed = true;
+            }
+        }
+        changed.then_some(kept)
+    }
+
+    fn oc_properties_mut(&mut self) -> Result<&mut Dictionary> {
+        match self.catalog()?.get(b"OCProperties").and_then(Object::as_reference) {
+            Ok(id) => self.get_dictionary_mut(id),
+            Err(_) => self
+                .catalog_mut()?
+                .get_mut(b"OCProperties")
+                .and_then(Object::as_dict_mut),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layer;
+    use crate::creator::tests::create_document;
+    use crate::{Error, Object};
+
+    #[test]
+    fn toggle_layer_visibility() {
+        let mut doc = create_document();
+        let walls_id = doc.add_object(dictionary! { "Type" => "OCG", "Name" => Object::string_literal("Walls") });
+        let doors_id = doc.add_object(dictionary! { "Type" => "OCG", "Name" => Object::text_string("Doors") });
+        let notes_id = doc.add_object(dictionary! { "Type" => "OCG", "Name" => Object::string_literal("Notes") });
+        let properties_id = doc.add_object(dictionary! {
+            "OCGs" => vec![walls_id.into(), doors_id.into(), notes_id.into()],
+            "D" => dictionary! { "Order" => vec![walls_id.into(), doors_id.into()], "OFF" => vec![notes_id.into()] },
+        });
+        doc.catalog_mut().unwrap().set("OCProperties", properties_id);
+        let layers = |doc: &crate::Document| -> Vec<(String, bool)> {
+            doc.layers()
+                .unwrap()
+                .into_iter()
+                .map(|Layer { name, visible, .. }| (name, visible))
+                .collect()
+        };
+        assert_eq!(doc.layers().unwrap()[0].id, walls_id);
+        assert_eq!(
+            layers(&doc),
+            [("Walls".into(), true), ("Doors".into(), true), ("Notes".into(), false)]
+        );
+
+        doc.set_layer_visible("Doors", false).unwrap();
+        doc.set_layer_visible("Notes", true).unwrap();
+        assert_eq!(
+            layers(&doc),
+            [("Walls".into(), true), ("Doors".into(), false), ("Notes".into(), true)]
+        );
+        let config = doc
+            .get_dictionary(properties_id)
+            .unwrap()
+            .get(b"D")
+            .unwrap()
+            .as_dict()
+            .unwrap();
+        assert_eq!(
+            config.get(b"OFF").unwrap().as_array().unwrap(),
+            &[Object::Reference(doors_id)]
+        );
+        assert!(!config.has(b"ON"));
+
+        // With a base state of OFF, visible groups are listed in ON.
+        let properties = doc.get_dictionary_mut(properties_id).unwrap();
+        properties.set("D", dictionary! { "BaseState" => "OFF" });
+        assert!(layers(&doc).iter().all(|(_, visible)| !visible));
+        doc.set_layer_visible("Walls", true).unwrap();
+        assert_eq!(
+            layers(&doc),
+            [("Walls".into(), true), ("Doors".into(), false), ("Notes".into(), false)]
+        );
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let mut doc = crate::Document::load_mem(&buffer).unwrap();
+        assert_eq!(layers(&doc)[0], ("Walls".into(), true));
+        assert!(matches!(
+            doc.set_layer_visible("Roof", true),
+            Err(Error::LayerNotFound(name)) if name == "Roof"
+        ));
+        assert!(create_document().layers().unwrap().is_empty());
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn flatten_hidden_layer() {
+        use crate::content::Content;
+        use crate::Stream;
+
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let walls_id = doc.add_object(dictionary! { "Type" => "OCG", "Name" => Object::string_literal("Walls") });
+        let notes_id = doc.add_object(dictionary! { "Type" => "OCG", "Name" => Object::string_literal("Notes") });
+        let either_id = doc.add_object(dictionary! {
+            "Type" => "OCMD",
+            "OCGs" => vec![walls_id.into(), notes_id.into()],
+            "P" => "AllOn",
+        });
+        doc.catalog_mut().unwrap().set(
+            "OCProperties",
+            dictionary! {
+                "OCGs" => vec![walls_id.into(), notes_id.into()],
+                "D" => dictionary! {},
+            },
+        );
+        let stamp_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+                "OC" => notes_id,
+            },
+            b"0 0 10 10 re f".to_vec(),
+        ));
+        let note_id = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Text", "OC" => notes_id });
+        let link_id = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link" });
+        let content = b"/OC /Walls BDC 0 0 100 100 re S EMC\n\
+            /OC /Notes BDC /Span <</MCID 0>> BDC BT (Note) Tj ET EMC EMC\n\
+            /OC /Both BDC 1 0 0 RG EMC\n\
+            /Stamp Do /Span <</MCID 1>> BDC 50 50 m 60 60 l S EMC";
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+        let page = doc.get_dictionary_mut(page_id).unwrap();
+        page.set("Contents", content_id);
+        page.set("Annots", vec![note_id.into(), link_id.into()]);
+        page.set(
+            "Resources",
+            dictionary! {
+                "Properties" => dictionary! { "Walls" => walls_id, "Notes" => notes_id, "Both" => either_id },
+                "XObject" => dictionary! { "Stamp" => stamp_id },
+            },
+        );
+
+        doc.set_layer_visible("Notes", false).unwrap();
+        doc.flatten_layers().unwrap();
+        let content = doc.get_and_decode_page_content(page_id).unwrap();
+        let expected = Content::decode(b"0 0 100 100 re S /Span <</MCID 1>> BDC 50 50 m 60 60 l S EMC").unwrap();
+        assert_eq!(content.encode().unwrap(), expected.encode().unwrap());
+        let page = doc.get_dictionary(page_id).unwrap();
+        assert_eq!(
+            page.get(b"Annots").unwrap().as_array().unwrap(),
+            &[Object::Reference(link_id)]
+        );
+        assert!(!doc.catalog().unwrap().has(b"OCProperties"));
+        assert!(!doc.get_object(stamp_id).unwrap().as_stream().unwrap().dict.has(b"OC"));
+        assert!(doc.layers().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,519 @@
+#![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+
+use linked_hash_map::LinkedHashMap;
+use log::{error, warn};
+use std::cmp;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::XrefError;
+use crate::object_stream::ObjectStream;
+use crate::parser;
+use crate::reader::HEADER_SEARCH_LIMIT;
+use crate::xref::{Xref, XrefEntry};
+use crate::{Dictionary, Document, Error, LoadOptions, Object, ObjectId, Reader, Result};
+
+/// Number of bytes read at once when the extent of a section is not known in advance.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Number of bytes at the end of the file searched for `startxref`.
+const TAIL_SIZE: u64 = 1024;
+
+/// A PDF document whose objects are only parsed the first time they are accessed.
+///
+/// Only the header, cross-reference sections and trailer are read when the document is opened.
+/// Every object requested through [`LazyDocument::get_object`] is read from the source, parsed
+/// and cached, so memory usage grows with the number of objects touched instead of the file
+/// size. Use [`LazyDocument::evict`] or [`LazyDocument::clear_cache`] to release cached objects,
+/// or bound the cache with [`LazyDocument::with_cache_capacity`] so the least recently used
+/// objects are released automatically.
+///
+/// Encrypted documents are not decrypted.
+pub struct LazyDocument<R> {
+    source: R,
+    /// The version of the PDF specification to which the file conforms.
+    pub version: String,
+    /// The trailer of the last cross-reference section.
+    pub trailer: Dictionary,
+    /// The cross-reference table of all sections, used to locate objects.
+    pub reference_table: Xref,
+    /// Sorted start offsets of all objects and cross-reference sections, and the file length.
+    /// An object never extends past the next boundary after its own offset.
+    boundaries: Vec<u64>,
+    /// Number of bytes in front of the header, that offsets in the file are relative to.
+    header_offset: u64,
+    /// Offset of the last cross-reference section.
+    xref_start: u64,
+    /// Cached objects, from least to most recently used.
+    cache: LinkedHashMap<ObjectId, Object>,
+    cache_capacity: Option<usize>,
+    /// Options of the readers that parse the objects.
+    options: LoadOptions,
+}
+
+impl Document {
+    /// Open a PDF document from a specified file path without parsing its objects.
+    pub fn load_lazy<P: AsRef<Path>>(path: P) -> Result<LazyDocument<File>> {
+        LazyDocument::new(File::open(path)?)
+    }
+
+    /// Load a PDF document from a seekable source, such as a file or a [`std::io::Cursor`].
+    ///
+    /// Unlike [`Document::load_from`], the source is not read into memory as a whole: the
+    /// cross-reference sections are read first, as by [`LazyDocument::new`], and then every
+    /// object is read from its own offset, see [`LazyDocument::into_document`].
+    pub fn load_seekable<R: Read + Seek>(source: R) -> Result<Document> {
+        Self::load_seekable_with_options(source, LoadOptions::default())
+    }
+
+    /// Load a PDF document from a seekable source with custom options, like
+    /// [`Document::load_seekable`].
+    pub fn load_seekable_with_options<R: Read + Seek>(source: R, options: LoadOptions) -> Result<Document> {
+        LazyDocument::with_options(source, options)?.into_document()
+    }
+}
+
+impl<R: Read + Seek> LazyDocument<R> {
+    /// Open a PDF document from a seekable source, reading only its cross-reference sections.
+    pub fn new(source: R) -> Result<Self> {
+        Self::with_options(source, LoadOptions::default())
+    }
+
+    /// Open a PDF document from a seekable source like [`LazyDocument::new`], parsing its
+    /// objects with custom options.
+    pub fn with_options(mut source: R, options: LoadOptions) -> Result<Self> {
+        let file_len = source.seek(SeekFrom::End(0))?;
+
+        // The header can come after some garbage, see `Reader::find_header`.
+        let head_len = cmp::min(file_len, HEADER_SEARCH_LIMIT as u64 + TAIL_SIZE);
+        let head = read_at(&mut source, 0, head_len)?;
+        let header_offset = Reader::find_header(&head).ok_or(Error::Header)?;
+        let version = parser::header(&head[header_offset..]).ok_or(Error::Header)?;
+        let header_offset = header_offset as u64;
+        // Length and offsets relative to the header.
+        let len = file_len - header_offset;
+
+        let tail_start = file_len - cmp::min(len, TAIL_SIZE);
+        let tail = read_at(&mut source, tail_start, file_len - tail_start)?;
+        let xref_start = Reader::get_xref_start(&tail)? as u64;
+        if xref_start > len {
+            return Err(Error::Xref(XrefError::Start));
+        }
+
+        let mut boundaries = vec![xref_start, len];
+        let (mut xref, mut trailer) = read_xref(&mut source, header_offset + xref_start, file_len, options)?;
+
+        // Read the xref stream of a hybrid-reference file and the previous sections of
+        // a linearized or incrementally updated file.
+        let mut section_trailer = trailer.clone();
+        loop {
+            if let Some(start) = section_trailer
+                .remove(b"XRefStm")
+                .and_then(|offset| offset.as_i64().ok())
+            {
+                if start < 0 || start as u64 > len {
+                    return Err(Error::Xref(XrefError::StreamStart));
+                }
+                boundaries.push(start as u64);
+                let (stream_xref, _) = read_xref(&mut source, header_offset + start as u64, file_len, options)?;
+                xref.merge(stream_xref);
+            }
+
+            match section_trailer.remove(b"Prev").and_then(|offset| offset.as_i64().ok()) {
+                Some(prev) => {
+                    if prev < 0 || prev as u64 > len {
+                        return Err(Error::Xref(XrefError::PrevStart));
+                    }
+                    if boundaries.contains(&(prev as u64)) {
+                        warn!("Cross-reference section at {} is referenced twice by Prev", prev);
+                        break;
+                    }
+                    boundaries.push(prev as u64);
+                    let (prev_xref, prev_trailer) =
+                        read_xref(&mut source, header_offset + prev as u64, file_len, options)?;
+                    xref.merge(prev_xref);
+                    section_trailer = prev_trailer;
+                }
+                None => break,
+            }
+        }
+        trailer.remove(b"Prev");
+        trailer.remove(b"XRefStm");
+
+        boundaries.extend(xref.entries.values().filter_map(|entry| match *entry {
+            XrefEntry::Normal { offset, .. } => Some(offset as u64),
+            _ => None,
+        }));
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        Ok(LazyDocument {
+            source,
+            version,
+            trailer,
+            reference_table: xref,
+            boundaries,
+            header_offset,
+            xref_start,
+            cache: LinkedHashMap::new(),
+            cache_capacity: options.cache_capacity.map(|capacity| cmp::max(capacity, 1)),
+            options,
+        })
+    }
+
+    /// Limit the number of cached objects to `capacity`, which is at least 1.
+    ///
+    /// When an object is read while the cache is full, the least recently used objects are
+    /// removed from the cache, to be read from the source again the next time they are accessed.
+    /// The capacity can also be set when opening the document, with
+    /// [`LoadOptions::cache_capacity`].
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = Some(cmp::max(capacity, 1));
+        self.shrink_cache();
+        self
+    }
+
+    /// Get object by object id, reading and parsing it if it is not cached yet.
+    pub fn get_object(&mut self, id: ObjectId) -> Result<&Object> {
+        if self.cache.get_refresh(&id).is_none() {
+            self.load_object(id)?;
+            self.shrink_cache();
+        }
+        self.cache.get(&id).ok_or(Error::ObjectNotFound)
+    }
+
+    /// Get dictionary object by id, following a single reference.
+    pub fn get_dictionary(&mut self, id: ObjectId) -> Result<&Dictionary> {
+        let id = match self.get_object(id)? {
+            Object::Reference(target) => *target,
+            _ => id,
+        };
+        self.get_object(id).and_then(Object::as_dict)
+    }
+
+    /// Get the document catalog.
+    pub fn catalog(&mut self) -> Result<&Dictionary> {
+        let id = self.trailer.get(b"Root").and_then(Object::as_reference)?;
+        self.get_dictionary(id)
+    }
+
+    /// Whether the object is currently held in the cache.
+    pub fn is_cached(&self, id: ObjectId) -> bool {
+        self.cache.contains_key(&id)
+    }
+
+    /// Number of objects currently held in the cache.
+    pub fn cached_objects(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Remove an object from the cache, returning it if it was cached.
+    /// It will be read from the source again the next time it is accessed.
+    pub fn evict(&mut self, id: ObjectId) -> Option<Object> {
+        self.cache.remove(&id)
+    }
+
+    /// Remove all objects from the cache.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Read all objects of the cross-reference table, including those already cached, into a
+    /// [`Document`].
+    ///
+    /// Objects that can't be read are skipped, like [`Document::load`] does. The object streams
+    /// of encrypted documents can only be parsed once they are decrypted, so the objects they
+    /// contain are left to [`Document::decrypt`].
+    pub fn into_document(mut self) -> Result<Document> {
+        let is_encrypted = self.trailer.has(b"Encrypt");
+        let ids: Vec<ObjectId> = self
+            .reference_table
+            .entries
+            .iter()
+            .filter_map(|(&number, entry)| match *entry {
+                XrefEntry::Normal { generation, .. } => Some((number, generation)),
+                XrefEntry::Compressed { .. } if !is_encrypted => Some((number, 0)),
+                _ => None,
+            })
+            .collect();
+        self.cache_capacity = None;
+        for id in ids {
+            if let Err(err) = self.get_object(id) {
+                error!("Object load error: {}", err);
+            }
+        }
+
+        let mut document = Document::new();
+        self.reference_table.size = self.reference_table.max_id() + 1;
+        document.version = self.version;
+        document.max_id = self.reference_table.size - 1;
+        document.trailer = self.trailer;
+        document.reference_table = self.reference_table;
+        document.objects = self.cache.into_iter().collect();
+        document.xref_start = self.xref_start as usize;
+        document.header_offset = self.header_offset as usize;
+        Ok(document)
+    }
+
+    fn shrink_cache(&mut self) {
+        if let Some(capacity) = self.cache_capacity {
+            while self.cache.len() > capacity {
+                self.cache.pop_front();
+            }
+        }
+    }
+
+    fn load_object(&mut self, id: ObjectId) -> Result<()> {
+        match *self.reference_table.get(id.0).ok_or(Error::ObjectNotFound)? {
+            XrefEntry::Normal { offset, generation } if generation == id.1 => {
+                let offset = offset as u64;
+                let end = self
+                    .boundaries
+                    .get(self.boundaries.partition_point(|&boundary| boundary <= offset))
+                    .copied()
+                    .ok_or(Error::Offset(offset as usize))?;
+                let buffer = read_at(&mut self.source, self.header_offset + offset, end - offset)?;
+                let parse = |reader: &Reader| {
+                    parser::indirect_object(&buffer, 0, Some(id), reader).map_err(|err| match err {
+                        // Report the offset in the file rather than in the buffer.
+                        Error::Parse {
+                            offset: at,
+                            id,
+                            context,
+                        } => Error::Parse {
+                            offset: offset as usize + at,
+                            id,
+                            context,
+                        },
+                        Error::ObjectIdRange { offset: at, id } => Error::ObjectIdRange {
+                            offset: offset as usize + at,
+                            id,
+                        },
+                        err => err,
+                    })
+                };
+                let mut reader = Reader::with_options(&buffer, self.options);
+                let (_, mut object) = parse(&reader)?;
+                // The buffer only holds this object, so the reader can't find the indirect
+                // `Length` of a stream and recovers its data by scanning for `endstream`, which
+                // may appear in the data. Read the length here and parse the object again.
+                let missing = std::mem::take(&mut *reader.missing.lock().unwrap());
+                let mut resolved = false;
+                for missing_id in missing.into_iter().filter(|&missing_id| missing_id != id) {
+                    if let Ok(length) = self.get_object(missing_id) {
+                        reader.document.objects.insert(missing_id, length.clone());
+                        resolved = true;
+                    }
+                }
+                if resolved {
+                    object = parse(&reader)?.1;
+                }
+                self.cache.insert(id, object);
+                Ok(())
+            }
+            XrefEntry::Compressed { container, .. } if id.1 == 0 => {
+                self.get_object((container, 0))?;
+                let stream = self
+                    .cache
+                    .get_mut(&(container, 0))
+                    .ok_or(Error::ObjectNotFound)?
+                    .as_stream_mut()?;
+                let objects = ObjectStream::new(stream)?.objects;
+                // Cache all objects that the xref table places in this object stream,
+                // so the stream doesn't need to be parsed again for its neighbours.
+                let mut requested = None;
+                for (object_id, object) in objects {
+                    if let Some(XrefEntry::Compressed { container: c, .. }) = self.reference_table.get(object_id.0) {
+                        if *c != container {
+                            continue;
+                        }
+                        if object_id == id {
+                            requested = Some(object);
+                        } else if !self.cache.contains_key(&object_id) {
+                            self.cache.insert(object_id, object);
+                        }
+                    }
+                }
+                // The requested object is the most recently used, so it is evicted last.
+                self.cache.insert(id, requested.ok_or(Error::ObjectNotFound)?);
+                Ok(())
+            }
+            _ => Err(Error::ObjectNotFound),
+        }
+    }
+}
+
+fn read_at<R: Read + Seek>(source: &mut R, offset: u64, len: u64) -> Result<Vec<u8>> {
+    source.seek(SeekFrom::Start(offset))?;
+    let mut buffer = Vec::with_capacity(len as usize);
+    source.take(len).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Read the cross-reference section at `offset`, reading larger chunks until it parses.
+fn read_xref<R: Read + Seek>(
+    source: &mut R, offset: u64, len: u64, options: LoadOptions,
+) -> Result<(Xref, Dictionary)> {
+    let mut size = CHUNK_SIZE;
+    loop {
+        let buffer = read_at(source, offset, size)?;
+        let reader = Reader::with_options(&buffer, options);
+        match parser::xref_and_trailer(&buffer, &reader) {
+            Ok(section) => return Ok(section),
+            Err(err) if offset + size >= len => return Err(err),
+            Err(_) => size *= 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lazy_objects_match_eager_load() {
+        for path in [
+            "assets/example.pdf",
+            "assets/AnnotationDemo.pdf",
+            "assets/Incremental.pdf",
+        ] {
+            let doc = Document::load(path).unwrap();
+            let mut lazy = Document::load_lazy(path).unwrap();
+            assert_eq!(lazy.version, doc.version);
+            assert_eq!(lazy.cached_objects(), 0);
+
+            for (id, object) in &doc.objects {
+                if object.type_name().ok() == Some("ObjStm") {
+                    continue;
+                }
+                assert_eq!(lazy.get_object(*id).unwrap(), object, "{} {:?}", path, id);
+            }
+            assert!(lazy.catalog().is_ok());
+        }
+    }
+
+    #[test]
+    fn load_seekable_matches_eager_load() {
+        for path in [
+            "assets/example.pdf",
+            "assets/AnnotationDemo.pdf",
+            "assets/Incremental.pdf",
+        ] {
+            let doc = Document::load(path).unwrap();
+            let loaded = Document::load_seekable(File::open(path).unwrap()).unwrap();
+            assert_eq!(loaded.version, doc.version);
+            assert_eq!(loaded.max_id, doc.max_id);
+            assert_eq!(loaded.trailer, doc.trailer);
+            assert_eq!(loaded.xref_start, doc.xref_start);
+            assert_eq!(loaded.objects, doc.objects, "{}", path);
+        }
+
+        let mut lazy = Document::load_lazy("assets/example.pdf").unwrap();
+        lazy.catalog().unwrap();
+        let doc = lazy.into_document().unwrap();
+        assert_eq!(doc.objects, Document::load("assets/example.pdf").unwrap().objects);
+    }
+
+    #[test]
+    fn load_stream_with_indirect_length() {
+        let buffer = crate::reader::build_document(
+            &[
+                b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+                b"2 0 obj<</Type/Pages/Kids[]/Count 0>>endobj",
+                b"3 0 obj<</Length 4 0 R>>stream\nBT endstream ET\n\nendstream\nendobj",
+                b"4 0 obj 16 endobj",
+            ],
+            "<</Root 1 0 R/Size 5>>",
+        );
+        let mut lazy = LazyDocument::new(std::io::Cursor::new(&buffer)).unwrap();
+        let stream = lazy.get_object((3, 0)).and_then(Object::as_stream).unwrap();
+        assert_eq!(stream.content, b"BT endstream ET\n");
+        assert_eq!(stream.recovered_length, None);
+        assert!(lazy.is_cached((4, 0)));
+
+        let doc = Document::load_seekable(std::io::Cursor::new(&buffer)).unwrap();
+        let stream = doc.get_object((3, 0)).and_then(Object::as_stream).unwrap();
+        assert_eq!(stream.content, b"BT endstream ET\n");
+        assert_eq!(stream.recovered_length, None);
+        assert_eq!(doc.objects, Document::load_mem(&buffer).unwrap().objects);
+
+        // The options apply to every object read.
+        let options = LoadOptions {
+            max_nesting_depth: 1,
+            ..LoadOptions::default()
+        };
+        let doc = Document::load_seekable_with_options(std::io::Cursor::new(&buffer), options).unwrap();
+        assert!(doc.get_object((1, 0)).is_ok());
+        assert!(doc.get_object((2, 0)).is_err());
+
+        // All objects are loaded, whatever the cache capacity.
+        let options = LoadOptions {
+            cache_capacity: Some(1),
+            ..LoadOptions::default()
+        };
+        let doc = Document::load_seekable_with_options(std::io::Cursor::new(&buffer), options).unwrap();
+        assert_eq!(doc.objects, Document::load_mem(&buffer).unwrap().objects);
+    }
+
+    #[test]
+    fn lazy_cache_eviction() {
+        let mut lazy = Document::load_lazy("assets/example.pdf").unwrap();
+        let root = lazy.trailer.get(b"Root").and_then(Object::as_reference).unwrap();
+
+        lazy.catalog().unwrap();
+        assert!(lazy.is_cached(root));
+        assert_eq!(lazy.cached_objects(), 1);
+
+        assert!(lazy.evict(root).is_some());
+        assert!(!lazy.is_cached(root));
+        assert!(lazy.get_object(root).is_ok());
+
+        lazy.clear_cache();
+        assert_eq!(lazy.cached_objects(), 0);
+        assert!(matches!(lazy.get_object((9999, 0)), Err(Error::ObjectNotFound)));
+    }
+
+    #[test]
+    fn lazy_cache_capacity() {
+        for path in ["assets/example.pdf", "assets/AnnotationDemo.pdf"] {
+            let doc = Document::load(path).unwrap();
+            let mut lazy = Document::load_lazy(path).unwrap().with_cache_capacity(2);
+            let ids: Vec<_> = doc
+                .objects
+                .keys()
+                .copied()
+                .filter(|id| matches!(lazy.reference_table.get(id.0), Some(XrefEntry::Normal { .. })))
+                .take(3)
+                .collect();
+
+            lazy.get_object(ids[0]).unwrap();
+            lazy.get_object(ids[1]).unwrap();
+            // Using the first object again makes the second the least recently used.
+            lazy.get_object(ids[0]).unwrap();
+            lazy.get_object(ids[2]).unwrap();
+            assert_eq!(lazy.cached_objects(), 2);
+            assert!(lazy.is_cached(ids[0]) && lazy.is_cached(ids[2]));
+            assert!(!lazy.is_cached(ids[1]));
+
+            // Evicted objects are parsed again, also those in object streams.
+            for (id, object) in &doc.objects {
+                if object.type_name().ok() != Some("ObjStm") {
+                    assert_eq!(lazy.get_object(*id).unwrap(), object, "{} {:?}", path, id);
+                    assert!(lazy.cached_objects() <= 2);
+                }
+            }
+        }
+
+        let options = LoadOptions {
+            cache_capacity: Some(2),
+            ..LoadOptions::default()
+        };
+        let mut lazy = LazyDocument::with_options(File::open("assets/example.pdf").unwrap(), options).unwrap();
+        let ids: Vec<_> = lazy.reference_table.entries.keys().map(|&number| (number, 0)).collect();
+        for id in ids {
+            let _ = lazy.get_object(id);
+            assert!(lazy.cached_objects() <= 2);
+        }
+    }
+}
@@ -1,36 +1,63 @@
 #![doc = include_str!("../README.md")]
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "mmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "mmap", deny(unsafe_code))]
 #![deny(clippy::all)]
 
 #[macro_use]
 mod object;
 mod datetime;
-pub use crate::object::{Dictionary, Object, ObjectId, Stream, StringFormat};
+pub use crate::object::{CompressOptions, Dictionary, Object, ObjectId, Stream, StringFormat};
+pub use datetime::PdfDate;
 
 mod document;
 mod incremental_document;
+mod linearization;
 mod object_stream;
 #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
 pub use object_stream::ObjectStream;
 pub mod xref;
 pub use crate::document::Document;
 pub use crate::incremental_document::IncrementalDocument;
+mod lazy;
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+pub use crate::lazy::LazyDocument;
+mod mmap;
 
+mod annotation;
+pub use crate::annotation::{Action, Annotation, AnnotationKind, Link, LinkTarget};
+mod attachments;
+pub use crate::attachments::Attachment;
 mod bookmarks;
 pub use crate::bookmarks::Bookmark;
 mod outlines;
-pub use crate::outlines::Outline;
+pub use crate::outlines::{Outline, OutlineItem};
 mod destinations;
 pub use crate::destinations::Destination;
 mod toc;
 pub use crate::toc::Toc;
+pub mod cmap;
+mod color_space;
+pub use color_space::ColorSpace;
 pub mod content;
 mod creator;
 mod encodings;
 pub mod encryption;
+#[cfg(feature = "encrypt")]
+pub use encryption::EncryptOptions;
 mod error;
 pub use error::XrefError;
+mod fonts;
+pub use fonts::EmbeddedFont;
+mod forms;
+pub use forms::FormField;
+mod info;
+pub use info::DocumentInfo;
+mod layers;
+pub use layers::Layer;
 pub mod filters;
+pub mod name_tree;
+mod page_labels;
+pub use page_labels::{PageLabelRange, PageLabelStyle};
 #[cfg(not(feature = "nom_parser"))]
 #[cfg(feature = "pom_parser")]
 mod parser;
@@ -38,13 +65,23 @@
 #[path = "nom_parser.rs"]
 mod parser;
 mod parser_aux;
+mod pretty_print;
 mod processor;
 #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
 mod reader;
 #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
-pub use reader::Reader;
+pub use reader::{LoadOptions, Reader};
 mod rc4;
+mod signature;
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+pub use signature::{SignatureOptions, SignaturePlaceholder};
+mod structure;
+pub use structure::{StructElement, StructKid, StructTree};
+mod truetype;
+mod validation;
+pub use validation::ValidationIssue;
 mod writer;
+pub use writer::SaveOptions;
 pub mod xobject;
 
 pub use error::{Error, Result};
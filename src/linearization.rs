@@ -0,0 +1,546 @@
+//! Writing of linearized documents, also known as fast web view.
+//!
+//! A linearized file starts with everything needed to display the first page, followed by the
+//! objects of every other page in order, so viewers can show the first page while the rest of
+//! the file is still being downloaded and fetch other pages with range requests.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::writer::{CountingWrite, Writer};
+use crate::xref::{Xref, XrefType};
+use crate::{Dictionary, Document, Error, Object, ObjectId, Result, Stream};
+
+impl Document {
+    /// Save PDF document to specified file path, linearized for fast web view.
+    ///
+    /// See [`Document::save_linearized_to`].
+    pub fn save_linearized<P: AsRef<Path>>(&self, path: P) -> Result<File> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.save_linearized_to(&mut file)?;
+        Ok(file.into_inner().map_err(|err| err.into_error())?)
+    }
+
+    /// Save PDF to arbitrary target, linearized for fast web view.
+    ///
+    /// The objects are renumbered and written in the order of Annex F of the PDF specification:
+    /// the linearization parameter dictionary and the cross-reference section of the first page,
+    /// the catalog, the primary hint stream, every object the first page needs, the objects of
+    /// each following page, the objects shared by several pages and finally all other objects,
+    /// such as the page tree, outlines and the document information dictionary. The hint stream
+    /// holds the page offset and shared object hint tables.
+    ///
+    /// This first implementation does not handle everything the specification allows:
+    ///
+    /// * The optional hint tables, such as those for thumbnails, outlines or named destinations,
+    ///   are not written, and the outlines are not moved to the start of the file when the
+    ///   catalog opens the document with them.
+    /// * Content stream offsets and lengths in the page offset hint table cover the whole page.
+    /// * Objects are not packed into object streams and cross-reference tables are always used.
+    /// * Encrypted documents must be decrypted first, [`Error::AlreadyEncrypted`] is returned
+    ///   otherwise.
+    /// * The document must have at least one page.
+    pub fn save_linearized_to<W: Write>(&self, target: &mut W) -> Result<()> {
+        if self.trailer.has(b"Encrypt") {
+            return Err(Error::AlreadyEncrypted);
+        }
+        let catalog_id = self.trailer.get(b"Root").and_then(Object::as_reference)?;
+        self.get_dictionary(catalog_id)?;
+        let pages: Vec<ObjectId> = self.page_iter().collect();
+        let first_page = *pages.first().ok_or(Error::PageNumberNotFound(1))?;
+
+        // Objects each page needs to be displayed, apart from other pages and the page tree.
+        let page_objects: Vec<BTreeSet<ObjectId>> = pages
+            .iter()
+            .map(|&page_id| {
+                let mut ids = BTreeSet::new();
+                if let Ok(page) = self.page_with_inherited_attributes(page_id) {
+                    self.collect_page_objects(&page, &mut ids);
+                }
+                ids.remove(&catalog_id);
+                ids
+            })
+            .collect();
+
+        let mut placed = BTreeSet::from([catalog_id, first_page]);
+        let mut first_page_section = vec![first_page];
+        first_page_section.extend(page_objects[0].iter().filter(|&&id| placed.insert(id)));
+
+        let mut users = BTreeMap::<ObjectId, usize>::new();
+        for ids in &page_objects[1..] {
+            for &id in ids {
+                *users.entry(id).or_default() += 1;
+            }
+        }
+        let mut page_sections = vec![];
+        for (&page_id, ids) in pages[1..].iter().zip(&page_objects[1..]) {
+            let mut section = vec![];
+            if placed.insert(page_id) {
+                section.push(page_id);
+            }
+            section.extend(ids.iter().filter(|&id| users[id] == 1 && placed.insert(*id)));
+            page_sections.push(section);
+        }
+        let shared_section: Vec<ObjectId> = users.keys().copied().filter(|&id| placed.insert(id)).collect();
+        let other_objects: Vec<ObjectId> = self
+            .objects
+            .iter()
+            .filter(|(id, object)| {
+                !placed.contains(id) && !matches!(object.type_name(), Ok("ObjStm") | Ok("XRef") | Ok("Linearized"))
+            })
+            .map(|(&id, _)| id)
+            .collect();
+
+        // Objects after the first page are numbered from 1, those at the start of the file after them.
+        let main_section: Vec<ObjectId> = page_sections
+            .iter()
+            .flatten()
+            .chain(&shared_section)
+            .chain(&other_objects)
+            .copied()
+            .collect();
+        let main_count = main_section.len() as u32;
+        let linearization_id = main_count + 1;
+        let hint_id = main_count + 3 + first_page_section.len() as u32;
+        let mut renumbering = BTreeMap::new();
+        for (new_id, &id) in (1..).zip(&main_section) {
+            renumbering.insert(id, (new_id, 0));
+        }
+        renumbering.insert(catalog_id, (main_count + 2, 0));
+        for (new_id, &id) in (main_count + 3..).zip(&first_page_section) {
+            renumbering.insert(id, (new_id, 0));
+        }
+        let new_id = |id: &ObjectId| renumbering[id].0;
+
+        let mut serialized = BTreeMap::new();
+        for (id, object) in &self.objects {
+            if let Some(&(new_id, _)) = renumbering.get(id) {
+                let mut object = object.clone();
+                renumber_references(&mut object, &renumbering);
+                serialized.insert(new_id, serialize_object(new_id, &object)?);
+            }
+        }
+
+        // Offsets as if the hint stream was not there, which is how hint tables store them.
+        let mut header = format!("%PDF-{}\n", self.version).into_bytes();
+        header.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+        let linearization_len = linearization_dictionary(linearization_id, &[0; 6], pages.len()).len();
+        let mut trailer = self.trailer.clone();
+        for key in [b"Size".as_slice(), b"Prev", b"XRefStm"] {
+            trailer.remove(key);
+        }
+        renumber_dictionary_references(&mut trailer, &renumbering);
+        trailer.set("Size", i64::from(hint_id + 1));
+        let first_xref_start = header.len() + linearization_len;
+        let first_section_ids: Vec<u32> = (linearization_id..=hint_id).collect();
+        let first_xref_len = first_page_xref(&first_section_ids, &BTreeMap::new(), &trailer, 0)?.len();
+
+        let catalog_offset = first_xref_start + first_xref_len;
+        let hint_offset = catalog_offset + serialized[&(main_count + 2)].len();
+        let mut offsets = BTreeMap::new();
+        let mut position = hint_offset;
+        for id in first_page_section.iter().chain(&main_section) {
+            offsets.insert(new_id(id), position);
+            position += serialized[&new_id(id)].len();
+        }
+        let length = |id: &ObjectId| serialized[&new_id(id)].len() as u64;
+        let first_page_end = hint_offset + first_page_section.iter().map(length).sum::<u64>() as usize;
+
+        let mut hints = HintTables::default();
+        let shared_index: BTreeMap<ObjectId, u64> = first_page_section
+            .iter()
+            .chain(&shared_section)
+            .copied()
+            .zip(0..)
+            .collect();
+        hints.pages.push(PageHint {
+            object_count: first_page_section.len() as u64,
+            length: first_page_section.iter().map(length).sum(),
+            shared_objects: vec![],
+        });
+        for (section, ids) in page_sections.iter().zip(&page_objects[1..]) {
+            hints.pages.push(PageHint {
+                object_count: section.len() as u64,
+                length: section.iter().map(length).sum(),
+                shared_objects: ids.iter().filter_map(|id| shared_index.get(id).copied()).collect(),
+            });
+        }
+        hints.first_page_offset = offsets[&new_id(&first_page)] as u64;
+        hints.shared_lengths = first_page_section.iter().chain(&shared_section).map(length).collect();
+        hints.first_page_shared_count = first_page_section.len() as u64;
+        if let Some(first_shared) = shared_section.first() {
+            hints.first_shared_id = new_id(first_shared) as u64;
+            hints.first_shared_offset = offsets[&new_id(first_shared)] as u64;
+        }
+        let (data, shared_table_start) = hints.encode();
+        let mut hint_stream = Stream::new(Dictionary::new(), data).with_compression(false);
+        hint_stream.dict.set("S", shared_table_start as i64);
+        let hint_bytes = serialize_object(hint_id, &Object::Stream(hint_stream))?;
+
+        // Move everything after the hint stream to its actual position.
+        for offset in offsets.values_mut() {
+            *offset += hint_bytes.len();
+        }
+        offsets.insert(main_count + 2, catalog_offset);
+        offsets.insert(hint_id, hint_offset);
+        offsets.insert(linearization_id, header.len());
+        let main_xref_start = position + hint_bytes.len();
+        let main_xref = main_xref(main_count, &offsets, first_xref_start);
+        let main_xref_header_len = format!("xref\n0 {}\n", main_count + 1).len();
+        let parameters = [
+            (main_xref_start + main_xref.len()) as u64,
+            hint_offset as u64,
+            hint_bytes.len() as u64,
+            new_id(&first_page) as u64,
+            (first_page_end + hint_bytes.len()) as u64,
+            (main_xref_start + main_xref_header_len - 1) as u64,
+        ];
+
+        let mut target = CountingWrite {
+            inner: target,
+            bytes_written: 0,
+        };
+        target.write_all(&header)?;
+        target.write_all(&linearization_dictionary(linearization_id, &parameters, pages.len()))?;
+        target.write_all(&first_page_xref(
+            &first_section_ids,
+            &offsets,
+            &trailer,
+            main_xref_start,
+        )?)?;
+        target.write_all(&serialized[&(main_count + 2)])?;
+        target.write_all(&hint_bytes)?;
+        for id in first_page_section.iter().chain(&main_section) {
+            target.write_all(&serialized[&new_id(id)])?;
+        }
+        debug_assert_eq!(target.bytes_written, main_xref_start);
+        target.write_all(&main_xref)?;
+        Ok(())
+    }
+}
+
+/// Point references to their renumbered objects, references to missing objects become null.
+pub(crate) fn renumber_references(object: &mut Object, renumbering: &BTreeMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => match renumbering.get(id) {
+            Some(&new_id) => *id = new_id,
+            None => *object = Object::Null,
+        },
+        Object::Array(array) => array.iter_mut().for_each(|item| renumber_references(item, renumbering)),
+        Object::Dictionary(dict) => renumber_dictionary_references(dict, renumbering),
+        Object::Stream(stream) => renumber_dictionary_references(&mut stream.dict, renumbering),
+        _ => {}
+    }
+}
+
+fn renumber_dictionary_references(dict: &mut Dictionary, renumbering: &BTreeMap<ObjectId, ObjectId>) {
+    dict.iter_mut()
+        .for_each(|(_, value)| renumber_references(value, renumbering));
+}
+
+fn serialize_object(id: u32, object: &Object) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let mut target = CountingWrite {
+        inner: &mut buffer,
+        bytes_written: 0,
+    };
+    let mut xref = Xref::new(id + 1, XrefType::CrossReferenceTable);
+    Writer::write_indirect_object(&mut target, id, 0, object, &mut xref)?;
+    Ok(buffer)
+}
+
+/// The linearization parameter dictionary, with all offsets and lengths padded to a fixed width
+/// so its size doesn't depend on them.
+///
+/// `parameters` holds the file length, the offset and length of the primary hint stream, the
+/// object number of the first page, the end of the first page and the offset of the first entry
+/// of the main cross-reference table.
+fn linearization_dictionary(id: u32, parameters: &[u64; 6], page_count: usize) -> Vec<u8> {
+    let [file_len, hint_offset, hint_len, first_page, first_page_end, main_xref_entries] = *parameters;
+    format!(
+        "{} 0 obj\n<</Linearized 1/L {:010}/H[{:010} {:010}]/O {:010}/E {:010}/N {}/T {:010}>>\nendobj\n",
+        id, file_len, hint_offset, hint_len, first_page, first_page_end, page_count, main_xref_entries
+    )
+    .into_bytes()
+}
+
+/// The cross-reference section of the objects at the start of the file and the trailer pointing
+/// to the main cross-reference table, padded like the linearization parameter dictionary.
+fn first_page_xref(
+    ids: &[u32], offsets: &BTreeMap<u32, usize>, trailer: &Dictionary, main_xref_start: usize,
+) -> Result<Vec<u8>> {
+    let mut buffer = format!("xref\n{} {}\n", ids[0], ids.len()).into_bytes();
+    for id in ids {
+        writeln!(buffer, "{:010} 00000 n ", offsets.get(id).copied().unwrap_or(0))?;
+    }
+    buffer.extend_from_slice(b"trailer\n");
+    Writer::write_dictionary(&mut buffer, trailer)?;
+    // Insert Prev before the closing `>>` of the trailer.
+    buffer.truncate(buffer.len() - 2);
+    write!(buffer, "/Prev {:010}>>\nstartxref\n0\n%%EOF\n", main_xref_start)?;
+    Ok(buffer)
+}
+
+/// The cross-reference table of the objects numbered from 1 to `count` and its trailer.
+fn main_xref(count: u32, offsets: &BTreeMap<u32, usize>, first_xref_start: usize) -> Vec<u8> {
+    let mut buffer = format!("xref\n0 {}\n0000000000 65535 f \n", count + 1);
+    for id in 1..=count {
+        buffer.push_str(&format!("{:010} 00000 n \n", offsets[&id]));
+    }
+    buffer.push_str(&format!(
+        "trailer\n<</Size {}>>\nstartxref\n{}\n%%EOF",
+        count + 1,
+        first_xref_start
+    ));
+    buffer.into_bytes()
+}
+
+/// Entry of a page in the page offset hint table.
+#[derive(Debug, Default)]
+struct PageHint {
+    object_count: u64,
+    length: u64,
+    /// Indices of the shared objects the page uses in the shared object hint table.
+    shared_objects: Vec<u64>,
+}
+
+/// Contents of the page offset and shared object hint tables, every shared object group holds a
+/// single object.
+#[derive(Debug, Default)]
+struct HintTables {
+    pages: Vec<PageHint>,
+    first_page_offset: u64,
+    first_shared_id: u64,
+    first_shared_offset: u64,
+    first_page_shared_count: u64,
+    shared_lengths: Vec<u64>,
+}
+
+impl HintTables {
+    /// Encode the hint stream data, returning it with the start of the shared object hint table.
+    fn encode(&self) -> (Vec<u8>, usize) {
+        let mut bits = BitWriter::default();
+        let (min_objects, object_bits) = range(self.pages.iter().map(|page| page.object_count));
+        let (min_length, length_bits) = range(self.pages.iter().map(|page| page.length));
+        let (_, shared_count_bits) = range(
+            self.pages
+                .iter()
+                .map(|page| page.shared_objects.len() as u64)
+                .chain([0]),
+        );
+        let shared_ids = self.pages.iter().flat_map(|page| page.shared_objects.iter().copied());
+        let (_, shared_id_bits) = range(shared_ids.chain([0]));
+
+        // Page offset hint table header (table F.3)
+        bits.write(min_objects, 32);
+        bits.write(self.first_page_offset, 32);
+        bits.write(object_bits as u64, 16);
+        bits.write(min_length, 32);
+        bits.write(length_bits as u64, 16);
+        // Content streams are assumed to span the whole page.
+        bits.write(0, 32);
+        bits.write(0, 16);
+        bits.write(min_length, 32);
+        bits.write(length_bits as u64, 16);
+        bits.write(shared_count_bits as u64, 16);
+        bits.write(shared_id_bits as u64, 16);
+        bits.write(0, 16);
+        bits.write(1, 16);
+
+        // Per-page entries (table F.4), each item for all pages
+        for page in &self.pages {
+            bits.write(page.object_count - min_objects, object_bits);
+        }
+        bits.flush();
+        for page in &self.pages {
+            bits.write(page.length - min_length, length_bits);
+        }
+        bits.flush();
+        for page in &self.pages {
+            bits.write(page.shared_objects.len() as u64, shared_count_bits);
+        }
+        bits.flush();
+        for page in &self.pages {
+            for &index in &page.shared_objects {
+                bits.write(index, shared_id_bits);
+            }
+        }
+        bits.flush();
+        for page in &self.pages {
+            bits.write(page.length - min_length, length_bits);
+        }
+        bits.flush();
+        let shared_table_start = bits.bytes.len();
+
+        // Shared object hint table header (table F.5)
+        let (min_shared_length, shared_length_bits) = range(self.shared_lengths.iter().copied());
+        bits.write(self.first_shared_id, 32);
+        bits.write(self.first_shared_offset, 32);
+        bits.write(self.first_page_shared_count, 32);
+        bits.write(self.shared_lengths.len() as u64, 32);
+        bits.write(0, 16);
+        bits.write(min_shared_length, 32);
+        bits.write(shared_length_bits as u64, 16);
+
+        // Shared object group entries (table F.6), without signatures
+        for &length in &self.shared_lengths {
+            bits.write(length - min_shared_length, shared_length_bits);
+        }
+        bits.flush();
+        for _ in &self.shared_lengths {
+            bits.write(0, 1);
+        }
+        bits.flush();
+        (bits.bytes, shared_table_start)
+    }
+}
+
+/// The least value and the number of bits needed for the difference to the greatest value.
+fn range(values: impl Iterator<Item = u64>) -> (u64, u32) {
+    let (min, max) = values.fold((u64::MAX, 0), |(min, max), value| (min.min(value), max.max(value)));
+    let min = min.min(max);
+    (min, u64::BITS - (max - min).leading_zeros())
+}
+
+/// Writes values with a given number of bits, most significant bit first.
+#[derive(Debug, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    used: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            self.current = self.current << 1 | ((value >> bit) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    /// Pad the last byte with zero bits.
+    fn flush(&mut self) {
+        if self.used > 0 {
+            self.bytes.push(self.current << (8 - self.used));
+            self.current = 0;
+            self.used = 0;
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "pom_parser", feature = "nom_parser")))]
+mod tests {
+    use super::*;
+
+    fn multi_page_document() -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Courier" });
+        let logo_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            },
+            b"0 0 10 10 re f".to_vec(),
+        ));
+        let mut kids = vec![];
+        for i in 0..4 {
+            let content = format!("BT /F1 12 Tf 100 700 Td (Page {}) Tj ET", i + 1);
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+            let mut page = dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id };
+            if i > 1 {
+                page.set(
+                    "Resources",
+                    dictionary! { "XObject" => dictionary! { "Logo" => logo_id } },
+                );
+            }
+            kids.push(doc.add_object(page).into());
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => 4,
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        let info_id = doc.add_object(dictionary! { "Title" => Object::string_literal("Linearized") });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+        doc
+    }
+
+    #[test]
+    fn save_linearized_document() {
+        let doc = multi_page_document();
+        let mut buffer = Vec::new();
+        doc.save_linearized_to(&mut buffer).unwrap();
+        assert!(buffer.starts_with(b"%PDF-1.5\n"));
+
+        let loaded = Document::load_mem(&buffer).unwrap();
+        let (&linearization_id, parameters) = loaded
+            .objects
+            .iter()
+            .find(|(_, object)| object.as_dict().is_ok_and(|dict| dict.has(b"Linearized")))
+            .unwrap();
+        let parameters = parameters.as_dict().unwrap();
+        let get = |key: &[u8]| parameters.get(key).and_then(Object::as_i64).unwrap() as usize;
+        let object_at = |offset: usize| {
+            let id = loaded
+                .objects
+                .keys()
+                .find(|id| buffer[offset..].starts_with(format!("{} 0 obj", id.0).as_bytes()));
+            id.copied()
+        };
+        assert_eq!(get(b"L"), buffer.len());
+        assert_eq!(get(b"N"), 4);
+        assert_eq!(
+            object_at(b"%PDF-1.5\n%\xE2\xE3\xCF\xD3\n".len()),
+            Some(linearization_id)
+        );
+
+        let hint = parameters.get(b"H").and_then(Object::as_array).unwrap();
+        let (hint_offset, hint_len) = (hint[0].as_i64().unwrap() as usize, hint[1].as_i64().unwrap() as usize);
+        let hint_id = object_at(hint_offset).unwrap();
+        assert!(loaded.get_object(hint_id).unwrap().as_stream().unwrap().dict.has(b"S"));
+        let first_page = loaded.page_iter().next().unwrap();
+        assert_eq!(first_page.0 as usize, get(b"O"));
+        assert_eq!(object_at(hint_offset + hint_len), Some(first_page));
+        let second_page = loaded.page_iter().nth(1).unwrap();
+        assert_eq!(object_at(get(b"E")), Some(second_page));
+        assert!(buffer[get(b"T")..].starts_with(b"\n0000000000 65535 f \n"));
+
+        // Every page still shows its own content.
+        for (page_number, page_id) in loaded.get_pages() {
+            let content = loaded.get_page_content(page_id).unwrap();
+            assert_eq!(
+                content,
+                format!("BT /F1 12 Tf 100 700 Td (Page {}) Tj ET", page_number).as_bytes()
+            );
+        }
+        assert_eq!(loaded.objects.len(), doc.objects.len() + 2);
+    }
+
+    #[test]
+    fn hint_tables_are_bit_packed() {
+        let mut bits = BitWriter::default();
+        bits.write(0b101, 3);
+        bits.write(0b1, 1);
+        bits.flush();
+        bits.write(0x1234, 16);
+        assert_eq!(bits.bytes, [0b1011_0000, 0x12, 0x34]);
+        assert_eq!(range([7, 3, 5].into_iter()), (3, 3));
+        assert_eq!(range([4].into_iter()), (4, 0));
+    }
+}
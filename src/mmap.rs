@@ -0,0 +1,77 @@
+#![cfg(all(feature = "mmap", any(feature = "pom_parser", feature = "nom_parser")))]
+
+//! Loading of memory-mapped files. This is the only module of the crate with unsafe code.
+
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{Document, LazyDocument, Result};
+
+impl Document {
+    /// Load a PDF document from a specified file path by mapping the file into memory, instead of
+    /// reading it into a buffer like [`Document::load`].
+    ///
+    /// The objects are parsed directly from the mapped pages, which the operating system reads on
+    /// demand, shares between processes mapping the same file and can evict under memory
+    /// pressure. The document owns its objects once loaded: the mapping is released before this
+    /// returns, and the document can be modified and saved like any other. As the parsed objects
+    /// hold copies of their data, the peak memory use is close to that of [`Document::load`]: use
+    /// [`Document::load_lazy_mmap`] to only read the objects that are accessed.
+    ///
+    /// The file must not be modified by another process while it is loaded. Truncating it can
+    /// crash the process with a bus error.
+    pub fn load_mmap<P: AsRef<Path>>(path: P) -> Result<Document> {
+        let map = map(&File::open(path)?)?;
+        Self::load_mem(&map)
+    }
+
+    /// Open a PDF document from a specified file path without parsing its objects, like
+    /// [`Document::load_lazy`], reading the objects from a memory mapping of the file.
+    ///
+    /// Only the pages of the objects that are accessed are read from the file. The file must not
+    /// be modified by another process as long as the returned document is used.
+    pub fn load_lazy_mmap<P: AsRef<Path>>(path: P) -> Result<LazyDocument<Cursor<Mmap>>> {
+        LazyDocument::new(Cursor::new(map(&File::open(path)?)?))
+    }
+}
+
+#[allow(unsafe_code)]
+fn map(file: &File) -> std::io::Result<Mmap> {
+    // SAFETY: the mapping is only ever read. Modifying the file while it is mapped, which could
+    // change the data under the parser or unmap pages, is ruled out by the documentation of the
+    // functions calling this one.
+    unsafe { Mmap::map(file) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creator::tests::create_document;
+    use crate::Document;
+
+    #[test]
+    fn load_mapped_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mapped.pdf");
+        create_document().save(&path).unwrap();
+
+        let load_mem = |path| Document::load_mem(&std::fs::read(path).unwrap()).unwrap();
+        let mut doc = Document::load_mmap(&path).unwrap();
+        let loaded = load_mem(&path);
+        assert_eq!(doc.objects, loaded.objects);
+        assert_eq!(doc.trailer, loaded.trailer);
+
+        // The document no longer depends on the file, which can be overwritten by saving.
+        doc.set_object(loaded.page_iter().next().unwrap(), dictionary! {});
+        doc.save(&path).unwrap();
+        let saved = Document::load_mmap(&path).unwrap();
+        assert_eq!(saved.objects, load_mem(&path).objects);
+        assert_ne!(saved.objects, loaded.objects);
+
+        let mut lazy = Document::load_lazy_mmap(&path).unwrap();
+        let page_id = loaded.page_iter().next().unwrap();
+        assert_eq!(lazy.get_object(page_id).unwrap(), saved.get_object(page_id).unwrap());
+    }
+}
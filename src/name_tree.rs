@@ -0,0 +1,209 @@
+//! Reading and writing of name trees, the maps with string keys of the catalog's `Names`
+//! dictionary, such as `Dests`, `EmbeddedFiles` and `JavaScript`, and reading of number trees,
+//! their counterpart with integer keys.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Dictionary, Document, Object, ObjectId};
+
+/// Maximum number of entries of a leaf node, and of kids of an intermediate node, written by
+/// [`write_name_tree`].
+pub const NODE_SIZE: usize = 32;
+
+/// Deeper trees are malformed or cyclic.
+const MAX_DEPTH: usize = 32;
+
+/// Read the entries of the name tree with the given root node, sorted by key.
+///
+/// The values are returned as stored in the `Names` arrays, references are not resolved. Entries
+/// that are not string keys followed by a value, and nodes that are not dictionaries or were
+/// already visited, are skipped.
+pub fn read_name_tree(doc: &Document, root: &Dictionary) -> BTreeMap<Vec<u8>, Object> {
+    let mut entries = BTreeMap::new();
+    let key = |key: &Object| match doc.dereference(key) {
+        Ok((_, Object::String(key, _))) => Some(key.clone()),
+        _ => None,
+    };
+    read_node(doc, root, b"Names", &key, &mut entries, &mut HashSet::new(), 0);
+    entries
+}
+
+/// Read the entries of the number tree with the given root node, such as the `PageLabels` of
+/// the catalog, sorted by key.
+///
+/// Like [`read_name_tree`], but with integer keys stored in `Nums` arrays.
+pub fn read_number_tree(doc: &Document, root: &Dictionary) -> BTreeMap<i64, Object> {
+    let mut entries = BTreeMap::new();
+    let key = |key: &Object| doc.dereference(key).and_then(|(_, key)| key.as_i64()).ok();
+    read_node(doc, root, b"Nums", &key, &mut entries, &mut HashSet::new(), 0);
+    entries
+}
+
+fn read_node<K: Ord>(
+    doc: &Document, node: &Dictionary, entries_key: &[u8], key: &impl Fn(&Object) -> Option<K>,
+    entries: &mut BTreeMap<K, Object>, visited: &mut HashSet<ObjectId>, depth: usize,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    if let Ok(names) = node.get_deref(entries_key, doc).and_then(Object::as_array) {
+        for pair in names.chunks_exact(2) {
+            if let Some(key) = key(&pair[0]) {
+                entries.insert(key, pair[1].clone());
+            }
+        }
+    }
+    let Ok(kids) = node.get_deref(b"Kids", doc).and_then(Object::as_array) else {
+        return;
+    };
+    for kid in kids {
+        if let Ok(id) = kid.as_reference() {
+            if !visited.insert(id) {
+                continue;
+            }
+        }
+        if let Ok((_, Object::Dictionary(kid))) = doc.dereference(kid) {
+            read_node(doc, kid, entries_key, key, entries, visited, depth + 1);
+        }
+    }
+}
+
+/// Write the entries as a balanced name tree, and return its root node.
+///
+/// Up to [`NODE_SIZE`] entries are stored in the root itself. Larger trees are split into leaves
+/// of at most [`NODE_SIZE`] entries, which are grouped into intermediate nodes of at most
+/// [`NODE_SIZE`] kids until they fit in the root. All leaves are at the same depth, and the
+/// leaves and intermediate nodes are added to the document with their `Limits`. The root is left
+/// to the caller, to add to the document or to store directly, for example in the catalog's
+/// `Names` dictionary.
+pub fn write_name_tree(doc: &mut Document, entries: &BTreeMap<Vec<u8>, Object>) -> Dictionary {
+    let names = |entries: &[(&Vec<u8>, &Object)]| -> Vec<Object> {
+        entries
+            .iter()
+            .flat_map(|&(key, value)| [Object::string_literal(key.clone()), value.clone()])
+            .collect()
+    };
+    let entries: Vec<_> = entries.iter().collect();
+    if entries.len() <= NODE_SIZE {
+        return dictionary! { "Names" => names(&entries) };
+    }
+
+    // The nodes of the current level, with the first and last key of each.
+    let mut nodes: Vec<(ObjectId, &[u8], &[u8])> = balanced_chunks(&entries)
+        .map(|leaf| {
+            let (first, last) = (leaf[0].0.as_slice(), leaf[leaf.len() - 1].0.as_slice());
+            let id = doc.add_object(dictionary! {
+                "Limits" => limits(first, last),
+                "Names" => names(leaf),
+            });
+            (id, first, last)
+        })
+        .collect();
+    while nodes.len() > NODE_SIZE {
+        nodes = balanced_chunks(&nodes)
+            .map(|kids| {
+                let (first, last) = (kids[0].1, kids[kids.len() - 1].2);
+                let id = doc.add_object(dictionary! {
+                    "Limits" => limits(first, last),
+                    "Kids" => kids.iter().map(|&(id, _, _)| Object::Reference(id)).collect::<Vec<_>>(),
+                });
+                (id, first, last)
+            })
+            .collect();
+    }
+    dictionary! {
+        "Kids" => nodes.into_iter().map(|(id, _, _)| Object::Reference(id)).collect::<Vec<_>>(),
+    }
+}
+
+/// Split the items into the fewest chunks of at most [`NODE_SIZE`] items, with sizes that differ
+/// by at most one.
+fn balanced_chunks<T>(items: &[T]) -> impl Iterator<Item = &[T]> {
+    let count = items.len().div_ceil(NODE_SIZE);
+    let (size, larger) = (items.len() / count, items.len() % count);
+    (0..count).map(move |i| {
+        let start = i * size + i.min(larger);
+        &items[start..start + size + usize::from(i < larger)]
+    })
+}
+
+fn limits(first: &[u8], last: &[u8]) -> Object {
+    vec![Object::string_literal(first), Object::string_literal(last)].into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{read_name_tree, write_name_tree, NODE_SIZE};
+    use crate::{Dictionary, Document, Object};
+
+    /// Check the `Limits` of the kids of a node, and return the depth of its leaves.
+    fn check_node(doc: &Document, node: &Dictionary) -> usize {
+        let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) else {
+            assert!(node.get(b"Names").unwrap().as_array().unwrap().len() <= NODE_SIZE * 2);
+            return 0;
+        };
+        assert!(kids.len() <= NODE_SIZE);
+        let depths: Vec<usize> = kids
+            .iter()
+            .map(|kid| {
+                let kid = doc.get_dictionary(kid.as_reference().unwrap()).unwrap();
+                let entries = read_name_tree(doc, kid);
+                let limits = kid.get(b"Limits").unwrap().as_array().unwrap();
+                assert_eq!(limits[0].as_str().unwrap(), entries.keys().next().unwrap());
+                assert_eq!(limits[1].as_str().unwrap(), entries.keys().last().unwrap());
+                check_node(doc, kid)
+            })
+            .collect();
+        assert!(depths.iter().all(|&depth| depth == depths[0]));
+        depths[0] + 1
+    }
+
+    fn entries(count: usize) -> BTreeMap<Vec<u8>, Object> {
+        (0..count)
+            .map(|i| (format!("name{:05}", i).into_bytes(), Object::Integer(i as i64)))
+            .collect()
+    }
+
+    #[test]
+    fn empty_name_tree() {
+        let mut doc = Document::new();
+        let root = write_name_tree(&mut doc, &BTreeMap::new());
+        assert_eq!(root, dictionary! { "Names" => Vec::<Object>::new() });
+        assert!(doc.objects.is_empty());
+        assert!(read_name_tree(&doc, &root).is_empty());
+        assert!(read_name_tree(&doc, &Dictionary::new()).is_empty());
+    }
+
+    #[test]
+    fn single_node_name_tree() {
+        let mut doc = Document::new();
+        let entries = entries(NODE_SIZE);
+        let root = write_name_tree(&mut doc, &entries);
+        assert!(doc.objects.is_empty());
+        assert!(!root.has(b"Limits"));
+        assert_eq!(check_node(&doc, &root), 0);
+        assert_eq!(read_name_tree(&doc, &root), entries);
+    }
+
+    #[test]
+    fn balanced_name_tree() {
+        let mut doc = Document::new();
+        let entries = entries(NODE_SIZE * NODE_SIZE + 1);
+        let root = write_name_tree(&mut doc, &entries);
+        assert_eq!(root.get(b"Kids").unwrap().as_array().unwrap().len(), 2);
+        assert!(!root.has(b"Limits"));
+        assert_eq!(check_node(&doc, &root), 2);
+        assert_eq!(read_name_tree(&doc, &root), entries);
+
+        // Kids referring back to an ancestor are only read once.
+        let root_id = doc.add_object(root.clone());
+        let leaf = doc
+            .objects
+            .values_mut()
+            .find_map(|object| object.as_dict_mut().ok().filter(|node| node.has(b"Names")));
+        leaf.unwrap().set("Kids", vec![root_id.into()]);
+        assert_eq!(read_name_tree(&doc, &root), entries);
+    }
+}
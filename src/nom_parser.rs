@@ -1,15 +1,19 @@
 use super::{Dictionary, Object, ObjectId, Reader, Stream, StringFormat};
 use crate::content::*;
 use crate::error::XrefError;
+use crate::parser_aux::{scan_inline_image_end, scan_stream_end, split_endobj};
+use crate::reader::MAX_NESTING_DEPTH;
 use crate::xref::*;
 use crate::Error;
+use log::warn;
+use std::ops::Range;
 use std::str::{self, FromStr};
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take, take_while, take_while1, take_while_m_n};
 use nom::character::complete::{digit0, digit1, one_of};
 use nom::character::{is_hex_digit, is_oct_digit};
-use nom::combinator::{map, map_opt, map_res, opt, verify};
+use nom::combinator::{map, map_opt, map_res, not, opt, verify};
 use nom::error::{ErrorKind, ParseError};
 use nom::multi::{fold_many0, fold_many1, many0, many0_count};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
@@ -26,13 +30,19 @@ fn strip_nom<O>(r: NomResult<O>) -> Option<O> {
 }
 
 #[inline]
-fn convert_result<O, E>(result: Result<O, E>, input: &[u8], error_kind: ErrorKind) -> NomResult<O> {
+fn convert_result<O, E>(result: Result<O, E>, input: &[u8], error_kind: ErrorKind) -> NomResult<'_, O> {
     result.map(|o| (input, o)).map_err(|_| {
         NomError::from_error_kind(input, error_kind);
         nom::Err::Error(())
     })
 }
 
+/// Parse a number from the bytes matched as one, such as digits with an optional sign.
+#[inline]
+fn parse_ascii<T: FromStr>(bytes: &[u8]) -> Option<T> {
+    str::from_utf8(bytes).ok()?.parse().ok()
+}
+
 #[inline]
 fn offset_stream(object: &mut Object, offset: usize) {
     if let Object::Stream(ref mut stream) = object {
@@ -40,11 +50,11 @@ fn offset_stream(object: &mut Object, offset: usize) {
     }
 }
 
-fn eol(input: &[u8]) -> NomResult<&[u8]> {
+fn eol(input: &[u8]) -> NomResult<'_, &[u8]> {
     alt((tag(b"\r\n"), tag(b"\n"), tag(b"\r")))(input)
 }
 
-fn comment(input: &[u8]) -> NomResult<()> {
+fn comment(input: &[u8]) -> NomResult<'_, ()> {
     map(
         tuple((tag(b"%"), take_while(|c: u8| !b"\r\n".contains(&c)), eol)),
         |_| (),
@@ -71,11 +81,11 @@ fn is_direct_literal_string(c: u8) -> bool {
     !b"()\\\r\n".contains(&c)
 }
 
-fn white_space(input: &[u8]) -> NomResult<()> {
+fn white_space(input: &[u8]) -> NomResult<'_, ()> {
     map(take_while(is_whitespace), |_| ())(input)
 }
 
-fn space(input: &[u8]) -> NomResult<()> {
+fn space(input: &[u8]) -> NomResult<'_, ()> {
     fold_many0(
         alt((map(take_while1(is_whitespace), |_| ()), comment)),
         || {},
@@ -83,14 +93,14 @@ fn space(input: &[u8]) -> NomResult<()> {
     )(input)
 }
 
-fn integer(input: &[u8]) -> NomResult<i64> {
+fn integer(input: &[u8]) -> NomResult<'_, i64> {
     let (i, _) = pair(opt(one_of("+-")), digit1)(input)?;
 
     let int_input = &input[..input.len() - i.len()];
-    convert_result(i64::from_str(str::from_utf8(int_input).unwrap()), i, ErrorKind::Digit)
+    convert_result(parse_ascii::<i64>(int_input).ok_or(()), i, ErrorKind::Digit)
 }
 
-fn real(input: &[u8]) -> NomResult<f32> {
+fn real(input: &[u8]) -> NomResult<'_, f32> {
     let (i, _) = pair(
         opt(one_of("+-")),
         alt((
@@ -100,25 +110,25 @@ fn real(input: &[u8]) -> NomResult<f32> {
     )(input)?;
 
     let float_input = &input[..input.len() - i.len()];
-    convert_result(f32::from_str(str::from_utf8(float_input).unwrap()), i, ErrorKind::Digit)
+    convert_result(parse_ascii::<f32>(float_input).ok_or(()), i, ErrorKind::Digit)
 }
 
-fn hex_char(input: &[u8]) -> NomResult<u8> {
-    map_res(
+fn hex_char(input: &[u8]) -> NomResult<'_, u8> {
+    map_opt(
         verify(take(2usize), |h: &[u8]| h.iter().cloned().all(is_hex_digit)),
-        |x| u8::from_str_radix(str::from_utf8(x).unwrap(), 16),
+        |x| u8::from_str_radix(str::from_utf8(x).ok()?, 16).ok(),
     )(input)
 }
 
-fn oct_char(input: &[u8]) -> NomResult<u8> {
-    map_res(
+fn oct_char(input: &[u8]) -> NomResult<'_, u8> {
+    map_opt(
         take_while_m_n(1, 3, is_oct_digit),
         // Spec requires us to ignore any overflow.
-        |x| u16::from_str_radix(str::from_utf8(x).unwrap(), 8).map(|o| o as u8),
+        |x| u16::from_str_radix(str::from_utf8(x).ok()?, 8).ok().map(|o| o as u8),
     )(input)
 }
 
-fn name(input: &[u8]) -> NomResult<Vec<u8>> {
+fn name(input: &[u8]) -> NomResult<'_, Vec<u8>> {
     preceded(
         tag(b"/"),
         many0(alt((
@@ -134,7 +144,7 @@ fn name(input: &[u8]) -> NomResult<Vec<u8>> {
     )(input)
 }
 
-fn escape_sequence(input: &[u8]) -> NomResult<Option<u8>> {
+fn escape_sequence(input: &[u8]) -> NomResult<'_, Option<u8>> {
     preceded(
         tag(b"\\"),
         alt((
@@ -202,64 +212,89 @@ fn nested_literal_string(depth: usize) -> impl Fn(&[u8]) -> NomResult<Vec<u8>> {
     }
 }
 
-fn literal_string(input: &[u8]) -> NomResult<Vec<u8>> {
+fn literal_string(input: &[u8]) -> NomResult<'_, Vec<u8>> {
     delimited(tag(b"("), inner_literal_string(crate::reader::MAX_BRACKET), tag(b")"))(input)
 }
 
 #[inline]
-fn hex_digit(input: &[u8]) -> NomResult<u8> {
+fn hex_digit(input: &[u8]) -> NomResult<'_, u8> {
     map_opt(take(1usize), |c: &[u8]| {
         str::from_utf8(c).ok().and_then(|c| u8::from_str_radix(c, 16).ok())
     })(input)
 }
 
-fn hexadecimal_string(input: &[u8]) -> NomResult<Object> {
-    map(
-        delimited(
-            tag(b"<"),
-            terminated(
-                fold_many0(
-                    preceded(white_space, hex_digit),
-                    || -> (Vec<u8>, bool) { (Vec::new(), false) },
-                    |state, c| match state {
-                        (mut out, false) => {
-                            out.push(c << 4);
-                            (out, true)
-                        }
-                        (mut out, true) => {
-                            *out.last_mut().unwrap() |= c;
-                            (out, false)
-                        }
-                    },
-                ),
-                white_space,
-            ),
-            tag(b">"),
+/// Parse a hexadecimal string.
+///
+/// White-space between the digits is ignored and an odd number of digits is padded with a
+/// final 0. Any other byte that is not a hex digit makes the string invalid: a warning with the
+/// offending byte and its offset from the opening `<` is logged and the parser fails without
+/// trying to read the input as another kind of object.
+fn hexadecimal_string(input: &[u8]) -> NomResult<'_, Object> {
+    let (i, (bytes, _)) = delimited(
+        pair(tag(b"<"), not(tag(b"<"))),
+        fold_many0(
+            preceded(white_space, hex_digit),
+            || -> (Vec<u8>, bool) { (Vec::new(), false) },
+            |state, c| match state {
+                (mut out, false) => {
+                    out.push(c << 4);
+                    (out, true)
+                }
+                (mut out, true) => {
+                    if let Some(last) = out.last_mut() {
+                        *last |= c;
+                    }
+                    (out, false)
+                }
+            },
         ),
-        |(bytes, _)| Object::String(bytes, StringFormat::Hexadecimal),
-    )(input)
+        white_space,
+    )(input)?;
+
+    match i.first() {
+        Some(b'>') => Ok((&i[1..], Object::String(bytes, StringFormat::Hexadecimal))),
+        Some(byte) => {
+            warn!(
+                "Invalid byte {:#04x} at offset {} in hexadecimal string",
+                byte,
+                input.len() - i.len()
+            );
+            Err(nom::Err::Failure(()))
+        }
+        None => Err(nom::Err::Error(())),
+    }
 }
 
-fn boolean(input: &[u8]) -> NomResult<Object> {
+fn boolean(input: &[u8]) -> NomResult<'_, Object> {
     alt((
         map(tag(b"true"), |_| Object::Boolean(true)),
         map(tag(b"false"), |_| Object::Boolean(false)),
     ))(input)
 }
 
-fn null(input: &[u8]) -> NomResult<Object> {
+fn null(input: &[u8]) -> NomResult<'_, Object> {
     map(tag(b"null"), |_| Object::Null)(input)
 }
 
-fn array(input: &[u8]) -> NomResult<Vec<Object>> {
-    delimited(pair(tag(b"["), space), many0(_direct_object), tag(b"]"))(input)
+/// Remaining nesting depth inside an array or dictionary allowed `depth` levels of nesting, or
+/// an error stopping the parsing when there is none left, instead of overflowing the stack.
+#[inline]
+fn nesting_limit(depth: usize) -> Result<usize, nom::Err<NomError>> {
+    depth.checked_sub(1).ok_or(nom::Err::Failure(()))
+}
+
+fn array(input: &[u8], depth: usize) -> NomResult<'_, Vec<Object>> {
+    let (i, _) = pair(tag(b"["), space)(input)?;
+    let depth = nesting_limit(depth)?;
+    terminated(many0(move |i| _direct_object(i, depth)), tag(b"]"))(i)
 }
 
-fn dictionary(input: &[u8]) -> NomResult<Dictionary> {
-    delimited(
-        pair(tag(b"<<"), space),
+fn dictionary(input: &[u8], depth: usize) -> NomResult<'_, Dictionary> {
+    let (i, _) = pair(tag(b"<<"), space)(input)?;
+    let depth = nesting_limit(depth)?;
+    terminated(
         fold_many0(
-            pair(terminated(name, space), _direct_object),
+            pair(terminated(name, space), move |i| _direct_object(i, depth)),
             Dictionary::new,
             |mut dict, (key, value)| {
                 dict.set(key, value);
@@ -267,43 +302,68 @@ fn dictionary(input: &[u8]) -> NomResult<Dictionary> {
             },
         ),
         tag(b">>"),
-    )(input)
+    )(i)
 }
 
 fn stream<'a>(input: &'a [u8], reader: &Reader) -> NomResult<'a, Object> {
-    let (i, dict) = terminated(dictionary, tuple((space, tag(b"stream"), eol)))(input)?;
+    let (i, dict) = terminated(
+        |i| dictionary(i, reader.options.max_nesting_depth),
+        tuple((space, tag(b"stream"), eol)),
+    )(input)?;
 
-    if let Ok(length) = dict.get(b"Length").and_then(|value| {
-        if let Ok(id) = value.as_reference() {
-            reader.get_object(id).and_then(|value| value.as_i64())
-        } else {
-            value.as_i64()
+    let length = match dict.get(b"Length") {
+        Ok(Object::Reference(id)) => match reader.get_object(*id) {
+            Ok(value) => value.as_i64().ok(),
+            // The length object can't be read yet, e.g. because it is stored in an object stream
+            // or the xref table is still being parsed, so look for the end of the stream instead.
+            Err(_) => {
+                return match scan_stream_end(i) {
+                    Some((data_len, end)) => Ok((
+                        &i[end..],
+                        Object::Stream(Stream::recovered(dict, i[..data_len].to_vec())),
+                    )),
+                    // Return position relative to the start of the stream dictionary.
+                    None => Ok((i, Object::Stream(Stream::with_position(dict, input.len() - i.len())))),
+                };
+            }
+        },
+        Ok(value) => value.as_i64().ok(),
+        Err(_) => None,
+    };
+
+    if let Some(length) = length.filter(|&length| length >= 0) {
+        let data: NomResult<&[u8]> = terminated(take(length as usize), pair(opt(eol), tag(b"endstream")))(i);
+        if let Ok((i, data)) = data {
+            return Ok((i, Object::Stream(Stream::new(dict, data.to_vec()))));
         }
-    }) {
-        if length < 0 {
-            return Err(nom::Err::Failure(()));
-        }
-        let (i, data) = terminated(take(length as usize), pair(opt(eol), tag(b"endstream")))(i)?;
-        Ok((i, Object::Stream(Stream::new(dict, data.to_vec()))))
-    } else {
-        // Return position relative to the start of the stream dictionary.
-        Ok((i, Object::Stream(Stream::with_position(dict, input.len() - i.len()))))
     }
+
+    // The Length entry is missing or does not match the stream data, look for the end of the stream instead.
+    let (data_len, end) = scan_stream_end(i).ok_or(nom::Err::Failure(()))?;
+    warn!(
+        "Stream Length {:?} is missing or invalid, recovered {} bytes by scanning for endstream",
+        dict.get(b"Length").ok(),
+        data_len
+    );
+    Ok((
+        &i[end..],
+        Object::Stream(Stream::recovered(dict, i[..data_len].to_vec())),
+    ))
 }
 
-fn unsigned_int<I: FromStr>(input: &[u8]) -> NomResult<I> {
-    map_res(digit1, |digits| I::from_str(str::from_utf8(digits).unwrap()))(input)
+fn unsigned_int<I: FromStr>(input: &[u8]) -> NomResult<'_, I> {
+    map_opt(digit1, parse_ascii)(input)
 }
 
-fn object_id(input: &[u8]) -> NomResult<ObjectId> {
+fn object_id(input: &[u8]) -> NomResult<'_, ObjectId> {
     pair(terminated(unsigned_int, space), terminated(unsigned_int, space))(input)
 }
 
-fn reference(input: &[u8]) -> NomResult<Object> {
+fn reference(input: &[u8]) -> NomResult<'_, Object> {
     map(terminated(object_id, tag(b"R")), Object::Reference)(input)
 }
 
-fn _direct_objects(input: &[u8]) -> NomResult<Object> {
+fn _direct_objects(input: &[u8], depth: usize) -> NomResult<'_, Object> {
     alt((
         null,
         boolean,
@@ -313,37 +373,56 @@ fn _direct_objects(input: &[u8]) -> NomResult<Object> {
         map(name, Object::Name),
         map(literal_string, Object::string_literal),
         hexadecimal_string,
-        map(array, Object::Array),
-        map(dictionary, Object::Dictionary),
+        map(|i| array(i, depth), Object::Array),
+        map(|i| dictionary(i, depth), Object::Dictionary),
     ))(input)
 }
 
-fn _direct_object(input: &[u8]) -> NomResult<Object> {
-    terminated(_direct_objects, space)(input)
+fn _direct_object(input: &[u8], depth: usize) -> NomResult<'_, Object> {
+    terminated(|i| _direct_objects(i, depth), space)(input)
 }
 
 pub fn direct_object(input: &[u8]) -> Option<Object> {
-    strip_nom(_direct_object(input))
+    strip_nom(_direct_object(input, MAX_NESTING_DEPTH))
 }
 
 fn object<'a>(input: &'a [u8], reader: &Reader) -> NomResult<'a, Object> {
-    terminated(alt((|input| stream(input, reader), _direct_objects)), space)(input)
+    terminated(
+        alt((
+            |input| stream(input, reader),
+            |input| _direct_objects(input, reader.options.max_nesting_depth),
+        )),
+        space,
+    )(input)
 }
 
+/// Parse the indirect object at `offset` of `input`, with stream positions relative to `input`.
+///
+/// Like most readers, this accepts whitespace in front of the object, a missing `endobj`, a
+/// missing object, which is read as null, and a name running into `endobj`.
 pub fn indirect_object(
     input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader,
 ) -> crate::Result<(ObjectId, Object)> {
-    let (id, mut object) = _indirect_object(&input[offset..], offset, expected_id, reader)?;
-
-    offset_stream(&mut object, offset);
+    _indirect_object(input, offset, expected_id, reader, false)
+}
 
-    Ok((id, object))
+/// Parse the indirect object at `offset` of `input` like [`indirect_object`], but requiring the
+/// object to start at `offset` and to end with `endobj`.
+pub fn indirect_object_strict(
+    input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader,
+) -> crate::Result<(ObjectId, Object)> {
+    _indirect_object(input, offset, expected_id, reader, true)
 }
 
 fn _indirect_object(
-    input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader,
+    input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader, strict: bool,
 ) -> crate::Result<(ObjectId, Object)> {
-    let (i, (_, object_id)) = terminated(tuple((space, object_id)), pair(tag(b"obj"), space))(input).map_err(|_| Error::Parse { offset })?;
+    let mut i = input.get(offset..).ok_or(Error::Offset(offset))?;
+    if !strict {
+        (i, _) = space(i).map_err(|_| Error::parse(input, offset, None))?;
+    }
+    let (i, object_id) =
+        terminated(object_id, pair(tag(b"obj"), space))(i).map_err(|_| Error::parse_object(input, offset, None))?;
     if let Some(expected_id) = expected_id {
         if object_id != expected_id {
             return Err(crate::error::Error::ObjectIdMismatch);
@@ -351,8 +430,16 @@ fn _indirect_object(
     }
 
     let object_offset = input.len() - i.len();
-    let (_, mut object) = terminated(|i| object(i, reader), tuple((space, opt(tag(b"endobj")), space)))(i)
-        .map_err(|_| Error::Parse { offset })?;
+    let mut object = match terminated(|i| object(i, reader), pair(space, tag(b"endobj")))(i) {
+        Ok((_, object)) => object,
+        Err(_) if strict => return Err(Error::parse_object(input, object_offset, Some(object_id))),
+        Err(_) => match space(i) {
+            Ok((rest, _)) if rest.is_empty() || rest.starts_with(b"endobj") => Object::Null,
+            _ => object(i, reader)
+                .map(|(_, object)| split_endobj(object))
+                .map_err(|_| Error::parse_object(input, object_offset, Some(object_id)))?,
+        },
+    };
 
     offset_stream(&mut object, object_offset);
 
@@ -371,7 +458,7 @@ pub fn header(input: &[u8]) -> Option<String> {
 }
 
 /// Decode CrossReferenceTable
-fn xref(input: &[u8]) -> NomResult<Xref> {
+fn xref(input: &[u8]) -> NomResult<'_, Xref> {
     let xref_eol = map(alt((tag(b" \r"), tag(b" \n"), tag(b"\r\n"))), |_| ());
     let xref_entry = pair(
         separated_pair(unsigned_int, tag(b" "), unsigned_int::<u32>),
@@ -390,10 +477,17 @@ fn xref(input: &[u8]) -> NomResult<Xref> {
             || -> Xref { Xref::new(0, XrefType::CrossReferenceTable) },
             |mut xref, ((start, _count), entries)| {
                 for (index, ((offset, generation), is_normal)) in entries.into_iter().enumerate() {
-                    if is_normal {
-                        if let Ok(generation) = generation.try_into() {
-                            xref.insert((start + index) as u32, XrefEntry::Normal { offset, generation });
+                    // Entries with numbers out of range are skipped.
+                    let id = u32::try_from(start + index);
+                    match (is_normal, id, generation.try_into()) {
+                        (true, Ok(id), Ok(generation)) => xref.insert(id, XrefEntry::Normal { offset, generation }),
+                        // Free entries hide the entries of the same objects in previous sections. Object 0 is the
+                        // head of the list of free objects.
+                        (false, Ok(id), generation) if id != 0 => {
+                            let generation = generation.unwrap_or(u16::MAX);
+                            xref.insert(id, XrefEntry::Free { generation })
                         }
+                        _ => {}
                     }
                 }
                 xref
@@ -403,21 +497,26 @@ fn xref(input: &[u8]) -> NomResult<Xref> {
     )(input)
 }
 
-fn trailer(input: &[u8]) -> NomResult<Dictionary> {
-    delimited(pair(tag(b"trailer"), space), dictionary, space)(input)
+fn trailer<'a>(input: &'a [u8], reader: &Reader) -> NomResult<'a, Dictionary> {
+    delimited(
+        pair(tag(b"trailer"), space),
+        |i| dictionary(i, reader.options.max_nesting_depth),
+        space,
+    )(input)
 }
 
 pub fn xref_and_trailer(input: &[u8], reader: &Reader) -> crate::Result<(Xref, Dictionary)> {
     alt((
-        map(pair(xref, trailer), |(mut xref, trailer)| {
-            xref.size = trailer
+        map(pair(xref, |i| trailer(i, reader)), |(mut xref, trailer)| {
+            let size = trailer
                 .get(b"Size")
                 .and_then(Object::as_i64)
-                .map_err(|_| Error::Trailer)? as u32;
+                .map_err(|_| Error::Trailer)?;
+            xref.size = u32::try_from(size).map_err(|_| Error::Trailer)?;
             Ok((xref, trailer))
         }),
         (|input| {
-            _indirect_object(input, 0, None, reader)
+            indirect_object(input, 0, None, reader)
                 .map(|(_, obj)| {
                     let res = match obj {
                         Object::Stream(stream) => decode_xref_stream(stream),
@@ -442,18 +541,20 @@ pub fn xref_start(input: &[u8]) -> Option<i64> {
 
 // The following code create parser to parse content stream.
 
-fn content_space(input: &[u8]) -> NomResult<()> {
+fn content_space(input: &[u8]) -> NomResult<'_, ()> {
     map(take_while(|c| b" \t\r\n".contains(&c)), |_| ())(input)
 }
 
-fn operator(input: &[u8]) -> NomResult<String> {
+fn operator(input: &[u8]) -> NomResult<'_, String> {
     map_res(
         take_while1(|c: u8| c.is_ascii_alphabetic() || b"*'\"".contains(&c)),
         |op| str::from_utf8(op).map(Into::into),
     )(input)
 }
 
-fn operand(input: &[u8]) -> NomResult<Object> {
+/// Content streams are parsed without a reader, so operands are limited to the default
+/// `MAX_NESTING_DEPTH`.
+fn operand(input: &[u8]) -> NomResult<'_, Object> {
     terminated(
         alt((
             null,
@@ -463,24 +564,65 @@ fn operand(input: &[u8]) -> NomResult<Object> {
             map(name, Object::Name),
             map(literal_string, Object::string_literal),
             hexadecimal_string,
-            map(array, Object::Array),
-            map(dictionary, Object::Dictionary),
+            map(|i| array(i, MAX_NESTING_DEPTH), Object::Array),
+            map(|i| dictionary(i, MAX_NESTING_DEPTH), Object::Dictionary),
         )),
         content_space,
     )(input)
 }
 
-fn operation(input: &[u8]) -> NomResult<Operation> {
-    map(
-        preceded(
-            many0(comment),
-            terminated(pair(many0(operand), operator), content_space),
+/// Parse an inline image `BI <dict> ID <data> EI` into a `BI` operation with a single stream operand.
+fn inline_image(input: &[u8]) -> NomResult<'_, Operation> {
+    let (i, dict) = preceded(
+        pair(verify(operator, |op: &str| op == "BI"), content_space),
+        fold_many0(
+            pair(terminated(name, content_space), operand),
+            Dictionary::new,
+            |mut dict, (key, value)| {
+                dict.set(key, value);
+                dict
+            },
         ),
-        |(operands, operator)| Operation { operator, operands },
+    )(input)?;
+    let (i, _) = pair(
+        tag(b"ID"),
+        alt((eol, take_while_m_n(1, 1, |c| b" \t\0\x0C".contains(&c)))),
+    )(i)?;
+
+    let length = dict
+        .get(b"L")
+        .or_else(|_| dict.get(b"Length"))
+        .and_then(Object::as_i64)
+        .ok()
+        .and_then(|length| usize::try_from(length).ok());
+    let (data_len, end) = scan_inline_image_end(i, length).ok_or(nom::Err::Error(()))?;
+    let image = Stream {
+        dict,
+        content: i[..data_len].to_vec(),
+        allows_compression: false,
+        start_position: None,
+        recovered_length: None,
+        decompressed: Default::default(),
+    };
+    let (i, _) = content_space(&i[end..])?;
+
+    Ok((i, Operation::new("BI", vec![Object::Stream(image)])))
+}
+
+fn operation(input: &[u8]) -> NomResult<'_, Operation> {
+    preceded(
+        many0(comment),
+        alt((
+            inline_image,
+            map(
+                terminated(pair(many0(operand), operator), content_space),
+                |(operands, operator)| Operation { operator, operands },
+            ),
+        )),
     )(input)
 }
 
-fn _content(input: &[u8]) -> NomResult<Content<Vec<Operation>>> {
+fn _content(input: &[u8]) -> NomResult<'_, Content<Vec<Operation>>> {
     preceded(
         content_space,
         map(many0(operation), |operations| Content { operations }),
@@ -491,6 +633,61 @@ pub fn content(input: &[u8]) -> Option<Content<Vec<Operation>>> {
     strip_nom(_content(input))
 }
 
+/// Parse content stream, returning every operation with the offset where it starts, or the
+/// offset of the first data that can't be parsed as an operation.
+pub fn content_with_offsets(input: &[u8]) -> std::result::Result<Vec<(usize, Operation)>, usize> {
+    let mut operations = vec![];
+    let mut i = input;
+    loop {
+        if let Ok((rest, _)) = pair(content_space, many0_count(terminated(comment, content_space)))(i) {
+            i = rest;
+        }
+        if i.is_empty() {
+            return Ok(operations);
+        }
+        let start = input.len() - i.len();
+        let (rest, operation) = operation(i).map_err(|_| start)?;
+        operations.push((start, operation));
+        i = rest;
+    }
+}
+
+/// Parse content stream, skipping over tokens that can't be parsed as part of an operation.
+/// Returns the operations together with the byte ranges that were skipped.
+pub fn content_tolerant(input: &[u8]) -> (Content<Vec<Operation>>, Vec<Range<usize>>) {
+    let mut operations = vec![];
+    let mut skipped: Vec<Range<usize>> = vec![];
+    let mut last_skipped = false;
+    let mut i = input;
+    loop {
+        if let Ok((rest, _)) = pair(content_space, many0_count(terminated(comment, content_space)))(i) {
+            i = rest;
+        }
+        if i.is_empty() {
+            break;
+        }
+
+        if let Ok((rest, operation)) = operation(i) {
+            operations.push(operation);
+            last_skipped = false;
+            i = rest;
+            continue;
+        }
+
+        // Drop the token up to the next whitespace and try again from there.
+        let start = input.len() - i.len();
+        let len = i.iter().position(|c| b" \t\r\n".contains(c)).unwrap_or(i.len()).max(1);
+        match skipped.last_mut() {
+            Some(range) if last_skipped => range.end = start + len,
+            _ => skipped.push(start..start + len),
+        }
+        last_skipped = true;
+        i = &i[len..];
+    }
+
+    (Content { operations }, skipped)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -562,6 +759,38 @@ fn parse_content() {
         assert!(content.is_some());
     }
 
+    #[test]
+    fn parse_inline_image() {
+        let input = b"q BI /W 2 /H 1 /BPC 8 /CS /G ID \x01EI\xffEI1\nEI Q\nBI /W 1 /L 4 ID a EI\nEI";
+        let content = tstrip(_content(input)).unwrap();
+        let operators: Vec<_> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["q", "BI", "Q", "BI"]);
+        match content.operations[1].operands.as_slice() {
+            [Object::Stream(image)] => {
+                assert_eq!(image.dict.get(b"W").unwrap().as_i64().unwrap(), 2);
+                assert_eq!(image.content, b"\x01EI\xffEI1");
+            }
+            operands => panic!("unexpected {:?}", operands),
+        }
+        match content.operations[3].operands.as_slice() {
+            [Object::Stream(image)] => assert_eq!(image.content, b"a EI"),
+            operands => panic!("unexpected {:?}", operands),
+        }
+
+        let encoded = content.encode().unwrap();
+        let decoded = tstrip(_content(&encoded)).unwrap();
+        assert_eq!(decoded.operations.len(), 4);
+        assert_eq!(decoded.operations[1].operands, content.operations[1].operands);
+    }
+
+    #[test]
+    fn parse_content_tolerant() {
+        let (content, skipped) = content_tolerant(b"q ))) BT /F1 12 Tf ET\n1 2 ]] 3 4 m\n% comment\nQ @");
+        let operators: Vec<_> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["q", "BT", "Tf", "ET", "m", "Q"]);
+        assert_eq!(skipped, [2..5, 22..28, 47..48]);
+    }
+
     #[test]
     fn hex_partial() {
         // Example from PDF specification.
@@ -583,6 +812,47 @@ fn hex_separated() {
         }
     }
 
+    #[test]
+    fn octal_escapes() {
+        for (input, expected) in [
+            (&b"(\\129)"[..], &b"\n9"[..]),
+            (b"(\\8)", b"8"),
+            (b"(\\39)", b"\x039"),
+            (b"(\\0053)", b"\x053"),
+            (b"(\\777)", b"\xff"),
+        ] {
+            assert_eq!(tstrip(literal_string(input)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn hex_odd_length() {
+        for (input, expected) in [
+            (&b"<F>"[..], &b"\xF0"[..]),
+            (b"<ABC>", b"\xAB\xC0"),
+            (b"<414>", b"\x41\x40"),
+        ] {
+            match tstrip(hexadecimal_string(input)) {
+                Some(Object::String(s, _)) => assert_eq!(s, expected),
+                out => panic!("unexpected {:?}", out),
+            }
+        }
+    }
+
+    #[test]
+    fn hex_invalid_byte() {
+        assert!(matches!(
+            hexadecimal_string(b"<48 65 6C 6CZ6F>"),
+            Err(nom::Err::Failure(()))
+        ));
+        assert!(tstrip(_direct_object(b"<48 65 6C 6CZ6F>", MAX_NESTING_DEPTH)).is_none());
+        assert!(content(b"<48 65 6C 6CZ6F> Tj").is_none());
+        assert!(matches!(
+            tstrip(_direct_object(b"<</A <48656C6C6F>>>", MAX_NESTING_DEPTH)),
+            Some(Object::Dictionary(_))
+        ));
+    }
+
     #[test]
     fn big_generation_value() {
         let input = b"xref
@@ -622,6 +892,45 @@ fn big_generation_value() {
         }
     }
 
+    #[test]
+    fn stream_with_invalid_length() {
+        let reader = Reader::new(b"");
+
+        for length in ["3", "100", "-1"] {
+            let input = format!("<</Length {}>>stream\nHello World\r\nendstream", length);
+            match tstrip(stream(input.as_bytes(), &reader)) {
+                Some(Object::Stream(stream)) => {
+                    assert_eq!(stream.content, b"Hello World");
+                    assert_eq!(stream.recovered_length, Some(11));
+                }
+                out => panic!("unexpected {:?}", out),
+            }
+        }
+    }
+
+    #[test]
+    fn stream_without_length() {
+        let reader = Reader::new(b"");
+
+        for dict in ["<<>>", "<</Length /Foo>>", "<</Length 1.5>>"] {
+            let input = format!("{}stream\nHello World\nendstream", dict);
+            match tstrip(stream(input.as_bytes(), &reader)) {
+                Some(Object::Stream(stream)) => {
+                    assert_eq!(stream.content, b"Hello World");
+                    assert_eq!(stream.recovered_length, Some(11));
+                    assert_eq!(stream.dict.get(b"Length").unwrap().as_i64().unwrap(), 11);
+                }
+                out => panic!("unexpected {:?}", out),
+            }
+        }
+
+        let input = b"<</Length 11>>stream\nHello World\nendstream";
+        match tstrip(stream(input, &reader)) {
+            Some(Object::Stream(stream)) => assert_eq!(stream.recovered_length, None),
+            out => panic!("unexpected {:?}", out),
+        }
+    }
+
     #[test]
     fn content_with_comments() {
         // It should be processed as usual but ignoring the comments
@@ -634,4 +943,60 @@ fn content_with_comments() {
         let out = content(input).unwrap();
         assert_eq!(out.operations.len(), 3);
     }
+
+    #[test]
+    fn parse_fuzzed_inputs() {
+        let reader = Reader::new(b"");
+
+        // Inputs that once made the parser panic or that reach its number conversions with
+        // invalid bytes, none of them may panic.
+        let inputs: &[&[u8]] = &[
+            b"",
+            b"+",
+            b"-.",
+            b"/#",
+            b"/#\xff\xfe",
+            b"/#g0",
+            b"<\xff>",
+            b"<0>",
+            b"(\\\xff)",
+            b"(\\777)",
+            b"[1 0 R 99999999999 0 R]",
+            b"<</Length 99999999999999999999>>stream\nendstream",
+            b"1 0 obj",
+            b"1 99999 obj null endobj",
+            b"\xff\xfe 0 obj",
+            b"xref\n0 1\n0000000000 99999 f \ntrailer<<>>",
+            b"startxref\n\xff\n%%EOF",
+            b"BI /W 99999999999999999999 ID \xff EI",
+        ];
+        for input in inputs {
+            let _ = direct_object(input);
+            let _ = content(input);
+            let _ = content_with_offsets(input);
+            let _ = content_tolerant(input);
+            let _ = xref_start(input);
+            let _ = xref_and_trailer(input, &reader);
+            for offset in [0, 1, input.len(), input.len() + 1] {
+                let _ = indirect_object(input, offset, None, &reader);
+                let _ = indirect_object_strict(input, offset, None, &reader);
+            }
+        }
+        assert!(matches!(
+            indirect_object(b"1 0 obj null endobj", 20, None, &reader),
+            Err(Error::Offset(20))
+        ));
+    }
+
+    #[test]
+    fn trailer_size_out_of_range() {
+        let reader = Reader::new(b"");
+        for size in ["-1", "4294967296"] {
+            let input = format!("xref\n0 1\n0000000000 65535 f \ntrailer<</Size {size}>>");
+            assert!(matches!(
+                xref_and_trailer(input.as_bytes(), &reader),
+                Err(Error::Trailer)
+            ));
+        }
+    }
 }
@@ -1,42 +1,68 @@
 use crate::{Document, Error, Result};
 use linked_hash_map::{self, Iter, IterMut, LinkedHashMap};
 use log::warn;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp::max;
 use std::fmt;
 use std::str;
+use std::sync::OnceLock;
 
 /// Object identifier consists of two parts: object number and generation number.
 pub type ObjectId = (u32, u16);
 
 /// Dictionary object.
+///
+/// Entries are kept in the order their keys were first set, which is the order in which they
+/// are iterated and written. Setting a key that is already present replaces its value in place,
+/// so serializing the same dictionary always produces the same bytes.
 #[derive(Clone, Default, PartialEq)]
 pub struct Dictionary(LinkedHashMap<Vec<u8>, Object>);
 
 /// Stream object
 /// Warning - all streams must be indirect objects, while
 /// the stream dictionary may be a direct object
-#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Stream {
     /// Associated stream dictionary
     pub dict: Dictionary,
     /// Contents of the stream in bytes
+    #[cfg_attr(feature = "serde", serde(with = "serde_encoding::hex"))]
     pub content: Vec<u8>,
     /// Can the stream be compressed by the `Document::compress()` function?
     /// Font streams may not be compressed, for example
     pub allows_compression: bool,
     /// Stream data's position in PDF file.
     pub start_position: Option<usize>,
+    /// Length of the stream data if it had to be recovered by scanning for `endstream`,
+    /// because the `Length` entry was missing or did not match the data.
+    pub recovered_length: Option<usize>,
+    /// Content decoded by the first successful call to `decompressed_content_ref`, dropped by the
+    /// methods changing the content or the filters. It is not part of the PDF object and is
+    /// neither written, compared nor cloned.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) decompressed: OnceLock<Box<[u8]>>,
 }
 
 /// Basic PDF object types defined in an enum.
+///
+/// With the `serde` feature, objects are serialized as externally tagged enums, such as
+/// `{"Integer":3}` or `"Null"`, with object ids as `[number, generation]` pairs. Names, including
+/// dictionary keys, are serialized as strings in PDF syntax, with the bytes other than `!` to `~`
+/// and `#` escaped as `#xx`, for example `"A#20B"`. The bytes of strings and of stream contents,
+/// which are often binary, are serialized as lowercase hexadecimal strings.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq)]
 pub enum Object {
     Null,
     Boolean(bool),
     Integer(i64),
     Real(f32),
-    Name(Vec<u8>),
-    String(Vec<u8>, StringFormat),
+    Name(#[cfg_attr(feature = "serde", serde(with = "serde_encoding::name"))] Vec<u8>),
+    String(
+        #[cfg_attr(feature = "serde", serde(with = "serde_encoding::hex"))] Vec<u8>,
+        StringFormat,
+    ),
     Array(Vec<Object>),
     Dictionary(Dictionary),
     Stream(Stream),
@@ -44,6 +70,7 @@ pub enum Object {
 }
 
 /// String objects can be written in two formats.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum StringFormat {
     #[default]
@@ -92,12 +119,16 @@ fn from(number: f32) -> Self {
     }
 }
 
+/// Strings convert to names, use [`Object::string_literal`] or [`Object::text_string`] for
+/// string objects.
 impl From<String> for Object {
     fn from(name: String) -> Self {
         Object::Name(name.into_bytes())
     }
 }
 
+/// Strings convert to names, use [`Object::string_literal`] or [`Object::text_string`] for
+/// string objects.
 impl<'a> From<&'a str> for Object {
     fn from(name: &'a str) -> Self {
         Object::Name(name.as_bytes().to_vec())
@@ -129,10 +160,32 @@ fn from(id: ObjectId) -> Self {
 }
 
 impl Object {
+    /// Create a name object. `&str` and `String` values convert to names as well, so this is
+    /// mostly useful for names that are not UTF-8.
+    ///
+    /// ```
+    /// use lopdf::Object;
+    ///
+    /// assert_eq!(Object::name("Type"), Object::from("Type"));
+    /// assert_eq!(Object::name(b"Caf\xe9".to_vec()), Object::Name(vec![b'C', b'a', b'f', 0xe9]));
+    /// assert_ne!(Object::name("Type"), Object::string_literal("Type"));
+    /// ```
+    pub fn name<N: Into<Vec<u8>>>(name: N) -> Self {
+        Object::Name(name.into())
+    }
+
+    /// Create a literal string object from its bytes, written as `(...)`. See
+    /// [`Object::text_string`] for text that may not be ASCII.
     pub fn string_literal<S: Into<Vec<u8>>>(s: S) -> Self {
         Object::String(s.into(), StringFormat::Literal)
     }
 
+    /// Create a text string, such as a metadata entry, encoded as PDFDocEncoding if possible
+    /// and as UTF-16BE otherwise.
+    pub fn text_string(text: &str) -> Self {
+        Object::string_literal(crate::encodings::encode_text_string(text))
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(*self, Object::Null)
     }
@@ -140,38 +193,47 @@ pub fn is_null(&self) -> bool {
     pub fn as_bool(&self) -> Result<bool> {
         match *self {
             Object::Boolean(ref value) => Ok(*value),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Boolean")),
         }
     }
 
     pub fn as_i64(&self) -> Result<i64> {
         match *self {
             Object::Integer(ref value) => Ok(*value),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Integer")),
         }
     }
 
     pub fn as_f32(&self) -> Result<f32> {
         match *self {
             Object::Real(ref value) => Ok(*value),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Real")),
         }
     }
 
     /// Get the object value as a float.
-    /// Unlike as_f64() this will also cast an Integer to a Real.
+    /// Unlike as_f32() this will also cast an Integer to a Real.
     pub fn as_float(&self) -> Result<f32> {
         match *self {
             Object::Integer(ref value) => Ok(*value as f32),
             Object::Real(ref value) => Ok(*value),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Integer or Real")),
+        }
+    }
+
+    /// Get the object value as a double precision float, from either an Integer or a Real.
+    pub fn as_f64(&self) -> Result<f64> {
+        match *self {
+            Object::Integer(ref value) => Ok(*value as f64),
+            Object::Real(ref value) => Ok(f64::from(*value)),
+            _ => Err(self.type_error("Integer or Real")),
         }
     }
 
     pub fn as_name(&self) -> Result<&[u8]> {
         match *self {
             Object::Name(ref name) => Ok(name),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Name")),
         }
     }
 
@@ -182,70 +244,76 @@ pub fn as_name_str(&self) -> Result<&str> {
     pub fn as_str(&self) -> Result<&[u8]> {
         match self {
             Object::String(string, _) => Ok(string),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("String")),
         }
     }
 
     pub fn as_str_mut(&mut self) -> Result<&mut Vec<u8>> {
         match self {
             Object::String(string, _) => Ok(string),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("String")),
         }
     }
 
     pub fn as_string(&self) -> Result<std::borrow::Cow<'_, str>> {
         match self {
             Object::String(string, _) => Ok(std::string::String::from_utf8_lossy(string)),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("String")),
         }
     }
 
+    /// Decode a text string, such as a metadata entry, from UTF-16BE if it starts with a byte
+    /// order mark and from PDFDocEncoding otherwise.
+    pub fn as_text_string(&self) -> Result<std::string::String> {
+        Ok(crate::encodings::decode_text_string(self.as_str()?))
+    }
+
     pub fn as_reference(&self) -> Result<ObjectId> {
         match *self {
             Object::Reference(ref id) => Ok(*id),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Reference")),
         }
     }
 
     pub fn as_array(&self) -> Result<&Vec<Object>> {
         match *self {
             Object::Array(ref arr) => Ok(arr),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Array")),
         }
     }
 
     pub fn as_array_mut(&mut self) -> Result<&mut Vec<Object>> {
         match *self {
             Object::Array(ref mut arr) => Ok(arr),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Array")),
         }
     }
 
     pub fn as_dict(&self) -> Result<&Dictionary> {
         match *self {
             Object::Dictionary(ref dict) => Ok(dict),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Dictionary")),
         }
     }
 
     pub fn as_dict_mut(&mut self) -> Result<&mut Dictionary> {
         match *self {
             Object::Dictionary(ref mut dict) => Ok(dict),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Dictionary")),
         }
     }
 
     pub fn as_stream(&self) -> Result<&Stream> {
         match *self {
             Object::Stream(ref stream) => Ok(stream),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Stream")),
         }
     }
 
     pub fn as_stream_mut(&mut self) -> Result<&mut Stream> {
         match *self {
             Object::Stream(ref mut stream) => Ok(stream),
-            _ => Err(Error::Type),
+            _ => Err(self.type_error("Stream")),
         }
     }
 
@@ -256,6 +324,29 @@ pub fn type_name(&self) -> Result<&str> {
             _ => Err(Error::Type),
         }
     }
+
+    /// Name of the kind of object, such as `Integer` or `Dictionary`.
+    pub fn enum_variant(&self) -> &'static str {
+        match *self {
+            Object::Null => "Null",
+            Object::Boolean(_) => "Boolean",
+            Object::Integer(_) => "Integer",
+            Object::Real(_) => "Real",
+            Object::Name(_) => "Name",
+            Object::String(..) => "String",
+            Object::Array(_) => "Array",
+            Object::Dictionary(_) => "Dictionary",
+            Object::Stream(_) => "Stream",
+            Object::Reference(_) => "Reference",
+        }
+    }
+
+    fn type_error(&self, expected: &'static str) -> Error {
+        Error::ObjectType {
+            expected,
+            found: self.enum_variant(),
+        }
+    }
 }
 
 impl fmt::Debug for Object {
@@ -306,7 +397,8 @@ pub fn get(&self, key: &[u8]) -> Result<&Object> {
     }
 
     /// Extract object from dictionary, dereferencing the object if it
-    /// is a reference.
+    /// is a reference. Chains of references are followed up to their end,
+    /// see [`Document::dereference`] for the errors on cycles.
     pub fn get_deref<'a>(&'a self, key: &[u8], doc: &'a Document) -> Result<&'a Object> {
         doc.dereference(self.get(key)?).map(|(_, object)| object)
     }
@@ -315,12 +407,19 @@ pub fn get_mut(&mut self, key: &[u8]) -> Result<&mut Object> {
         self.0.get_mut(key).ok_or(Error::DictKey)
     }
 
+    /// Set the value of `key`, adding it at the end of the dictionary if it is not present yet.
     pub fn set<K, V>(&mut self, key: K, value: V)
     where
         K: Into<Vec<u8>>,
         V: Into<Object>,
     {
-        self.0.insert(key.into(), value.into());
+        let key = key.into();
+        match self.0.get_mut(&key) {
+            Some(existing) => *existing = value.into(),
+            None => {
+                self.0.insert(key, value.into());
+            }
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -442,6 +541,32 @@ pub fn as_hashmap_mut(&mut self) -> &mut LinkedHashMap<Vec<u8>, Object> {
     }
 }
 
+/// Create a [`Dictionary`] from `key => value` pairs, kept in the given order. Values are
+/// converted with `Into<Object>`, so string values become names.
+///
+/// ```
+/// use lopdf::{dictionary, Object};
+///
+/// let font = dictionary! {
+///     "Type" => "Font",
+///     "Subtype" => "Type1",
+///     "BaseFont" => "Helvetica",
+/// };
+/// let page = dictionary! {
+///     "Type" => "Page",
+///     "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+///     "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+///     "UserUnit" => 1.5,
+///     "Hidden" => false,
+///     "Title" => Object::string_literal("Cover"),
+///     "Parent" => (1, 0),
+/// };
+/// assert_eq!(page.get(b"Type").unwrap(), &Object::name("Page"));
+/// assert_eq!(page.get(b"Parent").unwrap(), &Object::Reference((1, 0)));
+/// assert_eq!(page.get(b"UserUnit").unwrap(), &Object::Real(1.5));
+/// assert_eq!(page.iter().last().unwrap().0, b"Parent");
+/// assert!(dictionary! {}.is_empty());
+/// ```
 #[macro_export]
 macro_rules! dictionary {
 	() => {
@@ -489,7 +614,87 @@ fn from_iter<I: IntoIterator<Item = (K, Object)>>(iter: I) -> Self {
     }
 }
 
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stream")
+            .field("dict", &self.dict)
+            .field("content", &self.content)
+            .field("allows_compression", &self.allows_compression)
+            .field("start_position", &self.start_position)
+            .field("recovered_length", &self.recovered_length)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clone for Stream {
+    fn clone(&self) -> Stream {
+        Stream {
+            dict: self.dict.clone(),
+            content: self.content.clone(),
+            allows_compression: self.allows_compression,
+            start_position: self.start_position,
+            recovered_length: self.recovered_length,
+            decompressed: OnceLock::new(),
+        }
+    }
+}
+
+impl PartialEq for Stream {
+    fn eq(&self, other: &Stream) -> bool {
+        self.dict == other.dict
+            && self.content == other.content
+            && self.allows_compression == other.allows_compression
+            && self.start_position == other.start_position
+            && self.recovered_length == other.recovered_length
+    }
+}
+
+/// Options for [`Stream::compress_with_options`] and [`Document::compress_with_options`].
+///
+/// [`Document::compress_with_options`]: crate::Document::compress_with_options
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressOptions {
+    /// Zlib compression level, from 0 (no compression) to 9 (best compression). Default 9.
+    pub level: u32,
+    /// Highest Shannon entropy of the content, in bits per byte from 0 to 8, for which
+    /// compression is attempted. Default 7.9.
+    ///
+    /// Data that is already compressed, such as raw JPEG or PNG images embedded without a
+    /// filter, is close to 8 bits per byte and barely shrinks, so skipping it saves the time
+    /// spent compressing it. Use 8 to always attempt compression.
+    pub max_entropy: f64,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressOptions {
+            level: Stream::BEST_COMPRESSION,
+            max_entropy: 7.9,
+        }
+    }
+}
+
+/// Shannon entropy of `data` in bits per byte, 0 for empty data.
+fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
 impl Stream {
+    /// Highest zlib compression level, used by default.
+    pub const BEST_COMPRESSION: u32 = 9;
+
     pub fn new(mut dict: Dictionary, content: Vec<u8>) -> Stream {
         dict.set("Length", content.len() as i64);
         Stream {
@@ -497,6 +702,8 @@ pub fn new(mut dict: Dictionary, content: Vec<u8>) -> Stream {
             content,
             allows_compression: true,
             start_position: None,
+            recovered_length: None,
+            decompressed: OnceLock::new(),
         }
     }
 
@@ -506,6 +713,18 @@ pub fn with_position(dict: Dictionary, position: usize) -> Stream {
             content: vec![],
             allows_compression: true,
             start_position: Some(position),
+            recovered_length: None,
+            decompressed: OnceLock::new(),
+        }
+    }
+
+    /// Create a stream whose data was found by scanning for `endstream`.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    pub(crate) fn recovered(dict: Dictionary, content: Vec<u8>) -> Stream {
+        let recovered_length = Some(content.len());
+        Stream {
+            recovered_length,
+            ..Stream::new(dict, content)
         }
     }
 
@@ -542,25 +761,60 @@ pub fn filters(&self) -> Result<Vec<String>> {
         }
     }
 
+    /// Replace the content, which must already be encoded with the filters of the stream,
+    /// and update `Length`.
     pub fn set_content(&mut self, content: Vec<u8>) {
+        self.clear_decompressed_cache();
         self.content = content;
         self.dict.set("Length", self.content.len() as i64);
     }
 
+    /// Replace the content with unencoded data, removing `Filter` and `DecodeParms` and
+    /// updating `Length`.
     pub fn set_plain_content(&mut self, content: Vec<u8>) {
+        self.clear_decompressed_cache();
         self.dict.remove(b"DecodeParms");
         self.dict.remove(b"Filter");
         self.dict.set("Length", content.len() as i64);
         self.content = content;
     }
 
+    /// Replace the content with unencoded data and compress it with `FlateDecode`, replacing
+    /// any previous filters and updating `Length`.
+    pub fn set_compressed_content(&mut self, content: Vec<u8>) -> Result<()> {
+        self.set_plain_content(content);
+        self.compress_with("FlateDecode")
+    }
+
+    /// Compress the content with `FlateDecode` at the best compression level, see
+    /// [`Stream::compress_with_level`].
     pub fn compress(&mut self) -> Result<()> {
+        self.compress_with_level(Self::BEST_COMPRESSION)
+    }
+
+    /// Compress the content with `FlateDecode` at zlib `level`, from 0 (no compression) to 9
+    /// (best compression). Higher levels are treated as 9.
+    ///
+    /// Streams that already have a `Filter` are left unchanged, and so are streams that
+    /// compression would not make smaller or whose content looks already compressed, see
+    /// [`CompressOptions::max_entropy`].
+    pub fn compress_with_level(&mut self, level: u32) -> Result<()> {
+        self.compress_with_options(&CompressOptions {
+            level,
+            ..CompressOptions::default()
+        })
+    }
+
+    /// Compress the content with `FlateDecode` like [`Stream::compress_with_level`], with the
+    /// level and entropy threshold of `options`.
+    pub fn compress_with_options(&mut self, options: &CompressOptions) -> Result<()> {
         use flate2::write::ZlibEncoder;
         use flate2::Compression;
         use std::io::prelude::*;
 
-        if self.dict.get(b"Filter").is_err() {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        if self.dict.get(b"Filter").is_err() && entropy(&self.content) <= options.max_entropy {
+            let level = Compression::new(options.level.min(Self::BEST_COMPRESSION));
+            let mut encoder = ZlibEncoder::new(Vec::new(), level);
             encoder.write_all(self.content.as_slice())?;
             let compressed = encoder.finish()?;
             if compressed.len() + 19 < self.content.len() {
@@ -571,35 +825,140 @@ pub fn compress(&mut self) -> Result<()> {
         Ok(())
     }
 
+    /// Encode the content with `filter`, which becomes the first filter applied when decoding.
+    ///
+    /// Supported filters are `FlateDecode`, `LZWDecode`, `RunLengthDecode`, `ASCIIHexDecode`
+    /// and `ASCII85Decode`.
+    pub fn compress_with(&mut self, filter: &str) -> Result<()> {
+        use crate::filters::{ascii85, ascii_hex, lzw, run_length};
+        use flate2::write::ZlibEncoder;
+        use flate2::Compression;
+        use std::io::prelude::*;
+
+        let compressed = match filter {
+            "FlateDecode" => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(self.content.as_slice())?;
+                encoder.finish()?
+            }
+            "LZWDecode" => lzw::encode(&self.content, true),
+            "RunLengthDecode" => run_length::encode(&self.content),
+            "ASCIIHexDecode" => ascii_hex::encode(&self.content),
+            "ASCII85Decode" => ascii85::encode(&self.content),
+            _ => return Err(Error::UnsupportedFilter(filter.to_string())),
+        };
+
+        match self.dict.remove(b"Filter") {
+            None => self.dict.set("Filter", Object::from(filter)),
+            Some(previous) => {
+                let mut filters = vec![Object::from(filter)];
+                match previous {
+                    Object::Array(previous) => filters.extend(previous),
+                    previous => filters.push(previous),
+                }
+                let params_len = filters.len() - 1;
+                if let Some(params) = self.dict.remove(b"DecodeParms") {
+                    let mut params = match params {
+                        Object::Array(params) => params,
+                        params => vec![params],
+                    };
+                    params.resize(params_len, Object::Null);
+                    params.insert(0, Object::Null);
+                    self.dict.set("DecodeParms", params);
+                }
+                self.dict.set("Filter", filters);
+            }
+        }
+        self.set_content(compressed);
+        Ok(())
+    }
+
+    /// Parameters of each filter, from a `DecodeParms` dictionary for a single filter
+    /// or an array with an entry for each filter.
+    fn decode_params(&self, filters: usize) -> Vec<Option<&Dictionary>> {
+        match self.dict.get(b"DecodeParms") {
+            Ok(Object::Array(params)) => (0..filters)
+                .map(|i| params.get(i).and_then(|p| p.as_dict().ok()))
+                .collect(),
+            Ok(Object::Dictionary(params)) => {
+                let mut all_params = vec![None; filters];
+                if let Some(first) = all_params.first_mut() {
+                    *first = Some(params);
+                }
+                all_params
+            }
+            _ => vec![None; filters],
+        }
+    }
+
+    /// Get the decompressed content, decoded again unless it is cached by
+    /// [`Stream::decompressed_content_ref`]. The content is not cached by this method.
     pub fn decompressed_content(&self) -> Result<Vec<u8>> {
-        let params = self.dict.get(b"DecodeParms").and_then(Object::as_dict).ok();
+        match self.decompressed.get() {
+            Some(content) => Ok(content.to_vec()),
+            None => self.decode_all_filters(),
+        }
+    }
+
+    /// Get the decompressed content like [`Stream::decompressed_content`], without copying it.
+    ///
+    /// The content is decoded on the first successful call and cached in the stream, later
+    /// calls borrow the cached content. The methods of the stream that change its content or
+    /// filters drop the cache; after changing `content`, `Filter` or `DecodeParms` directly,
+    /// call [`Stream::clear_decompressed_cache`].
+    pub fn decompressed_content_ref(&self) -> Result<&[u8]> {
+        if let Some(content) = self.decompressed.get() {
+            return Ok(content);
+        }
+        let content = self.decode_all_filters()?;
+        Ok(self.decompressed.get_or_init(|| content.into_boxed_slice()))
+    }
+
+    /// Drop the cached decompressed content, freeing its memory.
+    pub fn clear_decompressed_cache(&mut self) {
+        self.decompressed.take();
+    }
+
+    fn decode_all_filters(&self) -> Result<Vec<u8>> {
         let filters = self.filters()?;
 
         if self.dict.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Image") {
             return Err(Error::Type);
         }
+        if filters.is_empty() {
+            return Err(Error::Type);
+        }
+
+        self.decode_filters(&filters)
+    }
+
+    /// Decode the content with `filters`, the first filters of the stream's `Filter` entry.
+    pub(crate) fn decode_filters(&self, filters: &[String]) -> Result<Vec<u8>> {
+        let params = self.decode_params(filters.len());
 
         let mut input = self.content.as_slice();
         let mut output = None;
 
         // Filters are in decoding order.
-        for filter in filters {
+        for (filter, params) in filters.iter().zip(params) {
             output = Some(match filter.as_str() {
                 "FlateDecode" => Self::decompress_zlib(input, params)?,
                 "LZWDecode" => Self::decompress_lzw(input, params)?,
+                "RunLengthDecode" => crate::filters::run_length::decode(input),
+                "ASCIIHexDecode" => crate::filters::ascii_hex::decode(input)?,
+                "ASCII85Decode" => crate::filters::ascii85::decode(input)?,
                 _ => {
-                    return Err(Error::Type);
+                    return Err(Error::UnsupportedFilter(filter.clone()));
                 }
             });
             input = output.as_ref().unwrap();
         }
 
-        output.ok_or(Error::Type)
+        Ok(output.unwrap_or_else(|| self.content.clone()))
     }
 
     fn decompress_lzw(input: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>> {
-        use weezl::{decode::Decoder, BitOrder};
-        const MIN_BITS: u8 = 9;
+        use crate::filters::lzw;
 
         let early_change = params
             .and_then(|p| p.get(b"EarlyChange").ok())
@@ -607,27 +966,10 @@ fn decompress_lzw(input: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>>
             .map(|v| v != 0)
             .unwrap_or(true);
 
-        let mut decoder = if early_change {
-            Decoder::with_tiff_size_switch(BitOrder::Msb, MIN_BITS - 1)
-        } else {
-            Decoder::new(BitOrder::Msb, MIN_BITS - 1)
-        };
-
-        let output = Self::decompress_lzw_loop(input, &mut decoder);
+        let output = lzw::decode(input, early_change);
         Self::decompress_predictor(output, params)
     }
 
-    fn decompress_lzw_loop(input: &[u8], decoder: &mut weezl::decode::Decoder) -> Vec<u8> {
-        let mut output = vec![];
-
-        let result = decoder.into_stream(&mut output).decode_all(input);
-        if let Err(err) = result.status {
-            warn!("{}", err);
-        }
-
-        output
-    }
-
     fn decompress_zlib(input: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>> {
         use flate2::read::ZlibDecoder;
         use std::io::prelude::*;
@@ -645,16 +987,24 @@ fn decompress_zlib(input: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>>
     }
 
     fn decompress_predictor(mut data: Vec<u8>, params: Option<&Dictionary>) -> Result<Vec<u8>> {
-        use crate::filters::png;
+        use crate::filters::{png, tiff};
 
         if let Some(params) = params {
             let predictor = params.get(b"Predictor").and_then(Object::as_i64).unwrap_or(1);
-            if (10..=15).contains(&predictor) {
-                let pixels_per_row = max(1, params.get(b"Columns").and_then(Object::as_i64).unwrap_or(1)) as usize;
-                let colors = max(1, params.get(b"Colors").and_then(Object::as_i64).unwrap_or(1)) as usize;
-                let bits = max(8, params.get(b"BitsPerComponent").and_then(Object::as_i64).unwrap_or(8)) as usize;
-                let bytes_per_pixel = colors * bits / 8;
-                data = png::decode_frame(data.as_slice(), bytes_per_pixel, pixels_per_row)?;
+            let columns = max(1, params.get(b"Columns").and_then(Object::as_i64).unwrap_or(1)) as usize;
+            let colors = max(1, params.get(b"Colors").and_then(Object::as_i64).unwrap_or(1)) as usize;
+            let bits = max(1, params.get(b"BitsPerComponent").and_then(Object::as_i64).unwrap_or(8)) as usize;
+            // Bounds the products computed by the predictors, which would overflow for huge parameters
+            let bits_per_row = colors
+                .checked_mul(bits)
+                .and_then(|bits_per_pixel| bits_per_pixel.checked_mul(columns))
+                .ok_or(Error::ContentDecode)?;
+            if predictor == 2 {
+                data = tiff::decode_frame(data.as_slice(), bits, colors, columns);
+            } else if (10..=15).contains(&predictor) {
+                let bytes_per_pixel = max(1, (colors * bits).div_ceil(8));
+                let bytes_per_row = bits_per_row.div_ceil(8);
+                data = png::decode_rows(data.as_slice(), bytes_per_pixel, bytes_per_row)?;
             }
             Ok(data)
         } else {
@@ -663,10 +1013,507 @@ fn decompress_predictor(mut data: Vec<u8>, params: Option<&Dictionary>) -> Resul
     }
 
     pub fn decompress(&mut self) {
-        if let Ok(data) = self.decompressed_content() {
+        let data = match self.decompressed.take() {
+            Some(content) => Ok(content.into_vec()),
+            None => self.decode_all_filters(),
+        };
+        if let Ok(data) = data {
             self.dict.remove(b"DecodeParms");
             self.dict.remove(b"Filter");
             self.set_content(data);
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl Serialize for Dictionary {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(&serde_encoding::encode_name(key), value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Dictionary {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct DictionaryVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for DictionaryVisitor {
+            type Value = Dictionary;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of names to objects")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<Dictionary, A::Error> {
+                let mut dict = Dictionary::new();
+                while let Some((key, value)) = map.next_entry::<std::string::String, Object>()? {
+                    let key = serde_encoding::decode_name(&key).map_err(serde::de::Error::custom)?;
+                    dict.set(key, value);
+                }
+                Ok(dict)
+            }
+        }
+
+        deserializer.deserialize_map(DictionaryVisitor)
+    }
+}
+
+/// Readable serde representations of the bytes of names, strings and streams, see [`Object`].
+#[cfg(feature = "serde")]
+mod serde_encoding {
+    pub fn encode_name(name: &[u8]) -> String {
+        let mut encoded = String::with_capacity(name.len());
+        for &byte in name {
+            if byte == b'#' || !(b'!'..=b'~').contains(&byte) {
+                encoded.push_str(&format!("#{:02X}", byte));
+            } else {
+                encoded.push(byte as char);
+            }
+        }
+        encoded
+    }
+
+    pub fn decode_name(name: &str) -> Result<Vec<u8>, String> {
+        let mut decoded = Vec::with_capacity(name.len());
+        let mut bytes = name.bytes();
+        while let Some(byte) = bytes.next() {
+            if byte != b'#' {
+                decoded.push(byte);
+                continue;
+            }
+            let digits = [bytes.next(), bytes.next()];
+            let escape = match digits {
+                [Some(high), Some(low)] => std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok()),
+                _ => None,
+            };
+            decoded.push(escape.ok_or_else(|| format!("invalid escape in name {:?}", name))?);
+        }
+        Ok(decoded)
+    }
+
+    pub mod name {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(name: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&super::encode_name(name))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            super::decode_name(&name).map_err(serde::de::Error::custom)
+        }
+    }
+
+    pub mod hex {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            serializer.serialize_str(&hex)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+            let hex = String::deserialize(deserializer)?;
+            if hex.len() % 2 != 0 {
+                return Err(serde::de::Error::custom("odd number of hexadecimal digits"));
+            }
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("invalid"), 16))
+                .collect::<Result<_, _>>()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{ascii85, lzw, png};
+
+    #[test]
+    fn set_stream_content() {
+        let dict = dictionary! {
+            "Filter" => vec!["ASCIIHexDecode".into(), "FlateDecode".into()],
+            "DecodeParms" => vec![Object::Null, dictionary! { "Predictor" => 12 }.into()],
+        };
+        let mut stream = Stream::new(dict, b"stale".to_vec());
+        let length = |stream: &Stream| stream.dict.get(b"Length").and_then(Object::as_i64).unwrap();
+
+        stream.set_plain_content(b"plain content".to_vec());
+        assert_eq!(length(&stream), 13);
+        assert!(!stream.dict.has(b"Filter") && !stream.dict.has(b"DecodeParms"));
+
+        let content = b"compressed content ".repeat(20);
+        stream.set_compressed_content(content.clone()).unwrap();
+        assert_eq!(length(&stream), stream.content.len() as i64);
+        assert!(stream.content.len() < content.len());
+        assert_eq!(stream.filters().unwrap(), ["FlateDecode"]);
+        assert!(!stream.dict.has(b"DecodeParms"));
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+    }
+
+    #[test]
+    fn compression_levels() {
+        let content: Vec<u8> = (0..20_000u32)
+            .flat_map(|i| format!("{} {} l\n", i % 97, i * 7 % 89).into_bytes())
+            .collect();
+        let compress = |level| {
+            let mut stream = Stream::new(dictionary! {}, content.clone());
+            stream.compress_with_level(level).unwrap();
+            assert_eq!(stream.decompressed_content().unwrap(), content);
+            stream.content.len()
+        };
+        assert!(compress(9) <= compress(1));
+        assert!(compress(1) < content.len());
+
+        let mut stream = Stream::new(dictionary! {}, content.clone());
+        stream.compress_with_level(0).unwrap();
+        // Stored without compression, the stream would grow.
+        assert!(!stream.dict.has(b"Filter"));
+    }
+
+    #[test]
+    fn cache_decompressed_content() {
+        let content = b"BT /F1 12 Tf (Cached) Tj ET\n".repeat(10);
+        let mut stream = Stream::new(dictionary! {}, content.clone());
+        stream.compress().unwrap();
+        let uncached = stream.clone();
+
+        // The owned content is not cached.
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+        assert!(stream.decompressed.get().is_none());
+        let first = stream.decompressed_content_ref().unwrap();
+        assert_eq!(first, content);
+        let second = stream.decompressed_content_ref().unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+        // The cache is neither compared nor written.
+        assert_eq!(stream, uncached);
+        let write = |stream: &Stream| {
+            let mut buffer = Vec::new();
+            crate::writer::Writer::write_object(&mut buffer, &Object::Stream(stream.clone())).unwrap();
+            buffer
+        };
+        assert_eq!(write(&stream), write(&uncached));
+
+        // Cloning does not copy the cache.
+        assert!(stream.clone().decompressed.get().is_none());
+
+        // Changing the content or the filters through the methods of the stream drops the cache.
+        stream.set_compressed_content(b"q Q".to_vec()).unwrap();
+        assert!(stream.decompressed.get().is_none());
+        assert_eq!(stream.decompressed_content_ref().unwrap(), b"q Q");
+        stream.compress_with("ASCIIHexDecode").unwrap();
+        assert!(stream.decompressed.get().is_none());
+        assert_eq!(stream.decompressed_content_ref().unwrap(), b"q Q");
+        stream.content = uncached.content.clone();
+        stream.dict = uncached.dict.clone();
+        stream.clear_decompressed_cache();
+        assert_eq!(stream.decompressed_content_ref().unwrap(), content);
+        stream.decompress();
+        assert_eq!(stream.content, content);
+    }
+
+    #[test]
+    fn decode_text_strings() {
+        let utf16 = Object::string_literal(b"\xFE\xFF\x00H\x00i\x00 \x4E\x16\xD8\x3D\xDE\x00".to_vec());
+        assert_eq!(utf16.as_text_string().unwrap(), "Hi \u{4E16}\u{1F600}");
+        let pdf_doc = Object::string_literal(b"\x93\x8D\xA0 5\xE9\x18\x7F".to_vec());
+        assert_eq!(
+            pdf_doc.as_text_string().unwrap(),
+            "\u{FB01}\u{201C}\u{20AC} 5\u{E9}\u{2D8}\u{FFFD}"
+        );
+        assert!(Object::Integer(1).as_text_string().is_err());
+    }
+
+    #[test]
+    fn encode_text_strings() {
+        let pdf_doc = Object::text_string("Caf\u{E9} \u{2014} \u{20AC}5");
+        assert_eq!(pdf_doc.as_str().unwrap(), b"Caf\xE9 \x84 \xA05");
+        assert_eq!(pdf_doc.as_text_string().unwrap(), "Caf\u{E9} \u{2014} \u{20AC}5");
+
+        let utf16 = Object::text_string("\u{041F}ривет");
+        assert_eq!(&utf16.as_str().unwrap()[..4], b"\xFE\xFF\x04\x1F");
+        assert_eq!(utf16.as_text_string().unwrap(), "\u{041F}ривет");
+
+        // Text that would be mistaken for a byte order mark is written as UTF-16BE.
+        let bom = Object::text_string("\u{FE}\u{FF}");
+        assert_eq!(bom.as_str().unwrap(), b"\xFE\xFF\x00\xFE\x00\xFF");
+        assert_eq!(bom.as_text_string().unwrap(), "\u{FE}\u{FF}");
+    }
+
+    #[test]
+    fn run_length_stream_round_trip() {
+        let content = b"aaaaaaaaaaaaaaaabcdefgh".to_vec();
+        let mut stream = Stream::new(dictionary! {}, content.clone());
+        stream.compress_with("RunLengthDecode").unwrap();
+        assert_eq!(stream.filters().unwrap(), ["RunLengthDecode"]);
+        assert_eq!(
+            stream.content,
+            [241, b'a', 6, b'b', b'c', b'd', b'e', b'f', b'g', b'h', 128]
+        );
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+    }
+
+    #[test]
+    fn decompress_lzw_stream() {
+        let stream = Stream::new(
+            dictionary! { "Filter" => "LZWDecode" },
+            vec![0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01],
+        );
+        assert_eq!(stream.decompressed_content().unwrap(), b"-----A---B");
+    }
+
+    #[test]
+    fn decompress_lzw_stream_with_predictor() {
+        // Two rows of three bytes, both using the PNG Up filter.
+        let encoded = lzw::encode(&[2, 1, 2, 3, 2, 1, 1, 1], false);
+        let stream = Stream::new(
+            dictionary! {
+                "Filter" => "LZWDecode",
+                "DecodeParms" => dictionary! { "EarlyChange" => 0, "Predictor" => 12, "Columns" => 3 },
+            },
+            encoded,
+        );
+        assert_eq!(stream.decompressed_content().unwrap(), [1, 2, 3, 2, 3, 4]);
+    }
+
+    fn predicted_stream(predictor: i64, data: Vec<u8>) -> Stream {
+        let mut stream = Stream::new(dictionary! {}, data);
+        stream.compress_with("FlateDecode").unwrap();
+        stream.dict.set(
+            "DecodeParms",
+            dictionary! { "Predictor" => predictor, "Colors" => 3, "Columns" => 2 },
+        );
+        stream
+    }
+
+    #[test]
+    fn decompress_predictor_with_overflowing_parameters() {
+        let mut stream = predicted_stream(12, vec![0; 16]);
+        let params = stream.dict.get_mut(b"DecodeParms").unwrap().as_dict_mut().unwrap();
+        params.set("Columns", i64::MAX);
+        params.set("BitsPerComponent", 16);
+        assert!(matches!(stream.decompressed_content(), Err(Error::ContentDecode)));
+    }
+
+    #[test]
+    fn decompress_png_predictors() {
+        let rows: [[u8; 6]; 3] = [
+            [10, 20, 30, 40, 50, 60],
+            [200, 100, 250, 250, 0, 255],
+            [1, 2, 3, 4, 5, 6],
+        ];
+        for filter in [
+            png::FilterType::Sub,
+            png::FilterType::Up,
+            png::FilterType::Avg,
+            png::FilterType::Paeth,
+        ] {
+            let mut encoded = vec![];
+            let mut previous = [0; 6];
+            for row in rows {
+                let mut current = row;
+                png::encode_row(filter, 3, &previous, &mut current);
+                encoded.push(filter as u8);
+                encoded.extend(current);
+                previous = row;
+            }
+            let stream = predicted_stream(15, encoded);
+            assert_eq!(stream.decompressed_content().unwrap(), rows.concat(), "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn decompress_png_average_predictor() {
+        // Average of the left and above bytes exceeding 255 must not wrap.
+        let stream = predicted_stream(13, vec![0, 200, 0, 0, 100, 0, 0, 3, 150, 0, 0, 75, 0, 0]);
+        assert_eq!(
+            stream.decompressed_content().unwrap(),
+            [200, 0, 0, 100, 0, 0, 250, 0, 0, 250, 0, 0]
+        );
+    }
+
+    #[test]
+    fn decompress_tiff_predictor() {
+        let stream = predicted_stream(2, vec![10, 20, 30, 5, 5, 5, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(
+            stream.decompressed_content().unwrap(),
+            [10, 20, 30, 15, 25, 35, 1, 1, 1, 2, 2, 2]
+        );
+    }
+
+    #[test]
+    fn decompress_ascii85_flate_stream() {
+        let content = b"BT /F1 12 Tf 72 712 Td (Hello, chained filters!) Tj ET".to_vec();
+        let mut stream = Stream::new(dictionary! {}, content.clone());
+        stream.compress_with("FlateDecode").unwrap();
+        let compressed = stream.content.clone();
+
+        let encoded = ascii85::encode(&compressed);
+
+        let stream = Stream::new(
+            dictionary! {
+                "Filter" => vec!["ASCII85Decode".into(), "FlateDecode".into()],
+                "DecodeParms" => vec![Object::Null, dictionary! { "Predictor" => 1 }.into()],
+            },
+            encoded,
+        );
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+    }
+
+    #[test]
+    fn decompress_unknown_filter_in_chain() {
+        let mut stream = Stream::new(dictionary! {}, b"data".to_vec());
+        stream.compress_with("FlateDecode").unwrap();
+        stream
+            .dict
+            .set("Filter", vec!["FlateDecode".into(), "JBIG2Decode".into()]);
+        assert!(matches!(
+            stream.decompressed_content(),
+            Err(Error::UnsupportedFilter(filter)) if filter == "JBIG2Decode"
+        ));
+    }
+
+    #[test]
+    fn compress_with_chains_decode_params() {
+        let mut stream = Stream::new(dictionary! {}, vec![1, 1, 1, 1, 2, 2]);
+        stream.compress_with("LZWDecode").unwrap();
+        stream.dict.set("DecodeParms", dictionary! { "EarlyChange" => 1 });
+        stream.compress_with("RunLengthDecode").unwrap();
+        assert_eq!(stream.filters().unwrap(), ["RunLengthDecode", "LZWDecode"]);
+        assert_eq!(
+            stream.dict.get(b"DecodeParms").unwrap(),
+            &Object::Array(vec![Object::Null, dictionary! { "EarlyChange" => 1 }.into()])
+        );
+        assert_eq!(stream.decompressed_content().unwrap(), [1, 1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn ascii_filters_round_trip() {
+        let content = b"\x00\x00\x00\x00\x00\x01 binary \xFF\xFE font data".to_vec();
+        let mut stream = Stream::new(dictionary! {}, content.clone());
+        stream.compress_with("ASCII85Decode").unwrap();
+        stream.compress_with("ASCIIHexDecode").unwrap();
+        assert_eq!(stream.filters().unwrap(), ["ASCIIHexDecode", "ASCII85Decode"]);
+        assert!(stream.content.iter().all(u8::is_ascii));
+        assert_eq!(stream.decompressed_content().unwrap(), content);
+    }
+
+    #[test]
+    fn dictionary_get_deref() {
+        let mut doc = Document::new();
+        let font_id = doc.add_object(dictionary! { "Type" => "Font" });
+        let indirect_id = doc.add_object(font_id);
+        let cycle_start = doc.new_object_id();
+        let cycle_end = doc.add_object(cycle_start);
+        doc.objects.insert(cycle_start, Object::Reference(cycle_end));
+
+        let dict = dictionary! {
+            "Direct" => 12,
+            "Font" => indirect_id,
+            "Cycle" => cycle_start,
+            "Missing" => (99, 0),
+        };
+        assert_eq!(dict.get_deref(b"Direct", &doc).unwrap(), &Object::Integer(12));
+        assert!(dict
+            .get_deref(b"Font", &doc)
+            .and_then(Object::as_dict)
+            .unwrap()
+            .type_is(b"Font"));
+        assert!(matches!(dict.get_deref(b"Cycle", &doc), Err(Error::ReferenceCycle(id)) if id == cycle_start));
+        assert!(matches!(dict.get_deref(b"Missing", &doc), Err(Error::ObjectNotFound)));
+        assert!(matches!(dict.get_deref(b"Absent", &doc), Err(Error::DictKey)));
+    }
+
+    #[test]
+    fn typed_accessors() {
+        assert_eq!(Object::Integer(3).as_f64().unwrap(), 3.0);
+        assert_eq!(Object::Real(0.5).as_f64().unwrap(), 0.5);
+        assert_eq!(Object::from("Font").as_name_str().unwrap(), "Font");
+
+        let mut array = Object::Array(vec![]);
+        array.as_array_mut().unwrap().push(1.into());
+        assert_eq!(array.as_array().unwrap().len(), 1);
+        let mut dict = Object::Dictionary(dictionary! {});
+        dict.as_dict_mut().unwrap().set("Key", true);
+        assert!(dict.as_dict().unwrap().get(b"Key").and_then(Object::as_bool).unwrap());
+
+        let err = Object::string_literal("12").as_f64().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ObjectType {
+                expected: "Integer or Real",
+                found: "String"
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Expected an object of type Integer or Real, found String"
+        );
+        assert!(matches!(
+            Object::Null.as_dict_mut(),
+            Err(Error::ObjectType {
+                expected: "Dictionary",
+                found: "Null"
+            })
+        ));
+    }
+
+    #[test]
+    fn dictionary_keeps_insertion_order() {
+        let mut dict = dictionary! { "Zeta" => 1, "Alpha" => 2, "Mid" => 3 };
+        dict.set("Alpha", 20);
+        dict.set("Beta", 4);
+        dict.remove(b"Zeta");
+        dict.set("Zeta", 10);
+        let keys: Vec<_> = dict.iter().map(|(key, _)| key.as_slice()).collect();
+        assert_eq!(keys, [&b"Alpha"[..], b"Mid", b"Beta", b"Zeta"]);
+        assert_eq!(format!("{:?}", dict), "<</Alpha 20/Mid 3/Beta 4/Zeta 10>>");
+
+        let mut stream = Stream::new(
+            dictionary! { "Type" => "XObject", "Subtype" => "Form" },
+            vec![b'a'; 100],
+        );
+        stream.compress_with("FlateDecode").unwrap();
+        stream.decompress();
+        let keys: Vec<_> = stream.dict.iter().map(|(key, _)| key.as_slice()).collect();
+        assert_eq!(keys, [&b"Type"[..], b"Subtype", b"Length"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut stream = Stream::new(dictionary! { "Type" => "XObject" }, b"\x00\xffdata".to_vec());
+        stream.start_position = Some(42);
+        let object = Object::Array(vec![
+            Object::Null,
+            true.into(),
+            3.into(),
+            1.5.into(),
+            Object::name(b"A B#".to_vec()),
+            Object::String(b"\xfe\xff".to_vec(), StringFormat::Hexadecimal),
+            dictionary! { "Key" => (1, 0), "Caf\u{e9}" => "Value" }.into(),
+            stream.into(),
+        ]);
+
+        let json = serde_json::to_string(&object).unwrap();
+        assert!(json.contains(r#"{"Name":"A#20B#23"}"#), "{}", json);
+        assert!(json.contains(r#"{"String":["feff","Hexadecimal"]}"#), "{}", json);
+        assert!(json.contains(r#""Key":{"Reference":[1,0]}"#), "{}", json);
+        assert!(json.contains(r#""Caf#C3#A9":{"Name":"Value"}"#), "{}", json);
+        assert!(json.contains(r#""content":"00ff64617461""#), "{}", json);
+        let deserialized: Object = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, object);
+        assert!(serde_json::from_str::<Object>(r#"{"Name":"A#2"}"#).is_err());
+    }
+}
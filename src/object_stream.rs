@@ -40,7 +40,7 @@ pub fn new(stream: &mut Stream) -> Result<ObjectStream> {
             let id = chunk[0]?;
             let offset = first_offset + chunk[1]? as usize;
 
-            let object = parser::direct_object(&stream.content[offset..])?;
+            let object = parser::direct_object(stream.content.get(offset..)?)?;
 
             Some(((id, 0), object))
         };
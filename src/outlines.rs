@@ -1,16 +1,38 @@
+use std::collections::HashSet;
+
 use indexmap::IndexMap;
 
-use super::{Destination, Dictionary, Document, Error, Object, Result};
+use super::{Action, Destination, Dictionary, Document, Error, LinkTarget, Object, ObjectId, Result};
 
 pub enum Outline {
     Destination(Destination),
     SubOutlines(Vec<Outline>),
 }
 
+/// Item of the document outline, see [`Document::get_outline_tree`] and [`Document::set_outline`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+    /// Target of the item, from its `Dest` entry, or its `GoTo` or `URI` action. `None` if the
+    /// item has neither or another kind of action.
+    pub dest: Option<LinkTarget>,
+    pub children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    pub fn new(title: impl Into<String>, dest: Option<LinkTarget>) -> Self {
+        OutlineItem {
+            title: title.into(),
+            dest,
+            children: Vec::new(),
+        }
+    }
+}
+
 fn build_outline_result(
     dest: &Object, title: &Object, named_destinations: &mut IndexMap<Vec<u8>, Destination>,
 ) -> Result<Option<Outline>> {
-    return Ok(Some(match dest {
+    Ok(Some(match dest {
         Object::Array(ref obj_array) => Outline::Destination(Destination::new(
             title.to_owned(),
             obj_array[0].clone(),
@@ -25,7 +47,7 @@ fn build_outline_result(
             }
         }
         _ => return Err(Error::Invalid(format!("Unexpected destination {:?}", dest))),
-    }));
+    }))
 }
 
 impl Document {
@@ -84,32 +106,251 @@ pub fn get_outlines(
             return Ok(outlines);
         }
         let node = node.unwrap();
+        let mut items = Vec::new();
+        self.collect_outlines(&node, &mut items, named_destinations, &mut HashSet::new())?;
+        Ok(outlines.map(|mut outlines| {
+            outlines.append(&mut items);
+            outlines
+        }))
+    }
+
+    /// Collect the outline items from `node` and its next siblings, with their children.
+    /// Fails with [`Error::ReferenceCycle`] if an item was already visited.
+    fn collect_outlines(
+        &self, node: &Object, outlines: &mut Vec<Outline>, named_destinations: &mut IndexMap<Vec<u8>, Destination>,
+        visited: &mut HashSet<ObjectId>,
+    ) -> Result<()> {
         let mut node = match node.as_dict() {
             Ok(n) => n,
             Err(_) => self.get_object(node.as_reference()?)?.as_dict()?,
         };
         loop {
             if let Ok(Some(outline)) = self.get_outline(node, named_destinations) {
-                if let Some(ref mut outlines) = outlines {
-                    outlines.push(outline);
-                }
+                outlines.push(outline);
             }
             if let Ok(first) = node.get(b"First") {
-                let sub_outlines = Vec::new();
-                let sub_outlines = self.get_outlines(Some(first.clone()), Some(sub_outlines), named_destinations)?;
-                if let Some(sub_outlines) = sub_outlines {
-                    if !sub_outlines.is_empty() {
-                        if let Some(ref mut outlines) = outlines {
-                            outlines.push(Outline::SubOutlines(sub_outlines));
-                        }
+                if let Ok(id) = first.as_reference() {
+                    if !visited.insert(id) {
+                        return Err(Error::ReferenceCycle(id));
                     }
                 }
+                let mut sub_outlines = Vec::new();
+                self.collect_outlines(first, &mut sub_outlines, named_destinations, visited)?;
+                if !sub_outlines.is_empty() {
+                    outlines.push(Outline::SubOutlines(sub_outlines));
+                }
+            }
+            if let Ok(id) = node.get(b"Next").and_then(Object::as_reference) {
+                if !visited.insert(id) {
+                    return Err(Error::ReferenceCycle(id));
+                }
             }
             node = match self.get_dict_in_dict(node, b"Next") {
                 Ok(n) => n,
                 Err(_) => break,
             };
         }
-        Ok(outlines)
+        Ok(())
+    }
+}
+
+impl Document {
+    /// Read the outline tree of the catalog's `Outlines` dictionary, with the destinations
+    /// resolved to pages of the document.
+    ///
+    /// Returns an empty list if the document has no outline. Items that are not dictionaries
+    /// end their list of siblings, as do items that were already visited.
+    pub fn get_outline_tree(&self) -> Result<Vec<OutlineItem>> {
+        let Ok(outlines) = self.catalog()?.get_deref(b"Outlines", self).and_then(Object::as_dict) else {
+            return Ok(vec![]);
+        };
+        let mut visited = HashSet::new();
+        Ok(self.outline_items(outlines, &mut visited))
+    }
+
+    fn outline_items(&self, parent: &Dictionary, visited: &mut HashSet<ObjectId>) -> Vec<OutlineItem> {
+        let mut items = vec![];
+        let mut next = parent.get(b"First").and_then(Object::as_reference);
+        while let Ok(id) = next {
+            if !visited.insert(id) {
+                break;
+            }
+            let Ok(node) = self.get_dictionary(id) else {
+                break;
+            };
+            let dest = match node.get_deref(b"A", self).and_then(Object::as_dict) {
+                Ok(action) => match self.parse_action(action) {
+                    Action::GoTo(dest) => Some(self.link_target(dest)),
+                    Action::Uri(uri) => Some(LinkTarget::Uri(uri)),
+                    _ => None,
+                },
+                Err(_) => node
+                    .get_deref(b"Dest", self)
+                    .ok()
+                    .map(|dest| self.link_target(dest.clone())),
+            };
+            items.push(OutlineItem {
+                title: self.outline_title(node),
+                dest,
+                children: self.outline_items(node, visited),
+            });
+            next = node.get(b"Next").and_then(Object::as_reference);
+        }
+        items
+    }
+
+    fn outline_title(&self, node: &Dictionary) -> String {
+        node.get_deref(b"Title", self)
+            .and_then(Object::as_text_string)
+            .unwrap_or_default()
+    }
+
+    /// Replace the outline of the document with a new tree of items, all of them open, and
+    /// return the id of the new `Outlines` dictionary.
+    ///
+    /// An empty list removes the outline. The objects of the previous outline are left in the
+    /// document, [`Document::prune_objects`] removes them.
+    pub fn set_outline(&mut self, items: &[OutlineItem]) -> Result<Option<ObjectId>> {
+        if items.is_empty() {
+            self.catalog_mut()?.remove(b"Outlines");
+            return Ok(None);
+        }
+        let outlines_id = self.new_object_id();
+        let mut outlines = dictionary! { "Type" => "Outlines" };
+        let count = self.add_outline_items(outlines_id, &mut outlines, items);
+        outlines.set("Count", count);
+        self.objects.insert(outlines_id, outlines.into());
+        self.catalog_mut()?.set("Outlines", outlines_id);
+        Ok(Some(outlines_id))
+    }
+
+    /// Add the outline items as children of `parent`, setting its `First` and `Last` entries,
+    /// and return the number of items added, including the descendants.
+    fn add_outline_items(&mut self, parent_id: ObjectId, parent: &mut Dictionary, items: &[OutlineItem]) -> i64 {
+        let ids: Vec<ObjectId> = items.iter().map(|_| self.new_object_id()).collect();
+        let mut count = 0;
+        for (i, item) in items.iter().enumerate() {
+            let mut node = dictionary! {
+                "Title" => Object::text_string(&item.title),
+                "Parent" => parent_id,
+            };
+            if i > 0 {
+                node.set("Prev", ids[i - 1]);
+            }
+            if let Some(&next) = ids.get(i + 1) {
+                node.set("Next", next);
+            }
+            match &item.dest {
+                Some(LinkTarget::Page { page_id, view }) => {
+                    let mut dest = vec![Object::Reference(*page_id)];
+                    if view.is_empty() {
+                        dest.push("Fit".into());
+                    } else {
+                        dest.extend(view.iter().cloned());
+                    }
+                    node.set("Dest", dest);
+                }
+                Some(LinkTarget::Uri(uri)) => {
+                    node.set(
+                        "A",
+                        dictionary! { "S" => "URI", "URI" => Object::string_literal(uri.as_str()) },
+                    );
+                }
+                Some(LinkTarget::Unresolved(dest)) => node.set("Dest", dest.clone()),
+                None => {}
+            }
+            if !item.children.is_empty() {
+                let descendants = self.add_outline_items(ids[i], &mut node, &item.children);
+                node.set("Count", descendants);
+                count += descendants;
+            }
+            count += 1;
+            self.objects.insert(ids[i], node.into());
+        }
+        if let (Some(&first), Some(&last)) = (ids.first(), ids.last()) {
+            parent.set("First", first);
+            parent.set("Last", last);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creator::tests::create_document;
+    use crate::{Error, LinkTarget, Object, OutlineItem};
+
+    #[test]
+    fn outline_round_trip() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let view = vec![Object::Name(b"XYZ".to_vec()), 0.into(), 700.into(), Object::Null];
+        let mut chapter = OutlineItem::new("Chapter", Some(LinkTarget::Page { page_id, view: vec![] }));
+        chapter.children = vec![
+            OutlineItem::new(
+                "Section 1",
+                Some(LinkTarget::Page {
+                    page_id,
+                    view: view.clone(),
+                }),
+            ),
+            OutlineItem::new(
+                "Section 2 \u{e9}\u{2014}",
+                Some(LinkTarget::Uri("https://example.com/".into())),
+            ),
+        ];
+        let items = vec![chapter, OutlineItem::new("Appendix", None)];
+
+        let outlines_id = doc.set_outline(&items).unwrap().unwrap();
+        let outlines = doc.get_dictionary(outlines_id).unwrap();
+        assert_eq!(outlines.get(b"Count").unwrap().as_i64().unwrap(), 4);
+        let first = outlines.get(b"First").unwrap().as_reference().unwrap();
+        let last = outlines.get(b"Last").unwrap().as_reference().unwrap();
+        let chapter = doc.get_dictionary(first).unwrap();
+        assert_eq!(chapter.get(b"Count").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(chapter.get(b"Next").unwrap().as_reference().unwrap(), last);
+        let appendix = doc.get_dictionary(last).unwrap();
+        assert_eq!(appendix.get(b"Prev").unwrap().as_reference().unwrap(), first);
+        assert_eq!(appendix.get(b"Parent").unwrap().as_reference().unwrap(), outlines_id);
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let mut doc = crate::Document::load_mem(&buffer).unwrap();
+        let mut read = doc.get_outline_tree().unwrap();
+        assert_eq!(
+            read[0].dest,
+            Some(LinkTarget::Page {
+                page_id,
+                view: vec![Object::Name(b"Fit".to_vec())]
+            })
+        );
+        read[0].dest = items[0].dest.clone();
+        assert_eq!(read, items);
+
+        assert_eq!(doc.set_outline(&[]).unwrap(), None);
+        assert!(doc.get_outline_tree().unwrap().is_empty());
+    }
+
+    #[test]
+    fn outline_cycle() {
+        let mut doc = create_document();
+        let items = vec![OutlineItem::new("Chapter", None), OutlineItem::new("Appendix", None)];
+        let outlines_id = doc.set_outline(&items).unwrap().unwrap();
+        let first = doc
+            .get_dictionary(outlines_id)
+            .and_then(|outlines| outlines.get(b"First"))
+            .and_then(Object::as_reference)
+            .unwrap();
+        let last = doc
+            .get_dictionary(first)
+            .and_then(|chapter| chapter.get(b"Next"))
+            .and_then(Object::as_reference)
+            .unwrap();
+        doc.get_dictionary_mut(last).unwrap().set("Next", first);
+        assert!(matches!(
+            doc.get_outlines(None, None, &mut Default::default()),
+            Err(Error::ReferenceCycle(_))
+        ));
     }
 }
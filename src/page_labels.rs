@@ -0,0 +1,283 @@
+use crate::name_tree::read_number_tree;
+use crate::{Document, Object, Result};
+
+/// Largest number written with roman numerals.
+const MAX_ROMAN: u32 = 3999;
+/// Largest number written with letters, with 100 of them.
+const MAX_LETTERS: u32 = 26 * 100;
+
+/// Numbering style of a page label range, from its `S` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLabelStyle {
+    /// Decimal arabic numerals, `D`.
+    Decimal,
+    /// Uppercase roman numerals, `R`. Numbers above 3999 are written in decimal.
+    UpperRoman,
+    /// Lowercase roman numerals, `r`.
+    LowerRoman,
+    /// Uppercase letters, `A`: A to Z for the first 26 pages, AA to ZZ for the next 26, and so on.
+    /// Numbers that would take more than 100 letters are written in decimal.
+    UpperLetters,
+    /// Lowercase letters, `a`.
+    LowerLetters,
+}
+
+impl PageLabelStyle {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"D" => Some(PageLabelStyle::Decimal),
+            b"R" => Some(PageLabelStyle::UpperRoman),
+            b"r" => Some(PageLabelStyle::LowerRoman),
+            b"A" => Some(PageLabelStyle::UpperLetters),
+            b"a" => Some(PageLabelStyle::LowerLetters),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PageLabelStyle::Decimal => "D",
+            PageLabelStyle::UpperRoman => "R",
+            PageLabelStyle::LowerRoman => "r",
+            PageLabelStyle::UpperLetters => "A",
+            PageLabelStyle::LowerLetters => "a",
+        }
+    }
+
+    /// Format the page number in this style.
+    fn format(self, number: u32) -> String {
+        // Larger numbers, which a hostile `St` entry can set, are written in decimal rather
+        // than with a huge number of numerals or letters.
+        match self {
+            PageLabelStyle::UpperRoman if number <= MAX_ROMAN => roman(number),
+            PageLabelStyle::LowerRoman if number <= MAX_ROMAN => roman(number).to_lowercase(),
+            PageLabelStyle::UpperLetters if number <= MAX_LETTERS => letters(number),
+            PageLabelStyle::LowerLetters if number <= MAX_LETTERS => letters(number).to_lowercase(),
+            _ => number.to_string(),
+        }
+    }
+}
+
+fn roman(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
+}
+
+fn letters(number: u32) -> String {
+    let letter = char::from(b'A' + ((number - 1) % 26) as u8);
+    letter.to_string().repeat((number as usize - 1) / 26 + 1)
+}
+
+/// Range of pages sharing a labelling scheme, see [`Document::page_label_ranges`] and
+/// [`Document::set_page_labels`]. A range ends where the next one starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLabelRange {
+    /// Index of the first page of the range, from 0.
+    pub first_page: u32,
+    /// Numbering style of the range, `None` if the labels only consist of the prefix.
+    pub style: Option<PageLabelStyle>,
+    pub prefix: String,
+    /// Number of the first page of the range, from its `St` entry. At least 1.
+    pub start: u32,
+}
+
+impl PageLabelRange {
+    pub fn new(first_page: u32, style: Option<PageLabelStyle>, prefix: impl Into<String>, start: u32) -> Self {
+        PageLabelRange {
+            first_page,
+            style,
+            prefix: prefix.into(),
+            start,
+        }
+    }
+
+    /// Label of the page at index `page` of the document, which is in this range.
+    fn label(&self, page: u32) -> String {
+        let number = self.start.max(1).saturating_add(page - self.first_page);
+        match self.style {
+            Some(style) => format!("{}{}", self.prefix, style.format(number)),
+            None => self.prefix.clone(),
+        }
+    }
+}
+
+impl Document {
+    /// Get the page label ranges from the `PageLabels` number tree of the catalog, sorted by
+    /// their first page. Empty if the catalog has no `PageLabels`.
+    ///
+    /// Entries whose key is not a page index or whose value is not a page label dictionary are
+    /// skipped, and so are unknown numbering styles.
+    pub fn page_label_ranges(&self) -> Result<Vec<PageLabelRange>> {
+        let Ok(tree) = self.catalog()?.get_deref(b"PageLabels", self).and_then(Object::as_dict) else {
+            return Ok(vec![]);
+        };
+        let ranges = read_number_tree(self, tree).into_iter().filter_map(|(key, label)| {
+            let label = self.dereference(&label).ok()?.1.as_dict().ok()?;
+            Some(PageLabelRange {
+                first_page: u32::try_from(key).ok()?,
+                style: label
+                    .get_deref(b"S", self)
+                    .and_then(Object::as_name)
+                    .ok()
+                    .and_then(PageLabelStyle::from_name),
+                prefix: label
+                    .get_deref(b"P", self)
+                    .and_then(Object::as_text_string)
+                    .unwrap_or_default(),
+                start: label
+                    .get_deref(b"St", self)
+                    .and_then(Object::as_i64)
+                    .ok()
+                    .and_then(|start| u32::try_from(start).ok())
+                    .unwrap_or(1)
+                    .max(1),
+            })
+        });
+        Ok(ranges.collect())
+    }
+
+    /// Get the label of each page, in page order, as a viewer displays it.
+    ///
+    /// Pages are labelled with their page number, from 1, if the catalog has no `PageLabels`,
+    /// and so are the pages before the first range.
+    pub fn page_labels(&self) -> Result<Vec<String>> {
+        let ranges = self.page_label_ranges()?;
+        let page_count = self.page_iter().count() as u32;
+        let labels = (0..page_count).map(
+            |page| match ranges.iter().rev().find(|range| range.first_page <= page) {
+                Some(range) => range.label(page),
+                None => (page + 1).to_string(),
+            },
+        );
+        Ok(labels.collect())
+    }
+
+    /// Set the page labels of the document, replacing the `PageLabels` of the catalog. The
+    /// ranges are written as a single number tree node stored in the catalog, and an empty
+    /// slice removes the page labels.
+    ///
+    /// The first range should start at the first page, for the labels to cover all pages.
+    pub fn set_page_labels(&mut self, ranges: &[PageLabelRange]) -> Result<()> {
+        let mut ranges: Vec<&PageLabelRange> = ranges.iter().collect();
+        ranges.sort_by_key(|range| range.first_page);
+        ranges.dedup_by_key(|range| range.first_page);
+        let catalog = self.catalog_mut()?;
+        if ranges.is_empty() {
+            catalog.remove(b"PageLabels");
+            return Ok(());
+        }
+        let nums = ranges.into_iter().flat_map(|range| {
+            let mut label = dictionary! { "Type" => "PageLabel" };
+            if let Some(style) = range.style {
+                label.set("S", style.name());
+            }
+            if !range.prefix.is_empty() {
+                label.set("P", Object::text_string(&range.prefix));
+            }
+            if range.start > 1 {
+                label.set("St", i64::from(range.start));
+            }
+            [Object::Integer(i64::from(range.first_page)), Object::Dictionary(label)]
+        });
+        catalog.set("PageLabels", dictionary! { "Nums" => nums.collect::<Vec<_>>() });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PageLabelRange, PageLabelStyle};
+    use crate::creator::tests::create_document;
+    use crate::{Document, Object};
+
+    /// A document with the given number of pages.
+    fn document(page_count: usize) -> Document {
+        let pages = vec![create_document(); page_count];
+        Document::merge(pages).unwrap()
+    }
+
+    #[test]
+    fn roman_then_decimal_page_labels() {
+        let mut doc = document(7);
+        assert_eq!(doc.page_labels().unwrap(), ["1", "2", "3", "4", "5", "6", "7"]);
+
+        let ranges = vec![
+            PageLabelRange::new(0, Some(PageLabelStyle::LowerRoman), "", 1),
+            PageLabelRange::new(4, Some(PageLabelStyle::Decimal), "", 1),
+        ];
+        doc.set_page_labels(&ranges).unwrap();
+        assert_eq!(doc.page_labels().unwrap(), ["i", "ii", "iii", "iv", "1", "2", "3"]);
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let mut doc = Document::load_mem(&buffer).unwrap();
+        assert_eq!(doc.page_label_ranges().unwrap(), ranges);
+
+        let ranges = [
+            PageLabelRange::new(0, Some(PageLabelStyle::UpperRoman), "", 8),
+            PageLabelRange::new(2, Some(PageLabelStyle::Decimal), "A-", 8),
+            PageLabelRange::new(4, None, "Cover", 1),
+            PageLabelRange::new(5, Some(PageLabelStyle::LowerLetters), "", 26),
+        ];
+        doc.set_page_labels(&ranges).unwrap();
+        assert_eq!(
+            doc.page_labels().unwrap(),
+            ["VIII", "IX", "A-8", "A-9", "Cover", "z", "aa"]
+        );
+
+        doc.set_page_labels(&[]).unwrap();
+        assert!(!doc.catalog().unwrap().has(b"PageLabels"));
+    }
+
+    #[test]
+    fn page_labels_from_number_tree() {
+        let mut doc = document(3);
+        let leaf = doc.add_object(dictionary! {
+            "Limits" => vec![1.into(), 1.into()],
+            "Nums" => vec![1.into(), dictionary! { "S" => "R", "St" => 4 }.into()],
+        });
+        let first = doc.add_object(dictionary! { "S" => "D", "P" => Object::string_literal("p") });
+        let tree = dictionary! {
+            "Nums" => vec![0.into(), first.into()],
+            "Kids" => vec![leaf.into()],
+        };
+        doc.catalog_mut().unwrap().set("PageLabels", tree);
+        assert_eq!(doc.page_labels().unwrap(), ["p1", "IV", "V"]);
+    }
+
+    #[test]
+    fn large_page_numbers_in_decimal() {
+        let mut doc = document(2);
+        let ranges = [
+            PageLabelRange::new(0, Some(PageLabelStyle::UpperRoman), "", 3999),
+            PageLabelRange::new(1, Some(PageLabelStyle::LowerLetters), "", 4_000_000_000),
+        ];
+        doc.set_page_labels(&ranges).unwrap();
+        assert_eq!(doc.page_labels().unwrap(), ["MMMCMXCIX", "4000000000"]);
+        assert_eq!(PageLabelStyle::LowerRoman.format(4000), "4000");
+        assert_eq!(PageLabelStyle::UpperLetters.format(2600), "Z".repeat(100));
+        assert_eq!(PageLabelStyle::UpperLetters.format(2601), "2601");
+    }
+}
@@ -1,12 +1,15 @@
 use super::{Dictionary, Object, ObjectId, Stream, StringFormat};
 use crate::content::*;
 use crate::error::XrefError;
-use crate::reader::Reader;
+use crate::parser_aux::{scan_inline_image_end, scan_stream_end, split_endobj};
+use crate::reader::{Reader, MAX_NESTING_DEPTH};
 use crate::xref::*;
 use crate::{Error, Result};
+use log::warn;
 use pom::char_class::{alpha, hex_digit, multispace, oct_digit};
 use pom::parser::*;
 use std::cmp::max;
+use std::ops::Range;
 use std::str::{self, FromStr};
 
 fn eol<'a>() -> Parser<'a, u8, u8> {
@@ -43,14 +46,17 @@ fn hex_char<'a>() -> Parser<'a, u8, u8> {
     let number = is_a(hex_digit).repeat(2);
     number
         .collect()
-        .convert(|v| u8::from_str_radix(str::from_utf8(v).unwrap(), 16))
+        .convert(str::from_utf8)
+        .convert(|v| u8::from_str_radix(v, 16))
 }
 
 fn oct_char<'a>() -> Parser<'a, u8, u8> {
     let number = is_a(oct_digit).repeat(1..4);
     number
         .collect()
-        .convert(|v| u8::from_str_radix(str::from_utf8(v).unwrap(), 8))
+        // Spec requires us to ignore any overflow.
+        .convert(str::from_utf8)
+        .convert(|v| u16::from_str_radix(v, 8).map(|o| o as u8))
 }
 
 fn name<'a>() -> Parser<'a, u8, Vec<u8>> {
@@ -105,16 +111,64 @@ fn literal_string<'a>() -> Parser<'a, u8, Vec<u8>> {
         - sym(b')')
 }
 
+/// Parse a hexadecimal string.
+///
+/// White-space between the digits is ignored and an odd number of digits is padded with a final 0.
+/// Any other byte that is not a hex digit makes the
+/// string invalid: a warning with the offending byte and its offset from the opening `<` is
+/// logged and the parser fails.
 fn hexadecimal_string<'a>() -> Parser<'a, u8, Vec<u8>> {
-    sym(b'<') * (white_space() * hex_char()).repeat(0..) - (white_space() * sym(b'>'))
+    let digit = is_a(hex_digit).map(|c| (c as char).to_digit(16).unwrap() as u8);
+    let digits = sym(b'<') * !sym(b'<') * (white_space() * digit).repeat(0..) - white_space();
+    Parser::new(move |input: &'a [u8], start: usize| {
+        let (digits, end) = digits.parse_at(input, start)?;
+        // A final odd digit is treated as if it were followed by 0.
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+            .collect();
+        match input.get(end) {
+            Some(b'>') => Ok((bytes, end + 1)),
+            Some(byte) => {
+                warn!(
+                    "Invalid byte {:#04x} at offset {} in hexadecimal string",
+                    byte,
+                    end - start
+                );
+                Err(pom::Error::Mismatch {
+                    message: "expect hexadecimal digit or '>'".to_string(),
+                    position: end,
+                })
+            }
+            None => Err(pom::Error::Incomplete),
+        }
+    })
+}
+
+/// Parser failing where arrays and dictionaries are nested deeper than allowed, instead of
+/// overflowing the stack.
+fn nesting_limit<'a, O: 'a>() -> Parser<'a, u8, O> {
+    Parser::new(|_, position| {
+        Err(pom::Error::Custom {
+            message: "nesting too deep".to_string(),
+            position,
+            inner: None,
+        })
+    })
 }
 
-fn array<'a>() -> Parser<'a, u8, Vec<Object>> {
-    sym(b'[') * space() * call(_direct_object).repeat(0..) - sym(b']')
+fn array<'a>(depth: usize) -> Parser<'a, u8, Vec<Object>> {
+    let Some(depth) = depth.checked_sub(1) else {
+        return nesting_limit();
+    };
+    sym(b'[') * space() * call(move || _direct_object(depth)).repeat(0..) - sym(b']')
 }
 
-fn dictionary<'a>() -> Parser<'a, u8, Dictionary> {
-    let entry = name() - space() + call(_direct_object);
+fn dictionary<'a>(depth: usize) -> Parser<'a, u8, Dictionary> {
+    let Some(depth) = depth.checked_sub(1) else {
+        return nesting_limit();
+    };
+    let entry = name() - space() + call(move || _direct_object(depth));
     let entries = seq(b"<<") * space() * entry.repeat(0..) - seq(b">>");
     entries.map(|entries| {
         entries
@@ -127,41 +181,73 @@ fn dictionary<'a>() -> Parser<'a, u8, Dictionary> {
 }
 
 fn stream<'a>(reader: &'a Reader) -> Parser<'a, u8, Stream> {
-    (dictionary() - space() - seq(b"stream") - eol())
+    (dictionary(reader.options.max_nesting_depth) - space() - seq(b"stream") - eol())
         >> move |dict: Dictionary| {
-            if let Ok(length) = dict.get(b"Length").and_then(|value| {
-                if let Ok(id) = value.as_reference() {
-                    return reader.get_object(id).and_then(|value| value.as_i64());
-                }
-                value.as_i64()
-            }) {
-                if length < 0 {
-                    println!("Warning: invalid stream length {}", length);
-                    return empty().map(move |_| Stream::new(dict.clone(), vec![]));
+            let length = match dict.get(b"Length") {
+                Ok(Object::Reference(id)) => match reader.get_object(*id) {
+                    Ok(value) => value.as_i64().ok(),
+                    // The length object can't be read yet, e.g. because it is stored in an object stream
+                    // or the xref table is still being parsed, so look for the end of the stream instead.
+                    Err(_) => {
+                        return Parser::new(move |input: &'a [u8], start: usize| {
+                            match scan_stream_end(&input[start..]) {
+                                Some((data_len, end)) => {
+                                    let data = input[start..start + data_len].to_vec();
+                                    Ok((Stream::recovered(dict.clone(), data), start + end))
+                                }
+                                None => Ok((Stream::with_position(dict.clone(), start), start)),
+                            }
+                        })
+                    }
+                },
+                Ok(value) => value.as_i64().ok(),
+                Err(_) => None,
+            };
+
+            Parser::new(move |input: &'a [u8], start: usize| {
+                if let Some(length) = length.filter(|&length| length >= 0) {
+                    let data = take(length as usize) - eol().opt() - seq(b"endstream");
+                    if let Ok((data, end)) = data.parse_at(input, start) {
+                        return Ok((Stream::new(dict.clone(), data.to_vec()), end));
+                    }
                 }
-                let stream = take(length as usize) - eol().opt() - seq(b"endstream").expect("endstream");
-                stream.map(move |data| Stream::new(dict.clone(), data.to_vec()))
-            } else {
-                empty().pos().map(move |pos| Stream::with_position(dict.clone(), pos))
-            }
+
+                // The Length entry is missing or does not match the stream data,
+                // look for the end of the stream instead.
+                let (data_len, end) = scan_stream_end(&input[start..]).ok_or_else(|| pom::Error::Mismatch {
+                    message: "expect endstream".to_string(),
+                    position: start,
+                })?;
+                warn!(
+                    "Stream Length {:?} is missing or invalid, recovered {} bytes by scanning for endstream",
+                    dict.get(b"Length").ok(),
+                    data_len
+                );
+                let data = input[start..start + data_len].to_vec();
+                Ok((Stream::recovered(dict.clone(), data), start + end))
+            })
         }
 }
 
 fn object_id<'a>() -> Parser<'a, u8, ObjectId> {
     let id = one_of(b"0123456789")
         .repeat(1..)
-        .convert(|v| u32::from_str(str::from_utf8(&v).unwrap()));
+        .collect()
+        .convert(str::from_utf8)
+        .convert(u32::from_str);
     let gen = one_of(b"0123456789")
         .repeat(1..)
-        .convert(|v| u16::from_str(str::from_utf8(&v).unwrap()));
+        .collect()
+        .convert(str::from_utf8)
+        .convert(u16::from_str);
     id - space() + gen - space()
 }
 
 pub fn direct_object(input: &[u8]) -> Option<Object> {
-    _direct_object().parse(input).ok()
+    _direct_object(MAX_NESTING_DEPTH).parse(input).ok()
 }
 
-fn _direct_object<'a>() -> Parser<'a, u8, Object> {
+fn _direct_object<'a>(depth: usize) -> Parser<'a, u8, Object> {
     (seq(b"null").map(|_| Object::Null)
         | seq(b"true").map(|_| Object::Boolean(true))
         | seq(b"false").map(|_| Object::Boolean(false))
@@ -171,8 +257,8 @@ fn _direct_object<'a>() -> Parser<'a, u8, Object> {
         | name().map(Object::Name)
         | literal_string().map(Object::string_literal)
         | hexadecimal_string().map(|bytes| Object::String(bytes, StringFormat::Hexadecimal))
-        | array().map(Object::Array)
-        | dictionary().map(Object::Dictionary))
+        | array(depth).map(Object::Array)
+        | dictionary(depth).map(Object::Dictionary))
         - space()
 }
 
@@ -186,32 +272,74 @@ fn object<'a>(reader: &'a Reader) -> Parser<'a, u8, Object> {
         | name().map(Object::Name)
         | literal_string().map(Object::string_literal)
         | hexadecimal_string().map(|bytes| Object::String(bytes, StringFormat::Hexadecimal))
-        | array().map(Object::Array)
+        | array(reader.options.max_nesting_depth).map(Object::Array)
         | stream(reader).map(Object::Stream)
-        | dictionary().map(Object::Dictionary))
+        | dictionary(reader.options.max_nesting_depth).map(Object::Dictionary))
         - space()
 }
 
+/// Parse the indirect object at `offset` of `input`.
+///
+/// Like most readers, this accepts whitespace in front of the object, a missing `endobj`, a
+/// missing object, which is read as null, and a name running into `endobj`.
 pub fn indirect_object(
     input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader,
 ) -> Result<(ObjectId, Object)> {
-    _indirect_object(expected_id, reader)
+    parse_indirect_object(input, offset, expected_id, reader, false)
+}
+
+/// Parse the indirect object at `offset` of `input` like [`indirect_object`], but requiring the
+/// object to start at `offset` and to end with `endobj`.
+pub fn indirect_object_strict(
+    input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader,
+) -> Result<(ObjectId, Object)> {
+    parse_indirect_object(input, offset, expected_id, reader, true)
+}
+
+fn parse_indirect_object(
+    input: &[u8], offset: usize, expected_id: Option<ObjectId>, reader: &Reader, strict: bool,
+) -> Result<(ObjectId, Object)> {
+    if offset > input.len() {
+        return Err(Error::Offset(offset));
+    }
+    let header = if strict {
+        indirect_object_header(expected_id)
+    } else {
+        space() * indirect_object_header(expected_id)
+    };
+    let (id, object_offset) = header
         .parse_at(input, offset)
-        .map(|(out, _)| out)
-        .map_err(|_| Error::Parse { offset })
+        .map_err(|_| Error::parse_object(input, offset, None))?;
+    let strict_object = (object(reader) - space() - seq(b"endobj")).parse_at(input, object_offset);
+    let object = match strict_object {
+        Ok((object, _)) => object,
+        Err(_) if strict => return Err(Error::parse_object(input, object_offset, Some(id))),
+        Err(_) => {
+            let empty = space() * (seq(b"endobj").discard() | end()).map(|_| Object::Null);
+            (empty | object(reader).map(split_endobj))
+                .parse_at(input, object_offset)
+                .map(|(object, _)| object)
+                .map_err(|_| Error::parse_object(input, object_offset, Some(id)))?
+        }
+    };
+    Ok((id, object))
 }
 
 fn _indirect_object<'a>(expected_id: Option<ObjectId>, reader: &'a Reader) -> Parser<'a, u8, (ObjectId, Object)> {
+    indirect_object_header(expected_id) + indirect_object_body(reader)
+}
+
+fn indirect_object_header<'a>(expected_id: Option<ObjectId>) -> Parser<'a, u8, ObjectId> {
     object_id().convert(move |id| match expected_id {
         Some(expected_id) if expected_id == id => Ok(id),
         Some(_) => Err(()),
         None => Ok(id),
     }) - seq(b"obj")
         - space()
-        + object(reader)
-        - space()
-        - seq(b"endobj").opt()
-        - space()
+}
+
+fn indirect_object_body<'a>(reader: &'a Reader) -> Parser<'a, u8, Object> {
+    object(reader) - space() - seq(b"endobj").opt() - space()
 }
 
 pub fn header(input: &[u8]) -> Option<String> {
@@ -222,9 +350,7 @@ pub fn header(input: &[u8]) -> Option<String> {
 
 /// Decode CrossReferenceTable
 fn xref<'a>() -> Parser<'a, u8, Xref> {
-    let xref_entry = integer().map(|i| max(0, i) as u32) - sym(b' ') + integer().map(|i| i as u16) - sym(b' ')
-        + one_of(b"nf").map(|k| k == b'n')
-        - take(2);
+    let xref_entry = integer() - sym(b' ') + integer() - sym(b' ') + one_of(b"nf").map(|k| k == b'n') - take(2);
     let xref_section = integer().map(|i| max(0, i) as usize) - sym(b' ') + integer() - sym(b' ').opt() - eol()
         + xref_entry.repeat(0..);
     let xref = seq(b"xref") * sym(b' ').opt() * eol() * xref_section.repeat(1..) - space();
@@ -233,8 +359,19 @@ fn xref<'a>() -> Parser<'a, u8, Xref> {
             Xref::new(0, XrefType::CrossReferenceTable),
             |mut xref: Xref, ((start, _count), entries): _| {
                 for (index, ((offset, generation), is_normal)) in entries.into_iter().enumerate() {
-                    if is_normal {
-                        xref.insert((start + index) as u32, XrefEntry::Normal { offset, generation });
+                    // Entries with numbers out of range are skipped.
+                    let id = u32::try_from(start + index);
+                    match (is_normal, id, offset.try_into(), generation.try_into()) {
+                        (true, Ok(id), Ok(offset), Ok(generation)) => {
+                            xref.insert(id, XrefEntry::Normal { offset, generation })
+                        }
+                        // Free entries hide the entries of the same objects in previous sections. Object 0 is the
+                        // head of the list of free objects.
+                        (false, Ok(id), _, generation) if id != 0 => {
+                            let generation = generation.unwrap_or(u16::MAX);
+                            xref.insert(id, XrefEntry::Free { generation })
+                        }
+                        _ => {}
                     }
                 }
                 xref
@@ -243,8 +380,8 @@ fn xref<'a>() -> Parser<'a, u8, Xref> {
     })
 }
 
-fn trailer<'a>() -> Parser<'a, u8, Dictionary> {
-    seq(b"trailer") * space() * dictionary() - space()
+fn trailer<'a>(reader: &Reader) -> Parser<'a, u8, Dictionary> {
+    seq(b"trailer") * space() * dictionary(reader.options.max_nesting_depth) - space()
 }
 
 pub fn xref_and_trailer<'a>(input: &'a [u8], reader: &'a Reader) -> Result<(Xref, Dictionary)> {
@@ -254,11 +391,12 @@ pub fn xref_and_trailer<'a>(input: &'a [u8], reader: &'a Reader) -> Result<(Xref
 }
 
 fn _xref_and_trailer<'a>(reader: &'a Reader) -> Parser<'a, u8, (Xref, Dictionary)> {
-    (xref() + trailer()).convert(|(mut xref, trailer)| -> Result<_> {
-        xref.size = trailer
+    (xref() + trailer(reader)).convert(|(mut xref, trailer)| -> Result<_> {
+        let size = trailer
             .get(b"Size")
             .and_then(Object::as_i64)
-            .map_err(|_| Error::Trailer)? as u32;
+            .map_err(|_| Error::Trailer)?;
+        xref.size = u32::try_from(size).map_err(|_| Error::Trailer)?;
         Ok((xref, trailer))
     }) | _indirect_object(None, reader).convert(|(_, obj)| match obj {
         Object::Stream(stream) => decode_xref_stream(stream),
@@ -282,6 +420,8 @@ fn operator<'a>() -> Parser<'a, u8, String> {
     (is_a(alpha) | one_of(b"*'\"")).repeat(1..).convert(String::from_utf8)
 }
 
+/// Content streams are parsed without a reader, so operands are limited to the default
+/// `MAX_NESTING_DEPTH`.
 fn operand<'a>() -> Parser<'a, u8, Object> {
     (seq(b"null").map(|_| Object::Null)
         | seq(b"true").map(|_| Object::Boolean(true))
@@ -291,14 +431,49 @@ fn operand<'a>() -> Parser<'a, u8, Object> {
         | name().map(Object::Name)
         | literal_string().map(Object::string_literal)
         | hexadecimal_string().map(|bytes| Object::String(bytes, StringFormat::Hexadecimal))
-        | array().map(Object::Array)
-        | dictionary().map(Object::Dictionary))
+        | array(MAX_NESTING_DEPTH).map(Object::Array)
+        | dictionary(MAX_NESTING_DEPTH).map(Object::Dictionary))
+        - content_space()
+}
+
+/// Parse an inline image `BI <dict> ID <data> EI` into a `BI` operation with a single stream operand.
+fn inline_image<'a>() -> Parser<'a, u8, Operation> {
+    let begin = operator().convert(|op| if op == "BI" { Ok(()) } else { Err(()) }) - content_space();
+    let entry = name() - content_space() + operand();
+    let dict = entry
+        .repeat(0..)
+        .map(|entries| entries.into_iter().collect::<Dictionary>());
+    (begin * dict - seq(b"ID") - (eol() | one_of(b" \t\0\x0C")))
+        >> |dict: Dictionary| {
+            Parser::new(move |input: &'a [u8], start: usize| {
+                let length = dict
+                    .get(b"L")
+                    .or_else(|_| dict.get(b"Length"))
+                    .and_then(Object::as_i64)
+                    .ok()
+                    .and_then(|length| usize::try_from(length).ok());
+                let (data_len, end) =
+                    scan_inline_image_end(&input[start..], length).ok_or_else(|| pom::Error::Mismatch {
+                        message: "expect EI".to_string(),
+                        position: start,
+                    })?;
+                let image = Stream {
+                    dict: dict.clone(),
+                    content: input[start..start + data_len].to_vec(),
+                    allows_compression: false,
+                    start_position: None,
+                    recovered_length: None,
+                    decompressed: Default::default(),
+                };
+                Ok((Operation::new("BI", vec![Object::Stream(image)]), start + end))
+            })
+        }
         - content_space()
 }
 
 fn operation<'a>() -> Parser<'a, u8, Operation> {
     let operation = operand().repeat(0..) + operator() - content_space();
-    operation.map(|(operands, operator)| Operation { operator, operands })
+    inline_image() | operation.map(|(operands, operator)| Operation { operator, operands })
 }
 
 pub fn content(input: &[u8]) -> Option<Content<Vec<Operation>>> {
@@ -307,6 +482,67 @@ pub fn content(input: &[u8]) -> Option<Content<Vec<Operation>>> {
         .ok()
 }
 
+/// Parse content stream, returning every operation with the offset where it starts, or the
+/// offset of the first data that can't be parsed as an operation.
+pub fn content_with_offsets(input: &[u8]) -> std::result::Result<Vec<(usize, Operation)>, usize> {
+    let space = content_space() - (comment() - content_space()).repeat(0..);
+    let operation = operation();
+    let mut operations = vec![];
+    let mut pos = 0;
+    loop {
+        if let Ok((_, end)) = space.parse_at(input, pos) {
+            pos = end;
+        }
+        if pos >= input.len() {
+            return Ok(operations);
+        }
+        let (operation, end) = operation.parse_at(input, pos).map_err(|_| pos)?;
+        operations.push((pos, operation));
+        pos = end;
+    }
+}
+
+/// Parse content stream, skipping over tokens that can't be parsed as part of an operation.
+/// Returns the operations together with the byte ranges that were skipped.
+pub fn content_tolerant(input: &[u8]) -> (Content<Vec<Operation>>, Vec<Range<usize>>) {
+    let space = content_space() - (comment() - content_space()).repeat(0..);
+    let operation = operation();
+    let mut operations = vec![];
+    let mut skipped: Vec<Range<usize>> = vec![];
+    let mut last_skipped = false;
+    let mut pos = 0;
+    loop {
+        if let Ok((_, end)) = space.parse_at(input, pos) {
+            pos = end;
+        }
+        if pos >= input.len() {
+            break;
+        }
+
+        if let Ok((operation, end)) = operation.parse_at(input, pos) {
+            operations.push(operation);
+            last_skipped = false;
+            pos = end;
+            continue;
+        }
+
+        // Drop the token up to the next whitespace and try again from there.
+        let len = input[pos..]
+            .iter()
+            .position(|c| b" \t\r\n".contains(c))
+            .unwrap_or(input.len() - pos)
+            .max(1);
+        match skipped.last_mut() {
+            Some(range) if last_skipped => range.end = pos + len,
+            _ => skipped.push(pos..pos + len),
+        }
+        last_skipped = true;
+        pos += len;
+    }
+
+    (Content { operations }, skipped)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -372,4 +608,103 @@ fn parse_content() {
         println!("{:?}", content);
         assert!(content.is_some());
     }
+
+    #[test]
+    fn octal_escapes() {
+        for (input, expected) in [
+            (&b"(\\129)"[..], &b"\n9"[..]),
+            (b"(\\8)", b"8"),
+            (b"(\\39)", b"\x039"),
+            (b"(\\0053)", b"\x053"),
+            (b"(\\777)", b"\xff"),
+        ] {
+            assert_eq!(literal_string().parse(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn hex_odd_length() {
+        for (input, expected) in [
+            (&b"<F>"[..], &b"\xF0"[..]),
+            (b"<ABC>", b"\xAB\xC0"),
+            (b"<414>", b"\x41\x40"),
+        ] {
+            assert_eq!(hexadecimal_string().parse(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn hex_invalid_byte() {
+        assert!(matches!(
+            hexadecimal_string().parse(b"<48 65 6C 6CZ6F>"),
+            Err(pom::Error::Mismatch { position: 12, .. })
+        ));
+        assert!(direct_object(b"<48 65 6C 6CZ6F>").is_none());
+        assert!(matches!(
+            direct_object(b"<</A <48656C6C6F>>>"),
+            Some(Object::Dictionary(_))
+        ));
+    }
+
+    #[test]
+    fn parse_inline_image() {
+        let input = b"q BI /W 2 /H 1 /BPC 8 /CS /G ID \x01EI\xffEI1\nEI Q\nBI /W 1 /L 4 ID a EI\nEI";
+        let content = content(input).unwrap();
+        let operators: Vec<_> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["q", "BI", "Q", "BI"]);
+        match content.operations[1].operands.as_slice() {
+            [Object::Stream(image)] => {
+                assert_eq!(image.dict.get(b"W").unwrap().as_i64().unwrap(), 2);
+                assert_eq!(image.content, b"\x01EI\xffEI1");
+            }
+            operands => panic!("unexpected {:?}", operands),
+        }
+        match content.operations[3].operands.as_slice() {
+            [Object::Stream(image)] => assert_eq!(image.content, b"a EI"),
+            operands => panic!("unexpected {:?}", operands),
+        }
+
+        let encoded = content.encode().unwrap();
+        let decoded = super::content(&encoded).unwrap();
+        assert_eq!(decoded.operations.len(), 4);
+        assert_eq!(decoded.operations[1].operands, content.operations[1].operands);
+    }
+
+    #[test]
+    fn parse_content_tolerant() {
+        let (content, skipped) = content_tolerant(b"q ))) BT /F1 12 Tf ET\n1 2 ]] 3 4 m\n% comment\nQ @");
+        let operators: Vec<_> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["q", "BT", "Tf", "ET", "m", "Q"]);
+        assert_eq!(skipped, [2..5, 22..28, 47..48]);
+    }
+
+    #[test]
+    fn stream_with_invalid_length() {
+        let reader = Reader::new(b"");
+
+        for length in ["3", "100", "-1"] {
+            let input = format!("<</Length {}>>stream\nHello World\r\nendstream", length);
+            let stream = stream(&reader).parse(input.as_bytes()).unwrap();
+            assert_eq!(stream.content, b"Hello World");
+            assert_eq!(stream.recovered_length, Some(11));
+        }
+    }
+
+    #[test]
+    fn stream_without_length() {
+        let reader = Reader::new(b"");
+
+        for dict in ["<<>>", "<</Length /Foo>>", "<</Length 1.5>>"] {
+            let input = format!("{}stream\nHello World\nendstream", dict);
+            let stream = stream(&reader).parse(input.as_bytes()).unwrap();
+            assert_eq!(stream.content, b"Hello World");
+            assert_eq!(stream.recovered_length, Some(11));
+            assert_eq!(stream.dict.get(b"Length").unwrap().as_i64().unwrap(), 11);
+        }
+
+        let stream = stream(&reader)
+            .parse(b"<</Length 11>>stream\nHello World\nendstream")
+            .unwrap();
+        assert_eq!(stream.recovered_length, None);
+    }
 }
@@ -1,7 +1,9 @@
 #![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
 
+use crate::linearization::renumber_references;
 use crate::{
-    content::{Content, Operation},
+    cmap::ToUnicodeCMap,
+    content::{Content, OperandCheck, OperandMismatch, Operation, TextRun},
     document::Document,
     error::XrefError,
     object::Object::Name,
@@ -9,10 +11,11 @@
     Error, Result,
 };
 use crate::{parser, Dictionary, Object, ObjectId, Stream};
-use log::info;
+use log::{info, warn};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     io::{Cursor, Read},
+    ops::Range,
 };
 
 impl Content<Vec<Operation>> {
@@ -20,6 +23,43 @@ impl Content<Vec<Operation>> {
     pub fn decode(data: &[u8]) -> Result<Self> {
         parser::content(data).ok_or(Error::ContentDecode)
     }
+
+    /// Decode content operations, checking the operands of every operator against the operator
+    /// table of the PDF specification.
+    ///
+    /// Unknown operators are accepted and logged as a warning. Data that can't be parsed is
+    /// reported as [`Error::Parse`] and operations whose operands don't match their operator as
+    /// [`Error::InvalidOperands`], both with byte offsets into `data`.
+    pub fn parse_validated(data: &[u8]) -> Result<Self> {
+        let operations = parser::content_with_offsets(data).map_err(|offset| Error::parse(data, offset, None))?;
+        let mut mismatches = vec![];
+        for (offset, operation) in &operations {
+            match operation.check_operands() {
+                OperandCheck::Valid => {}
+                OperandCheck::UnknownOperator => {
+                    warn!("Unknown operator {} at byte {} of content", operation.operator, offset)
+                }
+                OperandCheck::Mismatch(expected) => mismatches.push(OperandMismatch {
+                    offset: *offset,
+                    operator: operation.operator.clone(),
+                    expected,
+                }),
+            }
+        }
+        if !mismatches.is_empty() {
+            return Err(Error::InvalidOperands(mismatches));
+        }
+        Ok(Content {
+            operations: operations.into_iter().map(|(_, operation)| operation).collect(),
+        })
+    }
+
+    /// Decode content operations, skipping over tokens that can't be parsed instead of failing.
+    ///
+    /// Returns the decoded content together with the byte ranges of `data` that were skipped.
+    pub fn decode_tolerant(data: &[u8]) -> (Self, Vec<Range<usize>>) {
+        parser::content_tolerant(data)
+    }
 }
 
 impl Stream {
@@ -30,7 +70,8 @@ pub fn decode_content(&self) -> Result<Content<Vec<Operation>>> {
 }
 
 impl Document {
-    /// Get decoded page content;
+    /// Get decoded page content, with all the content streams of the page when its `Contents`
+    /// is an array, see [`Document::get_page_content`].
     pub fn get_and_decode_page_content(&self, page_id: ObjectId) -> Result<Content<Vec<Operation>>> {
         let content_data = self.get_page_content(page_id)?;
         Content::decode(&content_data)
@@ -43,22 +84,164 @@ pub fn add_to_page_content(&mut self, page_id: ObjectId, content: Content<Vec<Op
         Ok(())
     }
 
+    /// Draw `content` on top of the existing content of a page, adding it as a new content
+    /// stream, and return its id. The `resources` used by the overlay are merged into the
+    /// resources of the page, which get their own resource dictionary if they are inherited.
+    ///
+    /// The existing content is wrapped in `q`/`Q`, also closing any graphics state it leaves
+    /// open, so the overlay is drawn in default user space whatever transformations the page
+    /// applies. A resource of the overlay with a name the page already uses for another object
+    /// is renamed, in the resources and in the operands of the overlay content.
+    pub fn add_overlay(
+        &mut self, page_id: ObjectId, mut content: Content<Vec<Operation>>, resources: &Dictionary,
+    ) -> Result<ObjectId> {
+        let mut page_resources = self.get_page_inherited_resources(page_id)?;
+        for (category, overlay_entries) in resources {
+            let Ok((_, Object::Dictionary(overlay_entries))) = self.dereference(overlay_entries) else {
+                // Such as the obsolete `ProcSet` array.
+                if !page_resources.has(category) {
+                    page_resources.set(category.clone(), overlay_entries.clone());
+                }
+                continue;
+            };
+            let mut entries = match page_resources.get(category).map(|entries| self.dereference(entries)) {
+                Ok(Ok((_, Object::Dictionary(entries)))) => entries.clone(),
+                _ => Dictionary::new(),
+            };
+            for (name, value) in overlay_entries {
+                let mut new_name = name.clone();
+                let mut suffix = 0;
+                while entries.get(&new_name).is_ok_and(|existing| existing != value) {
+                    suffix += 1;
+                    new_name = [name.as_slice(), format!("_{}", suffix).as_bytes()].concat();
+                }
+                if new_name != *name {
+                    for operation in &mut content.operations {
+                        let index = match (operation.operator.as_str(), category.as_slice()) {
+                            ("Tf", b"Font")
+                            | ("Do", b"XObject")
+                            | ("gs", b"ExtGState")
+                            | ("cs" | "CS", b"ColorSpace")
+                            | ("sh", b"Shading") => 0,
+                            ("scn" | "SCN", b"Pattern") => operation.operands.len().saturating_sub(1),
+                            ("BDC" | "DP", b"Properties") => 1,
+                            _ => continue,
+                        };
+                        if let Some(Object::Name(operand)) = operation.operands.get_mut(index) {
+                            if operand == name {
+                                operand.clone_from(&new_name);
+                            }
+                        }
+                    }
+                }
+                entries.set(new_name, value.clone());
+            }
+            page_resources.set(category.clone(), entries);
+        }
+
+        let mut contents: Vec<Object> = vec![];
+        let existing = self.get_page_contents(page_id);
+        if !existing.is_empty() {
+            // Saves left on the stack by the page content are restored as well.
+            let open_states = Content::decode(&self.get_page_content(page_id)?).map_or(0, |existing| {
+                existing
+                    .operations
+                    .iter()
+                    .fold(0usize, |depth, operation| match operation.operator.as_str() {
+                        "q" => depth + 1,
+                        "Q" => depth.saturating_sub(1),
+                        _ => depth,
+                    })
+            });
+            let save = self.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+            let restore = self.add_object(Stream::new(Dictionary::new(), b"\nQ".repeat(open_states + 1)));
+            contents.push(save.into());
+            contents.extend(existing.into_iter().map(Object::Reference));
+            contents.push(restore.into());
+        }
+        let mut overlay = Stream::new(Dictionary::new(), content.encode()?);
+        // Ignore any compression error, the stream is stored uncompressed then.
+        let _ = overlay.compress();
+        let overlay_id = self.add_object(overlay);
+        contents.push(overlay_id.into());
+
+        let page = self.get_dictionary_mut(page_id)?;
+        page.set("Contents", contents);
+        page.set("Resources", page_resources);
+        Ok(overlay_id)
+    }
+
+    /// Draw a page of another document over a page of this one, such as a letterhead, and
+    /// return the id of the Form XObject holding it.
+    ///
+    /// The Form XObject gets the content and the resources of the source page, and its crop box
+    /// as bounding box. The objects used by the resources are copied under new ids. It is drawn
+    /// with [`Document::add_overlay`], transformed by `matrix` if given, so by default the
+    /// origins of both pages coincide. The rotation of the source page is not applied.
+    pub fn stamp_page_with(
+        &mut self, target_page: ObjectId, source: &Document, source_page: ObjectId, matrix: Option<[f32; 6]>,
+    ) -> Result<ObjectId> {
+        let page = source.page_with_inherited_attributes(source_page)?;
+        let resources = match page.get(b"Resources").map(|resources| source.dereference(resources)) {
+            Ok(Ok((_, Object::Dictionary(resources)))) => resources.clone(),
+            _ => Dictionary::new(),
+        };
+        let mut ids = BTreeSet::new();
+        source.collect_page_objects(&resources, &mut ids);
+        let renumbering: BTreeMap<ObjectId, ObjectId> = ids.into_iter().map(|id| (id, self.new_object_id())).collect();
+        for (id, new_id) in &renumbering {
+            if let Some(object) = source.objects.get(id) {
+                let mut object = object.clone();
+                renumber_references(&mut object, &renumbering);
+                self.objects.insert(*new_id, object);
+            }
+        }
+        let mut resources = Object::Dictionary(resources);
+        renumber_references(&mut resources, &renumbering);
+
+        let bbox: Vec<Object> = source
+            .get_page_crop_box(source_page)?
+            .into_iter()
+            .map(Object::Real)
+            .collect();
+        let mut form = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => bbox,
+                "Resources" => resources,
+            },
+            source.get_page_content(source_page)?,
+        );
+        // Ignore any compression error, the stream is stored uncompressed then.
+        let _ = form.compress();
+        let form_id = self.add_object(form);
+
+        let mut operations = vec![Operation::new("q", vec![])];
+        if let Some(matrix) = matrix {
+            operations.push(Operation::new("cm", matrix.into_iter().map(Object::Real).collect()));
+        }
+        operations.push(Operation::new("Do", vec![Name(b"Stamp".to_vec())]));
+        operations.push(Operation::new("Q", vec![]));
+        let resources = dictionary! { "XObject" => dictionary! { "Stamp" => form_id } };
+        self.add_overlay(target_page, Content { operations }, &resources)?;
+        Ok(form_id)
+    }
+
     pub fn extract_text(&self, page_numbers: &[u32]) -> Result<String> {
-        fn collect_text(text: &mut String, encoding: Option<&str>, operands: &[Object]) {
+        fn collect_text(text: &mut String, decoder: Option<&TextDecoder>, operands: &[Object]) {
             for operand in operands.iter() {
                 match *operand {
                     Object::String(ref bytes, _) => {
-                        let decoded_text = Document::decode_text(encoding, bytes);
+                        let decoded_text = TextDecoder::decode_with(decoder, bytes);
                         text.push_str(&decoded_text);
                     }
                     Object::Array(ref arr) => {
-                        collect_text(text, encoding, arr);
+                        collect_text(text, decoder, arr);
                         text.push(' ');
                     }
-                    Object::Integer(i) => {
-                        if i < -100 {
-                            text.push(' ');
-                        }
+                    Object::Integer(i) if i < -100 => {
+                        text.push(' ');
                     }
                     _ => {}
                 }
@@ -68,14 +251,10 @@ fn collect_text(text: &mut String, encoding: Option<&str>, operands: &[Object])
         let pages = self.get_pages();
         for page_number in page_numbers {
             let page_id = *pages.get(page_number).ok_or(Error::PageNumberNotFound(*page_number))?;
-            let fonts = self.get_page_fonts(page_id);
-            let encodings = fonts
-                .into_iter()
-                .map(|(name, font)| (name, font.get_font_encoding()))
-                .collect::<BTreeMap<Vec<u8>, &str>>();
+            let decoders = self.get_page_text_decoders(page_id);
             let content_data = self.get_page_content(page_id)?;
             let content = Content::decode(&content_data)?;
-            let mut current_encoding = None;
+            let mut current_decoder = None;
             for operation in &content.operations {
                 match operation.operator.as_ref() {
                     "Tf" => {
@@ -84,15 +263,13 @@ fn collect_text(text: &mut String, encoding: Option<&str>, operands: &[Object])
                             .first()
                             .ok_or_else(|| Error::Syntax("missing font operand".to_string()))?
                             .as_name()?;
-                        current_encoding = encodings.get(current_font).cloned();
+                        current_decoder = decoders.get(current_font);
                     }
                     "Tj" | "TJ" => {
-                        collect_text(&mut text, current_encoding, &operation.operands);
+                        collect_text(&mut text, current_decoder, &operation.operands);
                     }
-                    "ET" => {
-                        if !text.ends_with('\n') {
-                            text.push('\n')
-                        }
+                    "ET" if !text.ends_with('\n') => {
+                        text.push('\n');
                     }
                     _ => {}
                 }
@@ -101,6 +278,83 @@ fn collect_text(text: &mut String, encoding: Option<&str>, operands: &[Object])
         Ok(text)
     }
 
+    /// Extract the strings shown on a page, decoded with their font's encoding, together with
+    /// their position and font size. See [`Content::text_runs`] for how positions are tracked.
+    pub fn extract_text_with_positions(&self, page_id: ObjectId) -> Result<Vec<TextRun<String>>> {
+        let decoders = self.get_page_text_decoders(page_id);
+        let content = self.get_and_decode_page_content(page_id)?;
+        let runs = content
+            .text_runs()
+            .into_iter()
+            .map(|run| TextRun {
+                text: TextDecoder::decode_with(decoders.get(&run.font), &run.text),
+                font: run.font,
+                font_size: run.font_size,
+                x: run.x,
+                y: run.y,
+            })
+            .collect();
+        Ok(runs)
+    }
+
+    /// Get the text decoders of the fonts used by a page, keyed by font resource name.
+    fn get_page_text_decoders(&self, page_id: ObjectId) -> BTreeMap<Vec<u8>, TextDecoder<'_>> {
+        self.get_page_fonts(page_id)
+            .into_iter()
+            .map(|(name, font)| {
+                let decoder = match self.get_font_to_unicode(font) {
+                    Some(cmap) => TextDecoder::ToUnicode(cmap),
+                    None if font.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Type0") => {
+                        self.get_composite_font_decoder(font)
+                    }
+                    None => TextDecoder::Encoding(font.get_font_encoding()),
+                };
+                (name, decoder)
+            })
+            .collect()
+    }
+
+    /// Get the decoder of a composite (`Type0`) font without a `ToUnicode` CMap.
+    ///
+    /// Predefined Unicode CMaps such as `UniGB-UCS2-H` are decoded as UTF-16. With the
+    /// `Identity-H` or `Identity-V` encoding the two-byte codes are CIDs, which usually select
+    /// glyphs of the embedded font rather than characters. They are only taken as Unicode if the
+    /// `CIDSystemInfo` of the descendant font has the ordering `UCS`, otherwise they are decoded
+    /// as replacement characters.
+    fn get_composite_font_decoder<'a>(&self, font: &'a Dictionary) -> TextDecoder<'a> {
+        match font.get(b"Encoding").and_then(Object::as_name_str) {
+            Ok("Identity-H" | "Identity-V") => {
+                let ordering = font
+                    .get_deref(b"DescendantFonts", self)
+                    .and_then(Object::as_array)
+                    .ok()
+                    .and_then(|fonts| fonts.first())
+                    .and_then(|descendant| self.dereference(descendant).ok())
+                    .and_then(|(_, descendant)| descendant.as_dict().ok())
+                    .and_then(|descendant| descendant.get_deref(b"CIDSystemInfo", self).ok())
+                    .and_then(|info| info.as_dict().ok())
+                    .and_then(|info| info.get_deref(b"Ordering", self).ok())
+                    .and_then(|ordering| ordering.as_str().ok());
+                let unicode = ordering == Some(b"UCS");
+                TextDecoder::Identity { unicode }
+            }
+            Ok(encoding) => TextDecoder::Encoding(encoding),
+            // An embedded CMap, assume two-byte codes.
+            Err(_) => TextDecoder::Identity { unicode: false },
+        }
+    }
+
+    /// Parse the `ToUnicode` CMap of a font, if it has one.
+    fn get_font_to_unicode(&self, font: &Dictionary) -> Option<ToUnicodeCMap> {
+        let stream = font.get_deref(b"ToUnicode", self).and_then(Object::as_stream).ok()?;
+        let data = if stream.dict.has(b"Filter") {
+            stream.decompressed_content().ok()?
+        } else {
+            stream.content.clone()
+        };
+        Some(ToUnicodeCMap::parse(&data))
+    }
+
     pub fn replace_text(&mut self, page_number: u32, text: &str, other_text: &str) -> Result<()> {
         let page = page_number.saturating_sub(1) as usize;
         let page_id = self
@@ -191,10 +445,14 @@ pub fn insert_form_object(&mut self, page_id: ObjectId, form_obj: Stream) -> Res
 }
 
 /// Decode CrossReferenceStream
-pub fn decode_xref_stream(mut stream: Stream) -> Result<(Xref, Dictionary)> {
-    stream.decompress();
+pub fn decode_xref_stream(stream: Stream) -> Result<(Xref, Dictionary)> {
+    let content = if stream.dict.get(b"Filter").is_ok() {
+        stream.decompressed_content()?
+    } else {
+        stream.content
+    };
     let mut dict = stream.dict;
-    let mut reader = Cursor::new(stream.content);
+    let mut reader = Cursor::new(content);
     let size = dict
         .get(b"Size")
         .and_then(Object::as_i64)
@@ -210,11 +468,7 @@ pub fn decode_xref_stream(mut stream: Stream) -> Result<(Xref, Dictionary)> {
             .and_then(parse_integer_array)
             .map_err(|_| Error::Xref(XrefError::Parse))?;
 
-        if field_widths.len() < 3
-            || field_widths[0].is_negative()
-            || field_widths[1].is_negative()
-            || field_widths[2].is_negative()
-        {
+        if field_widths.len() < 3 || field_widths.iter().any(|&width| !(0..=8).contains(&width)) {
             return Err(Error::Xref(XrefError::Parse));
         }
 
@@ -222,54 +476,171 @@ pub fn decode_xref_stream(mut stream: Stream) -> Result<(Xref, Dictionary)> {
         let mut bytes2 = vec![0_u8; field_widths[1] as usize];
         let mut bytes3 = vec![0_u8; field_widths[2] as usize];
 
-        for i in 0..section_indice.len() / 2 {
-            let start = section_indice[2 * i];
-            let count = section_indice[2 * i + 1];
+        for section in section_indice.chunks_exact(2) {
+            let (start, count) = (section[0], section[1]);
+            if start < 0 || count < 0 {
+                return Err(Error::Xref(XrefError::Parse));
+            }
 
-            for j in 0..count {
+            for id in start..start + count {
+                // A missing type field means type 1, other missing fields default to zero.
                 let entry_type = if !bytes1.is_empty() {
                     read_big_endian_integer(&mut reader, bytes1.as_mut_slice())?
                 } else {
                     1
                 };
-                match entry_type {
-                    0 => {
-                        //free object
-                        read_big_endian_integer(&mut reader, bytes2.as_mut_slice())?;
-                        read_big_endian_integer(&mut reader, bytes3.as_mut_slice())?;
-                    }
-                    1 => {
-                        //normal object
-                        let offset = read_big_endian_integer(&mut reader, bytes2.as_mut_slice())?;
-                        let generation = if !bytes3.is_empty() {
-                            read_big_endian_integer(&mut reader, bytes3.as_mut_slice())?
-                        } else {
-                            0
-                        } as u16;
-                        xref.insert((start + j) as u32, XrefEntry::Normal { offset, generation });
-                    }
-                    2 => {
-                        //compressed object
-                        let container = read_big_endian_integer(&mut reader, bytes2.as_mut_slice())?;
-                        let index = read_big_endian_integer(&mut reader, bytes3.as_mut_slice())? as u16;
-                        xref.insert((start + j) as u32, XrefEntry::Compressed { container, index });
-                    }
-                    _ => {}
-                }
+                let field2 = read_big_endian_integer(&mut reader, bytes2.as_mut_slice())?;
+                let field3 = read_big_endian_integer(&mut reader, bytes3.as_mut_slice())?;
+                let entry = match entry_type {
+                    0 => XrefEntry::Free {
+                        generation: field3.try_into().unwrap_or(u16::MAX),
+                    },
+                    1 => XrefEntry::Normal {
+                        offset: field2.try_into().map_err(|_| Error::Xref(XrefError::Parse))?,
+                        generation: field3.try_into().map_err(|_| Error::Xref(XrefError::Parse))?,
+                    },
+                    2 => XrefEntry::Compressed {
+                        container: field2.try_into().map_err(|_| Error::Xref(XrefError::Parse))?,
+                        index: field3.try_into().map_err(|_| Error::Xref(XrefError::Parse))?,
+                    },
+                    // Entries of other types are references to the null object.
+                    _ => continue,
+                };
+                xref.insert(id.try_into().map_err(|_| Error::Xref(XrefError::Parse))?, entry);
             }
         }
     }
-    dict.remove(b"Length");
-    dict.remove(b"W");
-    dict.remove(b"Index");
+    for key in [&b"Length"[..], b"W", b"Index", b"Type", b"Filter", b"DecodeParms"] {
+        dict.remove(key);
+    }
     Ok((xref, dict))
 }
 
-fn read_big_endian_integer(reader: &mut Cursor<Vec<u8>>, buffer: &mut [u8]) -> Result<u32> {
+/// Decodes the strings shown with a font, preferring its `ToUnicode` CMap over its encoding.
+enum TextDecoder<'a> {
+    ToUnicode(ToUnicodeCMap),
+    Encoding(&'a str),
+    /// Two-byte codes of a composite font, taken as Unicode if `unicode` is set.
+    Identity {
+        unicode: bool,
+    },
+}
+
+impl TextDecoder<'_> {
+    /// Decode with the given decoder, or with the standard encoding if there is none.
+    fn decode_with(decoder: Option<&TextDecoder>, bytes: &[u8]) -> String {
+        match decoder {
+            Some(TextDecoder::ToUnicode(cmap)) => cmap.decode(bytes),
+            Some(TextDecoder::Encoding(encoding)) => Document::decode_text(Some(encoding), bytes),
+            Some(TextDecoder::Identity { unicode }) => bytes
+                .chunks(2)
+                .map(|code| {
+                    let code = u16::from_be_bytes([code[0], code.get(1).copied().unwrap_or(0)]);
+                    char::from_u32(code as u32)
+                        .filter(|_| *unicode)
+                        .unwrap_or(char::REPLACEMENT_CHARACTER)
+                })
+                .collect(),
+            None => Document::decode_text(None, bytes),
+        }
+    }
+}
+
+/// Split the `endobj` keyword off a name that ran into it, as in `1 0 obj /Name endobj` written
+/// without whitespace before `endobj`.
+pub(crate) fn split_endobj(object: Object) -> Object {
+    match object {
+        Object::Name(mut name) if name.len() > b"endobj".len() && name.ends_with(b"endobj") => {
+            name.truncate(name.len() - b"endobj".len());
+            Object::Name(name)
+        }
+        object => object,
+    }
+}
+
+/// Find the first object id `N G` followed by `R` or `obj` from `offset` of `input` up to the
+/// next `endobj`, whose object number is greater than `u32::MAX` or whose generation number is
+/// greater than 65535, and return its offset. Used to explain why an object failed to parse.
+pub(crate) fn find_object_id_overflow(input: &[u8], offset: usize) -> Option<usize> {
+    let rest = input.get(offset..)?;
+    let rest = &rest[..rest.windows(6).position(|w| w == b"endobj").unwrap_or(rest.len())];
+    let count = |from: usize, predicate: fn(&u8) -> bool| rest[from..].iter().take_while(|c| predicate(c)).count();
+    let fits = |digits: &[u8], max: u64| {
+        // Digits only, so a parse error means the number doesn't fit in a u64.
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse::<u64>().ok())
+            .is_some_and(|number| number <= max)
+    };
+    (0..rest.len())
+        .find(|&start| {
+            if start > 0 && (rest[start - 1].is_ascii_alphanumeric() || rest[start - 1] == b'.') {
+                return false;
+            }
+            let number_end = start + count(start, u8::is_ascii_digit);
+            let generation_start = number_end + count(number_end, u8::is_ascii_whitespace);
+            let generation_end = generation_start + count(generation_start, u8::is_ascii_digit);
+            let keyword = &rest[generation_end + count(generation_end, u8::is_ascii_whitespace)..];
+            start < number_end
+                && number_end < generation_start
+                && generation_start < generation_end
+                && (keyword.starts_with(b"R") || keyword.starts_with(b"obj"))
+                && !(fits(&rest[start..number_end], u32::MAX.into())
+                    && fits(&rest[generation_start..generation_end], u16::MAX.into()))
+        })
+        .map(|start| offset + start)
+}
+
+/// Find the end of the stream data by scanning for the `endstream` keyword.
+///
+/// Returns the length of the stream data, without the end-of-line marker in front
+/// of `endstream`, and the offset just past the keyword.
+pub(crate) fn scan_stream_end(data: &[u8]) -> Option<(usize, usize)> {
+    const KEYWORD: &[u8] = b"endstream";
+
+    let position = data.windows(KEYWORD.len()).position(|window| window == KEYWORD)?;
+    let data_len = if data[..position].ends_with(b"\r\n") {
+        position - 2
+    } else if data[..position].ends_with(b"\n") || data[..position].ends_with(b"\r") {
+        position - 1
+    } else {
+        position
+    };
+
+    Some((data_len, position + KEYWORD.len()))
+}
+
+/// Find the end of inline image data by scanning for the `EI` operator, which has to be
+/// preceded by white-space and followed by white-space or the end of the content.
+///
+/// If the image dictionary specifies the data `length`, it is used as long as `EI` follows it.
+/// Returns the length of the image data and the offset just past the operator.
+pub(crate) fn scan_inline_image_end(data: &[u8], length: Option<usize>) -> Option<(usize, usize)> {
+    let is_white_space = |c: u8| b" \t\n\r\0\x0C".contains(&c);
+    let is_end = |position: usize| {
+        data[position..].starts_with(b"EI") && data.get(position + 2).is_none_or(|&c| is_white_space(c))
+    };
+
+    if let Some(rest) = length.and_then(|length| data.get(length..)) {
+        let data_len = data.len() - rest.len();
+        let position = data_len + rest.iter().take_while(|&&c| is_white_space(c)).count();
+        if is_end(position) {
+            return Some((data_len, position + 2));
+        }
+    }
+
+    if is_end(0) {
+        return Some((0, 2));
+    }
+    let position = (1..data.len()).find(|&position| is_white_space(data[position - 1]) && is_end(position))?;
+    Some((position - 1, position + 2))
+}
+
+fn read_big_endian_integer(reader: &mut Cursor<Vec<u8>>, buffer: &mut [u8]) -> Result<u64> {
     reader.read_exact(buffer)?;
     let mut value = 0;
     for &mut byte in buffer {
-        value = (value << 8) + u32::from(byte);
+        value = (value << 8) + u64::from(byte);
     }
     Ok(value)
 }
@@ -286,6 +657,161 @@ fn parse_integer_array(array: &Object) -> Result<Vec<i64>> {
 }
 
 #[cfg(all(test, not(feature = "async")))]
+#[test]
+fn parse_validated_content() {
+    let data = b"q 1 0 0 1 0 0 cm BT /F1 12 Tf (Hi) Tj [(A) -20 (B)] TJ ET 0.5 g Q";
+    let content = Content::parse_validated(data).unwrap();
+    assert_eq!(content.operations.len(), 9);
+    // Unknown operators are let through, as are color operators with any number of components.
+    assert!(Content::parse_validated(b"1 2 foo 0.1 0.2 0.3 0.4 sc /P1 scn").is_ok());
+
+    match Content::parse_validated(b"BT\n12 /F1 Tf\n1 Td\n(ok) Tj\nET") {
+        Err(Error::InvalidOperands(mismatches)) => {
+            let found: Vec<_> = mismatches.iter().map(|m| (m.offset, m.operator.as_str())).collect();
+            assert_eq!(found, [(3, "Tf"), (13, "Td")]);
+            assert_eq!(mismatches[0].expected, "name, number");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(matches!(
+        Content::parse_validated(b"q ) Q"),
+        Err(Error::Parse { offset: 2, .. })
+    ));
+}
+
+#[test]
+fn decode_page_content_of_stream_array() {
+    use crate::creator::tests::create_document;
+
+    let mut doc = create_document();
+    let page_id = doc.page_iter().next().unwrap();
+    // Each stream ends right after its last token, they need whitespace between them.
+    let first = doc.add_object(Stream::new(Dictionary::new(), b"q 1 0 0 1 10 10 cm".to_vec()));
+    let mut second = Stream::new(Dictionary::new(), b"BT /F1 12 Tf (Hi) Tj ET Q".to_vec());
+    second.compress().unwrap();
+    let second = doc.add_object(second);
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    page.set("Contents", vec![first.into(), second.into()]);
+
+    let content = doc.get_and_decode_page_content(page_id).unwrap();
+    let operators: Vec<&str> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+    assert_eq!(operators, ["q", "cm", "BT", "Tf", "Tj", "ET", "Q"]);
+    assert_eq!(content.operations[1].operands.len(), 6);
+}
+
+#[test]
+fn add_overlay() {
+    use crate::creator::tests::create_document;
+
+    let mut doc = create_document();
+    let page_id = doc.page_iter().next().unwrap();
+    // The page content leaves a translation in effect.
+    let mut content = doc.get_and_decode_page_content(page_id).unwrap();
+    content.operations.insert(0, Operation::new("q", vec![]));
+    content.operations.insert(
+        1,
+        Operation::new("cm", vec![1.into(), 0.into(), 0.into(), 1.into(), 50.into(), 50.into()]),
+    );
+    doc.set_page_content(page_id, content).unwrap();
+    let page_font = doc.get_page_fonts(page_id)[b"F1".as_slice()].clone();
+
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+    let gs_id = doc.add_object(dictionary! { "Type" => "ExtGState", "ca" => 0.5 });
+    let overlay = Content {
+        operations: vec![
+            Operation::new("gs", vec![Name(b"GS1".to_vec())]),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Name(b"F1".to_vec()), 60.into()]),
+            Operation::new("Tj", vec![Object::string_literal("DRAFT")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let resources = dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+        "ExtGState" => dictionary! { "GS1" => gs_id },
+    };
+    let overlay_id = doc.add_overlay(page_id, overlay, &resources).unwrap();
+
+    let contents = doc.get_page_contents(page_id);
+    assert_eq!(contents.len(), 4);
+    assert_eq!(contents[3], overlay_id);
+    let content = doc.get_and_decode_page_content(page_id).unwrap();
+    let operators: Vec<&str> = content.operations.iter().map(|op| op.operator.as_str()).collect();
+    assert_eq!(
+        operators,
+        ["q", "q", "cm", "BT", "Tf", "Td", "Tj", "ET", "Q", "Q", "gs", "BT", "Tf", "Tj", "ET"]
+    );
+    assert_eq!(content.operations[4].operands[0], Name(b"F1".to_vec()));
+    // The overlay font is renamed, since the page uses F1 for another font.
+    assert_eq!(content.operations[12].operands[0], Name(b"F1_1".to_vec()));
+
+    let fonts = doc.get_page_fonts(page_id);
+    assert_eq!(fonts[b"F1".as_slice()], &page_font);
+    assert_eq!(
+        fonts[b"F1_1".as_slice()].get(b"BaseFont").unwrap(),
+        &Name(b"Helvetica".to_vec())
+    );
+    let resources = doc.get_page_inherited_resources(page_id).unwrap();
+    let states = resources.get(b"ExtGState").and_then(Object::as_dict).unwrap();
+    assert_eq!(states.get(b"GS1").unwrap(), &Object::Reference(gs_id));
+}
+
+#[test]
+fn stamp_page_with() {
+    use crate::creator::tests::create_document;
+
+    let source = create_document();
+    let source_page = source.page_iter().next().unwrap();
+    let mut doc = create_document();
+    let page_id = doc.page_iter().next().unwrap();
+    let matrix = [0.5, 0.0, 0.0, 0.5, 0.0, 421.0];
+    let form_id = doc
+        .stamp_page_with(page_id, &source, source_page, Some(matrix))
+        .unwrap();
+
+    let resources = doc.get_page_inherited_resources(page_id).unwrap();
+    let xobjects = resources.get(b"XObject").and_then(Object::as_dict).unwrap();
+    assert_eq!(xobjects.get(b"Stamp").unwrap(), &Object::Reference(form_id));
+    let content = doc.get_and_decode_page_content(page_id).unwrap();
+    let stamp = &content.operations[content.operations.len() - 4..];
+    assert_eq!(stamp[1].operator, "cm");
+    assert_eq!(stamp[1].operands[5].as_float().unwrap(), 421.0);
+    assert_eq!(
+        (stamp[2].operator.as_str(), &stamp[2].operands[..]),
+        ("Do", &[Name(b"Stamp".to_vec())][..])
+    );
+
+    let form = doc.get_object(form_id).and_then(Object::as_stream).unwrap();
+    assert_eq!(form.dict.get(b"Subtype").unwrap(), &Name(b"Form".to_vec()));
+    let bbox: Vec<f32> = form
+        .dict
+        .get(b"BBox")
+        .and_then(Object::as_array)
+        .unwrap()
+        .iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    assert_eq!(bbox, [0.0, 0.0, 595.0, 842.0]);
+    assert_eq!(
+        form.decompressed_content().unwrap_or_else(|_| form.content.clone()),
+        source.get_page_content(source_page).unwrap()
+    );
+    // The font of the source page is copied under a new id.
+    let form_resources = form.dict.get(b"Resources").and_then(Object::as_dict).unwrap();
+    let font_ref = form_resources
+        .get(b"Font")
+        .and_then(Object::as_dict)
+        .unwrap()
+        .get(b"F1")
+        .unwrap();
+    let font_id = font_ref.as_reference().unwrap();
+    assert!(font_id.0 > source.max_id);
+    assert_eq!(
+        doc.get_dictionary(font_id).unwrap().get(b"BaseFont").unwrap(),
+        &Name(b"Courier".to_vec())
+    );
+}
+
 #[test]
 fn load_and_save() {
     // test load_from() and save_to()
@@ -309,3 +835,104 @@ fn load_and_save() {
     // Check if saved file is not an empty bytes vector.
     assert!(!memory_cursor.get_ref().is_empty());
 }
+
+#[test]
+fn decode_xref_stream_with_two_ranges() {
+    use crate::filters::png;
+
+    // Rows of `W [1 2 1]` entries for objects 0 to 1 and 5 to 7.
+    let rows: [[u8; 4]; 5] = [
+        [0, 0, 0, 255],
+        [1, 0, 16, 0],
+        [2, 0, 7, 1],
+        [1, 18, 52, 2],
+        [3, 0, 0, 0],
+    ];
+    let mut data = vec![];
+    let mut previous = [0; 4];
+    for row in rows {
+        let mut current = row;
+        png::encode_row(png::FilterType::Up, 1, &previous, &mut current);
+        data.push(png::FilterType::Up as u8);
+        data.extend(current);
+        previous = row;
+    }
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XRef",
+            "Size" => 8,
+            "W" => vec![1.into(), 2.into(), 1.into()],
+            "Index" => vec![0.into(), 2.into(), 5.into(), 3.into()],
+            "Prev" => 1234,
+        },
+        data,
+    );
+    stream.compress_with("FlateDecode").unwrap();
+    stream
+        .dict
+        .set("DecodeParms", dictionary! { "Predictor" => 12, "Columns" => 4 });
+
+    let (xref, trailer) = decode_xref_stream(stream).unwrap();
+    assert_eq!(xref.size, 8);
+    assert_eq!(xref.entries.len(), 4);
+    assert!(matches!(xref.get(0), Some(XrefEntry::Free { .. })));
+    assert!(matches!(
+        xref.get(1),
+        Some(XrefEntry::Normal {
+            offset: 16,
+            generation: 0
+        })
+    ));
+    assert!(xref.get(2).is_none());
+    assert!(matches!(
+        xref.get(5),
+        Some(XrefEntry::Compressed { container: 7, index: 1 })
+    ));
+    assert!(matches!(
+        xref.get(6),
+        Some(XrefEntry::Normal {
+            offset: 0x1234,
+            generation: 2
+        })
+    ));
+    assert!(xref.get(7).is_none());
+
+    assert_eq!(trailer.get(b"Prev").and_then(Object::as_i64).unwrap(), 1234);
+    assert_eq!(trailer.get(b"Size").and_then(Object::as_i64).unwrap(), 8);
+    for key in [&b"W"[..], b"Index", b"Filter", b"DecodeParms", b"Length"] {
+        assert!(!trailer.has(key));
+    }
+}
+
+#[test]
+fn decode_xref_stream_with_wide_fields() {
+    let mut data = vec![1, 0, 0, 0, 0, 0, 0, 0x12, 0x34];
+    data.extend([2, 0, 0, 0, 0, 0, 0, 0, 3]);
+    let stream = Stream::new(
+        dictionary! {
+            "Type" => "XRef",
+            "Size" => 3,
+            "Index" => vec![1.into(), 2.into()],
+            "W" => vec![1.into(), 8.into(), 0.into()],
+        },
+        data,
+    );
+    let (xref, _) = decode_xref_stream(stream).unwrap();
+    assert!(matches!(
+        xref.get(1),
+        Some(XrefEntry::Normal {
+            offset: 0x1234,
+            generation: 0
+        })
+    ));
+    assert!(matches!(
+        xref.get(2),
+        Some(XrefEntry::Compressed { container: 3, index: 0 })
+    ));
+
+    let stream = Stream::new(
+        dictionary! { "Type" => "XRef", "Size" => 1, "W" => vec![1.into(), 9.into(), 0.into()] },
+        vec![0; 10],
+    );
+    assert!(decode_xref_stream(stream).is_err());
+}
@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use crate::{Dictionary, Document, Object, ObjectId, Stream};
+
+/// Number of bytes of the decoded content of a stream printed by [`Document::pretty_print`].
+const STREAM_PREVIEW: usize = 256;
+
+/// Number of bytes of binary stream content printed, as hexadecimal.
+const BINARY_PREVIEW: usize = 32;
+
+impl Document {
+    /// Dump the objects reachable from the trailer as indented text, for debugging.
+    ///
+    /// A reference is followed the first time it is reached and printed as `3 0 R => <object>`,
+    /// later references to the same object as `3 0 R (above)`, and references to objects missing
+    /// from the document as `3 0 R (missing)`. Streams are printed with their dictionary, their
+    /// size and the first 256 bytes of their decoded content, binary content in hexadecimal.
+    ///
+    /// With a `max_depth`, objects nested deeper below the trailer are elided: references are
+    /// not followed, and dictionaries and arrays are printed as `<<...>>` and `[...]`.
+    pub fn pretty_print(&self, max_depth: Option<usize>) -> String {
+        let mut printer = PrettyPrinter {
+            doc: self,
+            max_depth: max_depth.unwrap_or(usize::MAX),
+            visited: HashSet::new(),
+            out: String::from("trailer "),
+        };
+        printer.dictionary(&self.trailer, 0);
+        printer.out.push('\n');
+        printer.out
+    }
+}
+
+struct PrettyPrinter<'a> {
+    doc: &'a Document,
+    max_depth: usize,
+    visited: HashSet<ObjectId>,
+    out: String,
+}
+
+impl PrettyPrinter<'_> {
+    fn object(&mut self, object: &Object, depth: usize) {
+        match object {
+            _ if is_inline(object) => {
+                let _ = write!(self.out, "{:?}", object);
+            }
+            Object::Array(array) => self.array(array, depth),
+            Object::Dictionary(dict) => self.dictionary(dict, depth),
+            Object::Stream(stream) => self.stream(stream, depth),
+            Object::Reference(id) => self.reference(*id, depth),
+            _ => unreachable!("scalars are printed inline"),
+        }
+    }
+
+    fn reference(&mut self, id: ObjectId, depth: usize) {
+        let _ = write!(self.out, "{} {} R", id.0, id.1);
+        let Some(object) = self.doc.objects.get(&id) else {
+            self.out.push_str(" (missing)");
+            return;
+        };
+        if depth >= self.max_depth {
+            return;
+        }
+        if !self.visited.insert(id) {
+            self.out.push_str(" (above)");
+            return;
+        }
+        self.out.push_str(" => ");
+        self.object(object, depth);
+    }
+
+    fn array(&mut self, array: &[Object], depth: usize) {
+        if depth >= self.max_depth {
+            self.out.push_str("[...]");
+            return;
+        }
+        self.out.push_str("[\n");
+        for item in array {
+            self.indent(depth + 1);
+            self.object(item, depth + 1);
+            self.out.push('\n');
+        }
+        self.indent(depth);
+        self.out.push(']');
+    }
+
+    fn dictionary(&mut self, dict: &Dictionary, depth: usize) {
+        if dict.is_empty() {
+            self.out.push_str("<<>>");
+            return;
+        }
+        if depth >= self.max_depth {
+            self.out.push_str("<<...>>");
+            return;
+        }
+        self.out.push_str("<<\n");
+        for (key, value) in dict {
+            self.indent(depth + 1);
+            let _ = write!(self.out, "/{} ", String::from_utf8_lossy(key));
+            self.object(value, depth + 1);
+            self.out.push('\n');
+        }
+        self.indent(depth);
+        self.out.push_str(">>");
+    }
+
+    fn stream(&mut self, stream: &Stream, depth: usize) {
+        self.dictionary(&stream.dict, depth);
+        let decoded = stream.decompressed_content_ref();
+        let _ = match (&decoded, stream.dict.has(b"Filter")) {
+            (Ok(content), _) => write!(
+                self.out,
+                " stream ({} bytes, {} decoded)",
+                stream.content.len(),
+                content.len()
+            ),
+            (Err(_), true) => write!(self.out, " stream ({} bytes, not decoded)", stream.content.len()),
+            (Err(_), false) => write!(self.out, " stream ({} bytes)", stream.content.len()),
+        };
+        if depth >= self.max_depth {
+            return;
+        }
+        let content = decoded.unwrap_or(&stream.content);
+        let is_text = content.iter().all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+        let shown = if is_text {
+            let preview = &content[..content.len().min(STREAM_PREVIEW)];
+            for line in String::from_utf8_lossy(preview).lines() {
+                self.out.push('\n');
+                self.indent(depth + 1);
+                self.out.push_str(line.trim_end());
+            }
+            preview.len()
+        } else {
+            let preview = &content[..content.len().min(BINARY_PREVIEW)];
+            self.out.push('\n');
+            self.indent(depth + 1);
+            self.out.push('<');
+            for b in preview {
+                let _ = write!(self.out, "{:02x}", b);
+            }
+            self.out.push('>');
+            preview.len()
+        };
+        if shown < content.len() {
+            self.out.push('\n');
+            self.indent(depth + 1);
+            let _ = write!(self.out, "... ({} more bytes)", content.len() - shown);
+        }
+        self.out.push('\n');
+        self.indent(depth);
+        self.out.push_str("endstream");
+    }
+
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+    }
+}
+
+/// Whether the object is printed on a single line, which are the objects that don't contain
+/// dictionaries, streams or references.
+fn is_inline(object: &Object) -> bool {
+    match object {
+        Object::Array(array) => array.iter().all(is_inline),
+        Object::Dictionary(_) | Object::Stream(_) | Object::Reference(_) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creator::tests::create_document;
+    use crate::{Object, Stream};
+
+    #[test]
+    fn pretty_print_document() {
+        let mut doc = create_document();
+        let image_id = doc.add_object(Stream::new(
+            dictionary! { "Type" => "XObject", "Subtype" => "Image" },
+            (0..=255).collect(),
+        ));
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("Image", image_id);
+        catalog.set("Missing", Object::Reference((999, 0)));
+        let dump = doc.pretty_print(None);
+
+        assert!(dump.starts_with("trailer <<\n  /Root 7 0 R => <<\n    /Type /Catalog\n"));
+        assert!(dump.contains("\n    /Pages 2 0 R => <<\n      /Type /Pages\n      /Kids [\n        6 0 R => <<"));
+        assert!(dump.contains("\n          /Parent 2 0 R (above)\n"));
+        assert!(dump.contains("\n      /MediaBox [0 0 595 842]\n"));
+        assert!(dump.contains(" stream (44 bytes)\n            BT\n            /F1 48 Tf\n"));
+        assert!(dump.contains("\n    /Missing 999 0 R (missing)\n"));
+        assert!(dump.contains(" stream (256 bytes)\n      <000102"));
+        assert!(dump.contains("1e1f>\n      ... (224 more bytes)\n    endstream\n"));
+        assert!(dump.ends_with(">>\n"));
+
+        let dump = doc.pretty_print(Some(2));
+        assert!(dump.contains("\n    /Pages 2 0 R\n"));
+        assert!(!dump.contains("/Kids"));
+        assert_eq!(doc.pretty_print(Some(0)), "trailer <<...>>\n");
+    }
+}
@@ -1,9 +1,16 @@
-use crate::Result;
-use crate::{Document, Object, ObjectId};
-use std::collections::BTreeMap;
+use crate::content::{Content, Operation};
+use crate::writer::Writer;
+use crate::xref::XrefEntry;
+use crate::{CompressOptions, Dictionary, Document, Error, Object, ObjectId, Result, Stream};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
 impl Document {
     /// Change producer of document information dictionary.
     pub fn change_producer(&mut self, producer: &str) {
@@ -19,18 +26,41 @@ pub fn change_producer(&mut self, producer: &str) {
     }
 
     /// Compress PDF stream objects.
+    ///
+    /// Streams without a `Filter` are compressed with `FlateDecode`, unless compression is not
+    /// allowed for them, would not make them smaller or their content looks already compressed.
+    /// Streams that already have a filter are left unchanged, so compressing twice does not compress any stream twice.
     pub fn compress(&mut self) {
+        self.compress_with_level(Stream::BEST_COMPRESSION);
+    }
+
+    /// Compress PDF stream objects like [`Document::compress`], at zlib `level` from 0 (no
+    /// compression) to 9 (best compression). Lower levels are faster.
+    pub fn compress_with_level(&mut self, level: u32) {
+        self.compress_with_options(&CompressOptions {
+            level,
+            ..CompressOptions::default()
+        });
+    }
+
+    /// Compress PDF stream objects like [`Document::compress`], with the level and entropy
+    /// threshold of `options`. Streams whose content looks already compressed are skipped.
+    pub fn compress_with_options(&mut self, options: &CompressOptions) {
         for object in self.objects.values_mut() {
             if let Object::Stream(ref mut stream) = *object {
                 if stream.allows_compression {
                     // Ignore any error and continue to compress other streams.
-                    let _ = stream.compress();
+                    let _ = stream.compress_with_options(options);
                 }
             }
         }
     }
 
     /// Decompress PDF stream objects.
+    ///
+    /// All streams whose filters are supported are decoded and their `Filter` and `DecodeParms`
+    /// removed, which makes content streams readable for inspection and editing. Images and
+    /// streams that fail to decode are left unchanged.
     pub fn decompress(&mut self) {
         for object in self.objects.values_mut() {
             if let Object::Stream(ref mut stream) = *object {
@@ -39,38 +69,86 @@ pub fn decompress(&mut self) {
         }
     }
 
+    /// Decompress PDF stream objects in parallel, on the global rayon thread pool.
+    ///
+    /// The result is the same as that of [`Document::decompress`]. No speedup over it has been
+    /// measured yet, run the `bench_decompress_streams` benchmarks on the target machine to compare.
+    #[cfg(feature = "rayon")]
+    pub fn decompress_parallel(&mut self) {
+        self.objects.par_iter_mut().for_each(|(_, object)| {
+            if let Object::Stream(ref mut stream) = *object {
+                stream.decompress()
+            }
+        });
+    }
+
     /// Delete pages.
+    ///
+    /// Each page is removed from the `Kids` of its parent and the `Count` of all its ancestors is
+    /// updated. Page tree nodes left without any kids are removed as well, except for the root.
+    /// Objects that were only used by the deleted pages stay in the document, use
+    /// [`Document::delete_pages_and_prune`] to remove them too.
     pub fn delete_pages(&mut self, page_numbers: &[u32]) {
         let pages = self.get_pages();
         for page_number in page_numbers {
-            if let Some(page) = pages.get(page_number).and_then(|page_id| self.delete_object(*page_id)) {
-                let mut page_tree_ref = page
-                    .as_dict()
-                    .and_then(|dict| dict.get(b"Parent"))
-                    .and_then(Object::as_reference);
-                while let Ok(page_tree_id) = page_tree_ref {
-                    if let Some(page_tree) = self.objects.get_mut(&page_tree_id).and_then(|pt| pt.as_dict_mut().ok()) {
-                        if let Ok(count) = page_tree.get(b"Count").and_then(Object::as_i64) {
-                            page_tree.set("Count", count - 1);
-                        }
-                        page_tree_ref = page_tree.get(b"Parent").and_then(Object::as_reference);
-                    } else {
-                        break;
-                    }
+            if let Some(page_id) = pages.get(page_number) {
+                self.delete_page(*page_id);
+            }
+        }
+    }
+
+    /// Delete pages, then remove all objects that are no longer reachable from the trailer.
+    ///
+    /// Returns the ids of the pruned objects, see [`Document::prune_objects`].
+    pub fn delete_pages_and_prune(&mut self, page_numbers: &[u32]) -> Vec<ObjectId> {
+        self.delete_pages(page_numbers);
+        self.prune_objects()
+    }
+
+    fn delete_page(&mut self, page_id: ObjectId) {
+        let Some(page) = self.objects.remove(&page_id) else {
+            return;
+        };
+        let mut parent_ref = page
+            .as_dict()
+            .and_then(|dict| dict.get(b"Parent"))
+            .and_then(Object::as_reference);
+        // The node to remove from the kids of the next parent, once it is deleted.
+        let mut removed = Some(page_id);
+        let mut visited = BTreeSet::new();
+        while let Ok(parent_id) = parent_ref {
+            if !visited.insert(parent_id) {
+                break;
+            }
+            let Some(parent) = self.objects.get_mut(&parent_id).and_then(|o| o.as_dict_mut().ok()) else {
+                break;
+            };
+            let mut is_empty = false;
+            if let Some(id) = removed {
+                if let Ok(kids) = parent.get_mut(b"Kids").and_then(Object::as_array_mut) {
+                    kids.retain(|kid| kid.as_reference().ok() != Some(id));
+                    is_empty = kids.is_empty();
                 }
             }
+            if let Ok(count) = parent.get(b"Count").and_then(Object::as_i64) {
+                parent.set("Count", count - 1);
+            }
+            parent_ref = parent.get(b"Parent").and_then(Object::as_reference);
+            removed = None;
+            if is_empty && parent_ref.is_ok() {
+                self.objects.remove(&parent_id);
+                removed = Some(parent_id);
+            }
         }
     }
 
     /// Prune all unused objects.
+    ///
+    /// An object is used when it can be reached from the trailer, for example from `Root` or
+    /// `Info`. Returns the ids of the removed objects.
     pub fn prune_objects(&mut self) -> Vec<ObjectId> {
-        let mut ids = vec![];
-        let refs = self.traverse_objects(|_| {});
-        for id in self.objects.keys() {
-            if !refs.contains(id) {
-                ids.push(*id);
-            }
-        }
+        let refs: BTreeSet<ObjectId> = self.traverse_objects(|_| {}).into_iter().collect();
+        let ids: Vec<ObjectId> = self.objects.keys().filter(|id| !refs.contains(id)).copied().collect();
 
         for id in &ids {
             self.objects.remove(id);
@@ -79,6 +157,70 @@ pub fn prune_objects(&mut self) -> Vec<ObjectId> {
         ids
     }
 
+    /// Prune all unused objects, then renumber the remaining ones so their ids have no gaps.
+    ///
+    /// Returns the ids the removed objects had before renumbering.
+    pub fn prune_objects_and_renumber(&mut self) -> Vec<ObjectId> {
+        let ids = self.prune_objects();
+        self.renumber_objects();
+        ids
+    }
+
+    /// Merge identical objects into the one with the lowest id, and point the references to the
+    /// others at it. Objects are compared by their serialized form, so streams are only merged
+    /// if their content is identical too.
+    ///
+    /// Objects that become identical once the objects they refer to are merged, such as fonts
+    /// with identical font descriptors, are merged as well. Pages and page tree nodes are never
+    /// merged, since each must appear once in the page tree. Returns the ids of the removed
+    /// objects.
+    pub fn dedup_objects(&mut self) -> Vec<ObjectId> {
+        let serialize = |object: &Object| {
+            let mut bytes = vec![];
+            Writer::write_object(&mut bytes, object).ok().map(|_| bytes)
+        };
+        let mut removed = vec![];
+        loop {
+            // Ids of the objects kept so far by the hash of their serialized form.
+            let mut kept: HashMap<u64, Vec<ObjectId>> = HashMap::new();
+            let mut replace = BTreeMap::new();
+            for (&id, object) in &self.objects {
+                if let Ok(dict) = object.as_dict() {
+                    if dict.type_is(b"Page") || dict.type_is(b"Pages") {
+                        continue;
+                    }
+                }
+                let Some(bytes) = serialize(object) else {
+                    continue;
+                };
+                let mut hasher = DefaultHasher::new();
+                bytes.hash(&mut hasher);
+                let candidates = kept.entry(hasher.finish()).or_default();
+                let duplicate = candidates
+                    .iter()
+                    .find(|candidate| serialize(&self.objects[candidate]).as_ref() == Some(&bytes));
+                match duplicate {
+                    Some(&canonical) => {
+                        replace.insert(id, canonical);
+                    }
+                    None => candidates.push(id),
+                }
+            }
+            if replace.is_empty() {
+                break;
+            }
+
+            for (old, new) in &replace {
+                self.objects.remove(old);
+                self.renumber_bookmarks(old, new);
+                removed.push(*old);
+            }
+            self.replace_references(&replace);
+        }
+        removed.sort_unstable();
+        removed
+    }
+
     /// Delete object by object ID.
     pub fn delete_object(&mut self, id: ObjectId) -> Option<Object> {
         let action = |object: &mut Object| match *object {
@@ -132,6 +274,10 @@ pub fn delete_zero_length_streams(&mut self) -> Vec<ObjectId> {
     }
 
     /// Renumber objects, normally called after delete_unused_objects.
+    ///
+    /// The objects get the consecutive ids from 1 in the order of their current ids, except that
+    /// pages are first swapped so they are numbered in page order. All references are rewritten,
+    /// in the trailer and in every object, and so is the `Size` of the trailer if it has one.
     pub fn renumber_objects(&mut self) {
         self.renumber_objects_with(1)
     }
@@ -207,15 +353,8 @@ pub fn renumber_objects_with(&mut self, starting_id: u32) {
                 self.objects.insert(new, object);
             }
 
-            let action = |object: &mut Object| {
-                if let Object::Reference(ref mut id) = *object {
-                    if replace.contains_key(id) {
-                        *id = replace[id];
-                    }
-                }
-            };
-
-            self.traverse_objects(action);
+            self.replace_references(&replace);
+            self.renumber_reference_table(&replace);
             replace.clear();
         }
 
@@ -248,17 +387,364 @@ pub fn renumber_objects_with(&mut self, starting_id: u32) {
             self.objects.insert(new, object);
         }
 
-        let action = |object: &mut Object| {
-            if let Object::Reference(ref mut id) = *object {
-                if replace.contains_key(id) {
-                    *id = replace[id];
+        self.replace_references(&replace);
+        self.renumber_reference_table(&replace);
+
+        self.max_id = new_id - 1;
+        if self.trailer.has(b"Size") {
+            self.trailer.set("Size", i64::from(self.max_id + 1));
+        }
+    }
+
+    /// Replace the references to the keys of `replace` with references to their values, in all
+    /// objects including the unreferenced ones and in the trailer.
+    fn replace_references(&mut self, replace: &BTreeMap<ObjectId, ObjectId>) {
+        fn replace_in(object: &mut Object, replace: &BTreeMap<ObjectId, ObjectId>) {
+            match object {
+                Object::Reference(id) => {
+                    if let Some(&new) = replace.get(id) {
+                        *id = new;
+                    }
                 }
+                Object::Array(array) => array.iter_mut().for_each(|item| replace_in(item, replace)),
+                Object::Dictionary(dict) => dict.iter_mut().for_each(|(_, value)| replace_in(value, replace)),
+                Object::Stream(stream) => stream.dict.iter_mut().for_each(|(_, value)| replace_in(value, replace)),
+                _ => {}
             }
-        };
+        }
 
-        self.traverse_objects(action);
+        for object in self.objects.values_mut() {
+            replace_in(object, replace);
+        }
+        for (_, value) in self.trailer.iter_mut() {
+            replace_in(value, replace);
+        }
+    }
 
-        self.max_id = new_id - 1;
+    /// Move the entries of the `reference_table` of the objects renumbered by `replace` to their
+    /// new object numbers.
+    fn renumber_reference_table(&mut self, replace: &BTreeMap<ObjectId, ObjectId>) {
+        let numbers: BTreeMap<u32, u32> = replace.iter().map(|(old, new)| (old.0, new.0)).collect();
+        let mut entries = BTreeMap::new();
+        let mut kept = Vec::new();
+        for (number, mut entry) in std::mem::take(&mut self.reference_table.entries) {
+            if let XrefEntry::Compressed { container, .. } = &mut entry {
+                *container = numbers.get(container).copied().unwrap_or(*container);
+            }
+            match numbers.get(&number) {
+                Some(&new) => {
+                    entries.insert(new, entry);
+                }
+                None => kept.push((number, entry)),
+            }
+        }
+        // Entries of objects that kept their number don't replace the entries of moved objects.
+        for (number, entry) in kept {
+            entries.entry(number).or_insert(entry);
+        }
+        self.reference_table.entries = entries;
+    }
+
+    /// Merge the pages of several documents into a new document, in the given order.
+    ///
+    /// See [`Document::append`] for how the pages and their objects are carried over.
+    pub fn merge(documents: Vec<Document>) -> Result<Document> {
+        let mut merged = Document::with_version("1.0");
+        let pages_id = merged.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![],
+            "Count" => 0,
+        });
+        let catalog_id = merged.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        merged.trailer.set("Root", catalog_id);
+
+        for document in documents {
+            merged.append(document)?;
+        }
+        Ok(merged)
+    }
+
+    /// Append all pages of another document after the pages of this one.
+    ///
+    /// The objects of `other` are renumbered to follow the objects of this document, so none of
+    /// their ids collide. Pages are added to the root of the page tree and keep the attributes they
+    /// inherited in `other`, such as `Resources` and `MediaBox`. A font that is equal to a font of
+    /// this document is replaced by a reference to the existing one.
+    ///
+    /// Only the pages and the objects they use are taken over. The catalog of `other`, with its
+    /// outlines and bookmarks, is dropped.
+    pub fn append(&mut self, mut other: Document) -> Result<()> {
+        let pages_id = self.catalog()?.get(b"Pages").and_then(Object::as_reference)?;
+        other.renumber_objects_with(self.max_id + 1);
+
+        let mut duplicate_fonts = BTreeMap::new();
+        for (id, object) in &other.objects {
+            if object.type_name().ok() != Some("Font") {
+                continue;
+            }
+            if let Some((existing_id, _)) = self
+                .objects
+                .iter()
+                .find(|(_, font)| font.type_name().ok() == Some("Font") && *font == object)
+            {
+                duplicate_fonts.insert(*id, *existing_id);
+            }
+        }
+        if !duplicate_fonts.is_empty() {
+            other.traverse_objects(|object| {
+                if let Object::Reference(ref mut id) = *object {
+                    if let Some(existing_id) = duplicate_fonts.get(id) {
+                        *id = *existing_id;
+                    }
+                }
+            });
+        }
+
+        let root = self.get_dictionary(pages_id)?;
+        let mut pages = vec![];
+        for page_id in other.page_iter() {
+            let mut page = other.page_with_inherited_attributes(page_id)?;
+            for key in INHERITABLE_PAGE_ATTRIBUTES {
+                // Don't let the attributes of the new page tree leak into the page.
+                if !page.has(key) && root.has(key) {
+                    match key {
+                        b"Resources" => page.set("Resources", Dictionary::new()),
+                        b"Rotate" => page.set("Rotate", 0),
+                        _ => {}
+                    }
+                }
+            }
+            page.set("Parent", pages_id);
+            pages.push((page_id, page));
+        }
+
+        let mut object_ids = BTreeSet::new();
+        for (_, page) in &pages {
+            other.collect_page_objects(page, &mut object_ids);
+        }
+        for id in object_ids {
+            if let Some(object) = other.objects.remove(&id) {
+                self.objects.insert(id, object);
+            }
+        }
+
+        let root = self.get_dictionary_mut(pages_id)?;
+        let count = root.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+        root.set("Count", count + pages.len() as i64);
+        let mut kids = root
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .cloned()
+            .unwrap_or_default();
+        kids.extend(pages.iter().map(|(id, _)| Object::Reference(*id)));
+        root.set("Kids", kids);
+
+        for (id, page) in pages {
+            self.objects.insert(id, Object::Dictionary(page));
+        }
+        self.max_id = self.max_id.max(other.max_id);
+        if other.version > self.version {
+            self.version = other.version;
+        }
+        Ok(())
+    }
+
+    /// Extract a page into a new standalone document.
+    ///
+    /// The new document contains the page with the attributes it inherits from the page tree,
+    /// and every object the page uses, such as its content streams, resources and annotations.
+    /// Objects shared with other pages are copied as well. Other pages of this document are not
+    /// copied, so links to them no longer have a target.
+    pub fn extract_page(&self, page_number: u32) -> Result<Document> {
+        let page_id = *self
+            .get_pages()
+            .get(&page_number)
+            .ok_or(Error::PageNumberNotFound(page_number))?;
+
+        let mut doc = Document::with_version(self.version.clone());
+        doc.max_id = self.max_id;
+        let pages_id = doc.new_object_id();
+        let mut page = self.page_with_inherited_attributes(page_id)?;
+        page.set("Parent", pages_id);
+
+        let mut object_ids = BTreeSet::new();
+        self.collect_page_objects(&page, &mut object_ids);
+        for id in object_ids {
+            if let Some(object) = self.objects.get(&id) {
+                doc.objects.insert(id, object.clone());
+            }
+        }
+        doc.objects.insert(page_id, Object::Dictionary(page));
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.renumber_objects();
+        Ok(doc)
+    }
+
+    /// Split the document into one standalone document per page, see [`Document::extract_page`].
+    pub fn split(&self) -> Result<Vec<Document>> {
+        (1..=self.get_pages().len() as u32)
+            .map(|page_number| self.extract_page(page_number))
+            .collect()
+    }
+
+    /// Flatten the page tree into a single level: every page becomes a kid of the root `Pages`
+    /// node and gets its own copy of the `Resources`, `MediaBox`, `CropBox` and `Rotate`
+    /// attributes it inherited. The intermediate `Pages` nodes are deleted.
+    ///
+    /// Page ids are unchanged, so annotations and other references to pages stay valid.
+    pub fn flatten_page_tree(&mut self) -> Result<()> {
+        let root_id = self.catalog()?.get(b"Pages").and_then(Object::as_reference)?;
+        let page_ids: Vec<ObjectId> = self.page_iter().collect();
+
+        let mut pages = Vec::with_capacity(page_ids.len());
+        for &page_id in &page_ids {
+            let mut page = self.page_with_inherited_attributes(page_id)?;
+            page.set("Parent", root_id);
+            pages.push((page_id, page));
+        }
+
+        let mut nodes = vec![];
+        let mut pending = vec![root_id];
+        let mut visited = BTreeSet::new();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Ok(node) = self.get_dictionary(id) else {
+                continue;
+            };
+            if node.type_name().ok() != Some("Pages") && id != root_id {
+                continue;
+            }
+            if id != root_id {
+                nodes.push(id);
+            }
+            if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+                pending.extend(kids.iter().filter_map(|kid| kid.as_reference().ok()));
+            }
+        }
+        for id in nodes {
+            self.objects.remove(&id);
+        }
+        for (page_id, page) in pages {
+            self.objects.insert(page_id, Object::Dictionary(page));
+        }
+
+        let root = self.get_dictionary_mut(root_id)?;
+        for key in INHERITABLE_PAGE_ATTRIBUTES {
+            root.remove(key);
+        }
+        root.set(
+            "Kids",
+            page_ids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>(),
+        );
+        root.set("Count", page_ids.len() as i64);
+        Ok(())
+    }
+
+    /// Reorder the pages, `order` lists the current page numbers, starting at 1, in their new
+    /// order and must contain every page exactly once.
+    ///
+    /// A page tree with intermediate `Pages` nodes is flattened first, see
+    /// [`Document::flatten_page_tree`], so pages can move between nodes.
+    pub fn reorder_pages(&mut self, order: &[u32]) -> Result<()> {
+        let pages = self.get_pages();
+        let mut seen = BTreeSet::new();
+        for &page_number in order {
+            if !pages.contains_key(&page_number) {
+                return Err(Error::PageNumberNotFound(page_number));
+            }
+            if !seen.insert(page_number) {
+                return Err(Error::Invalid(format!(
+                    "Page {} appears twice in the page order",
+                    page_number
+                )));
+            }
+        }
+        if seen.len() != pages.len() {
+            return Err(Error::Invalid(format!(
+                "Page order has {} pages, the document has {}",
+                seen.len(),
+                pages.len()
+            )));
+        }
+
+        let root_id = self.catalog()?.get(b"Pages").and_then(Object::as_reference)?;
+        let is_flat = pages.values().all(|&page_id| {
+            self.get_dictionary(page_id)
+                .and_then(|page| page.get(b"Parent"))
+                .and_then(Object::as_reference)
+                .ok()
+                == Some(root_id)
+        });
+        if !is_flat {
+            self.flatten_page_tree()?;
+        }
+        let kids: Vec<Object> = order
+            .iter()
+            .map(|page_number| Object::Reference(pages[page_number]))
+            .collect();
+        self.get_dictionary_mut(root_id)?.set("Kids", kids);
+        Ok(())
+    }
+
+    /// Rotate a page clockwise by a multiple of 90 degrees, on top of its current rotation,
+    /// which may be inherited. The `Rotate` of the page is set to 0, 90, 180 or 270.
+    pub fn rotate_page(&mut self, page_id: ObjectId, degrees: i64) -> Result<()> {
+        if degrees % 90 != 0 {
+            return Err(Error::Invalid(format!(
+                "Rotation of {} degrees is not a multiple of 90",
+                degrees
+            )));
+        }
+        let rotation = (self.get_page_rotation(page_id)? + degrees).rem_euclid(360);
+        self.get_dictionary_mut(page_id)?.set("Rotate", rotation);
+        Ok(())
+    }
+
+    /// Clone a page dictionary, copying the attributes it inherits from its ancestors into it.
+    pub(crate) fn page_with_inherited_attributes(&self, page_id: ObjectId) -> Result<Dictionary> {
+        let mut page = self.get_dictionary(page_id)?.clone();
+        for key in INHERITABLE_PAGE_ATTRIBUTES {
+            if !page.has(key) {
+                if let Some(value) = inherited_attribute(self, &page, key) {
+                    page.set(key.to_vec(), value);
+                }
+            }
+        }
+        Ok(page)
+    }
+
+    /// Collect the ids of all objects a page refers to, directly or through other objects.
+    ///
+    /// Pages, page tree nodes and the catalog are not collected, nor followed.
+    pub(crate) fn collect_page_objects(&self, page: &Dictionary, ids: &mut BTreeSet<ObjectId>) {
+        let mut pending = vec![];
+        collect_dictionary_references(page, &mut pending);
+        while let Some(id) = pending.pop() {
+            let Some(object) = self.objects.get(&id) else {
+                continue;
+            };
+            if matches!(object.type_name(), Ok("Page") | Ok("Pages") | Ok("Catalog")) || !ids.insert(id) {
+                continue;
+            }
+            collect_references(object, &mut pending);
+        }
     }
 
     pub fn change_content_stream(&mut self, stream_id: ObjectId, content: Vec<u8>) {
@@ -290,6 +776,39 @@ pub fn change_page_content(&mut self, page_id: ObjectId, content: Vec<u8>) -> Re
         Ok(())
     }
 
+    /// Replace the content of a page with a single content stream, compressed if that makes it
+    /// smaller, and return its id. `Contents` refers to the new stream, also if it was an array.
+    ///
+    /// The previous content streams, and the array holding them if it is an indirect object, are
+    /// removed from the document unless another page uses them as well.
+    pub fn set_page_content(&mut self, page_id: ObjectId, content: Content<Vec<Operation>>) -> Result<ObjectId> {
+        let mut stream = Stream::new(Dictionary::new(), content.encode()?);
+        // Ignore any compression error, the stream is stored uncompressed then.
+        let _ = stream.compress();
+
+        let content_objects = |doc: &Document, page_id: ObjectId| {
+            let mut ids = doc.get_page_contents(page_id);
+            let contents = doc.get_dictionary(page_id).and_then(|page| page.get(b"Contents"));
+            ids.extend(contents.and_then(Object::as_reference));
+            ids
+        };
+        let previous = content_objects(self, page_id);
+        let stream_id = self.add_object(stream);
+        self.get_dictionary_mut(page_id)?.set("Contents", stream_id);
+
+        let shared: BTreeSet<ObjectId> = self
+            .page_iter()
+            .filter(|&id| id != page_id)
+            .flat_map(|id| content_objects(self, id))
+            .collect();
+        for id in previous {
+            if !shared.contains(&id) {
+                self.objects.remove(&id);
+            }
+        }
+        Ok(stream_id)
+    }
+
     pub fn extract_stream(&self, stream_id: ObjectId, decompress: bool) -> Result<()> {
         let mut file = File::create(format!("{:?}.bin", stream_id))?;
         if let Ok(Object::Stream(ref stream)) = self.get_object(stream_id) {
@@ -306,3 +825,618 @@ pub fn extract_stream(&self, stream_id: ObjectId, decompress: bool) -> Result<()
         Ok(())
     }
 }
+
+/// Page attributes that may be inherited from an ancestor in the page tree.
+const INHERITABLE_PAGE_ATTRIBUTES: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Find the value of an attribute in the ancestors of a page tree node.
+fn inherited_attribute(doc: &Document, node: &Dictionary, key: &[u8]) -> Option<Object> {
+    let mut visited = BTreeSet::new();
+    let mut node = node;
+    while let Ok(parent_id) = node.get(b"Parent").and_then(Object::as_reference) {
+        if !visited.insert(parent_id) {
+            return None;
+        }
+        node = doc.get_dictionary(parent_id).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value.clone());
+        }
+    }
+    None
+}
+
+/// Collect the ids of all objects referenced by an object and its children.
+fn collect_references(object: &Object, refs: &mut Vec<ObjectId>) {
+    match *object {
+        Object::Reference(id) => refs.push(id),
+        Object::Array(ref array) => array.iter().for_each(|item| collect_references(item, refs)),
+        Object::Dictionary(ref dict) => collect_dictionary_references(dict, refs),
+        Object::Stream(ref stream) => collect_dictionary_references(&stream.dict, refs),
+        _ => {}
+    }
+}
+
+fn collect_dictionary_references(dict: &Dictionary, refs: &mut Vec<ObjectId>) {
+    dict.iter().for_each(|(_, value)| collect_references(value, refs));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creator::tests::create_document;
+    use crate::{CompressOptions, Document, Error, Object, ObjectId, Stream};
+
+    #[test]
+    fn decompress_and_compress_streams() {
+        let mut doc = create_document();
+        let content = b"BT /F1 48 Tf 100 600 Td (Hello World!) Tj ET\n".repeat(10);
+        let mut compressed = Stream::new(dictionary! {}, content.clone());
+        compressed.compress_with("FlateDecode").unwrap();
+        let compressed_id = doc.add_object(compressed);
+        let small_id = doc.add_object(Stream::new(dictionary! {}, b"q Q".to_vec()));
+        let image_id = doc.add_object(Stream::new(
+            dictionary! { "Subtype" => "Image", "Filter" => "DCTDecode" },
+            b"JPEG".to_vec(),
+        ));
+        let stream = |doc: &Document, id| doc.get_object(id).and_then(Object::as_stream).unwrap().clone();
+        let length = |stream: &Stream| stream.dict.get(b"Length").and_then(Object::as_i64).unwrap();
+
+        doc.decompress();
+        let decompressed = stream(&doc, compressed_id);
+        assert_eq!(decompressed.content, content);
+        assert_eq!(length(&decompressed), content.len() as i64);
+        assert!(!decompressed.dict.has(b"Filter"));
+        assert_eq!(stream(&doc, image_id).content, b"JPEG");
+
+        doc.compress();
+        doc.compress();
+        let recompressed = stream(&doc, compressed_id);
+        assert_eq!(recompressed.filters().unwrap(), ["FlateDecode"]);
+        assert_eq!(length(&recompressed), recompressed.content.len() as i64);
+        assert_eq!(recompressed.decompressed_content().unwrap(), content);
+        // Compressing would make this stream grow.
+        let small = stream(&doc, small_id);
+        assert!(!small.dict.has(b"Filter"));
+        assert_eq!(small.content, b"q Q");
+        assert_eq!(stream(&doc, image_id).filters().unwrap(), ["DCTDecode"]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decompress_in_parallel() {
+        let mut doc = create_document();
+        doc.compress();
+        let mut parallel = doc.clone();
+        doc.decompress();
+        parallel.decompress_parallel();
+        assert_eq!(parallel.objects, doc.objects);
+    }
+
+    #[test]
+    fn compress_skips_random_data() {
+        let mut doc = create_document();
+        // Deterministic pseudo-random bytes from a xorshift generator.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let random: Vec<u8> = (0..16384)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect();
+        let random_id = doc.add_object(Stream::new(dictionary! {}, random.clone()));
+        let text = b"BT /F1 12 Tf 50 700 Td (Compressible) Tj ET\n".repeat(100);
+        let text_id = doc.add_object(Stream::new(dictionary! {}, text.clone()));
+        let stream = |doc: &Document, id| doc.get_object(id).and_then(Object::as_stream).unwrap().clone();
+
+        doc.compress();
+        let skipped = stream(&doc, random_id);
+        assert!(!skipped.dict.has(b"Filter"));
+        assert_eq!(skipped.content, random);
+        assert_eq!(stream(&doc, text_id).filters().unwrap(), ["FlateDecode"]);
+
+        // A high enough threshold attempts compression, which still does not shrink the data.
+        let mut attempted = Stream::new(dictionary! {}, random.clone());
+        let options = CompressOptions {
+            max_entropy: 8.0,
+            ..CompressOptions::default()
+        };
+        attempted.compress_with_options(&options).unwrap();
+        assert_eq!(attempted.content, random);
+
+        // A low threshold skips even compressible content.
+        let mut plain = Stream::new(dictionary! {}, text.clone());
+        let options = CompressOptions {
+            max_entropy: 1.0,
+            ..CompressOptions::default()
+        };
+        plain.compress_with_options(&options).unwrap();
+        assert!(!plain.dict.has(b"Filter"));
+        assert_eq!(plain.content, text);
+    }
+
+    #[test]
+    fn merge_documents() {
+        let merged = Document::merge(vec![create_document(), create_document()]).unwrap();
+
+        let pages = merged.get_pages();
+        assert_eq!(pages.len(), 2);
+        let root_id = merged
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let root = merged.get_dictionary(root_id).unwrap();
+        assert_eq!(root.get(b"Count").and_then(Object::as_i64).unwrap(), 2);
+
+        let fonts: Vec<_> = pages
+            .values()
+            .map(|page_id| {
+                let page = merged.get_dictionary(*page_id).unwrap();
+                assert_eq!(page.get(b"Parent").and_then(Object::as_reference).unwrap(), root_id);
+                assert!(page.has(b"MediaBox"));
+                let resources = merged.get_dict_in_dict(page, b"Resources").unwrap();
+                let fonts = merged.get_dict_in_dict(resources, b"Font").unwrap();
+                fonts.get(b"F1").and_then(Object::as_reference).unwrap()
+            })
+            .collect();
+        // Both documents use the same standard font, which is only kept once.
+        assert_eq!(fonts[0], fonts[1]);
+        let catalogs = merged
+            .objects
+            .values()
+            .filter(|object| object.type_name().ok() == Some("Catalog"));
+        assert_eq!(catalogs.count(), 1);
+        for page_id in pages.values() {
+            assert!(!merged.get_page_content(*page_id).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn inherited_page_attributes() {
+        let mut doc = create_document();
+        let page_id = doc.get_pages()[&1];
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        // The MediaBox and Resources are only set on the root Pages node.
+        assert!(!doc.get_dictionary(page_id).unwrap().has(b"MediaBox"));
+        assert_eq!(doc.get_page_media_box(page_id).unwrap(), [0.0, 0.0, 595.0, 842.0]);
+        assert_eq!(doc.get_page_crop_box(page_id).unwrap(), [0.0, 0.0, 595.0, 842.0]);
+        assert_eq!(doc.get_page_rotation(page_id).unwrap(), 0);
+        assert!(doc.get_page_inherited_resources(page_id).unwrap().has(b"Font"));
+
+        let root = doc.get_dictionary_mut(root_id).unwrap();
+        root.set("Rotate", -90);
+        root.set("CropBox", vec![(-10).into(), 10.into(), 700.into(), 800.into()]);
+        doc.get_dictionary_mut(page_id)
+            .unwrap()
+            .set("MediaBox", vec![0.into(), 612.into(), 792.into(), 0.into()]);
+        assert_eq!(doc.get_page_media_box(page_id).unwrap(), [0.0, 0.0, 792.0, 612.0]);
+        assert_eq!(doc.get_page_crop_box(page_id).unwrap(), [0.0, 10.0, 700.0, 612.0]);
+        assert_eq!(doc.get_page_rotation(page_id).unwrap(), 270);
+
+        doc.get_dictionary_mut(root_id).unwrap().set("Parent", page_id);
+        doc.get_dictionary_mut(page_id).unwrap().remove(b"MediaBox");
+        doc.get_dictionary_mut(root_id).unwrap().remove(b"MediaBox");
+        assert!(matches!(
+            doc.get_page_attribute(page_id, b"UserUnit"),
+            Err(Error::ReferenceCycle(_))
+        ));
+        let (_, resource_ids) = doc.get_page_resources(page_id);
+        assert!(resource_ids.len() <= 2);
+    }
+
+    #[test]
+    fn flatten_nested_page_tree() {
+        let mut doc = Document::merge(vec![create_document(), create_document(), create_document()]).unwrap();
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let page_ids: Vec<_> = doc.page_iter().collect();
+        let media_box = doc.get_page_media_box(page_ids[0]).unwrap();
+        let resources = doc.get_page_inherited_resources(page_ids[0]).unwrap();
+
+        // Nest the last two pages two levels deep, with attributes set on the intermediate nodes.
+        let inner_id = doc.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_ids[1].into(), page_ids[2].into()],
+            "Count" => 2,
+            "Rotate" => 90,
+        });
+        let outer_id = doc.add_object(dictionary! {
+            "Type" => "Pages",
+            "Parent" => root_id,
+            "Kids" => vec![inner_id.into()],
+            "Count" => 2,
+            "MediaBox" => vec![0.into(), 0.into(), 300.into(), 400.into()],
+        });
+        doc.get_dictionary_mut(inner_id).unwrap().set("Parent", outer_id);
+        for &page_id in &page_ids[1..] {
+            let page = doc.get_dictionary_mut(page_id).unwrap();
+            page.set("Parent", inner_id);
+            page.remove(b"MediaBox");
+        }
+        let annotation_id = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Text", "P" => page_ids[2] });
+        doc.get_dictionary_mut(page_ids[2])
+            .unwrap()
+            .set("Annots", vec![annotation_id.into()]);
+        let root = doc.get_dictionary_mut(root_id).unwrap();
+        root.set("Kids", vec![page_ids[0].into(), outer_id.into()]);
+        root.set("MediaBox", vec![0.into(), 0.into(), 595.into(), 842.into()]);
+        root.set("Resources", resources.clone());
+        doc.get_dictionary_mut(page_ids[0]).unwrap().remove(b"MediaBox");
+        assert_eq!(doc.page_iter().collect::<Vec<_>>(), page_ids);
+
+        doc.flatten_page_tree().unwrap();
+        assert!(!doc.has_object(inner_id));
+        assert!(!doc.has_object(outer_id));
+        assert_eq!(doc.page_iter().collect::<Vec<_>>(), page_ids);
+        let root = doc.get_dictionary(root_id).unwrap();
+        assert_eq!(root.get(b"Count").and_then(Object::as_i64).unwrap(), 3);
+        assert!(!root.has(b"MediaBox"));
+        for (index, &page_id) in page_ids.iter().enumerate() {
+            let page = doc.get_dictionary(page_id).unwrap();
+            assert_eq!(page.get(b"Parent").and_then(Object::as_reference).unwrap(), root_id);
+            assert!(page.has(b"MediaBox") && page.has(b"Resources"));
+            let expected_media_box = if index == 0 {
+                media_box
+            } else {
+                [0.0, 0.0, 300.0, 400.0]
+            };
+            assert_eq!(doc.get_page_media_box(page_id).unwrap(), expected_media_box);
+            assert_eq!(doc.get_page_rotation(page_id).unwrap(), if index == 0 { 0 } else { 90 });
+        }
+        assert_eq!(doc.get_page_annotations(page_ids[2]).len(), 1);
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn set_page_content() {
+        use crate::content::Operation;
+
+        let mut doc = Document::merge(vec![create_document(), create_document()]).unwrap();
+        let pages = doc.get_pages();
+        // The first page has its content split over two streams, held by an indirect array, the
+        // second page shares the first of them.
+        let first = doc.get_page_contents(pages[&1])[0];
+        let extra = doc.add_object(Stream::new(dictionary! {}, b"0 g".to_vec()));
+        let contents = doc.add_object(vec![first.into(), extra.into()]);
+        doc.get_dictionary_mut(pages[&1]).unwrap().set("Contents", contents);
+        let shared = doc.get_page_contents(pages[&2])[0];
+        doc.get_dictionary_mut(pages[&2])
+            .unwrap()
+            .set("Contents", vec![shared.into(), first.into()]);
+
+        let mut content = doc.get_and_decode_page_content(pages[&1]).unwrap();
+        assert_eq!(content.operations.len(), 6);
+        content.operations.retain(|op| op.operator != "g");
+        content.operations.insert(0, Operation::new("q", vec![]));
+        content.operations.push(Operation::new("Q", vec![]));
+        let stream_id = doc.set_page_content(pages[&1], content.clone()).unwrap();
+
+        assert_eq!(doc.get_page_contents(pages[&1]), [stream_id]);
+        let reread = doc.get_and_decode_page_content(pages[&1]).unwrap();
+        assert_eq!(reread.encode().unwrap(), content.encode().unwrap());
+        // The streams used by the other page are kept.
+        assert!(doc.objects.contains_key(&first));
+        assert!(!doc.objects.contains_key(&extra));
+        assert!(!doc.objects.contains_key(&contents));
+    }
+
+    #[test]
+    fn reorder_pages() {
+        let mut doc = Document::merge(vec![create_document(), create_document(), create_document()]).unwrap();
+        let pages = doc.get_pages();
+        doc.reorder_pages(&[3, 2, 1]).unwrap();
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let doc = Document::load_mem(&buffer).unwrap();
+        let reordered = doc.get_pages();
+        assert_eq!(
+            reordered.values().collect::<Vec<_>>(),
+            [&pages[&3], &pages[&2], &pages[&1]]
+        );
+        let root = doc.get_dictionary(
+            doc.catalog()
+                .unwrap()
+                .get(b"Pages")
+                .and_then(Object::as_reference)
+                .unwrap(),
+        );
+        assert_eq!(root.unwrap().get(b"Count").and_then(Object::as_i64).unwrap(), 3);
+
+        let mut doc = doc;
+        assert!(matches!(
+            doc.reorder_pages(&[1, 2, 4]),
+            Err(Error::PageNumberNotFound(4))
+        ));
+        assert!(matches!(doc.reorder_pages(&[1, 2, 2]), Err(Error::Invalid(_))));
+        assert!(matches!(doc.reorder_pages(&[2, 1]), Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn rotate_page() {
+        let mut doc = create_document();
+        let page_id = doc.get_pages()[&1];
+        doc.rotate_page(page_id, 450).unwrap();
+        let rotate = |doc: &Document| {
+            doc.get_dictionary(page_id)
+                .unwrap()
+                .get(b"Rotate")
+                .unwrap()
+                .as_i64()
+                .unwrap()
+        };
+        assert_eq!(rotate(&doc), 90);
+        doc.rotate_page(page_id, -180).unwrap();
+        assert_eq!(rotate(&doc), 270);
+        assert!(matches!(doc.rotate_page(page_id, 45), Err(Error::Invalid(_))));
+        assert_eq!(rotate(&doc), 270);
+
+        // The rotation of the page tree is inherited until the page gets its own.
+        let mut doc = create_document();
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        doc.get_dictionary_mut(root_id).unwrap().set("Rotate", 180);
+        doc.rotate_page(page_id, 90).unwrap();
+        assert_eq!(rotate(&doc), 270);
+    }
+
+    #[test]
+    fn append_document() {
+        let mut doc = create_document();
+        doc.append(create_document()).unwrap();
+        assert_eq!(doc.get_pages().len(), 2);
+        assert!(doc.objects.keys().all(|id| id.0 <= doc.max_id));
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        assert_eq!(Document::load_mem(&buffer).unwrap().get_pages().len(), 2);
+    }
+
+    #[test]
+    fn delete_pages_and_prune() {
+        let mut doc = Document::merge(vec![create_document(), create_document()]).unwrap();
+        let object_count = doc.objects.len();
+        let page_id = doc.get_pages()[&2];
+
+        let pruned = doc.delete_pages_and_prune(&[2]);
+        // The content stream and resources of the page, the font is still used by the first page.
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(doc.objects.len(), object_count - 3);
+        assert!(!doc.has_object(page_id));
+
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let root = doc.get_dictionary(root_id).unwrap();
+        assert_eq!(root.get(b"Count").and_then(Object::as_i64).unwrap(), 1);
+        assert_eq!(root.get(b"Kids").and_then(Object::as_array).unwrap().len(), 1);
+        assert_eq!(doc.get_pages().len(), 1);
+    }
+
+    #[test]
+    fn delete_pages_removes_empty_page_tree_nodes() {
+        let mut doc = create_document();
+        let root_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let page_id = doc.get_pages()[&1];
+        let node_id = doc.add_object(dictionary! {
+            "Type" => "Pages",
+            "Parent" => root_id,
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        });
+        doc.get_dictionary_mut(page_id).unwrap().set("Parent", node_id);
+        doc.get_dictionary_mut(root_id)
+            .unwrap()
+            .set("Kids", vec![node_id.into()]);
+
+        doc.delete_pages(&[1]);
+        assert!(!doc.has_object(node_id));
+        let root = doc.get_dictionary(root_id).unwrap();
+        assert_eq!(root.get(b"Count").and_then(Object::as_i64).unwrap(), 0);
+        assert!(root.get(b"Kids").and_then(Object::as_array).unwrap().is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_annotation_and_outline_streams() {
+        let mut doc = create_document();
+        let page_id = doc.get_pages()[&1];
+        let appearance_id = doc.add_object(Stream::new(dictionary! {}, b"0 0 m 10 10 l S".to_vec()));
+        let annotation_id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Square",
+            "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            "AP" => dictionary! { "N" => appearance_id },
+        });
+        doc.get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Annots", vec![annotation_id.into()]);
+        let script_id = doc.add_object(Stream::new(dictionary! {}, b"app.alert('Hi');".to_vec()));
+        let outlines_id = doc.new_object_id();
+        let item_id = doc.add_object(dictionary! {
+            "Title" => Object::string_literal("Script"),
+            "Parent" => outlines_id,
+            "A" => dictionary! { "S" => "JavaScript", "JS" => script_id },
+        });
+        doc.objects.insert(
+            outlines_id,
+            Object::Dictionary(dictionary! { "First" => item_id, "Last" => item_id, "Count" => 1 }),
+        );
+        doc.catalog_mut().unwrap().set("Outlines", outlines_id);
+        let unused_id = doc.add_object(Stream::new(dictionary! {}, b"unused".to_vec()));
+        let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+
+        assert_eq!(doc.prune_objects(), vec![unused_id]);
+        for id in [appearance_id, script_id, info_id] {
+            assert!(doc.has_object(id));
+        }
+        assert!(doc.prune_objects().is_empty());
+
+        doc.add_object(Stream::new(dictionary! {}, b"unused".to_vec()));
+        let object_count = doc.objects.len();
+        assert_eq!(doc.prune_objects_and_renumber().len(), 1);
+        assert_eq!(doc.objects.len(), object_count - 1);
+        assert_eq!(doc.max_id as usize, doc.objects.len());
+    }
+
+    #[test]
+    fn renumber_sparse_objects() {
+        let mut doc = Document::with_version("1.5");
+        doc.objects
+            .insert((5, 0), dictionary! { "Type" => "Catalog", "Pages" => (99, 0) }.into());
+        doc.objects.insert(
+            (99, 0),
+            dictionary! { "Type" => "Pages", "Kids" => vec![(10000, 0).into()], "Count" => 1 }.into(),
+        );
+        let content = Stream::new(
+            dictionary! { "Ref" => vec![Object::from(dictionary! { "P" => (10000, 0) })] },
+            vec![],
+        );
+        doc.objects.insert((1, 0), content.into());
+        doc.objects.insert(
+            (10000, 0),
+            dictionary! { "Type" => "Page", "Parent" => (99, 0), "Contents" => (1, 0) }.into(),
+        );
+        // Unreferenced objects get their references rewritten too.
+        doc.objects
+            .insert((20000, 0), vec![(5, 0).into(), (10000, 0).into()].into());
+        doc.trailer.set("Root", (5, 0));
+        doc.trailer.set("Size", 20001);
+        doc.max_id = 20000;
+
+        doc.renumber_objects();
+        let ids: Vec<ObjectId> = doc.objects.keys().copied().collect();
+        assert_eq!(ids, [(1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]);
+        assert_eq!(doc.max_id, 5);
+        assert_eq!(doc.trailer.get(b"Size").unwrap().as_i64().unwrap(), 6);
+        for object in doc.objects.values() {
+            let mut objects = vec![object];
+            while let Some(object) = objects.pop() {
+                match object {
+                    Object::Reference(id) => assert!(doc.has_object(*id)),
+                    Object::Array(array) => objects.extend(array),
+                    Object::Dictionary(dict) => objects.extend(dict.iter().map(|(_, value)| value)),
+                    Object::Stream(stream) => objects.extend(stream.dict.iter().map(|(_, value)| value)),
+                    _ => {}
+                }
+            }
+        }
+        let page_id = doc.page_iter().next().unwrap();
+        assert_eq!(page_id, (4, 0));
+        assert_eq!(
+            doc.get_dictionary(page_id).unwrap().get(b"Contents").unwrap(),
+            &Object::Reference((1, 0))
+        );
+        let content = doc.get_object((1, 0)).and_then(Object::as_stream).unwrap();
+        let nested = content.dict.get(b"Ref").and_then(Object::as_array).unwrap()[0]
+            .as_dict()
+            .unwrap();
+        assert_eq!(nested.get(b"P").unwrap(), &Object::Reference(page_id));
+        let unreferenced = doc.get_object((5, 0)).and_then(Object::as_array).unwrap();
+        assert_eq!(unreferenced, &[Object::Reference((2, 0)), Object::Reference(page_id)]);
+    }
+
+    #[test]
+    fn dedup_identical_objects() {
+        let mut doc = create_document();
+        let page_id = doc.get_pages()[&1];
+        let font = || {
+            dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+            }
+        };
+        let font_ids = [doc.add_object(font()), doc.add_object(font())];
+        let image = |content: &[u8]| Stream::new(dictionary! { "Subtype" => "Image" }, content.to_vec());
+        let image_ids = [
+            doc.add_object(image(b"\x00\x01")),
+            doc.add_object(image(b"\x00\x01")),
+            doc.add_object(image(b"\x00\x02")),
+        ];
+        // Identical once the fonts they refer to are merged.
+        let wrapper_ids = [
+            doc.add_object(dictionary! { "Font" => font_ids[0] }),
+            doc.add_object(dictionary! { "Font" => font_ids[1] }),
+        ];
+        let resources = dictionary! {
+            "Font" => dictionary! { "F2" => font_ids[0], "F3" => font_ids[1] },
+            "XObject" => dictionary! {
+                "Im1" => image_ids[0],
+                "Im2" => image_ids[1],
+                "Im3" => image_ids[2],
+            },
+            "Wrappers" => vec![wrapper_ids[0].into(), wrapper_ids[1].into()],
+        };
+        doc.get_dictionary_mut(page_id).unwrap().set("Resources", resources);
+        // A second page identical to the first one is kept.
+        let page = doc.get_object(page_id).unwrap().clone();
+        let second_page_id = doc.add_object(page);
+        let pages_id = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let pages = doc.get_dictionary_mut(pages_id).unwrap();
+        pages.set("Kids", vec![page_id.into(), second_page_id.into()]);
+        pages.set("Count", 2);
+        // References in objects that can't be reached from the trailer are replaced too.
+        let orphan_id = doc.add_object(vec![font_ids[1].into()]);
+
+        assert_eq!(doc.dedup_objects(), vec![font_ids[1], image_ids[1], wrapper_ids[1]]);
+        let orphan = doc.get_object(orphan_id).and_then(Object::as_array).unwrap();
+        assert_eq!(orphan[0].as_reference().unwrap(), font_ids[0]);
+        assert_eq!(doc.get_pages().len(), 2);
+        let resources = doc.get_dictionary(page_id).unwrap().get(b"Resources").unwrap();
+        let resources = resources.as_dict().unwrap();
+        let fonts = resources.get(b"Font").and_then(Object::as_dict).unwrap();
+        assert_eq!(fonts.get(b"F3").and_then(Object::as_reference).unwrap(), font_ids[0]);
+        let images = resources.get(b"XObject").and_then(Object::as_dict).unwrap();
+        assert_eq!(images.get(b"Im2").and_then(Object::as_reference).unwrap(), image_ids[0]);
+        assert_eq!(images.get(b"Im3").and_then(Object::as_reference).unwrap(), image_ids[2]);
+        assert!(doc.dedup_objects().is_empty());
+    }
+
+    #[test]
+    fn split_document() {
+        let doc = Document::merge(vec![create_document(), create_document()]).unwrap();
+        let pages = doc.split().unwrap();
+        assert_eq!(pages.len(), 2);
+
+        for page_doc in &pages {
+            let page_ids: Vec<_> = page_doc.page_iter().collect();
+            assert_eq!(page_ids.len(), 1);
+            assert_eq!(page_doc.get_page_fonts(page_ids[0]).len(), 1);
+            assert!(page_doc.objects.keys().all(|id| id.0 <= page_doc.max_id));
+            assert!(!page_doc.get_page_content(page_ids[0]).unwrap().is_empty());
+        }
+        // Page, pages, catalog, content, resources and the shared font.
+        assert_eq!(pages[1].objects.len(), 6);
+        assert!(matches!(doc.extract_page(3), Err(Error::PageNumberNotFound(3))));
+    }
+}
@@ -1,13 +1,13 @@
 // This module exists because the rust-crypto module is really old and not maintained.
 // Fortunately the RC4 algorithm is very simple to implement.
 
-///
+/// RC4 stream cipher, as used by the standard security handler.
 pub struct Rc4 {
     initial_state: [u8; 256],
 }
 
 impl Rc4 {
-    ///
+    /// Run the key-scheduling algorithm for `key`.
     pub fn new<Key: AsRef<[u8]>>(key: Key) -> Self {
         let key = key.as_ref();
         assert!(!key.is_empty() && key.len() <= 256);
@@ -1,7 +1,7 @@
 #![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
 
 use log::{error, warn};
-use std::cmp;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::convert::TryInto;
 #[cfg(not(feature = "async"))]
@@ -9,6 +9,7 @@
 #[cfg(not(feature = "async"))]
 use std::io::Read;
 use std::path::Path;
+use std::str;
 use std::sync::Mutex;
 
 #[cfg(feature = "rayon")]
@@ -23,8 +24,8 @@
 use crate::parser;
 use crate::error::XrefError;
 use crate::object_stream::ObjectStream;
-use crate::xref::XrefEntry;
-use crate::{Document, Error, IncrementalDocument, Result, Object, ObjectId};
+use crate::xref::{Xref, XrefEntry, XrefType};
+use crate::{Dictionary, Document, Error, IncrementalDocument, Object, ObjectId, Result};
 
 type FilterFunc = fn((u32, u16), &mut Object) -> Option<((u32, u16), Object)>;
 
@@ -34,89 +35,168 @@ impl Document {
     /// Load a PDF document from a specified file path.
     #[inline]
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Document> {
+        Self::load_with_options(path, LoadOptions::default())
+    }
+
+    /// Load a PDF document from a specified file path with custom options.
+    #[inline]
+    pub fn load_with_options<P: AsRef<Path>>(path: P, options: LoadOptions) -> Result<Document> {
         let file = File::open(path)?;
         let capacity = Some(file.metadata()?.len() as usize);
-        Self::load_internal(file, capacity, None)
+        Self::load_internal(file, capacity, None, options)
     }
 
     #[inline]
     pub fn load_filtered<P: AsRef<Path>>(path: P, filter_func: FilterFunc) -> Result<Document> {
         let file = File::open(path)?;
         let capacity = Some(file.metadata()?.len() as usize);
-        Self::load_internal(file, capacity, Some(filter_func))
+        Self::load_internal(file, capacity, Some(filter_func), LoadOptions::default())
     }
 
-    /// Load a PDF document from an arbitrary source.
+    /// Load a PDF document from an arbitrary source, such as a network stream, an archive entry
+    /// or an in-memory [`std::io::Cursor`], without touching the filesystem.
+    ///
+    /// The source is read to its end and the objects are parsed from memory, so it does not need
+    /// to implement `Seek`. Sources that do can be loaded without buffering them as a whole with
+    /// [`Document::load_seekable`].
     #[inline]
     pub fn load_from<R: Read>(source: R) -> Result<Document> {
-        Self::load_internal(source, None, None)
+        Self::load_internal(source, None, None, LoadOptions::default())
     }
 
     fn load_internal<R: Read>(
-        mut source: R, capacity: Option<usize>, filter_func: Option<FilterFunc>,
+        mut source: R, capacity: Option<usize>, filter_func: Option<FilterFunc>, options: LoadOptions,
     ) -> Result<Document> {
         let mut buffer = capacity.map(Vec::with_capacity).unwrap_or_default();
         source.read_to_end(&mut buffer)?;
 
-        Reader {
-            buffer: &buffer,
-            document: Document::new(),
-        }
-        .read(filter_func)
+        Reader::with_options(&buffer, options).read(filter_func)
     }
 
     /// Load a PDF document from a memory slice.
     pub fn load_mem(buffer: &[u8]) -> Result<Document> {
         buffer.try_into()
     }
+
+    /// Load a PDF document from a memory slice with custom options.
+    pub fn load_mem_with_options(buffer: &[u8], options: LoadOptions) -> Result<Document> {
+        Reader::with_options(buffer, options).read(None)
+    }
+
+    /// Load a PDF document from a specified file path and decrypt it with the user or owner
+    /// password if it is encrypted.
+    pub fn load_encrypted<P: AsRef<Path>, Q: AsRef<[u8]>>(path: P, password: Q) -> Result<Document> {
+        let mut document = Self::load(path)?;
+        if document.is_encrypted() {
+            document.decrypt(password)?;
+        }
+        Ok(document)
+    }
+
+    /// Load a PDF document from a memory slice and decrypt it with the user or owner password if
+    /// it is encrypted.
+    pub fn load_mem_encrypted<P: AsRef<[u8]>>(buffer: &[u8], password: P) -> Result<Document> {
+        let mut document = Self::load_mem(buffer)?;
+        if document.is_encrypted() {
+            document.decrypt(password)?;
+        }
+        Ok(document)
+    }
 }
 
 #[cfg(feature = "async")]
 impl Document {
     pub async fn load<P: AsRef<Path>>(path: P) -> Result<Document> {
+        Self::load_with_options(path, LoadOptions::default()).await
+    }
+
+    /// Load a PDF document from a specified file path with custom options.
+    pub async fn load_with_options<P: AsRef<Path>>(path: P, options: LoadOptions) -> Result<Document> {
         let file = File::open(path).await?;
         let metadata = file.metadata().await?;
         let capacity = Some(metadata.len() as usize);
-        Self::load_internal(file, capacity, None).await
+        Self::load_internal(file, capacity, None, options).await
     }
 
     pub async fn load_filtered<P: AsRef<Path>>(path: P, filter_func: FilterFunc) -> Result<Document> {
         let file = File::open(path).await?;
         let metadata = file.metadata().await?;
         let capacity = Some(metadata.len() as usize);
-        Self::load_internal(file, capacity, Some(filter_func)).await
+        Self::load_internal(file, capacity, Some(filter_func), LoadOptions::default()).await
     }
 
     async fn load_internal<R: AsyncRead>(
-        source: R, capacity: Option<usize>, filter_func: Option<FilterFunc>,
+        source: R, capacity: Option<usize>, filter_func: Option<FilterFunc>, options: LoadOptions,
     ) -> Result<Document> {
         pin!(source);
 
         let mut buffer = capacity.map(Vec::with_capacity).unwrap_or_default();
         source.read_to_end(&mut buffer).await?;
 
-        Reader {
-            buffer: &buffer,
-            document: Document::new(),
-        }
-            .read(filter_func)
+        Reader::with_options(&buffer, options).read(filter_func)
     }
 
     /// Load a PDF document from a memory slice.
     pub fn load_mem(buffer: &[u8]) -> Result<Document> {
         buffer.try_into()
     }
+
+    /// Load a PDF document from a memory slice with custom options.
+    pub fn load_mem_with_options(buffer: &[u8], options: LoadOptions) -> Result<Document> {
+        Reader::with_options(buffer, options).read(None)
+    }
+
+    /// Load a PDF document from a specified file path and decrypt it with the user or owner
+    /// password if it is encrypted.
+    pub async fn load_encrypted<P: AsRef<Path>, Q: AsRef<[u8]>>(path: P, password: Q) -> Result<Document> {
+        let mut document = Self::load(path).await?;
+        if document.is_encrypted() {
+            document.decrypt(password)?;
+        }
+        Ok(document)
+    }
+
+    /// Load a PDF document from a memory slice and decrypt it with the user or owner password if
+    /// it is encrypted.
+    pub fn load_mem_encrypted<P: AsRef<[u8]>>(buffer: &[u8], password: P) -> Result<Document> {
+        let mut document = Self::load_mem(buffer)?;
+        if document.is_encrypted() {
+            document.decrypt(password)?;
+        }
+        Ok(document)
+    }
 }
 
 impl TryInto<Document> for &[u8] {
     type Error = Error;
 
     fn try_into(self) -> Result<Document> {
-        Reader {
-            buffer: self,
-            document: Document::new(),
+        Reader::new(self).read(None)
+    }
+}
+
+impl Document {
+    /// Load a PDF document from a memory slice like [`Document::load_mem`], but fail instead of
+    /// recovering from syntax errors, for validating files.
+    ///
+    /// The cross-reference table must be readable, and every object in it must start at its
+    /// offset and end with `endobj`. Objects that can't be parsed at all are skipped by
+    /// [`Document::load_mem`], here they are reported as [`Error::Parse`].
+    pub fn load_mem_strict(buffer: &[u8]) -> Result<Document> {
+        let document = Reader::new(buffer).read(None)?;
+        // Offsets in the file are relative to its header.
+        let buffer = &buffer[document.header_offset..];
+        let mut reader = Reader::new(buffer);
+        reader.document = document;
+        if reader.document.xref_rebuilt {
+            return Err(Error::Xref(XrefError::Parse));
         }
-        .read(None)
+        for (&id, entry) in &reader.document.reference_table.entries {
+            if let XrefEntry::Normal { offset, generation } = *entry {
+                parser::indirect_object_strict(buffer, offset as usize, Some((id, generation)), &reader)?;
+            }
+        }
+        Ok(reader.document)
     }
 }
 
@@ -140,11 +220,7 @@ fn load_internal<R: Read>(mut source: R, capacity: Option<usize>) -> Result<Self
         let mut buffer = capacity.map(Vec::with_capacity).unwrap_or_default();
         source.read_to_end(&mut buffer)?;
 
-        let document = Reader {
-            buffer: &buffer,
-            document: Document::new(),
-        }
-        .read(None)?;
+        let document = Reader::new(&buffer).read(None)?;
 
         Ok(IncrementalDocument::create_from(buffer, document))
     }
@@ -178,11 +254,7 @@ async fn load_internal<R: AsyncRead>(source: R, capacity: Option<usize>) -> Resu
         let mut buffer = capacity.map(Vec::with_capacity).unwrap_or_default();
         source.read_to_end(&mut buffer).await?;
 
-        let document = Reader {
-            buffer: &buffer,
-            document: Document::new(),
-        }
-            .read(None)?;
+        let document = Reader::new(&buffer).read(None)?;
 
         Ok(IncrementalDocument::create_from(buffer, document))
     }
@@ -197,62 +269,105 @@ impl TryInto<IncrementalDocument> for &[u8] {
     type Error = Error;
 
     fn try_into(self) -> Result<IncrementalDocument> {
-        let document = Reader {
-            buffer: self,
-            document: Document::new(),
-        }
-        .read(None)?;
+        let document = Reader::new(self).read(None)?;
 
         Ok(IncrementalDocument::create_from(self.to_vec(), document))
     }
 }
 
+/// Options for [`Document::load_with_options`] and [`Document::load_mem_with_options`].
+///
+/// The default options load a document the same way as [`Document::load`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Number of bytes before and after the offset of an object in the cross-reference table
+    /// searched for its `N G obj` header when it isn't found at the offset, for files written
+    /// with slightly wrong offsets. Zero disables the search. Default 32.
+    pub offset_search_window: usize,
+    /// Maximum nesting depth of arrays and dictionaries in an object or the trailer. Objects
+    /// nested deeper fail to parse, rather than overflowing the stack on untrusted input.
+    /// Default 256.
+    ///
+    /// Content streams are decoded on demand, after loading, and always use the default limit.
+    pub max_nesting_depth: usize,
+    /// Maximum number of objects a [`LazyDocument`](crate::LazyDocument) keeps cached, see
+    /// [`LazyDocument::with_cache_capacity`](crate::LazyDocument::with_cache_capacity). The other
+    /// loaders ignore it, as they keep every object. Default `None`, which caches all objects.
+    pub cache_capacity: Option<usize>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            offset_search_window: OFFSET_SEARCH_WINDOW,
+            max_nesting_depth: MAX_NESTING_DEPTH,
+            cache_capacity: None,
+        }
+    }
+}
+
 pub struct Reader<'a> {
     pub buffer: &'a [u8],
     pub document: Document,
+    pub(crate) options: LoadOptions,
+    /// Objects that [`Reader::get_object`] could not find in the cross-reference table.
+    pub(crate) missing: Mutex<Vec<ObjectId>>,
 }
 
 /// Maximum allowed embedding of literal strings.
 pub const MAX_BRACKET: usize = 100;
 
+/// Number of bytes at the start of the file searched for the `%PDF-` header.
+pub const HEADER_SEARCH_LIMIT: usize = 1024;
+
+/// Default [`LoadOptions::offset_search_window`].
+pub const OFFSET_SEARCH_WINDOW: usize = 32;
+
+/// Default [`LoadOptions::max_nesting_depth`], also the limit for content streams and objects parsed
+/// without a reader.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
 impl<'a> Reader<'a> {
+    /// Create a reader of the document in `buffer` with the default options.
+    pub fn new(buffer: &'a [u8]) -> Reader<'a> {
+        Self::with_options(buffer, LoadOptions::default())
+    }
+
+    /// Create a reader of the document in `buffer`.
+    pub fn with_options(buffer: &'a [u8], options: LoadOptions) -> Reader<'a> {
+        Reader {
+            buffer,
+            document: Document::new(),
+            options,
+            missing: Mutex::new(Vec::new()),
+        }
+    }
+
     /// Read whole document.
     pub fn read(mut self, filter_func: Option<FilterFunc>) -> Result<Document> {
         // The document structure can be expressed in PEG as:
         //   document <- header indirect_object* xref trailer xref_start
-        let version = parser::header(self.buffer).ok_or(Error::Header)?;
-
-        let xref_start = Self::get_xref_start(self.buffer)?;
-        if xref_start > self.buffer.len() {
-            return Err(Error::Xref(XrefError::Start));
+        let header_offset = Self::find_header(self.buffer).ok_or(Error::Header)?;
+        if header_offset > 0 {
+            warn!("Skipping {} bytes in front of the file header.", header_offset);
+            self.buffer = &self.buffer[header_offset..];
+            self.document.header_offset = header_offset;
         }
-        self.document.xref_start = xref_start;
-
-        let (mut xref, mut trailer) = parser::xref_and_trailer(&self.buffer[xref_start..], &self)?;
-
-        // Read previous Xrefs of linearized or incremental updated document.
-        let mut prev_xref_start = trailer.remove(b"Prev");
-        while let Some(prev) = prev_xref_start.and_then(|offset| offset.as_i64().ok()) {
-            if prev < 0 || prev as usize > self.buffer.len() {
-                return Err(Error::Xref(XrefError::PrevStart));
-            }
-
-            let (prev_xref, mut prev_trailer) = parser::xref_and_trailer(&self.buffer[prev as usize..], &self)?;
-            xref.merge(prev_xref);
-
-            // Read xref stream in hybrid-reference file
-            let prev_xref_stream_start = trailer.remove(b"XRefStm");
-            if let Some(prev) = prev_xref_stream_start.and_then(|offset| offset.as_i64().ok()) {
-                if prev < 0 || prev as usize > self.buffer.len() {
-                    return Err(Error::Xref(XrefError::StreamStart));
-                }
+        let version = parser::header(self.buffer).ok_or(Error::Header)?;
 
-                let (prev_xref, _) = parser::xref_and_trailer(&self.buffer[prev as usize..], &self)?;
-                xref.merge(prev_xref);
+        let (mut xref, trailer) = match self.read_xref() {
+            Ok(sections) => sections,
+            Err(err) => {
+                warn!(
+                    "Cross-reference table is broken ({}), rebuilding it by scanning for objects.",
+                    err
+                );
+                self.document.xref_rebuilt = true;
+                self.document.xref_start = 0;
+                // Report the original problem if the file doesn't contain any objects either.
+                self.rebuild_xref().map_err(|_| err)?
             }
-
-            prev_xref_start = prev_trailer.remove(b"Prev");
-        }
+        };
 
         let xref_entry_count = xref.max_id() + 1;
         if xref.size != xref_entry_count {
@@ -270,18 +385,21 @@ pub fn read(mut self, filter_func: Option<FilterFunc>) -> Result<Document> {
 
         let zero_length_streams = Mutex::new(vec![]);
         let object_streams = Mutex::new(vec![]);
+        // Object streams of encrypted documents can only be parsed once they are decrypted
+        let is_encrypted = self.document.trailer.has(b"Encrypt");
 
-        let entries_filter_map = |(_, entry): (&_, &_)| {
-            if let XrefEntry::Normal { offset, .. } = *entry {
+        let entries_filter_map = |(&number, entry): (&_, &_)| {
+            if let XrefEntry::Normal { offset, generation } = *entry {
                 let (object_id, mut object) = self
-                    .read_object(offset as usize, None)
-                    .map_err(|e| error!("Object load error: {:?}", e))
+                    .read_xref_object(offset as usize, (number, generation))
+                    .or_else(|_| self.read_object(offset as usize, None))
+                    .map_err(|e| error!("Object load error: {}", e))
                     .ok()?;
                 if let Some(filter_func) = filter_func {
                     filter_func(object_id, &mut object)?;
                 }
                 if let Ok(ref mut stream) = object.as_stream_mut() {
-                    if stream.dict.type_is(b"ObjStm") {
+                    if stream.dict.type_is(b"ObjStm") && !is_encrypted {
                         let obj_stream = ObjectStream::new(stream).ok()?;
                         let mut object_streams = object_streams.lock().unwrap();
                         // TODO: Is insert and replace intended behavior?
@@ -338,6 +456,133 @@ pub fn read(mut self, filter_func: Option<FilterFunc>) -> Result<Document> {
         Ok(self.document)
     }
 
+    /// Read the cross-reference sections, starting from the last one.
+    fn read_xref(&mut self) -> Result<(Xref, Dictionary)> {
+        let xref_start = Self::get_xref_start(self.buffer)?;
+        if xref_start > self.buffer.len() {
+            return Err(Error::Xref(XrefError::Start));
+        }
+        self.document.xref_start = xref_start;
+
+        let (mut xref, mut trailer) = parser::xref_and_trailer(&self.buffer[xref_start..], self)?;
+
+        // Read the xref stream of a hybrid-reference file and the previous Xrefs of a linearized
+        // or incremental updated document. Entries of later sections take precedence.
+        let mut section_trailer = trailer.clone();
+        let mut section_starts = vec![xref_start];
+        loop {
+            if let Some(start) = section_trailer
+                .remove(b"XRefStm")
+                .and_then(|offset| offset.as_i64().ok())
+            {
+                if start < 0 || start as usize > self.buffer.len() {
+                    return Err(Error::Xref(XrefError::StreamStart));
+                }
+
+                let (stream_xref, _) = parser::xref_and_trailer(&self.buffer[start as usize..], self)?;
+                xref.merge(stream_xref);
+            }
+
+            let Some(prev) = section_trailer.remove(b"Prev").and_then(|offset| offset.as_i64().ok()) else {
+                break;
+            };
+            if prev < 0 || prev as usize > self.buffer.len() {
+                return Err(Error::Xref(XrefError::PrevStart));
+            }
+            if section_starts.contains(&(prev as usize)) {
+                warn!("Cross-reference section at {} is referenced twice by Prev", prev);
+                break;
+            }
+            section_starts.push(prev as usize);
+
+            let (prev_xref, prev_trailer) = parser::xref_and_trailer(&self.buffer[prev as usize..], self)?;
+            xref.merge(prev_xref);
+            section_trailer = prev_trailer;
+        }
+        trailer.remove(b"Prev");
+        trailer.remove(b"XRefStm");
+
+        Ok((xref, trailer))
+    }
+
+    /// Rebuild the cross-reference table by scanning the whole file for `N G obj` keywords.
+    ///
+    /// When an object is defined more than once, the last definition wins. The trailer is the
+    /// last `trailer` dictionary with a `Root` entry, otherwise the dictionary of the last
+    /// cross-reference stream, otherwise a new trailer referring to the last catalog found.
+    fn rebuild_xref(&self) -> Result<(Xref, Dictionary)> {
+        let buffer = self.buffer;
+        let mut xref = Xref::new(0, XrefType::CrossReferenceTable);
+        let mut trailer = None;
+
+        let mut pos = 0;
+        while pos < buffer.len() {
+            let rest = &buffer[pos..];
+            if rest.starts_with(b"obj") && rest.get(3).is_none_or(|&c| !is_regular(c)) {
+                if let Some((offset, id, generation)) = object_header_before(buffer, pos) {
+                    xref.insert(id, XrefEntry::Normal { offset, generation });
+                }
+                pos += 3;
+            } else if rest.starts_with(b"trailer") {
+                let start = rest[7..]
+                    .iter()
+                    .position(|c| !c.is_ascii_whitespace())
+                    .map_or(rest.len(), |n| n + 7);
+                if let Some(Object::Dictionary(dict)) = parser::direct_object(&rest[start..]) {
+                    if dict.has(b"Root") {
+                        trailer = Some(dict);
+                    }
+                }
+                pos += 7;
+            } else {
+                pos += 1;
+            }
+        }
+        if xref.entries.is_empty() {
+            return Err(Error::Xref(XrefError::Parse));
+        }
+
+        // Objects from the end of the file first, as later ones belong to later updates.
+        let mut offsets: Vec<_> = xref
+            .entries
+            .values()
+            .filter_map(|entry| match *entry {
+                XrefEntry::Normal { offset, .. } => Some(offset as usize),
+                _ => None,
+            })
+            .collect();
+        offsets.sort_unstable_by(|a, b| b.cmp(a));
+        let mut trailer = trailer
+            .or_else(|| {
+                offsets.iter().find_map(|&offset| {
+                    let (_, object) = self.read_object(offset, None).ok()?;
+                    if !object.as_stream().ok()?.dict.type_is(b"XRef") {
+                        return None;
+                    }
+                    parser::xref_and_trailer(&buffer[offset..], self)
+                        .ok()
+                        .map(|(_, trailer)| trailer)
+                })
+            })
+            .or_else(|| {
+                offsets.iter().find_map(|&offset| {
+                    let (id, object) = self.read_object(offset, None).ok()?;
+                    object
+                        .as_dict()
+                        .ok()?
+                        .type_is(b"Catalog")
+                        .then(|| dictionary! { "Root" => id })
+                })
+            })
+            .ok_or(Error::Trailer)?;
+
+        trailer.remove(b"Prev");
+        trailer.remove(b"XRefStm");
+        xref.size = xref.max_id() + 1;
+        trailer.set("Size", i64::from(xref.size));
+        Ok((xref, trailer))
+    }
+
     fn set_stream_content(&mut self, object_id: ObjectId) -> Result<()> {
         let length = self.get_stream_length(object_id)?;
         let stream = self
@@ -387,13 +632,79 @@ fn get_offset(&self, id: ObjectId) -> Result<u32> {
         }
     }
 
+    /// Get object by object id, reading it from the buffer.
+    ///
+    /// An object stored in an object stream is found by decoding the whole object stream,
+    /// which is not cached between calls.
+    ///
+    /// Objects already in the reader's document are returned without reading the buffer.
+    ///
+    /// Fails with [`Error::ReferenceCycle`] if the object is already being read, when the
+    /// `Length` of a stream refers back to the stream itself.
     pub fn get_object(&self, id: ObjectId) -> Result<Object> {
+        if let Some(object) = self.document.objects.get(&id) {
+            return Ok(object.clone());
+        }
+        thread_local! {
+            /// Objects being read by `get_object`, the `Length` of a stream could refer back
+            /// to the stream, directly or through other streams.
+            static READING: RefCell<Vec<ObjectId>> = const { RefCell::new(Vec::new()) };
+        }
+
+        if READING.with(|reading| reading.borrow().contains(&id)) {
+            return Err(Error::ReferenceCycle(id));
+        }
+        READING.with(|reading| reading.borrow_mut().push(id));
+        let object = self.read_object_by_id(id);
+        READING.with(|reading| reading.borrow_mut().pop());
+        object
+    }
+
+    fn read_object_by_id(&self, id: ObjectId) -> Result<Object> {
+        let Some(entry) = self.document.reference_table.get(id.0) else {
+            self.missing.lock().unwrap().push(id);
+            return Err(Error::ObjectNotFound);
+        };
+        if let XrefEntry::Compressed { container, .. } = *entry {
+            return if id.1 == 0 {
+                self.get_compressed_object(id, container)
+            } else {
+                Err(Error::ObjectNotFound)
+            };
+        }
+
         let offset = self.get_offset(id)?;
-        let (_, obj) = self.read_object(offset as usize, Some(id))?;
+        let (_, obj) = self.read_xref_object(offset as usize, id)?;
 
         Ok(obj)
     }
 
+    fn get_compressed_object(&self, id: ObjectId, container: u32) -> Result<Object> {
+        thread_local! {
+            /// Object streams being decoded by `get_object`, the stream's own `Length`
+            /// could refer to an object inside it.
+            static CONTAINERS: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+        }
+
+        if CONTAINERS.with(|containers| containers.borrow().contains(&container)) {
+            return Err(Error::ReferenceLimit);
+        }
+        CONTAINERS.with(|containers| containers.borrow_mut().push(container));
+        let object = self.get_offset((container, 0)).and_then(|offset| {
+            let (_, mut object) = self.read_xref_object(offset as usize, (container, 0))?;
+            let stream = object.as_stream_mut()?;
+            if !stream.dict.type_is(b"ObjStm") {
+                return Err(Error::Type);
+            }
+            ObjectStream::new(stream)?
+                .objects
+                .remove(&id)
+                .ok_or(Error::ObjectNotFound)
+        });
+        CONTAINERS.with(|containers| containers.borrow_mut().pop());
+        object
+    }
+
     fn read_object(&self, offset: usize, expected_id: Option<ObjectId>) -> Result<(ObjectId, Object)> {
         if offset > self.buffer.len() {
             return Err(Error::Offset(offset));
@@ -402,45 +713,96 @@ fn read_object(&self, offset: usize, expected_id: Option<ObjectId>) -> Result<(O
         parser::indirect_object(self.buffer, offset, expected_id, self)
     }
 
-    fn get_xref_start(buffer: &[u8]) -> Result<usize> {
-        let seek_pos = buffer.len() - cmp::min(buffer.len(), 512);
-        Self::search_substring(buffer, b"%%EOF", seek_pos)
-            .and_then(|eof_pos| if eof_pos > 25 { Some(eof_pos) } else { None })
-            .and_then(|eof_pos| Self::search_substring(buffer, b"startxref", eof_pos - 25))
-            .ok_or(Error::Xref(XrefError::Start))
-            .and_then(|xref_pos| {
-                if xref_pos <= buffer.len() {
-                    match parser::xref_start(&buffer[xref_pos..]) {
-                        Some(startxref) => Ok(startxref as usize),
-                        None => Err(Error::Xref(XrefError::Start)),
-                    }
-                } else {
-                    Err(Error::Xref(XrefError::Start))
-                }
+    /// Read the object `id` at its `offset` from the cross-reference table, or, if it isn't
+    /// there, within [`LoadOptions::offset_search_window`] bytes of it, the closest header first.
+    fn read_xref_object(&self, offset: usize, id: ObjectId) -> Result<(ObjectId, Object)> {
+        let err = match self.read_object(offset, Some(id)) {
+            Ok(object) => return Ok(object),
+            Err(err) => err,
+        };
+        let window = self.options.offset_search_window;
+        if window == 0 {
+            return Err(err);
+        }
+        // The `obj` keyword follows a header starting up to `window` bytes after the offset.
+        let start = offset.saturating_sub(window).min(self.buffer.len());
+        let end = offset.saturating_add(window + 24).min(self.buffer.len());
+        let mut headers: Vec<usize> = self.buffer[start..end]
+            .windows(b"obj".len())
+            .enumerate()
+            .filter(|&(_, keyword)| keyword == b"obj")
+            .filter_map(|(pos, _)| object_header_before(self.buffer, start + pos))
+            .filter(|&(_, number, generation)| (number, generation) == id)
+            .map(|(header, ..)| header as usize)
+            .filter(|&header| header != offset && header.abs_diff(offset) <= window)
+            .collect();
+        headers.sort_by_key(|header| header.abs_diff(offset));
+        headers
+            .into_iter()
+            .find_map(|header| {
+                let object = self.read_object(header, Some(id)).ok()?;
+                warn!(
+                    "Object {} {} found at offset {} instead of {}",
+                    id.0, id.1, header, offset
+                );
+                Some(object)
             })
+            .ok_or(err)
     }
 
-    fn search_substring(buffer: &[u8], pattern: &[u8], start_pos: usize) -> Option<usize> {
-        let mut seek_pos = start_pos;
-        let mut index = 0;
+    /// Find the `%PDF-` header in the first [`HEADER_SEARCH_LIMIT`] bytes of the file, which
+    /// some files have garbage, such as a byte order mark, in front of.
+    pub(crate) fn find_header(buffer: &[u8]) -> Option<usize> {
+        let head = &buffer[..buffer.len().min(HEADER_SEARCH_LIMIT + b"%PDF-".len())];
+        head.windows(b"%PDF-".len()).position(|window| window == b"%PDF-")
+    }
 
-        while seek_pos < buffer.len() && index < pattern.len() {
-            if buffer[seek_pos] == pattern[index] {
-                index += 1;
-            } else if index > 0 {
-                seek_pos -= index;
-                index = 0;
-            }
-            seek_pos += 1;
+    /// Get the offset of the last cross-reference section from the last `startxref` keyword in
+    /// the buffer, searching backward from its end past any garbage after `%%EOF`.
+    pub(crate) fn get_xref_start(buffer: &[u8]) -> Result<usize> {
+        buffer
+            .windows(b"startxref".len())
+            .rposition(|window| window == b"startxref")
+            .and_then(|pos| parser::xref_start(&buffer[pos..]))
+            .and_then(|start| usize::try_from(start).ok())
+            .ok_or(Error::Xref(XrefError::Start))
+    }
+}
 
-            if index == pattern.len() {
-                let res = seek_pos - index;
-                return Self::search_substring(buffer, pattern, res + 1).or(Some(res));
-            }
-        }
+/// Whether the byte is a regular character, neither whitespace nor a delimiter.
+fn is_regular(c: u8) -> bool {
+    !c.is_ascii_whitespace() && c != 0 && !b"()<>[]{}/%".contains(&c)
+}
 
-        None
+/// Find the `N G` in front of the `obj` keyword at `obj_pos`, returning its offset,
+/// the object number and the generation number.
+fn object_header_before(buffer: &[u8], obj_pos: usize) -> Option<(u32, u32, u16)> {
+    let skip_back = |end: usize, predicate: fn(&u8) -> bool| {
+        buffer[..end]
+            .iter()
+            .rposition(|c| !predicate(c))
+            .map_or(0, |pos| pos + 1)
+    };
+    let generation_end = skip_back(obj_pos, u8::is_ascii_whitespace);
+    let generation_start = skip_back(generation_end, u8::is_ascii_digit);
+    let id_end = skip_back(generation_start, u8::is_ascii_whitespace);
+    let id_start = skip_back(id_end, u8::is_ascii_digit);
+    if generation_end == obj_pos
+        || generation_start == generation_end
+        || id_end == generation_start
+        || id_start == id_end
+        || (id_start > 0 && is_regular(buffer[id_start - 1]))
+    {
+        return None;
     }
+
+    // Both numbers consist of ASCII digits only.
+    let id = str::from_utf8(&buffer[id_start..id_end]).ok()?.parse().ok()?;
+    let generation = str::from_utf8(&buffer[generation_start..generation_end])
+        .ok()?
+        .parse()
+        .ok()?;
+    Some((id_start.try_into().ok()?, id, generation))
 }
 
 #[cfg(all(test, not(feature = "async")))]
@@ -467,6 +829,18 @@ async fn load_document() {
     doc.save(file_path).unwrap();
 }
 
+#[cfg(all(test, not(feature = "async")))]
+#[test]
+fn load_from_cursor() {
+    use std::io::Cursor;
+
+    let mut buffer = Vec::new();
+    crate::creator::tests::create_document().save_to(&mut buffer).unwrap();
+    let doc = Document::load_from(Cursor::new(buffer.clone())).unwrap();
+    assert_eq!(doc.get_pages().len(), 1);
+    assert_eq!(doc.objects.len(), Document::load_mem(&buffer).unwrap().objects.len());
+}
+
 #[test]
 #[should_panic(expected = "Xref(Start)")]
 fn load_short_document() {
@@ -475,8 +849,7 @@ fn load_short_document() {
 
 #[test]
 fn load_many_shallow_brackets() {
-    let content: String = std::iter::repeat("()")
-        .take(MAX_BRACKET * 10)
+    let content: String = std::iter::repeat_n("()", MAX_BRACKET * 10)
         .flat_map(|x| x.chars())
         .collect();
     const STREAM_CRUFT: usize = 33;
@@ -521,9 +894,8 @@ fn load_many_shallow_brackets() {
 
 #[test]
 fn load_too_deep_brackets() {
-    let content: Vec<u8> = std::iter::repeat(b'(')
-        .take(MAX_BRACKET + 1)
-        .chain(std::iter::repeat(b')').take(MAX_BRACKET + 1))
+    let content: Vec<u8> = std::iter::repeat_n(b'(', MAX_BRACKET + 1)
+        .chain(std::iter::repeat_n(b')', MAX_BRACKET + 1))
         .collect();
     let content = String::from_utf8(content).unwrap();
     const STREAM_CRUFT: usize = 33;
@@ -572,3 +944,663 @@ fn load_too_deep_brackets() {
     let pages = doc.get_pages().keys().cloned().collect::<Vec<_>>();
     assert_eq!("Hello World!\n", doc.extract_text(&pages).unwrap());
 }
+
+#[cfg(test)]
+pub(crate) fn build_document(objects: &[&[u8]], trailer: &str) -> Vec<u8> {
+    let mut doc = b"%PDF-1.5\n".to_vec();
+    let mut offsets = vec![];
+    for object in objects {
+        offsets.push(doc.len());
+        doc.extend_from_slice(object);
+        doc.push(b'\n');
+    }
+    let xref_start = doc.len();
+    doc.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        doc.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    doc.extend_from_slice(format!("trailer\n{}\nstartxref\n{}\n%%EOF", trailer, xref_start).as_bytes());
+    doc
+}
+
+#[test]
+fn load_encrypted_document() {
+    let encrypt = concat!(
+        "4 0 obj<</Filter/Standard/V 4/R 4/Length 128/P -1028",
+        "/CF<</StdCF<</CFM/AESV2/AuthEvent/DocOpen/Length 16>>>>/StmF/StdCF/StrF/StdCF",
+        "/O<5588040988E54A8AE991AC9D80AAC232A494D494B4BCC37A4DCC6D07206C9192>",
+        "/U<D33A28EB05426784E4F6333FFBEAF06800000000000000000000000000000000>>>endobj"
+    );
+    let content = b"6 0 obj<</Length 48>>stream\n\
+        \x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F\xC1\x3E\x1A\x7F\x67\x1A\xAD\xE7\
+        \x0A\x0D\x99\x6A\x40\x48\xA2\x3C\x15\xE7\x9A\x3F\x50\x70\x87\x78\x13\x1C\xB7\x21\x53\x83\x36\x65\
+        \nendstream\nendobj";
+    let buffer = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1/MediaBox[0 0 595 842]>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Contents 6 0 R>>endobj",
+            encrypt.as_bytes(),
+            b"5 0 obj<</Title<000102030405060708090A0B0C0D0E0F853EB9302237FD8CF077F24D8D6B8562>>>endobj",
+            content,
+        ],
+        concat!(
+            "<</Root 1 0 R/Info 5 0 R/Encrypt 4 0 R/Size 7",
+            "/ID[<101112131415161718191A1B1C1D1E1F><101112131415161718191A1B1C1D1E1F>]>>"
+        ),
+    );
+
+    assert!(Document::load_mem(&buffer).unwrap().is_encrypted());
+    assert!(Document::load_mem_encrypted(&buffer, "wrong").is_err());
+    for password in ["", "secret"] {
+        let doc = Document::load_mem_encrypted(&buffer, password).unwrap();
+        assert!(!doc.is_encrypted());
+        let info = doc.get_dictionary((5, 0)).unwrap();
+        assert_eq!(info.get(b"Title").and_then(Object::as_str).unwrap(), b"Hello");
+        assert_eq!(doc.get_page_content((3, 0)).unwrap(), b"BT /F1 12 Tf (Secret) Tj ET");
+    }
+}
+
+#[cfg(feature = "encrypt")]
+#[test]
+fn decrypt_permissions_without_user_password() {
+    let mut doc = Document::with_version("1.5");
+    let content_id = doc.add_object(crate::Stream::new(dictionary! {}, b"BT (Text) Tj ET".to_vec()));
+    let root = doc.add_object(dictionary! { "Type" => "Catalog", "Content" => content_id });
+    doc.trailer.set("Root", root);
+
+    let mut restricted = Vec::new();
+    let options = crate::EncryptOptions {
+        owner_password: "owner".to_string(),
+        permissions: -3904,
+        ..Default::default()
+    };
+    doc.save_encrypted_to(&mut restricted, options.clone()).unwrap();
+    let mut loaded = Document::load_mem(&restricted).unwrap();
+    loaded.decrypt_permissions().unwrap();
+    assert!(!loaded.is_encrypted());
+    assert_eq!(
+        loaded.get_object(content_id).unwrap(),
+        doc.get_object(content_id).unwrap()
+    );
+    assert!(loaded.decrypt_permissions().is_ok());
+
+    let mut protected = Vec::new();
+    let options = crate::EncryptOptions {
+        user_password: "user".to_string(),
+        ..options
+    };
+    doc.save_encrypted_to(&mut protected, options).unwrap();
+    let mut loaded = Document::load_mem(&protected).unwrap();
+    assert!(matches!(
+        loaded.decrypt_permissions(),
+        Err(Error::Decryption(crate::encryption::DecryptionError::PasswordRequired))
+    ));
+    assert!(loaded.is_encrypted());
+}
+
+#[test]
+fn load_forward_length_reference() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1/MediaBox[0 0 595 842]>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Contents 4 0 R>>endobj",
+            b"4 0 obj<</Length 5 0 R>>stream\nBT ET endstream\nendstream\nendobj",
+            b"5 0 obj 16 endobj",
+        ],
+        "<</Root 1 0 R/Size 6>>",
+    );
+
+    let doc = Document::load_mem(&doc).unwrap();
+    let stream = doc.get_object((4, 0)).and_then(Object::as_stream).unwrap();
+    assert_eq!(stream.content, b"BT ET endstream\n");
+    assert_eq!(stream.recovered_length, None);
+}
+
+#[test]
+fn load_with_junk_around_file() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    // A byte order mark and a line break in front of the header, garbage after `%%EOF`.
+    let file = [b"\xEF\xBB\xBF\r\n", doc.as_slice(), b"\r\n\x00\x00garbage"].concat();
+
+    let loaded = Document::load_mem(&file).unwrap();
+    assert_eq!(loaded.header_offset, 5);
+    assert!(!loaded.xref_rebuilt);
+    assert_eq!(loaded.get_pages().len(), 1);
+
+    let mut lazy = crate::LazyDocument::new(std::io::Cursor::new(&file)).unwrap();
+    assert_eq!(lazy.get_object((3, 0)).unwrap(), loaded.get_object((3, 0)).unwrap());
+
+    let incremental: IncrementalDocument = file.as_slice().try_into().unwrap();
+    assert_eq!(incremental.get_prev_documents_bytes(), file.as_slice());
+
+    let file = [vec![b' '; HEADER_SEARCH_LIMIT + 1], doc].concat();
+    assert!(matches!(Document::load_mem(&file), Err(Error::Header)));
+}
+
+#[test]
+fn report_raw_file_offsets() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    let file = [b"junk\n", doc.as_slice()].concat();
+
+    let loaded = Document::load_mem(&file).unwrap();
+    for id in 1..=3 {
+        let offset = loaded.object_offset((id, 0)).unwrap();
+        assert!(file[offset..].starts_with(format!("{} 0 obj", id).as_bytes()));
+    }
+    assert_eq!(loaded.object_offset((3, 1)), None);
+    assert_eq!(loaded.object_offset((4, 0)), None);
+    let startxref = loaded.startxref_offset().unwrap();
+    assert!(file[startxref..].starts_with(b"xref"));
+    assert_eq!(startxref, file.windows(6).rposition(|w| w == b"\nxref\n").unwrap() + 1);
+
+    assert_eq!(Document::new().startxref_offset(), None);
+}
+
+#[test]
+fn load_last_startxref() {
+    let mut file = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    let first_xref = file.windows(7).position(|w| w == b"\nxref\n0").unwrap() + 1;
+    // An incremental update replacing the page, followed by garbage.
+    file.extend_from_slice(b"\n");
+    let page_offset = file.len();
+    file.extend_from_slice(b"3 0 obj<</Type/Page/Parent 2 0 R/Rotate 90>>endobj\n");
+    let xref = file.len();
+    file.extend_from_slice(
+        format!(
+            "xref\n3 1\n{:010} 00000 n \ntrailer\n<</Root 1 0 R/Size 4/Prev {}>>\nstartxref\n{}\n%%EOF\n",
+            page_offset, first_xref, xref
+        )
+        .as_bytes(),
+    );
+    file.extend_from_slice(&[b'\0'; 600]);
+
+    let doc = Document::load_mem(&file).unwrap();
+    assert_eq!(doc.xref_start, xref);
+    assert!(!doc.xref_rebuilt);
+    assert_eq!(doc.get_page_rotation((3, 0)).unwrap(), 90);
+
+    // Without `startxref` the cross-reference table is rebuilt.
+    let pos = file.windows(9).rposition(|w| w == b"startxref").unwrap();
+    file[pos..pos + 9].copy_from_slice(b"startxrex");
+    let pos = file.windows(9).rposition(|w| w == b"startxref").unwrap();
+    file[pos..pos + 9].copy_from_slice(b"startxrex");
+    let doc = Document::load_mem(&file).unwrap();
+    assert!(doc.xref_rebuilt);
+    assert_eq!(doc.get_page_rotation((3, 0)).unwrap(), 90);
+}
+
+#[test]
+fn iterate_objects_in_file_order() {
+    let objects: [(u32, &[u8]); 3] = [
+        (3, b"3 0 obj<</Type/Page/Parent 2 0 R>>endobj"),
+        (1, b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj"),
+        (2, b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj"),
+    ];
+    let mut buffer = b"%PDF-1.5\n".to_vec();
+    let mut offsets = [0; 4];
+    for (number, object) in objects {
+        offsets[number as usize] = buffer.len();
+        buffer.extend_from_slice(object);
+        buffer.push(b'\n');
+    }
+    let xref_start = buffer.len();
+    buffer.extend_from_slice(b"xref\n0 4\n0000000000 65535 f \n");
+    for offset in &offsets[1..] {
+        buffer.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    buffer.extend_from_slice(format!("trailer\n<</Root 1 0 R/Size 4>>\nstartxref\n{}\n%%EOF", xref_start).as_bytes());
+
+    let mut doc = Document::load_mem(&buffer).unwrap();
+    let added = doc.add_object(Object::Null);
+    let ids: Vec<ObjectId> = doc.iter_objects_by_id().map(|(id, _)| id).collect();
+    assert_eq!(ids, [(1, 0), (2, 0), (3, 0), added]);
+    let ids: Vec<ObjectId> = doc.iter_objects_by_offset().map(|(id, _)| id).collect();
+    assert_eq!(ids, [(3, 0), (1, 0), (2, 0), added]);
+    let (_, catalog) = doc.iter_objects_by_offset().nth(1).unwrap();
+    assert!(catalog.as_dict().unwrap().type_is(b"Catalog"));
+}
+
+#[test]
+fn load_shifted_offsets() {
+    let mut doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Rotate 90>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    // Shift the offset of object 2 forward by 3 bytes, and of object 3 backward by 3 bytes.
+    let xref = doc.windows(5).position(|w| w == b"\nxref").unwrap();
+    let entries = xref + b"\nxref\n0 4\n0000000000 65535 f \n".len();
+    for (entry, shift) in [(1, 3), (2, -3)] {
+        let pos = entries + 20 * entry;
+        let offset: i64 = str::from_utf8(&doc[pos..pos + 10]).unwrap().parse().unwrap();
+        doc[pos..pos + 10].copy_from_slice(format!("{:010}", offset + shift).as_bytes());
+    }
+
+    let loaded = Document::load_mem(&doc).unwrap();
+    assert!(!loaded.xref_rebuilt);
+    assert_eq!(loaded.get_pages().len(), 1);
+    assert_eq!(loaded.get_page_rotation((3, 0)).unwrap(), 90);
+
+    let mut reader = Reader::new(&doc);
+    reader.document.reference_table = loaded.reference_table.clone();
+    assert!(reader.get_object((2, 0)).unwrap().as_dict().unwrap().type_is(b"Pages"));
+    reader.options.offset_search_window = 2;
+    assert!(reader.get_object((2, 0)).is_err());
+    assert!(reader.get_object((1, 0)).is_ok());
+
+    let options = LoadOptions {
+        offset_search_window: 0,
+        ..LoadOptions::default()
+    };
+    let loaded = Document::load_mem_with_options(&doc, options).unwrap();
+    assert!(loaded.get_object((2, 0)).is_err());
+    assert!(loaded.get_object((1, 0)).is_ok());
+}
+
+#[test]
+fn read_corrupted_object() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj <</Type/Pages/Kids[3 0 R)/Count 1>> endobj",
+            b"3 0 obk<</Type/Page/Parent 2 0 R>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    let reader = Reader::new(&doc);
+
+    // Errors in the object itself are reported at its start, after the object header.
+    let offset = doc.windows(7).position(|w| w == b"2 0 obj").unwrap();
+    let err = reader.read_object(offset, None).unwrap_err();
+    assert!(matches!(err, Error::Parse { offset: at, id: Some((2, 0)), .. } if at == offset + 8));
+    let message = format!(
+        "Invalid object 2 0 at byte {} near 322030206f626a20 3c3c2f547970652f",
+        offset + 8
+    );
+    assert_eq!(err.to_string(), message);
+
+    // Errors in the object header are reported at the offset of the object, without an id.
+    let offset = doc.windows(7).position(|w| w == b"3 0 obk").unwrap();
+    let err = reader.read_object(offset, None).unwrap_err();
+    let context = [&doc[offset - 8..offset], b"3 0 obk<"].concat();
+    assert!(matches!(err, Error::Parse { offset: at, id: None, context: ref c } if at == offset && *c == context));
+}
+
+#[test]
+fn read_object_id_out_of_range() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[99999999999 0 R]/Count 1>>endobj",
+            b"3 70000 obj<</Type/Page/Parent 2 0 R>>endobj",
+        ],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    let reader = Reader::new(&doc);
+
+    let object_offset = doc.windows(7).position(|w| w == b"2 0 obj").unwrap();
+    let offset = doc.windows(11).position(|w| w == b"99999999999").unwrap();
+    let err = reader.read_object(object_offset, None).unwrap_err();
+    assert!(matches!(err, Error::ObjectIdRange { offset: at, id: Some((2, 0)) } if at == offset));
+    let message = format!(
+        "Object or generation number out of range at byte {} in object 2 0",
+        offset
+    );
+    assert_eq!(err.to_string(), message);
+
+    let offset = doc.windows(11).position(|w| w == b"3 70000 obj").unwrap();
+    let err = reader.read_object(offset, None).unwrap_err();
+    assert!(matches!(err, Error::ObjectIdRange { offset: at, id: None } if at == offset));
+    assert_eq!(parser::direct_object(b"[99999999999 0 R]"), None);
+}
+
+#[test]
+fn read_deeply_nested_object() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let object = |depth: usize| format!("1 0 obj<</Kids{}>>endobj", nested(depth)).into_bytes();
+    let mut reader = Reader::new(b"");
+
+    // The dictionary counts as one level of nesting.
+    let buffer = object(MAX_NESTING_DEPTH - 1);
+    reader.buffer = &buffer;
+    assert!(reader.read_object(0, None).is_ok());
+    let buffer = object(10_000);
+    reader.buffer = &buffer;
+    let err = reader.read_object(0, None).unwrap_err();
+    assert!(matches!(err, Error::Parse { offset: 7, .. }));
+
+    reader.options.max_nesting_depth = 8;
+    let buffer = object(7);
+    reader.buffer = &buffer;
+    assert!(reader.read_object(0, None).is_ok());
+    let buffer = object(8);
+    reader.buffer = &buffer;
+    assert!(reader.read_object(0, None).is_err());
+    let section = format!("xref\n0 1\n0000000000 65535 f \ntrailer\n<</Size 1/ID{}>>\n", nested(7));
+    assert!(parser::xref_and_trailer(section.as_bytes(), &reader).is_ok());
+    let section = format!("xref\n0 1\n0000000000 65535 f \ntrailer\n<</Size 1/ID{}>>\n", nested(8));
+    assert!(parser::xref_and_trailer(section.as_bytes(), &reader).is_err());
+
+    assert!(parser::direct_object(nested(10_000).as_bytes()).is_none());
+    let content = format!("{} 0 0 Td", nested(10_000));
+    let content = crate::content::Content::decode(content.as_bytes());
+    assert!(content.map_or(true, |content| content.operations.is_empty()));
+}
+
+#[test]
+fn read_object_with_unavailable_length_reference() {
+    let doc = b"%PDF-1.5\n1 0 obj<</Length 2 0 R>>stream\nBT ET\nendstream\nendobj\n";
+    let reader = Reader::new(doc);
+
+    let (_, object) = reader.read_object(9, None).unwrap();
+    let stream = object.as_stream().unwrap();
+    assert_eq!(stream.content, b"BT ET");
+    assert_eq!(stream.recovered_length, Some(5));
+}
+
+#[test]
+fn load_self_referencing_length() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Contents[4 0 R 5 0 R]>>endobj",
+            // A stream whose Length is itself, and two streams whose Lengths refer to each other.
+            b"4 0 obj<</Length 4 0 R>>stream\nBT ET\nendstream\nendobj",
+            b"5 0 obj<</Length 6 0 R>>stream\nBT\nendstream\nendobj",
+            b"6 0 obj<</Length 5 0 R>>stream\nET\nendstream\nendobj",
+        ],
+        "<</Root 1 0 R/Size 7>>",
+    );
+    let doc = Document::load_mem(&doc).unwrap();
+    assert_eq!(doc.get_page_content((3, 0)).unwrap(), b"BT ET\nBT");
+    let stream = doc.get_object((6, 0)).and_then(Object::as_stream).unwrap();
+    assert_eq!(
+        (stream.content.as_slice(), stream.recovered_length),
+        (b"ET".as_slice(), Some(2))
+    );
+}
+
+#[test]
+fn get_object_from_object_stream() {
+    use crate::xref::{Xref, XrefType};
+
+    let objects = b"2 0 3 3 ";
+    let data = [&objects[..], b"42 (text)"].concat();
+    let mut buffer = format!(
+        "%PDF-1.5\n1 0 obj<</Type/ObjStm/N 2/First {}/Length {}>>stream\n",
+        objects.len(),
+        data.len()
+    )
+    .into_bytes();
+    buffer.extend_from_slice(&data);
+    buffer.extend_from_slice(b"\nendstream\nendobj\n");
+    let stream_offset = buffer.len();
+    buffer.extend_from_slice(b"4 0 obj<</Length 2 0 R>>stream\n");
+    buffer.extend_from_slice(b"abcdefghijklmnopqrstuvwxyz0123456789ABCDEF\nendstream\nendobj\n");
+
+    let mut xref = Xref::new(5, XrefType::CrossReferenceStream);
+    xref.insert(
+        1,
+        XrefEntry::Normal {
+            offset: 9,
+            generation: 0,
+        },
+    );
+    xref.insert(2, XrefEntry::Compressed { container: 1, index: 0 });
+    xref.insert(3, XrefEntry::Compressed { container: 1, index: 1 });
+    xref.insert(
+        4,
+        XrefEntry::Normal {
+            offset: stream_offset as u32,
+            generation: 0,
+        },
+    );
+    let mut reader = Reader::new(&buffer);
+    reader.document.reference_table = xref;
+
+    assert_eq!(reader.get_object((2, 0)).unwrap(), Object::Integer(42));
+    assert_eq!(reader.get_object((3, 0)).unwrap(), Object::string_literal("text"));
+    assert!(matches!(reader.get_object((2, 1)), Err(Error::ObjectNotFound)));
+
+    let stream = reader.get_object((4, 0)).unwrap();
+    let stream = stream.as_stream().unwrap();
+    assert_eq!(stream.content, b"abcdefghijklmnopqrstuvwxyz0123456789ABCDEF");
+    assert_eq!(stream.recovered_length, None);
+}
+
+#[test]
+fn get_object_from_object_stream_with_length_inside() {
+    use crate::xref::{Xref, XrefType};
+
+    let buffer = b"%PDF-1.5\n1 0 obj<</Type/ObjStm/N 1/First 4/Length 2 0 R>>stream\n2 0 9\nendstream\nendobj\n";
+    let mut xref = Xref::new(3, XrefType::CrossReferenceStream);
+    xref.insert(
+        1,
+        XrefEntry::Normal {
+            offset: 9,
+            generation: 0,
+        },
+    );
+    xref.insert(2, XrefEntry::Compressed { container: 1, index: 0 });
+    let mut reader = Reader::new(buffer);
+    reader.document.reference_table = xref;
+
+    // The stream's length can't be read from the stream itself, it is recovered instead.
+    assert_eq!(reader.get_object((2, 0)).unwrap(), Object::Integer(9));
+}
+
+#[test]
+fn load_incremental_update() {
+    let mut doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1/MediaBox[0 0 595 842]>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Resources 4 0 R>>endobj",
+            b"4 0 obj<</Font<<>>>>endobj",
+        ],
+        "<</Root 1 0 R/Size 5>>",
+    );
+    let base_xref_start = doc.windows(6).position(|window| window == b"\nxref\n").unwrap() + 1;
+
+    // The update replaces object 4 in its xref table and adds object 5 in its xref stream.
+    doc.push(b'\n');
+    let object_4 = doc.len();
+    doc.extend_from_slice(b"4 0 obj<</Font<</F1 5 0 R>>>>endobj\n");
+    let object_5 = doc.len();
+    doc.extend_from_slice(b"5 0 obj<</Type/Font/Subtype/Type1/BaseFont/Courier>>endobj\n");
+    let xref_stream = doc.len();
+    doc.extend_from_slice(b"6 0 obj<</Type/XRef/Size 7/W[1 2 1]/Index[5 1]/Length 4>>stream\n\x01");
+    doc.extend_from_slice(&(object_5 as u16).to_be_bytes());
+    doc.extend_from_slice(b"\x00\nendstream\nendobj\n");
+    let xref_start = doc.len();
+    doc.extend_from_slice(
+        format!(
+            "xref\n4 1\n{:010} 00000 n \n6 1\n{:010} 00000 n \n\
+             trailer\n<</Root 1 0 R/Size 7/Prev {}/XRefStm {}>>\nstartxref\n{}\n%%EOF",
+            object_4, xref_stream, base_xref_start, xref_stream, xref_start
+        )
+        .as_bytes(),
+    );
+
+    let doc = Document::load_mem(&doc).unwrap();
+    assert!(!doc.trailer.has(b"Prev"));
+    assert!(!doc.trailer.has(b"XRefStm"));
+    let resources = doc.get_dictionary((4, 0)).unwrap();
+    assert_eq!(resources.get(b"Font").and_then(Object::as_dict).unwrap().len(), 1);
+    assert!(doc.get_dictionary((5, 0)).unwrap().type_is(b"Font"));
+    assert!(doc.get_dictionary((3, 0)).is_ok());
+}
+
+#[test]
+fn load_cyclic_prev_chain() {
+    let doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[]/Count 0>>endobj",
+        ],
+        "<</Root 1 0 R/Size 3/Prev 96>>",
+    );
+    assert_eq!(doc.windows(6).position(|window| window == b"\nxref\n"), Some(95));
+
+    let doc = Document::load_mem(&doc).unwrap();
+    assert_eq!(doc.objects.len(), 2);
+}
+
+#[test]
+fn load_with_broken_xref() {
+    let mut doc = build_document(
+        &[
+            b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+            b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1/MediaBox[0 0 595 842]>>endobj",
+            b"3 0 obj<</Type/Page/Parent 2 0 R/Contents 4 0 R/Resources<</Font<</F1 5 0 R>>>>>>endobj",
+            b"4 0 obj<</Length 44>>stream\nBT /F1 48 Tf 100 600 Td (Hello World!) Tj ET\nendstream\nendobj",
+            b"5 0 obj<</Type/Font/Subtype/Type1/BaseFont/Courier>>endobj",
+        ],
+        "<</Root 1 0 R/Size 6>>",
+    );
+    let startxref = doc.windows(10).rposition(|window| window == b"startxref\n").unwrap();
+    doc.splice(startxref + 10.., b"12\n%%EOF".iter().copied());
+
+    let doc = Document::load_mem(&doc).unwrap();
+    assert!(doc.xref_rebuilt);
+    assert_eq!(doc.objects.len(), 5);
+    assert_eq!(doc.trailer.get(b"Root").and_then(Object::as_reference).unwrap(), (1, 0));
+    assert_eq!(doc.extract_text(&[1]).unwrap(), "Hello World!\n");
+}
+
+#[test]
+fn load_without_xref_and_trailer() {
+    let doc = b"%PDF-1.4
+1 0 obj<</Type/Pages/Kids[2 0 R]/Count 1>>endobj
+2 0 obj<</Type/Page/Parent 1 0 R>>endobj
+3 0 obj<</Type/Catalog/Pages 1 0 R>>endobj
+2 0 obj<</Type/Page/Parent 1 0 R/Rotate 90>>endobj
+%%EOF";
+    let doc = Document::load_mem(doc).unwrap();
+    assert!(doc.xref_rebuilt);
+    assert_eq!(doc.trailer.get(b"Root").and_then(Object::as_reference).unwrap(), (3, 0));
+    assert_eq!(doc.trailer.get(b"Size").and_then(Object::as_i64).unwrap(), 4);
+    // The later definition of an object replaces the earlier one.
+    assert!(doc.get_dictionary((2, 0)).unwrap().has(b"Rotate"));
+    assert_eq!(doc.get_pages().len(), 1);
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn load_assets_with_rebuilt_xref() {
+    for path in [
+        "assets/example.pdf",
+        "assets/AnnotationDemo.pdf",
+        "assets/Incremental.pdf",
+    ] {
+        let buffer = std::fs::read(path).unwrap();
+        let doc = Document::load_mem(&buffer).unwrap();
+        assert!(!doc.xref_rebuilt);
+
+        let startxref = buffer.windows(9).rposition(|window| window == b"startxref").unwrap();
+        let broken = [&buffer[..startxref], b"startxref\n999999999\n%%EOF"].concat();
+        let rebuilt = Document::load_mem(&broken).unwrap();
+        assert!(rebuilt.xref_rebuilt, "{}", path);
+        assert_eq!(
+            rebuilt.trailer.get(b"Root").ok(),
+            doc.trailer.get(b"Root").ok(),
+            "{}",
+            path
+        );
+        assert_eq!(rebuilt.get_pages(), doc.get_pages(), "{}", path);
+        for (id, object) in &doc.objects {
+            assert_eq!(rebuilt.objects.get(id), Some(object), "{} {:?}", path, id);
+        }
+    }
+}
+
+#[test]
+fn read_lenient_objects() {
+    // The objects, how they are read, and whether they are accepted by the strict parsing.
+    let corpus: &[(&[u8], &str, bool)] = &[
+        (b"1 0 obj<</A 1>>endobj", "<</A 1>>", true),
+        (b"1 0 obj\r<</A 1>>\rendobj", "<</A 1>>", true),
+        (b"1  0  obj <</A 1>> endobj", "<</A 1>>", true),
+        (b"1 0 obj\n%comment\n<</A 1>>\nendobj", "<</A 1>>", true),
+        (b"1\n0\nobj\n42\nendobj", "42", true),
+        (b"1 0 obj\x0042\x0cendobj", "42", true),
+        (b"1 0obj 42 endobj", "42", true),
+        (b"1 0 obj 42endobj", "42", true),
+        (b"1 0 obj 3 0 Rendobj", "3 0 R", true),
+        (b"1 0 obj[1 2]endobj", "[1 2]", true),
+        (
+            b"1 0 obj<</Length 2>>stream\nab\nendstreamendobj",
+            "<</Length 2>>stream...endstream",
+            true,
+        ),
+        // Accepted by the lenient parsing only.
+        (b" \r\n1 0 obj 42 endobj", "42", false),
+        (b"1 0 obj 42 end obj", "42", false),
+        (b"1 0 obj 42", "42", false),
+        (b"1 0 obj /Nameendobj", "/Name", false),
+        (b"1 0 obj endobj", "null", false),
+        (b"1 0 obj", "null", false),
+    ];
+    for &(data, expected, strict) in corpus {
+        let reader = Reader::new(data);
+        let (id, object) = reader.read_object(0, Some((1, 0))).unwrap();
+        assert_eq!((id, format!("{:?}", object)), ((1, 0), expected.to_string()));
+        let result = parser::indirect_object_strict(data, 0, Some((1, 0)), &reader);
+        assert_eq!(result.is_ok(), strict, "{}", String::from_utf8_lossy(data));
+    }
+}
+
+#[test]
+fn load_strict() {
+    let objects: [&[u8]; 3] = [
+        b"1 0 obj<</Type/Catalog/Pages 2 0 R>>endobj",
+        b"2 0 obj<</Type/Pages/Kids[3 0 R]/Count 1>>endobj",
+        b"3 0 obj<</Type/Page/Parent 2 0 R>>endobj",
+    ];
+    let doc = build_document(&objects, "<</Root 1 0 R/Size 4>>");
+    assert_eq!(Document::load_mem_strict(&doc).unwrap().get_pages().len(), 1);
+    let file = [b"junk\n", doc.as_slice()].concat();
+    assert_eq!(Document::load_mem_strict(&file).unwrap().get_pages().len(), 1);
+
+    let doc = build_document(
+        &[objects[0], objects[1], b"3 0 obj<</Type/Page/Parent 2 0 R>>"],
+        "<</Root 1 0 R/Size 4>>",
+    );
+    assert_eq!(Document::load_mem(&doc).unwrap().get_pages().len(), 1);
+    let offset = doc.windows(7).position(|w| w == b"3 0 obj").unwrap();
+    let err = Document::load_mem_strict(&doc).unwrap_err();
+    assert!(matches!(err, Error::Parse { offset: at, id: Some((3, 0)), .. } if at == offset + 7));
+
+    // An offset past the end of the file is an error, not a panic.
+    let mut doc = build_document(&objects, "<</Root 1 0 R/Size 4>>");
+    let pos = doc.windows(19).rposition(|w| w.ends_with(b" 00000 n ")).unwrap();
+    doc[pos..pos + 10].copy_from_slice(b"0099999999");
+    assert!(matches!(Document::load_mem_strict(&doc), Err(Error::Offset(99999999))));
+}
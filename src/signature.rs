@@ -0,0 +1,305 @@
+#![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+
+//! Placeholders for digital signatures, filled in by an external signer.
+
+use std::io::Write;
+use std::ops::Range;
+
+use crate::{Document, Error, IncrementalDocument, Object, ObjectId, PdfDate, Result, StringFormat};
+
+/// Value written for each number of the `ByteRange` placeholder, wide enough for any offset
+/// below 10 GB. The actual values are written over it, padded with spaces.
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+
+/// Options for [`Document::save_signature_placeholder`].
+#[derive(Debug, Clone)]
+pub struct SignatureOptions {
+    /// Partial name `T` of the signature field, which should not be the name of another field.
+    pub field_name: String,
+    /// Page the widget annotation of the field is put on. When not set, the first page is used.
+    pub page: Option<ObjectId>,
+    /// Location of the widget annotation as `[llx, lly, urx, ury]`. The default empty rectangle
+    /// makes the signature invisible.
+    pub rect: [f32; 4],
+    /// Number of bytes reserved in `Contents` for the encoded signature. Default 8192.
+    pub contents_size: usize,
+    /// Encoding of the signature, the `SubFilter` of the signature dictionary. Default
+    /// `adbe.pkcs7.detached`, use `ETSI.CAdES.detached` for PAdES signatures.
+    pub sub_filter: String,
+    /// Name of the signer `Name`.
+    pub name: Option<String>,
+    /// Reason for signing `Reason`.
+    pub reason: Option<String>,
+    /// Location of the signing `Location`.
+    pub location: Option<String>,
+    /// Time of signing `M`.
+    pub signing_time: Option<PdfDate>,
+}
+
+impl Default for SignatureOptions {
+    fn default() -> Self {
+        SignatureOptions {
+            field_name: "Signature1".to_string(),
+            page: None,
+            rect: [0.0; 4],
+            contents_size: 8192,
+            sub_filter: "adbe.pkcs7.detached".to_string(),
+            name: None,
+            reason: None,
+            location: None,
+            signing_time: None,
+        }
+    }
+}
+
+/// Location of the signature placeholder in a file written by
+/// [`Document::save_signature_placeholder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignaturePlaceholder {
+    /// The `ByteRange` written in the signature dictionary, `[0, start, end, length]`: the
+    /// signature covers the whole file except the `Contents` string.
+    pub byte_range: [usize; 4],
+    /// Bytes of the hexadecimal `Contents` string in the file, angle brackets included.
+    pub contents: Range<usize>,
+}
+
+impl SignaturePlaceholder {
+    /// Get the bytes of `file` covered by the `ByteRange`, which the signature is computed over.
+    pub fn signed_data(&self, file: &[u8]) -> Result<Vec<u8>> {
+        let [_, start, end, length] = self.byte_range;
+        let before = file.get(..start).ok_or(Error::Offset(start))?;
+        let after = file.get(end..end + length).ok_or(Error::Offset(end + length))?;
+        Ok([before, after].concat())
+    }
+
+    /// Write `signature`, such as a DER-encoded CMS signature, into the `Contents` string of
+    /// `file` as hexadecimal, padded with zeros.
+    pub fn embed_signature(&self, file: &mut [u8], signature: &[u8]) -> Result<()> {
+        let capacity = (self.contents.len() - 2) / 2;
+        if signature.len() > capacity {
+            return Err(Error::Invalid(format!(
+                "Signature of {} bytes does not fit in a placeholder of {} bytes",
+                signature.len(),
+                capacity
+            )));
+        }
+        let contents = file
+            .get_mut(self.contents.start + 1..self.contents.end - 1)
+            .ok_or(Error::Offset(self.contents.end))?;
+        for (hex, byte) in contents.chunks_mut(2).zip(signature) {
+            hex.copy_from_slice(format!("{:02X}", byte).as_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl Document {
+    /// Add a signature field with a placeholder signature and save the document as an
+    /// incremental update of the file it was loaded from, like [`Document::save_incremental`]
+    /// with the same `original` and `previous`.
+    ///
+    /// The signature dictionary holds a `ByteRange` covering the whole written file except its
+    /// `Contents`, which is a hexadecimal string of zeros of [`SignatureOptions::contents_size`]
+    /// bytes. The returned placeholder locates both, so an external signer can compute the
+    /// signature over [`SignaturePlaceholder::signed_data`] and write it with
+    /// [`SignaturePlaceholder::embed_signature`] without changing any other byte.
+    ///
+    /// The field is added to the `AcroForm` of the catalog, which is created if needed, and its
+    /// `SigFlags` are set to mark the document as signed and to be updated incrementally.
+    pub fn save_signature_placeholder<W: Write>(
+        &self, original: &[u8], previous: &Document, options: &SignatureOptions, target: &mut W,
+    ) -> Result<SignaturePlaceholder> {
+        if self.form_fields()?.iter().any(|field| field.name == options.field_name) {
+            return Err(Error::Invalid(format!(
+                "Form field {} already exists",
+                options.field_name
+            )));
+        }
+        let mut document = self.clone();
+        let page_id = match options.page {
+            Some(page_id) => page_id,
+            None => document.page_iter().next().ok_or(Error::PageNumberNotFound(1))?,
+        };
+
+        let unknown = Object::Integer(BYTE_RANGE_PLACEHOLDER);
+        let mut signature = dictionary! {
+            "Type" => "Sig",
+            "Filter" => "Adobe.PPKLite",
+            "SubFilter" => Object::Name(options.sub_filter.as_bytes().to_vec()),
+            "ByteRange" => vec![0.into(), unknown.clone(), unknown.clone(), unknown],
+            "Contents" => Object::String(vec![0; options.contents_size], StringFormat::Hexadecimal),
+        };
+        for (key, value) in [
+            ("Name", &options.name),
+            ("Reason", &options.reason),
+            ("Location", &options.location),
+        ] {
+            if let Some(value) = value {
+                signature.set(key, Object::text_string(value));
+            }
+        }
+        if let Some(time) = options.signing_time {
+            signature.set("M", time);
+        }
+        let signature_id = document.add_object(signature);
+        let field_id = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "FT" => "Sig",
+            "T" => Object::text_string(&options.field_name),
+            "V" => signature_id,
+            "Rect" => options.rect.iter().map(|&value| value.into()).collect::<Vec<Object>>(),
+            // Print and Locked.
+            "F" => 132,
+            "P" => page_id,
+        });
+
+        push_reference(&mut document, page_id, b"Annots", field_id)?;
+        let form_id = match document.catalog()?.get(b"AcroForm") {
+            Ok(Object::Reference(id)) => *id,
+            Ok(Object::Dictionary(form)) => {
+                let form = form.clone();
+                let id = document.add_object(form);
+                document.catalog_mut()?.set("AcroForm", id);
+                id
+            }
+            _ => {
+                let id = document.add_object(dictionary! { "Fields" => vec![] });
+                document.catalog_mut()?.set("AcroForm", id);
+                id
+            }
+        };
+        push_reference(&mut document, form_id, b"Fields", field_id)?;
+        // SignaturesExist and AppendOnly.
+        document.get_dictionary_mut(form_id)?.set("SigFlags", 3);
+
+        let mut incremental = IncrementalDocument::create_from_changes(original.to_vec(), previous.clone(), &document);
+        let mut file = Vec::new();
+        incremental.save_to(&mut file)?;
+
+        let update = incremental.get_prev_documents_bytes().len();
+        let find = |pattern: &[u8]| {
+            file[update..]
+                .windows(pattern.len())
+                .position(|window| window == pattern)
+                .map(|pos| update + pos)
+                .ok_or(Error::ObjectNotFound)
+        };
+        let contents = format!("/Contents<{}>", "0".repeat(options.contents_size * 2));
+        let contents_start = find(contents.as_bytes())? + b"/Contents".len();
+        let contents = contents_start..contents_start + contents.len() - b"/Contents".len();
+        let byte_range = format!("/ByteRange[0 {0} {0} {0}]", BYTE_RANGE_PLACEHOLDER);
+        let byte_range_start = find(byte_range.as_bytes())? + b"/ByteRange".len();
+        let byte_range_len = byte_range.len() - b"/ByteRange".len();
+
+        let placeholder = SignaturePlaceholder {
+            byte_range: [0, contents.start, contents.end, file.len() - contents.end],
+            contents,
+        };
+        let [_, start, end, length] = placeholder.byte_range;
+        let value = format!("[0 {} {} {}", start, end, length);
+        let patched = format!("{:width$}]", value, width = byte_range_len - 1);
+        file[byte_range_start..byte_range_start + byte_range_len].copy_from_slice(patched.as_bytes());
+
+        target.write_all(&file)?;
+        Ok(placeholder)
+    }
+}
+
+/// Add a reference to `value` to the array `key` of the dictionary `id`, which may be referenced.
+/// The array is created if it doesn't exist.
+fn push_reference(document: &mut Document, id: ObjectId, key: &[u8], value: ObjectId) -> Result<()> {
+    let array_id = document.get_dictionary(id)?.get(key).and_then(Object::as_reference);
+    let array = match array_id {
+        Ok(array_id) => document.get_object_mut(array_id)?,
+        Err(_) => {
+            let dict = document.get_dictionary_mut(id)?;
+            if dict.get(key).and_then(Object::as_array).is_err() {
+                dict.set(key, vec![]);
+            }
+            dict.get_mut(key)?
+        }
+    };
+    array.as_array_mut()?.push(value.into());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignatureOptions;
+    use crate::creator::tests::create_document;
+    use crate::{Document, Object};
+
+    #[test]
+    fn signature_byte_range() {
+        let mut original = Vec::new();
+        create_document().save_to(&mut original).unwrap();
+        let doc = Document::load_mem(&original).unwrap();
+        let options = SignatureOptions {
+            contents_size: 64,
+            reason: Some("Approval".to_string()),
+            ..SignatureOptions::default()
+        };
+        let mut file = Vec::new();
+        let placeholder = doc
+            .save_signature_placeholder(&original, &doc, &options, &mut file)
+            .unwrap();
+
+        assert!(file.starts_with(&original));
+        let [start, contents_start, contents_end, length] = placeholder.byte_range;
+        assert_eq!(start, 0);
+        assert_eq!(placeholder.contents, contents_start..contents_end);
+        assert_eq!(contents_end + length, file.len());
+        let contents = &file[placeholder.contents.clone()];
+        assert_eq!(contents, format!("<{}>", "0".repeat(128)).as_bytes());
+        let signed = placeholder.signed_data(&file).unwrap();
+        assert_eq!(signed.len(), file.len() - contents.len());
+
+        let signature = b"\x30\x82\x01\x00signature";
+        placeholder.embed_signature(&mut file, signature).unwrap();
+        assert_eq!(placeholder.signed_data(&file).unwrap(), signed);
+        assert!(placeholder.embed_signature(&mut file.clone(), &[0; 65]).is_err());
+
+        let signed_doc = Document::load_mem(&file).unwrap();
+        let fields = signed_doc.form_fields().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].field_type.as_deref(), Some("Sig"));
+        let field = signed_doc.get_dictionary(fields[0].id).unwrap();
+        let value = field.get_deref(b"V", &signed_doc).and_then(Object::as_dict).unwrap();
+        let byte_range: Vec<_> = value
+            .get(b"ByteRange")
+            .and_then(Object::as_array)
+            .unwrap()
+            .iter()
+            .map(|value| value.as_i64().unwrap() as usize)
+            .collect();
+        assert_eq!(byte_range, placeholder.byte_range);
+        let mut expected = signature.to_vec();
+        expected.resize(64, 0);
+        assert_eq!(value.get(b"Contents").and_then(Object::as_str).unwrap(), expected);
+        assert_eq!(
+            value.get(b"Reason").and_then(Object::as_text_string).unwrap(),
+            "Approval"
+        );
+        let form = signed_doc
+            .catalog()
+            .unwrap()
+            .get_deref(b"AcroForm", &signed_doc)
+            .unwrap();
+        assert_eq!(
+            form.as_dict()
+                .unwrap()
+                .get(b"SigFlags")
+                .and_then(Object::as_i64)
+                .unwrap(),
+            3
+        );
+        let page_id = signed_doc.page_iter().next().unwrap();
+        assert_eq!(signed_doc.get_page_annotations(page_id).len(), 1);
+
+        let options = SignatureOptions::default();
+        assert!(signed_doc
+            .save_signature_placeholder(&file, &signed_doc, &options, &mut Vec::new())
+            .is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Read access to the logical structure tree of tagged documents.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Dictionary, Document, Object, ObjectId, Result};
+
+/// Logical structure tree of a tagged document, from the catalog's `StructTreeRoot`, see
+/// [`Document::struct_tree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructTree {
+    /// Top-level structure elements, from the `K` entry of the root.
+    pub elements: Vec<StructElement>,
+    /// Standard structure type of each custom structure type, from the `RoleMap` of the root.
+    pub role_map: BTreeMap<String, String>,
+}
+
+/// Structure element of the structure tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructElement {
+    /// Structure type `S`, such as `P`, `H1` or `Figure`, or a custom type of the `RoleMap`.
+    pub struct_type: String,
+    /// Id of the structure element dictionary, `None` if it is stored directly in its parent.
+    pub id: Option<ObjectId>,
+    /// Page `Pg` on which the content of the element is, which its kids inherit.
+    pub page: Option<ObjectId>,
+    /// Title `T`.
+    pub title: Option<String>,
+    /// Alternate description `Alt`, such as the description of a figure.
+    pub alt: Option<String>,
+    /// Replacement text `ActualText`.
+    pub actual_text: Option<String>,
+    pub kids: Vec<StructKid>,
+}
+
+/// Kid of a structure element, from its `K` entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructKid {
+    Element(StructElement),
+    /// Marked-content sequence with the given `MCID` in the content stream of a page, or in the
+    /// content stream `stream` of another object, such as a form XObject.
+    MarkedContent {
+        mcid: i64,
+        page: Option<ObjectId>,
+        stream: Option<ObjectId>,
+    },
+    /// Whole object, such as an annotation, from an object reference dictionary.
+    Object {
+        object: ObjectId,
+        page: Option<ObjectId>,
+    },
+}
+
+impl Document {
+    /// Get the logical structure tree of the document, or `None` if the catalog has no
+    /// `StructTreeRoot`.
+    ///
+    /// Kids that are neither structure elements, marked-content identifiers, marked-content
+    /// references nor object references are skipped, and so are structure elements that were
+    /// already reached through another parent. The page of the content marked by a kid is
+    /// inherited from its element if it has no `Pg` of its own.
+    pub fn struct_tree(&self) -> Result<Option<StructTree>> {
+        let Ok(root) = self
+            .catalog()?
+            .get_deref(b"StructTreeRoot", self)
+            .and_then(Object::as_dict)
+        else {
+            return Ok(None);
+        };
+        let role_map = root
+            .get_deref(b"RoleMap", self)
+            .and_then(Object::as_dict)
+            .map(|role_map| {
+                role_map
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        let value = self.dereference(value).ok()?.1.as_name().ok()?;
+                        Some((
+                            String::from_utf8_lossy(key).into_owned(),
+                            String::from_utf8_lossy(value).into_owned(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut kids = vec![];
+        let mut visited = HashSet::new();
+        if let Ok(k) = root.get(b"K") {
+            self.collect_struct_kids(k, None, &mut kids, &mut visited);
+        }
+        let elements = kids
+            .into_iter()
+            .filter_map(|kid| match kid {
+                StructKid::Element(element) => Some(element),
+                _ => None,
+            })
+            .collect();
+        Ok(Some(StructTree { elements, role_map }))
+    }
+
+    /// Collect the kids of `K`, which is a single kid or an array of kids, on the given page.
+    fn collect_struct_kids(
+        &self, k: &Object, page: Option<ObjectId>, kids: &mut Vec<StructKid>, visited: &mut HashSet<ObjectId>,
+    ) {
+        let id = k.as_reference().ok();
+        if let Some(id) = id {
+            if !visited.insert(id) {
+                return;
+            }
+        }
+        let Ok((_, k)) = self.dereference(k) else {
+            return;
+        };
+        match k {
+            Object::Integer(mcid) => kids.push(StructKid::MarkedContent {
+                mcid: *mcid,
+                page,
+                stream: None,
+            }),
+            Object::Array(array) => {
+                for kid in array {
+                    self.collect_struct_kids(kid, page, kids, visited);
+                }
+            }
+            Object::Dictionary(dict) => {
+                let own_page = dict.get(b"Pg").and_then(Object::as_reference).ok().or(page);
+                if dict.type_is(b"MCR") {
+                    if let Ok(mcid) = dict.get_deref(b"MCID", self).and_then(Object::as_i64) {
+                        kids.push(StructKid::MarkedContent {
+                            mcid,
+                            page: own_page,
+                            stream: dict.get(b"Stm").and_then(Object::as_reference).ok(),
+                        });
+                    }
+                } else if dict.type_is(b"OBJR") {
+                    if let Ok(object) = dict.get(b"Obj").and_then(Object::as_reference) {
+                        kids.push(StructKid::Object { object, page: own_page });
+                    }
+                } else if let Some(element) = self.struct_element(dict, id, page, visited) {
+                    kids.push(StructKid::Element(element));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn struct_element(
+        &self, dict: &Dictionary, id: Option<ObjectId>, page: Option<ObjectId>, visited: &mut HashSet<ObjectId>,
+    ) -> Option<StructElement> {
+        let struct_type = dict.get_deref(b"S", self).and_then(Object::as_name).ok()?;
+        let text = |key: &[u8]| dict.get_deref(key, self).and_then(Object::as_text_string).ok();
+        let page = dict.get(b"Pg").and_then(Object::as_reference).ok().or(page);
+        let mut kids = vec![];
+        if let Ok(k) = dict.get(b"K") {
+            self.collect_struct_kids(k, page, &mut kids, visited);
+        }
+        Some(StructElement {
+            struct_type: String::from_utf8_lossy(struct_type).into_owned(),
+            id,
+            page,
+            title: text(b"T"),
+            alt: text(b"Alt"),
+            actual_text: text(b"ActualText"),
+            kids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StructElement, StructKid};
+    use crate::creator::tests::create_document;
+    use crate::{Object, Stream};
+
+    #[test]
+    fn read_struct_tree() {
+        let mut doc = create_document();
+        let page_id = doc.page_iter().next().unwrap();
+        let content = b"/H1 <</MCID 0>> BDC BT /F1 24 Tf 50 800 Td (Title) Tj ET EMC\n\
+            /P <</MCID 1>> BDC BT /F1 12 Tf 50 760 Td (First) Tj ET EMC\n\
+            /P <</MCID 2>> BDC BT /F1 12 Tf 50 740 Td (Second) Tj ET EMC\n";
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+        let link_id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![50.into(), 740.into(), 100.into(), 752.into()],
+            "StructParent" => 1,
+        });
+        let page = doc.get_dictionary_mut(page_id).unwrap();
+        page.set("Contents", content_id);
+        page.set("Annots", vec![link_id.into()]);
+        page.set("StructParents", 0);
+
+        let root_id = doc.new_object_id();
+        let document_id = doc.new_object_id();
+        let heading_id = doc.add_object(dictionary! {
+            "Type" => "StructElem",
+            "S" => "H1",
+            "P" => document_id,
+            "Pg" => page_id,
+            "T" => Object::string_literal("Title"),
+            "K" => 0,
+        });
+        let paragraph = dictionary! {
+            "S" => "Para",
+            "P" => document_id,
+            "Pg" => page_id,
+            "ActualText" => Object::text_string("First Second"),
+            "K" => vec![
+                1.into(),
+                dictionary! { "Type" => "MCR", "MCID" => 2 }.into(),
+                dictionary! { "Type" => "OBJR", "Obj" => link_id }.into(),
+            ],
+        };
+        doc.objects.insert(
+            document_id,
+            dictionary! {
+                "Type" => "StructElem",
+                "S" => "Document",
+                "P" => root_id,
+                // The heading is only read once.
+                "K" => vec![heading_id.into(), paragraph.into(), heading_id.into()],
+            }
+            .into(),
+        );
+        doc.objects.insert(
+            root_id,
+            dictionary! {
+                "Type" => "StructTreeRoot",
+                "K" => document_id,
+                "RoleMap" => dictionary! { "Para" => "P" },
+            }
+            .into(),
+        );
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("StructTreeRoot", root_id);
+        catalog.set("MarkInfo", dictionary! { "Marked" => true });
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        let doc = crate::Document::load_mem(&buffer).unwrap();
+        let tree = doc.struct_tree().unwrap().unwrap();
+        assert_eq!(tree.role_map.get("Para").map(String::as_str), Some("P"));
+        assert_eq!(tree.elements.len(), 1);
+        let document = &tree.elements[0];
+        assert_eq!(
+            (document.struct_type.as_str(), document.id, document.page),
+            ("Document", Some(document_id), None)
+        );
+        assert_eq!(document.kids.len(), 2);
+        assert_eq!(
+            document.kids[0],
+            StructKid::Element(StructElement {
+                struct_type: "H1".into(),
+                id: Some(heading_id),
+                page: Some(page_id),
+                title: Some("Title".into()),
+                alt: None,
+                actual_text: None,
+                kids: vec![StructKid::MarkedContent {
+                    mcid: 0,
+                    page: Some(page_id),
+                    stream: None
+                }],
+            })
+        );
+        let StructKid::Element(paragraph) = &document.kids[1] else {
+            panic!("expected a structure element");
+        };
+        assert_eq!((paragraph.struct_type.as_str(), paragraph.id), ("Para", None));
+        assert_eq!(paragraph.actual_text.as_deref(), Some("First Second"));
+        assert_eq!(
+            paragraph.kids,
+            [
+                StructKid::MarkedContent {
+                    mcid: 1,
+                    page: Some(page_id),
+                    stream: None
+                },
+                StructKid::MarkedContent {
+                    mcid: 2,
+                    page: Some(page_id),
+                    stream: None
+                },
+                StructKid::Object {
+                    object: link_id,
+                    page: Some(page_id)
+                },
+            ]
+        );
+
+        let doc = create_document();
+        assert_eq!(doc.struct_tree().unwrap(), None);
+    }
+}
@@ -1,5 +1,5 @@
 use indexmap::IndexMap;
-use std::{usize, vec::Vec}; // Import IndexMap
+use std::vec::Vec;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,1218 @@
+//! Embedding and subsetting of TrueType fonts.
+//!
+//! Only the sfnt tables needed to describe the font to a PDF reader are parsed: `head`, `hhea`,
+//! `hmtx`, `cmap`, `OS/2`, `post` and `name`, and `maxp`, `loca` and `glyf` for subsetting.
+
+use std::collections::BTreeMap;
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use std::collections::BTreeSet;
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use crate::content::Content;
+use crate::encodings::WIN_ANSI_ENCODING;
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use crate::encodings::{MAC_ROMAN_ENCODING, STANDARD_ENCODING};
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+use crate::Dictionary;
+use crate::{Document, Error, Object, ObjectId, Result, Stream};
+
+/// Tables kept in a subset, anything else such as layout tables is dropped.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+const SUBSET_TABLES: [&[u8; 4]; 13] = [
+    b"OS/2", b"cmap", b"cvt ", b"fpgm", b"gasp", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"name", b"post",
+];
+
+/// Deepest nesting of form XObjects followed when collecting the glyphs shown by a page.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+const MAX_FORM_DEPTH: usize = 16;
+
+/// First character code given a width in simple TrueType font dictionaries.
+const FIRST_CHAR: u16 = 32;
+/// Last character code given a width in simple TrueType font dictionaries.
+const LAST_CHAR: u16 = 255;
+
+/// Font descriptor flags, see section 9.8.2 of the PDF specification.
+const FIXED_PITCH: i64 = 1 << 0;
+const SYMBOLIC: i64 = 1 << 2;
+const NONSYMBOLIC: i64 = 1 << 5;
+const ITALIC: i64 = 1 << 6;
+const FORCE_BOLD: i64 = 1 << 18;
+
+/// A parsed TrueType font file.
+pub(crate) struct TrueTypeFont<'a> {
+    data: &'a [u8],
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    tables: BTreeMap<[u8; 4], &'a [u8]>,
+    pub units_per_em: u16,
+    /// Bounding box of all glyphs as `[x_min, y_min, x_max, y_max]`, in font units.
+    pub bbox: [i16; 4],
+    pub ascent: i16,
+    pub descent: i16,
+    pub cap_height: i16,
+    pub weight: u16,
+    /// Italic angle in degrees counter-clockwise from the vertical.
+    pub italic_angle: f32,
+    pub is_fixed_pitch: bool,
+    pub is_bold: bool,
+    pub is_italic: bool,
+    pub postscript_name: String,
+    advance_widths: Vec<u16>,
+    /// Mapping from Unicode, or character codes of a symbol font, to glyph ids.
+    cmap: BTreeMap<u32, u16>,
+    /// Whether the font has a symbol cmap instead of a Unicode one.
+    pub is_symbolic: bool,
+}
+
+impl<'a> TrueTypeFont<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<TrueTypeFont<'a>> {
+        let version = read_u32(data, 0)?;
+        if version == u32::from_be_bytes(*b"OTTO") {
+            return Err(Error::InvalidFont(
+                "font has CFF outlines instead of TrueType outlines".to_string(),
+            ));
+        }
+        if version != 0x0001_0000 && version != u32::from_be_bytes(*b"true") {
+            return Err(Error::InvalidFont("not a TrueType font".to_string()));
+        }
+        let num_tables = read_u16(data, 4)? as usize;
+        let mut tables = BTreeMap::new();
+        for index in 0..num_tables {
+            let record = 12 + index * 16;
+            let tag = data.get(record..record + 4).ok_or_else(truncated)?;
+            let offset = read_u32(data, record + 8)? as usize;
+            let len = read_u32(data, record + 12)? as usize;
+            let table = data.get(offset..offset.saturating_add(len)).ok_or_else(truncated)?;
+            tables.insert([tag[0], tag[1], tag[2], tag[3]], table);
+        }
+
+        let table = |tag: &[u8; 4]| {
+            tables
+                .get(tag)
+                .copied()
+                .ok_or_else(|| Error::InvalidFont(format!("missing {} table", String::from_utf8_lossy(tag))))
+        };
+        let head = table(b"head")?;
+        let hhea = table(b"hhea")?;
+        let hmtx = table(b"hmtx")?;
+        let units_per_em = read_u16(head, 18)?;
+        if units_per_em == 0 {
+            return Err(Error::InvalidFont("unitsPerEm is 0".to_string()));
+        }
+        let bbox = [
+            read_i16(head, 36)?,
+            read_i16(head, 38)?,
+            read_i16(head, 40)?,
+            read_i16(head, 42)?,
+        ];
+        let mac_style = read_u16(head, 44)?;
+        let ascent = read_i16(hhea, 4)?;
+        let descent = read_i16(hhea, 6)?;
+
+        let num_h_metrics = read_u16(hhea, 34)? as usize;
+        let advance_widths = (0..num_h_metrics)
+            .map(|index| read_u16(hmtx, index * 4))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (mut weight, mut cap_height) = (400, ascent);
+        if let Some(os2) = tables.get(b"OS/2") {
+            weight = read_u16(os2, 4)?;
+            if read_u16(os2, 0)? >= 2 {
+                cap_height = read_i16(os2, 88)?;
+            }
+        }
+        let (mut italic_angle, mut is_fixed_pitch) = (0.0, false);
+        if let Some(post) = tables.get(b"post") {
+            italic_angle = read_u32(post, 4)? as i32 as f32 / 65536.0;
+            is_fixed_pitch = read_u32(post, 12)? != 0;
+        }
+        let postscript_name = tables
+            .get(b"name")
+            .and_then(|name| postscript_name(name))
+            .unwrap_or_else(|| "EmbeddedFont".to_string());
+        let (cmap, is_symbolic) = tables
+            .get(b"cmap")
+            .map(|cmap| parse_cmap(cmap))
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(TrueTypeFont {
+            data,
+            units_per_em,
+            bbox,
+            ascent,
+            descent,
+            cap_height,
+            weight,
+            italic_angle,
+            is_fixed_pitch,
+            is_bold: mac_style & 1 != 0 || weight >= 700,
+            is_italic: mac_style & 2 != 0 || italic_angle != 0.0,
+            postscript_name,
+            advance_widths,
+            cmap,
+            is_symbolic,
+            #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+            tables,
+        })
+    }
+
+    /// Get a table of the font by its tag.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    pub fn table(&self, tag: &[u8; 4]) -> Option<&'a [u8]> {
+        self.tables.get(tag).copied()
+    }
+
+    /// Get the glyph id of a character, or of a character code for a symbol font.
+    pub fn glyph_id(&self, code: u32) -> Option<u16> {
+        if self.is_symbolic {
+            // Symbol fonts usually map the codes to the private use area at U+F000.
+            self.cmap
+                .get(&code)
+                .or_else(|| self.cmap.get(&(0xF000 + code)))
+                .copied()
+        } else {
+            self.cmap.get(&code).copied()
+        }
+    }
+
+    /// Get the advance width of a glyph in font units.
+    pub fn advance_width(&self, glyph_id: u16) -> u16 {
+        // Glyphs after the last horizontal metric have the advance width of the last one.
+        let index = (glyph_id as usize).min(self.advance_widths.len().saturating_sub(1));
+        self.advance_widths.get(index).copied().unwrap_or(0)
+    }
+
+    /// Build a subset of the font with only the given glyphs, the glyphs their composite glyphs
+    /// are made of and the `.notdef` glyph.
+    ///
+    /// Glyph ids are kept, the outlines of the other glyphs are removed and the `cmap` only maps
+    /// to the kept glyphs, so widths and glyph ids used by the document stay valid.
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    pub fn subset(&self, glyphs: &BTreeSet<u16>) -> Result<Vec<u8>> {
+        let table = |tag: &[u8; 4]| {
+            self.table(tag)
+                .ok_or_else(|| Error::InvalidFont(format!("missing {} table", String::from_utf8_lossy(tag))))
+        };
+        let head = table(b"head")?;
+        let glyf = table(b"glyf")?;
+        let loca = table(b"loca")?;
+        let num_glyphs = read_u16(table(b"maxp")?, 4)?;
+        let long_offsets = read_u16(head, 50)? != 0;
+        let glyph_data = |glyph_id: u16| -> Result<&[u8]> {
+            let (start, end) = if long_offsets {
+                let offset = glyph_id as usize * 4;
+                (read_u32(loca, offset)? as usize, read_u32(loca, offset + 4)? as usize)
+            } else {
+                let offset = glyph_id as usize * 2;
+                (
+                    read_u16(loca, offset)? as usize * 2,
+                    read_u16(loca, offset + 2)? as usize * 2,
+                )
+            };
+            glyf.get(start..end.max(start)).ok_or_else(truncated)
+        };
+
+        let mut kept = BTreeSet::new();
+        let mut pending: Vec<u16> = glyphs.iter().copied().chain([0]).collect();
+        while let Some(glyph_id) = pending.pop() {
+            if glyph_id < num_glyphs && kept.insert(glyph_id) {
+                pending.extend(composite_components(glyph_data(glyph_id)?)?);
+            }
+        }
+
+        let mut new_glyf = vec![];
+        let mut new_loca = Vec::with_capacity((num_glyphs as usize + 1) * 4);
+        for glyph_id in 0..num_glyphs {
+            new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+            if kept.contains(&glyph_id) {
+                new_glyf.extend_from_slice(glyph_data(glyph_id)?);
+                if new_glyf.len() % 2 != 0 {
+                    new_glyf.push(0);
+                }
+            }
+        }
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+        let mut new_head = head.to_vec();
+        // Reset checkSumAdjustment, it is computed once the font is assembled.
+        new_head[8..12].fill(0);
+        new_head[50..52].copy_from_slice(&1u16.to_be_bytes());
+        let mapping: BTreeMap<u32, u16> = self
+            .cmap
+            .iter()
+            .filter(|(_, glyph_id)| kept.contains(glyph_id))
+            .map(|(&code, &glyph_id)| (code, glyph_id))
+            .collect();
+
+        let mut tables = vec![];
+        for tag in SUBSET_TABLES {
+            let data = match tag {
+                b"glyf" => new_glyf.clone(),
+                b"loca" => new_loca.clone(),
+                b"head" => new_head.clone(),
+                b"cmap" => build_cmap(&mapping, self.is_symbolic),
+                // Version 3 of the post table has no glyph names.
+                b"post" => match self.table(tag) {
+                    Some(post) if post.len() >= 32 => {
+                        let mut post = post[..32].to_vec();
+                        post[..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+                        post
+                    }
+                    _ => continue,
+                },
+                _ => match self.table(tag) {
+                    Some(data) => data.to_vec(),
+                    None => continue,
+                },
+            };
+            tables.push((*tag, data));
+        }
+        Ok(build_sfnt(&tables))
+    }
+
+    /// Scale a value in font units to the 1000 units per em of text space.
+    pub fn scale(&self, value: i32) -> i64 {
+        (value as f64 * 1000.0 / self.units_per_em as f64).round() as i64
+    }
+
+    /// Font descriptor flags.
+    pub fn flags(&self) -> i64 {
+        let mut flags = if self.is_symbolic { SYMBOLIC } else { NONSYMBOLIC };
+        if self.is_fixed_pitch {
+            flags |= FIXED_PITCH;
+        }
+        if self.is_italic {
+            flags |= ITALIC;
+        }
+        if self.is_bold {
+            flags |= FORCE_BOLD;
+        }
+        flags
+    }
+
+    /// Estimate the vertical stem width from the weight class, fonts don't record it.
+    pub fn stem_v(&self) -> i64 {
+        10 + 220 * (self.weight.clamp(50, 950) as i64 - 50) / 900
+    }
+}
+
+/// Get the glyph ids a composite glyph is made of, none for a simple glyph.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn composite_components(glyph: &[u8]) -> Result<Vec<u16>> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 1 << 0;
+    const WE_HAVE_A_SCALE: u16 = 1 << 3;
+    const MORE_COMPONENTS: u16 = 1 << 5;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 1 << 6;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 1 << 7;
+
+    let mut components = vec![];
+    if glyph.is_empty() || read_i16(glyph, 0)? >= 0 {
+        return Ok(components);
+    }
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(glyph, offset)?;
+        components.push(read_u16(glyph, offset + 2)?);
+        offset += 4;
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(components);
+        }
+    }
+}
+
+/// Build a `cmap` table with a format 4 subtable, and a format 12 subtable if characters outside
+/// of the Basic Multilingual Plane are mapped.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn build_cmap(mapping: &BTreeMap<u32, u16>, is_symbolic: bool) -> Vec<u8> {
+    // Segments of consecutive codes mapped to consecutive glyphs.
+    let mut segments: Vec<(u32, u32, u16)> = vec![];
+    for (&code, &glyph_id) in mapping {
+        match segments.last_mut() {
+            Some((start, end, start_glyph))
+                if code == *end + 1 && glyph_id as u32 == *start_glyph as u32 + (code - *start) =>
+            {
+                *end = code
+            }
+            _ => segments.push((code, code, glyph_id)),
+        }
+    }
+
+    // The format 4 subtable must end with a segment for 0xFFFF.
+    let bmp: Vec<(u16, u16, u16)> = segments
+        .iter()
+        .filter(|(start, _, _)| *start < 0xFFFF)
+        .map(|&(start, end, glyph_id)| {
+            (
+                start as u16,
+                end.min(0xFFFE) as u16,
+                glyph_id.wrapping_sub(start as u16),
+            )
+        })
+        .chain([(0xFFFF, 0xFFFF, 1)])
+        .collect();
+    let segment_count = bmp.len() as u16;
+    let entry_selector = 15 - segment_count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+    let mut format4 = vec![];
+    for value in [
+        4,
+        16 + 8 * segment_count,
+        0,
+        segment_count * 2,
+        search_range,
+        entry_selector,
+    ] {
+        format4.extend_from_slice(&value.to_be_bytes());
+    }
+    format4.extend_from_slice(&(segment_count * 2 - search_range).to_be_bytes());
+    bmp.iter()
+        .for_each(|&(_, end, _)| format4.extend_from_slice(&end.to_be_bytes()));
+    format4.extend_from_slice(&[0, 0]);
+    bmp.iter()
+        .for_each(|&(start, _, _)| format4.extend_from_slice(&start.to_be_bytes()));
+    bmp.iter()
+        .for_each(|&(_, _, delta)| format4.extend_from_slice(&delta.to_be_bytes()));
+    format4.extend(std::iter::repeat_n(0, bmp.len() * 2));
+
+    let mut subtables = vec![((3, if is_symbolic { 0 } else { 1 }), format4)];
+    if mapping.keys().any(|&code| code > 0xFFFF) && !is_symbolic {
+        let mut format12 = vec![0, 12, 0, 0];
+        format12.extend_from_slice(&(16 + 12 * segments.len() as u32).to_be_bytes());
+        format12.extend_from_slice(&0u32.to_be_bytes());
+        format12.extend_from_slice(&(segments.len() as u32).to_be_bytes());
+        for &(start, end, glyph_id) in &segments {
+            for value in [start, end, glyph_id as u32] {
+                format12.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        subtables.push(((3, 10), format12));
+    }
+
+    let mut cmap = vec![0, 0];
+    cmap.extend_from_slice(&(subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + 8 * subtables.len() as u32;
+    for ((platform, encoding), subtable) in &subtables {
+        cmap.extend_from_slice(&(*platform as u16).to_be_bytes());
+        cmap.extend_from_slice(&(*encoding as u16).to_be_bytes());
+        cmap.extend_from_slice(&offset.to_be_bytes());
+        offset += subtable.len() as u32;
+    }
+    subtables.into_iter().for_each(|(_, subtable)| cmap.extend(subtable));
+    cmap
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Assemble a font file from its tables, which are sorted by tag, computing the checksums.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn build_sfnt(tables: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let entry_selector = 15 - num_tables.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+    let mut font = vec![0, 1, 0, 0];
+    for value in [num_tables, search_range, entry_selector, num_tables * 16 - search_range] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+    let mut data = vec![];
+    let mut head_offset = None;
+    for (tag, table) in tables {
+        let offset = 12 + tables.len() * 16 + data.len();
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(table).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        data.extend_from_slice(table);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+    font.extend(data);
+    if let Some(offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+        font[offset + 8..offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+fn truncated() -> Error {
+    Error::InvalidFont("font data is truncated".to_string())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data.get(offset..offset + 2).ok_or_else(truncated)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Result<i16> {
+    read_u16(data, offset).map(|value| value as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).ok_or_else(truncated)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Get the PostScript name, name id 6, from the `name` table.
+fn postscript_name(name: &[u8]) -> Option<String> {
+    let count = read_u16(name, 2).ok()? as usize;
+    let storage = read_u16(name, 4).ok()? as usize;
+    for index in 0..count {
+        let record = 6 + index * 12;
+        let platform = read_u16(name, record).ok()?;
+        let name_id = read_u16(name, record + 6).ok()?;
+        if name_id != 6 {
+            continue;
+        }
+        let len = read_u16(name, record + 8).ok()? as usize;
+        let offset = storage + read_u16(name, record + 10).ok()? as usize;
+        let bytes = name.get(offset..offset + len)?;
+        let text = match platform {
+            // Windows and Unicode platforms use UTF-16BE.
+            0 | 3 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            _ => String::from_utf8_lossy(bytes).into_owned(),
+        };
+        // PostScript names are limited to printable ASCII without delimiters.
+        let text: String = text
+            .chars()
+            .filter(|ch| ch.is_ascii_graphic() && !"[](){}<>/%".contains(*ch))
+            .collect();
+        if !text.is_empty() {
+            return Some(text);
+        }
+    }
+    None
+}
+
+/// Parse the best subtable of the `cmap` table, returning the mapping to glyph ids and whether
+/// it is a symbol cmap.
+fn parse_cmap(cmap: &[u8]) -> Result<(BTreeMap<u32, u16>, bool)> {
+    let count = read_u16(cmap, 2)? as usize;
+    let mut best = None;
+    for index in 0..count {
+        let record = 4 + index * 8;
+        let platform = read_u16(cmap, record)?;
+        let encoding = read_u16(cmap, record + 2)?;
+        let offset = read_u32(cmap, record + 4)? as usize;
+        let format = read_u16(cmap, offset)?;
+        // Prefer full Unicode, then Unicode BMP, then symbol subtables.
+        let rank = match (platform, encoding, format) {
+            (3, 10, 12) | (0, 4, 12) => 0,
+            (3, 1, 4) | (0, 3, 4) => 1,
+            (3, 0, 4) => 2,
+            _ => continue,
+        };
+        if best.is_none_or(|(best_rank, _, _)| rank < best_rank) {
+            best = Some((rank, offset, platform == 3 && encoding == 0));
+        }
+    }
+    let Some((_, offset, is_symbolic)) = best else {
+        return Ok((BTreeMap::new(), false));
+    };
+
+    let subtable = cmap.get(offset..).ok_or_else(truncated)?;
+    let mut map = BTreeMap::new();
+    if read_u16(subtable, 0)? == 12 {
+        let groups = read_u32(subtable, 12)? as usize;
+        for group in 0..groups {
+            let record = 16 + group * 12;
+            let start = read_u32(subtable, record)?;
+            let end = read_u32(subtable, record + 4)?;
+            let glyph = read_u32(subtable, record + 8)?;
+            // Limit the range so a corrupt font can't make the map huge.
+            for code in start..=end.min(start.saturating_add(0xFFFF)) {
+                map.insert(code, (glyph + code - start) as u16);
+            }
+        }
+    } else {
+        let segments = read_u16(subtable, 6)? as usize / 2;
+        let end_codes = 14;
+        let start_codes = end_codes + segments * 2 + 2;
+        let deltas = start_codes + segments * 2;
+        let range_offsets = deltas + segments * 2;
+        for segment in 0..segments {
+            let end = read_u16(subtable, end_codes + segment * 2)?;
+            let start = read_u16(subtable, start_codes + segment * 2)?;
+            let delta = read_u16(subtable, deltas + segment * 2)?;
+            let range_offset_position = range_offsets + segment * 2;
+            let range_offset = read_u16(subtable, range_offset_position)? as usize;
+            for code in start..=end {
+                if code == 0xFFFF {
+                    break;
+                }
+                let glyph = if range_offset == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    let position = range_offset_position + range_offset + (code - start) as usize * 2;
+                    match read_u16(subtable, position)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                };
+                if glyph != 0 {
+                    map.insert(code as u32, glyph);
+                }
+            }
+        }
+    }
+    Ok((map, is_symbolic))
+}
+
+impl Document {
+    /// Embed a TrueType font and add a simple font dictionary for it, returning the id of the
+    /// font dictionary.
+    ///
+    /// The font uses `WinAnsiEncoding`, or the built-in encoding of the font for symbol fonts.
+    /// The widths and the font descriptor are computed from the font tables, the font file is
+    /// embedded as is in the `FontFile2` stream.
+    pub fn add_truetype_font(&mut self, data: &[u8]) -> Result<ObjectId> {
+        let font = TrueTypeFont::parse(data)?;
+
+        let widths: Vec<Object> = (FIRST_CHAR..=LAST_CHAR)
+            .map(|code| {
+                let character = if font.is_symbolic {
+                    Some(code)
+                } else {
+                    WIN_ANSI_ENCODING[code as usize]
+                };
+                let width = character
+                    .and_then(|character| font.glyph_id(character as u32))
+                    .map(|glyph_id| font.scale(font.advance_width(glyph_id) as i32))
+                    .unwrap_or(0);
+                Object::Integer(width)
+            })
+            .collect();
+
+        let font_file = Stream::new(dictionary! { "Length1" => data.len() as i64 }, font.data.to_vec());
+        let font_file_id = self.add_object(font_file);
+        let bbox: Vec<Object> = font.bbox.iter().map(|&value| font.scale(value as i32).into()).collect();
+        let descriptor_id = self.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => font.postscript_name.as_str(),
+            "Flags" => font.flags(),
+            "FontBBox" => bbox,
+            "ItalicAngle" => font.italic_angle,
+            "Ascent" => font.scale(font.ascent as i32),
+            "Descent" => font.scale(font.descent as i32),
+            "CapHeight" => font.scale(font.cap_height as i32),
+            "StemV" => font.stem_v(),
+            "FontFile2" => font_file_id,
+        });
+        let mut font_dict = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "TrueType",
+            "BaseFont" => font.postscript_name.as_str(),
+            "FirstChar" => FIRST_CHAR as i64,
+            "LastChar" => LAST_CHAR as i64,
+            "Widths" => widths,
+            "FontDescriptor" => descriptor_id,
+        };
+        if !font.is_symbolic {
+            font_dict.set("Encoding", "WinAnsiEncoding");
+        }
+        Ok(self.add_object(font_dict))
+    }
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+impl Document {
+    /// Subset the embedded TrueType fonts to the glyphs shown by the content streams of the
+    /// pages, form XObjects and tiling patterns. Returns the number of font files subset.
+    ///
+    /// Simple TrueType fonts with a named encoding and `Identity` encoded `CIDFontType2` fonts
+    /// are supported. Form XObjects painted by a content stream are followed with the font it
+    /// selected, and with its resources if they have none. Fonts that can't be subset safely are
+    /// left as they are: fonts used by content that can't be parsed, fonts of the interactive
+    /// form, which may show any text, font files shared with unsupported fonts and font files
+    /// that can't be parsed. CFF fonts in `FontFile3` are not subset.
+    pub fn subset_fonts(&mut self) -> Result<usize> {
+        let mut shown = ShownStrings::default();
+        for page_id in self.page_iter() {
+            let Ok(resources) = self.get_page_inherited_resources(page_id) else {
+                continue;
+            };
+            match self.get_page_content(page_id) {
+                Ok(content) => self.collect_shown_strings(&content, &resources, None, &mut vec![], &mut shown),
+                Err(_) => shown.unsafe_fonts.extend(self.resource_fonts(&resources).into_values()),
+            }
+        }
+        for (&id, object) in &self.objects {
+            let Ok(stream) = object.as_stream() else {
+                continue;
+            };
+            let is_form = stream.dict.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Form");
+            let is_tiling_pattern = stream.dict.get(b"PatternType").and_then(Object::as_i64).ok() == Some(1);
+            if is_form || is_tiling_pattern {
+                let content = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+                let resources = stream.dict.get_deref(b"Resources", self).and_then(Object::as_dict);
+                let resources = resources.cloned().unwrap_or_default();
+                self.collect_shown_strings(&content, &resources, None, &mut vec![id], &mut shown);
+            }
+        }
+        let ShownStrings {
+            strings: shown,
+            mut unsafe_fonts,
+        } = shown;
+        if let Ok(form_resources) = self
+            .catalog()
+            .and_then(|catalog| catalog.get_deref(b"AcroForm", self))
+            .and_then(Object::as_dict)
+            .and_then(|form| form.get_deref(b"DR", self))
+            .and_then(Object::as_dict)
+        {
+            unsafe_fonts.extend(self.resource_fonts(form_resources).into_values());
+        }
+
+        // Collect the glyphs used from each font file, over all fonts that embed it.
+        let mut font_files: BTreeMap<ObjectId, (BTreeSet<u16>, Vec<ObjectId>, bool)> = BTreeMap::new();
+        for (&font_id, object) in &self.objects {
+            let Ok(font) = object.as_dict() else {
+                continue;
+            };
+            if font.get(b"Type").and_then(Object::as_name_str).ok() != Some("Font") {
+                continue;
+            }
+            let Some((font_file_id, supported)) = self.embedded_truetype_file(font) else {
+                continue;
+            };
+            let strings = shown.get(&font_id);
+            let glyphs = match (unsafe_fonts.contains(&font_id), supported, strings) {
+                (false, _, None) => Some(BTreeSet::new()),
+                (false, true, Some(strings)) => self.shown_glyphs(font, font_file_id, strings),
+                _ => None,
+            };
+            let entry = font_files
+                .entry(font_file_id)
+                .or_insert((BTreeSet::new(), vec![], true));
+            entry.1.push(font_id);
+            match glyphs {
+                Some(glyphs) => entry.0.extend(glyphs),
+                None => entry.2 = false,
+            }
+        }
+
+        let mut count = 0;
+        for (font_file_id, (glyphs, font_ids, can_subset)) in font_files {
+            if !can_subset {
+                continue;
+            }
+            let Ok(stream) = self.get_object(font_file_id).and_then(Object::as_stream) else {
+                continue;
+            };
+            let data = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+            let Ok(subset) = TrueTypeFont::parse(&data).and_then(|font| font.subset(&glyphs)) else {
+                continue;
+            };
+            let mut stream = Stream::new(stream.dict.clone(), vec![]);
+            stream.set_plain_content(subset);
+            stream.dict.set("Length1", stream.content.len() as i64);
+            // Ignore any compression error, the font file stays uncompressed.
+            let _ = stream.compress();
+            self.objects.insert(font_file_id, Object::Stream(stream));
+
+            let tag = subset_tag(&glyphs);
+            for font_id in font_ids {
+                self.tag_subset_font(font_id, &tag);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Collect the strings shown with each font by `content`, which is drawn with `resources`
+    /// starting with the current font `font`, and by the form XObjects it paints. `forms` holds
+    /// the form XObjects being drawn, so that cyclic forms are not followed.
+    fn collect_shown_strings(
+        &self, content: &[u8], resources: &Dictionary, mut font: Option<ObjectId>, forms: &mut Vec<ObjectId>,
+        shown: &mut ShownStrings,
+    ) {
+        let fonts = self.resource_fonts(resources);
+        let Ok(content) = Content::decode(content) else {
+            shown.unsafe_fonts.extend(fonts.values());
+            shown.unsafe_fonts.extend(font);
+            return;
+        };
+        let mut saved_fonts = vec![];
+        for operation in &content.operations {
+            let name = || operation.operands.first().and_then(|name| name.as_name().ok());
+            let strings = match operation.operator.as_str() {
+                "q" => {
+                    saved_fonts.push(font);
+                    continue;
+                }
+                "Q" => {
+                    font = saved_fonts.pop().unwrap_or(font);
+                    continue;
+                }
+                "Tf" => {
+                    font = name().and_then(|name| fonts.get(name)).copied();
+                    continue;
+                }
+                "Do" => {
+                    let Some((form_id, form)) = name().and_then(|name| self.resource_form(resources, name)) else {
+                        continue;
+                    };
+                    if forms.contains(&form_id) || forms.len() >= MAX_FORM_DEPTH {
+                        continue;
+                    }
+                    let form_content = form.decompressed_content().unwrap_or_else(|_| form.content.clone());
+                    let form_resources = form.dict.get_deref(b"Resources", self).and_then(Object::as_dict);
+                    forms.push(form_id);
+                    self.collect_shown_strings(&form_content, form_resources.unwrap_or(resources), font, forms, shown);
+                    forms.pop();
+                    continue;
+                }
+                "Tj" | "'" => operation.operands.first().into_iter().collect(),
+                "\"" => operation.operands.get(2).into_iter().collect(),
+                "TJ" => match operation.operands.first() {
+                    Some(Object::Array(array)) => array.iter().collect(),
+                    _ => vec![],
+                },
+                _ => continue,
+            };
+            let Some(font_id) = font else {
+                continue;
+            };
+            let entry = shown.strings.entry(font_id).or_default();
+            entry.extend(
+                strings
+                    .into_iter()
+                    .filter_map(|string| string.as_str().ok())
+                    .map(<[u8]>::to_vec),
+            );
+        }
+    }
+
+    /// Get a form XObject of a resource dictionary by name.
+    fn resource_form(&self, resources: &Dictionary, name: &[u8]) -> Option<(ObjectId, &Stream)> {
+        let xobjects = resources.get_deref(b"XObject", self).and_then(Object::as_dict).ok()?;
+        let id = xobjects.get(name).and_then(Object::as_reference).ok()?;
+        let form = self.get_object(id).and_then(Object::as_stream).ok()?;
+        let is_form = form.dict.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Form");
+        is_form.then_some((id, form))
+    }
+
+    /// Get the font dictionaries of a resource dictionary that are indirect objects.
+    fn resource_fonts(&self, resources: &Dictionary) -> BTreeMap<Vec<u8>, ObjectId> {
+        let fonts = resources.get_deref(b"Font", self).and_then(Object::as_dict);
+        fonts
+            .map(|fonts| {
+                fonts
+                    .iter()
+                    .filter_map(|(name, font)| Some((name.clone(), font.as_reference().ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the embedded TrueType font file of a font, and whether subsetting the font is supported.
+    fn embedded_truetype_file(&self, font: &Dictionary) -> Option<(ObjectId, bool)> {
+        let subtype = font.get(b"Subtype").and_then(Object::as_name_str).ok()?;
+        let (descriptor_font, supported) = match subtype {
+            "TrueType" => {
+                let encoding = font.get_deref(b"Encoding", self).ok();
+                let supported = matches!(encoding, None | Some(Object::Name(_)));
+                (font, supported)
+            }
+            "Type0" => {
+                let descendants = font
+                    .get_deref(b"DescendantFonts", self)
+                    .and_then(Object::as_array)
+                    .ok()?;
+                let descendant = self.dereference(descendants.first()?).ok()?.1.as_dict().ok()?;
+                if descendant.get(b"Subtype").and_then(Object::as_name_str).ok() != Some("CIDFontType2") {
+                    return None;
+                }
+                let encoding = font.get(b"Encoding").and_then(Object::as_name_str).ok();
+                let cid_to_gid = descendant.get_deref(b"CIDToGIDMap", self).ok();
+                let supported = matches!(encoding, Some("Identity-H") | Some("Identity-V"))
+                    && matches!(cid_to_gid, None | Some(Object::Name(_)));
+                (descendant, supported)
+            }
+            _ => return None,
+        };
+        let descriptor = descriptor_font
+            .get_deref(b"FontDescriptor", self)
+            .and_then(Object::as_dict)
+            .ok()?;
+        let font_file_id = descriptor.get(b"FontFile2").and_then(Object::as_reference).ok()?;
+        Some((font_file_id, supported))
+    }
+
+    /// Get the glyphs of the font file shown by the strings, `None` if they can't be determined.
+    fn shown_glyphs(
+        &self, font: &Dictionary, font_file_id: ObjectId, strings: &BTreeSet<Vec<u8>>,
+    ) -> Option<BTreeSet<u16>> {
+        if font.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Type0") {
+            // With Identity encoding and CIDToGIDMap, character codes are glyph ids.
+            let glyphs = strings.iter().flat_map(|string| {
+                string
+                    .chunks(2)
+                    .map(|code| u16::from_be_bytes([code[0], *code.get(1).unwrap_or(&0)]))
+            });
+            return Some(glyphs.collect());
+        }
+
+        let stream = self.get_object(font_file_id).and_then(Object::as_stream).ok()?;
+        let data = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+        let font_file = TrueTypeFont::parse(&data).ok()?;
+        let encoding = match font.get(b"Encoding").and_then(Object::as_name_str) {
+            Ok("WinAnsiEncoding") => WIN_ANSI_ENCODING,
+            Ok("MacRomanEncoding") => MAC_ROMAN_ENCODING,
+            Ok(_) => return None,
+            Err(_) => STANDARD_ENCODING,
+        };
+        let codes: BTreeSet<u8> = strings.iter().flatten().copied().collect();
+        let glyphs = codes.into_iter().filter_map(|code| {
+            if font_file.is_symbolic {
+                font_file.glyph_id(code as u32)
+            } else {
+                font_file.glyph_id(encoding[code as usize]? as u32)
+            }
+        });
+        Some(glyphs.collect())
+    }
+
+    /// Prefix the font name of a subset font and its descendant and descriptor with the tag.
+    fn tag_subset_font(&mut self, font_id: ObjectId, tag: &str) {
+        let mut ids = vec![font_id];
+        if let Ok(font) = self.get_dictionary(font_id) {
+            if let Ok(descendants) = font.get_deref(b"DescendantFonts", self).and_then(Object::as_array) {
+                ids.extend(
+                    descendants
+                        .iter()
+                        .filter_map(|descendant| descendant.as_reference().ok()),
+                );
+            }
+        }
+        let mut descriptors = vec![];
+        for id in ids {
+            let Ok(font) = self.get_dictionary_mut(id) else {
+                continue;
+            };
+            if let Ok(Object::Name(name)) = font.get_mut(b"BaseFont") {
+                *name = tagged_name(name, tag);
+            }
+            if let Ok(descriptor_id) = font.get(b"FontDescriptor").and_then(Object::as_reference) {
+                descriptors.push(descriptor_id);
+            }
+        }
+        for descriptor_id in descriptors {
+            if let Ok(Object::Name(name)) = self
+                .get_dictionary_mut(descriptor_id)
+                .and_then(|d| d.get_mut(b"FontName"))
+            {
+                *name = tagged_name(name, tag);
+            }
+        }
+    }
+}
+
+/// Strings shown with each font by the content streams of a document, and the fonts that can't
+/// be subset.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[derive(Default)]
+struct ShownStrings {
+    strings: BTreeMap<ObjectId, BTreeSet<Vec<u8>>>,
+    unsafe_fonts: BTreeSet<ObjectId>,
+}
+
+/// Tag of six uppercase letters derived from the glyphs of a subset.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn subset_tag(glyphs: &BTreeSet<u16>) -> String {
+    // FNV-1a hash of the glyph ids.
+    let mut hash = glyphs.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, &glyph_id| {
+        glyph_id
+            .to_be_bytes()
+            .iter()
+            .fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01B3))
+    });
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
+
+/// Replace the subset tag of a font name, or add one.
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+fn tagged_name(name: &[u8], tag: &str) -> Vec<u8> {
+    let untagged = match name.get(6) {
+        Some(b'+') if name[..6].iter().all(u8::is_ascii_uppercase) => &name[7..],
+        _ => name,
+    };
+    [tag.as_bytes(), b"+", untagged].concat()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn words(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|&value| (value as u16).to_be_bytes()).collect()
+    }
+
+    /// Build a small font with glyphs for space, `A` and `B`, with 2048 units per em.
+    /// `B` is a composite glyph made of the fifth glyph, which isn't mapped.
+    pub(crate) fn test_font() -> Vec<u8> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&2048u16.to_be_bytes());
+        head[36..44].copy_from_slice(&words(&[-100, -400, 1900, 1800]));
+        head[44..46].copy_from_slice(&2u16.to_be_bytes());
+        head[50..52].copy_from_slice(&1u16.to_be_bytes());
+        let glyphs = [
+            words(&[1, 0, 0, 100, 100, 0, 0, 0x0101]),
+            vec![],
+            words(&[1, 0, 0, 500, 700, 2, 0, 0x0102]),
+            words(&[-1, 0, 0, 600, 700, 0, 4, 0x0A14]),
+            words(&[1, 0, 0, 300, 300, 1, 0, 0x0103]),
+        ];
+        let mut glyf = vec![];
+        let mut loca = vec![];
+        for glyph in &glyphs {
+            loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+            glyf.extend_from_slice(glyph);
+        }
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+        let maxp = words(&[0, 0x5000, glyphs.len() as i32]);
+        let mut hhea = vec![0; 36];
+        hhea[4..8].copy_from_slice(&words(&[1600, -450]));
+        hhea[34..36].copy_from_slice(&3u16.to_be_bytes());
+        // Glyph 0 is .notdef, the fourth glyph shares the advance width of the last metric.
+        let hmtx = words(&[1000, 0, 512, 0, 1366, 50, 60]);
+        let mut os2 = vec![0; 96];
+        os2[0..2].copy_from_slice(&2u16.to_be_bytes());
+        os2[4..6].copy_from_slice(&700u16.to_be_bytes());
+        os2[88..90].copy_from_slice(&1434u16.to_be_bytes());
+        let mut post = vec![0; 32];
+        post[4..8].copy_from_slice(&(-12i32 * 65536).to_be_bytes());
+        // Format 4 subtable mapping space to glyph 1 and A, B to glyphs 2, 3.
+        let mut cmap = words(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(words(&[4, 40, 0, 6, 4, 1, 0]));
+        cmap.extend(words(&[
+            0x20, 0x42, 0xFFFF, 0, 0x20, 0x41, 0xFFFF, -0x1F, -0x3F, 1, 0, 0, 0,
+        ]));
+        let name_text: Vec<u8> = "Test Sans-Bold".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let mut name = words(&[0, 1, 18, 3, 1, 0x409, 6, name_text.len() as i32, 0]);
+        name.extend(name_text);
+        build_sfnt(&[
+            (*b"OS/2", os2),
+            (*b"cmap", cmap),
+            (*b"glyf", glyf),
+            (*b"head", head),
+            (*b"hhea", hhea),
+            (*b"hmtx", hmtx),
+            (*b"loca", loca),
+            (*b"maxp", maxp),
+            (*b"name", name),
+            (*b"post", post),
+        ])
+    }
+
+    #[test]
+    fn embed_truetype_font() {
+        let data = test_font();
+        let mut doc = Document::with_version("1.5");
+        let font_id = doc.add_truetype_font(&data).unwrap();
+
+        let font = doc.get_dictionary(font_id).unwrap();
+        assert_eq!(font.get(b"Subtype").and_then(Object::as_name_str).unwrap(), "TrueType");
+        assert_eq!(
+            font.get(b"BaseFont").and_then(Object::as_name_str).unwrap(),
+            "TestSans-Bold"
+        );
+        assert_eq!(
+            font.get(b"Encoding").and_then(Object::as_name_str).unwrap(),
+            "WinAnsiEncoding"
+        );
+        let widths = font.get(b"Widths").and_then(Object::as_array).unwrap();
+        assert_eq!(widths.len(), 224);
+        // Space, A and B are mapped, with scaled widths, other characters have none.
+        assert_eq!(widths[0].as_i64().unwrap(), 250);
+        assert_eq!(widths[(b'A' - 32) as usize].as_i64().unwrap(), 667);
+        assert_eq!(widths[(b'B' - 32) as usize].as_i64().unwrap(), 667);
+        assert_eq!(widths[(b'C' - 32) as usize].as_i64().unwrap(), 0);
+
+        let descriptor_id = font.get(b"FontDescriptor").and_then(Object::as_reference).unwrap();
+        let descriptor = doc.get_dictionary(descriptor_id).unwrap();
+        let get = |key: &[u8]| descriptor.get(key).unwrap();
+        assert_eq!(get(b"FontName").as_name_str().unwrap(), "TestSans-Bold");
+        assert_eq!(get(b"Flags").as_i64().unwrap(), NONSYMBOLIC | ITALIC | FORCE_BOLD);
+        let bbox: Vec<i64> = get(b"FontBBox")
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_i64().unwrap())
+            .collect();
+        assert_eq!(bbox, [-49, -195, 928, 879]);
+        assert_eq!(get(b"ItalicAngle").as_float().unwrap(), -12.0);
+        assert_eq!(get(b"Ascent").as_i64().unwrap(), 781);
+        assert_eq!(get(b"Descent").as_i64().unwrap(), -220);
+        assert_eq!(get(b"CapHeight").as_i64().unwrap(), 700);
+        assert_eq!(get(b"StemV").as_i64().unwrap(), 168);
+
+        let font_file_id = get(b"FontFile2").as_reference().unwrap();
+        let font_file = doc.get_object(font_file_id).and_then(Object::as_stream).unwrap();
+        assert_eq!(font_file.content, data);
+        assert_eq!(
+            font_file.dict.get(b"Length1").and_then(Object::as_i64).unwrap(),
+            data.len() as i64
+        );
+    }
+
+    #[test]
+    fn reject_invalid_fonts() {
+        let mut doc = Document::with_version("1.5");
+        assert!(matches!(
+            doc.add_truetype_font(b"OTTO\0\0\0\0"),
+            Err(Error::InvalidFont(_))
+        ));
+        assert!(matches!(
+            doc.add_truetype_font(&test_font()[..200]),
+            Err(Error::InvalidFont(_))
+        ));
+        assert!(doc.objects.is_empty());
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn subset_truetype_font() {
+        use crate::content::{Content, Operation};
+
+        let data = test_font();
+        let mut doc = Document::with_version("1.5");
+        let font_id = doc.add_truetype_font(&data).unwrap();
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new(
+                    "TJ",
+                    vec![vec![Object::string_literal("B"), 50.into(), Object::string_literal(" ")].into()],
+                ),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let resources = dictionary! { "Font" => dictionary! { "F1" => font_id } };
+        doc.add_page([0.0, 0.0, 612.0, 792.0], content, resources).unwrap();
+        assert_eq!(doc.subset_fonts().unwrap(), 1);
+
+        let font = doc.get_dictionary(font_id).unwrap();
+        let base_font = font.get(b"BaseFont").and_then(Object::as_name_str).unwrap().to_string();
+        assert_eq!(base_font.len(), 20);
+        assert!(base_font.ends_with("+TestSans-Bold"), "{}", base_font);
+        let descriptor_id = font.get(b"FontDescriptor").and_then(Object::as_reference).unwrap();
+        let descriptor = doc.get_dictionary(descriptor_id).unwrap();
+        assert_eq!(
+            descriptor.get(b"FontName").and_then(Object::as_name_str).unwrap(),
+            base_font
+        );
+
+        let font_file_id = descriptor.get(b"FontFile2").and_then(Object::as_reference).unwrap();
+        let font_file = doc.get_object(font_file_id).and_then(Object::as_stream).unwrap();
+        let subset = font_file
+            .decompressed_content()
+            .unwrap_or_else(|_| font_file.content.clone());
+        assert_eq!(
+            font_file.dict.get(b"Length1").and_then(Object::as_i64).unwrap(),
+            subset.len() as i64
+        );
+        assert!(subset.len() < data.len());
+        let subset = TrueTypeFont::parse(&subset).unwrap();
+        // The glyph of A is dropped, B and the glyph it is made of are kept with their ids.
+        let loca = subset.table(b"loca").unwrap();
+        let lengths: Vec<u32> = (0..5)
+            .map(|i| read_u32(loca, i * 4 + 4).unwrap() - read_u32(loca, i * 4).unwrap())
+            .collect();
+        assert_eq!(lengths, [16, 0, 0, 16, 16]);
+        assert_eq!(subset.glyph_id(b'A' as u32), None);
+        assert_eq!(subset.glyph_id(b'B' as u32), Some(3));
+        assert_eq!(subset.glyph_id(b' ' as u32), Some(1));
+        assert_eq!(subset.advance_width(3), 1366);
+        assert!(subset.table(b"post").is_some_and(|post| post.len() == 32));
+        assert_eq!(checksum(subset.data), 0xB1B0_AFBA);
+
+        // Subsetting again keeps the same glyphs under a single tag.
+        assert_eq!(doc.subset_fonts().unwrap(), 1);
+        let font = doc.get_dictionary(font_id).unwrap();
+        assert_eq!(font.get(b"BaseFont").and_then(Object::as_name_str).unwrap(), base_font);
+    }
+
+    #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+    #[test]
+    fn subset_fonts_shown_by_forms() {
+        use crate::content::Content;
+
+        let data = test_font();
+        let mut doc = Document::with_version("1.5");
+        let font_id = doc.add_truetype_font(&data).unwrap();
+        let broken_id = doc.add_truetype_font(&data).unwrap();
+        let font_file_id = |doc: &Document, font_id| {
+            let font = doc.get_dictionary(font_id).unwrap();
+            let descriptor = font
+                .get_deref(b"FontDescriptor", doc)
+                .and_then(Object::as_dict)
+                .unwrap();
+            descriptor.get(b"FontFile2").and_then(Object::as_reference).unwrap()
+        };
+        let broken_file_id = font_file_id(&doc, broken_id);
+        let broken_file = doc
+            .get_object_mut(broken_file_id)
+            .and_then(Object::as_stream_mut)
+            .unwrap();
+        broken_file.set_plain_content(b"garbage".to_vec());
+        // The form has no resources and no font of its own, it shows A with the font of the page.
+        let form_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            },
+            b"BT (A) Tj ET".to_vec(),
+        ));
+        let content = Content::decode(b"BT /F1 12 Tf ET q BT /F2 12 Tf ET Q /Fm0 Do").unwrap();
+        let resources = dictionary! {
+            "Font" => dictionary! { "F1" => font_id, "F2" => broken_id },
+            "XObject" => dictionary! { "Fm0" => form_id },
+        };
+        doc.add_page([0.0, 0.0, 612.0, 792.0], content, resources).unwrap();
+
+        // The font file that can't be parsed is left as it is.
+        assert_eq!(doc.subset_fonts().unwrap(), 1);
+        let broken_file = doc.get_object(broken_file_id).and_then(Object::as_stream).unwrap();
+        assert_eq!(broken_file.content, b"garbage");
+        let font_file = doc
+            .get_object(font_file_id(&doc, font_id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let subset = font_file
+            .decompressed_content()
+            .unwrap_or_else(|_| font_file.content.clone());
+        let subset = TrueTypeFont::parse(&subset).unwrap();
+        assert_eq!(subset.glyph_id(b'A' as u32), Some(2));
+        assert_eq!(subset.glyph_id(b'B' as u32), None);
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{Dictionary, Document, Object, ObjectId};
+
+/// Deeper page trees are malformed or cyclic.
+const PAGE_TREE_DEPTH_LIMIT: usize = 256;
+
+/// Structural problem of a document, found by [`Document::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Object the problem was found in, or `None` if it was found in the trailer.
+    pub id: Option<ObjectId>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(id: Option<ObjectId>, message: impl Into<String>) -> Self {
+        ValidationIssue {
+            id,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "object {} {}: {}", id.0, id.1, self.message),
+            None => write!(f, "trailer: {}", self.message),
+        }
+    }
+}
+
+impl Document {
+    /// Check the structure of the document, and return the problems found.
+    ///
+    /// The checks are for references to missing objects, object numbers that are reserved or
+    /// used with more than one generation, streams whose `Length` doesn't match their data, a
+    /// missing `Root` or `Pages`, and page tree nodes whose `Parent` isn't the node that lists
+    /// them in its `Kids`, or whose `Count` isn't their number of pages. An empty list doesn't
+    /// mean the document is valid, only that none of these problems were found.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        self.validate_ids(&mut issues);
+        self.validate_dictionary_references(None, &self.trailer, &mut issues);
+        for (&id, object) in &self.objects {
+            self.validate_references(Some(id), object, &mut issues);
+        }
+        self.validate_streams(&mut issues);
+        self.validate_page_tree(&mut issues);
+        issues
+    }
+
+    fn validate_ids(&self, issues: &mut Vec<ValidationIssue>) {
+        let mut previous: Option<ObjectId> = None;
+        for &id in self.objects.keys() {
+            if id.0 == 0 {
+                issues.push(ValidationIssue::new(Some(id), "object number 0 is reserved"));
+            } else if let Some(previous) = previous.filter(|previous| previous.0 == id.0) {
+                let message = format!("object number {} is also used by {} {}", id.0, previous.0, previous.1);
+                issues.push(ValidationIssue::new(Some(id), message));
+            }
+            previous = Some(id);
+        }
+    }
+
+    fn validate_references(&self, holder: Option<ObjectId>, object: &Object, issues: &mut Vec<ValidationIssue>) {
+        match object {
+            Object::Reference(id) if !self.objects.contains_key(id) => {
+                let message = format!("reference to missing object {} {} R", id.0, id.1);
+                issues.push(ValidationIssue::new(holder, message));
+            }
+            Object::Array(array) => {
+                for item in array {
+                    self.validate_references(holder, item, issues);
+                }
+            }
+            Object::Dictionary(dict) => self.validate_dictionary_references(holder, dict, issues),
+            Object::Stream(stream) => self.validate_dictionary_references(holder, &stream.dict, issues),
+            _ => {}
+        }
+    }
+
+    fn validate_dictionary_references(
+        &self, holder: Option<ObjectId>, dict: &Dictionary, issues: &mut Vec<ValidationIssue>,
+    ) {
+        for (_, value) in dict {
+            self.validate_references(holder, value, issues);
+        }
+    }
+
+    fn validate_streams(&self, issues: &mut Vec<ValidationIssue>) {
+        for (&id, object) in &self.objects {
+            let Object::Stream(stream) = object else {
+                continue;
+            };
+            if let Some(length) = stream.recovered_length {
+                let message = format!("/Length doesn't match the stream data, {} bytes were recovered", length);
+                issues.push(ValidationIssue::new(Some(id), message));
+                continue;
+            }
+            match stream.dict.get_deref(b"Length", self).and_then(Object::as_i64) {
+                Ok(length) if length == stream.content.len() as i64 => {}
+                Ok(length) => {
+                    let message = format!(
+                        "/Length is {}, but the stream has {} bytes",
+                        length,
+                        stream.content.len()
+                    );
+                    issues.push(ValidationIssue::new(Some(id), message));
+                }
+                Err(_) => issues.push(ValidationIssue::new(Some(id), "/Length is missing or not an integer")),
+            }
+        }
+    }
+
+    fn validate_page_tree(&self, issues: &mut Vec<ValidationIssue>) {
+        let root_id = match self.trailer.get(b"Root") {
+            Ok(Object::Reference(id)) => *id,
+            Ok(_) => return issues.push(ValidationIssue::new(None, "/Root is not a reference")),
+            Err(_) => return issues.push(ValidationIssue::new(None, "/Root is missing")),
+        };
+        // A missing catalog is reported as a reference to a missing object.
+        let Ok(catalog) = self.get_object(root_id) else {
+            return;
+        };
+        let Ok(catalog) = catalog.as_dict() else {
+            return issues.push(ValidationIssue::new(Some(root_id), "catalog is not a dictionary"));
+        };
+        match catalog.get(b"Pages") {
+            Ok(Object::Reference(pages_id)) => {
+                self.validate_page_tree_node(*pages_id, None, &mut HashSet::new(), 0, issues);
+            }
+            Ok(_) => issues.push(ValidationIssue::new(Some(root_id), "/Pages is not a reference")),
+            Err(_) => issues.push(ValidationIssue::new(Some(root_id), "/Pages is missing")),
+        }
+    }
+
+    /// Check a node of the page tree and its descendants, and return its number of pages.
+    fn validate_page_tree_node(
+        &self, id: ObjectId, parent: Option<ObjectId>, visited: &mut HashSet<ObjectId>, depth: usize,
+        issues: &mut Vec<ValidationIssue>,
+    ) -> i64 {
+        if !visited.insert(id) {
+            issues.push(ValidationIssue::new(
+                Some(id),
+                "page tree node is listed more than once",
+            ));
+            return 0;
+        }
+        // A missing node is reported as a reference to a missing object.
+        let Ok(node) = self.get_object(id) else {
+            return 0;
+        };
+        let Ok(node) = node.as_dict() else {
+            issues.push(ValidationIssue::new(Some(id), "page tree node is not a dictionary"));
+            return 0;
+        };
+
+        let node_parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+        if node_parent != parent {
+            let reference = |id: Option<ObjectId>| match id {
+                Some(id) => format!("{} {} R", id.0, id.1),
+                None => "missing".to_string(),
+            };
+            let message = format!("/Parent is {}, expected {}", reference(node_parent), reference(parent));
+            issues.push(ValidationIssue::new(Some(id), message));
+        }
+
+        match node.type_name() {
+            Ok("Page") => 1,
+            Ok("Pages") if depth >= PAGE_TREE_DEPTH_LIMIT => {
+                issues.push(ValidationIssue::new(Some(id), "page tree is nested too deeply"));
+                0
+            }
+            Ok("Pages") => {
+                let kids = node.get_deref(b"Kids", self).and_then(Object::as_array);
+                let Ok(kids) = kids else {
+                    issues.push(ValidationIssue::new(Some(id), "/Kids is missing or not an array"));
+                    return 0;
+                };
+                let mut pages = 0;
+                for kid in kids {
+                    match kid.as_reference() {
+                        Ok(kid_id) => {
+                            pages += self.validate_page_tree_node(kid_id, Some(id), visited, depth + 1, issues)
+                        }
+                        Err(_) => issues.push(ValidationIssue::new(
+                            Some(id),
+                            "/Kids has an entry that is not a reference",
+                        )),
+                    }
+                }
+                match node.get_deref(b"Count", self).and_then(Object::as_i64) {
+                    Ok(count) if count == pages => {}
+                    Ok(count) => {
+                        let message = format!("/Count is {}, but the node has {} pages", count, pages);
+                        issues.push(ValidationIssue::new(Some(id), message));
+                    }
+                    Err(_) => issues.push(ValidationIssue::new(Some(id), "/Count is missing or not an integer")),
+                }
+                pages
+            }
+            _ => {
+                issues.push(ValidationIssue::new(
+                    Some(id),
+                    "page tree node is neither /Page nor /Pages",
+                ));
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationIssue;
+    use crate::creator::tests::create_document;
+    use crate::{Document, Object, Stream};
+
+    /// Ids of the pages dictionary, the page and the content stream of `create_document`.
+    const PAGES: (u32, u16) = (2, 0);
+    const PAGE: (u32, u16) = (6, 0);
+    const CONTENT: (u32, u16) = (5, 0);
+
+    fn messages(doc: &Document) -> Vec<String> {
+        doc.validate().iter().map(ValidationIssue::to_string).collect()
+    }
+
+    #[test]
+    fn valid_document() {
+        let doc = create_document();
+        assert_eq!(doc.get_dictionary(PAGE).unwrap().type_name().unwrap(), "Page");
+        assert_eq!(doc.validate(), vec![]);
+
+        let mut buffer = Vec::new();
+        create_document().save_to(&mut buffer).unwrap();
+        #[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+        assert_eq!(Document::load_mem(&buffer).unwrap().validate(), vec![]);
+    }
+
+    #[test]
+    fn dangling_reference() {
+        let mut doc = create_document();
+        let page = doc.get_dictionary_mut(PAGE).unwrap();
+        page.set("Annots", vec![Object::Reference((99, 0))]);
+        assert_eq!(messages(&doc), ["object 6 0: reference to missing object 99 0 R"]);
+
+        doc.trailer.set("Info", Object::Reference((98, 0)));
+        assert_eq!(
+            doc.validate()[0],
+            ValidationIssue::new(None, "reference to missing object 98 0 R")
+        );
+    }
+
+    #[test]
+    fn missing_root() {
+        let mut doc = create_document();
+        doc.trailer.remove(b"Root");
+        assert_eq!(messages(&doc), ["trailer: /Root is missing"]);
+
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog" });
+        doc.trailer.set("Root", catalog_id);
+        assert_eq!(messages(&doc), ["object 8 0: /Pages is missing"]);
+    }
+
+    #[test]
+    fn wrong_parent() {
+        let mut doc = create_document();
+        let other_id = doc.add_object(dictionary! { "Type" => "Pages", "Kids" => vec![], "Count" => 0 });
+        doc.get_dictionary_mut(PAGE).unwrap().set("Parent", other_id);
+        assert_eq!(messages(&doc), ["object 6 0: /Parent is 8 0 R, expected 2 0 R"]);
+
+        doc.get_dictionary_mut(PAGE).unwrap().remove(b"Parent");
+        doc.get_dictionary_mut(PAGES).unwrap().set("Parent", other_id);
+        assert_eq!(
+            messages(&doc),
+            [
+                "object 2 0: /Parent is 8 0 R, expected missing",
+                "object 6 0: /Parent is missing, expected 2 0 R",
+            ]
+        );
+    }
+
+    #[test]
+    fn count_mismatch() {
+        let mut doc = create_document();
+        doc.get_dictionary_mut(PAGES).unwrap().set("Count", 2);
+        assert_eq!(messages(&doc), ["object 2 0: /Count is 2, but the node has 1 pages"]);
+
+        // A page listed twice is counted once.
+        let page = Object::Reference(PAGE);
+        doc.get_dictionary_mut(PAGES)
+            .unwrap()
+            .set("Kids", vec![page.clone(), page]);
+        assert_eq!(
+            messages(&doc),
+            [
+                "object 6 0: page tree node is listed more than once",
+                "object 2 0: /Count is 2, but the node has 1 pages",
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_length_mismatch() {
+        let mut doc = create_document();
+        let content = doc.get_object_mut(CONTENT).and_then(Object::as_stream_mut).unwrap();
+        content.content.truncate(10);
+        assert_eq!(
+            messages(&doc),
+            ["object 5 0: /Length is 44, but the stream has 10 bytes"]
+        );
+
+        let content = doc.get_object_mut(CONTENT).and_then(Object::as_stream_mut).unwrap();
+        content.dict.remove(b"Length");
+        assert_eq!(messages(&doc), ["object 5 0: /Length is missing or not an integer"]);
+
+        let mut stream = Stream::new(dictionary! {}, b"content".to_vec());
+        stream.recovered_length = Some(7);
+        doc.objects.insert(CONTENT, stream.into());
+        assert_eq!(
+            messages(&doc),
+            ["object 5 0: /Length doesn't match the stream data, 7 bytes were recovered"]
+        );
+    }
+
+    #[test]
+    fn duplicate_object_ids() {
+        let mut doc = create_document();
+        doc.objects.insert((6, 1), Object::Null);
+        doc.objects.insert((0, 0), Object::Null);
+        assert_eq!(
+            messages(&doc),
+            [
+                "object 0 0: object number 0 is reserved",
+                "object 6 1: object number 6 is also used by 6 0"
+            ]
+        );
+    }
+}
@@ -1,36 +1,97 @@
 use std::fs::File;
 use std::io::{BufWriter, Result, Write};
 use std::path::Path;
-use std::vec;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 
 use super::Object::*;
 use super::{Dictionary, Document, Object, Stream, StringFormat};
-use crate::{xref::*, IncrementalDocument};
+#[cfg(feature = "encrypt")]
+use crate::encryption::EncryptOptions;
+use crate::{filters, xref::*, IncrementalDocument};
 
 impl Document {
     /// Save PDF document to specified file path.
     #[inline]
     pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<File> {
         let mut file = BufWriter::new(File::create(path)?);
-        self.save_internal(&mut file)?;
+        self.save_internal(&mut file, SaveOptions::default())?;
         Ok(file.into_inner()?)
     }
 
-    /// Save PDF to arbitrary target
+    /// Save PDF to arbitrary target, such as a socket or a `Vec<u8>`, without a temporary file.
+    ///
+    /// The target only needs to implement `Write`: the byte offsets of the cross-reference table
+    /// are tracked by counting the bytes written, and nothing is written before the start of
+    /// the target. Wrap unbuffered targets in a [`std::io::BufWriter`], since objects are
+    /// written in many small pieces.
     #[inline]
     pub fn save_to<W: Write>(&mut self, target: &mut W) -> Result<()> {
-        self.save_internal(target)
+        self.save_internal(target, SaveOptions::default())
     }
 
-    fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
+    /// Save PDF to arbitrary target, choosing how the file is written.
+    #[inline]
+    pub fn save_with_options<W: Write>(&mut self, target: &mut W, options: SaveOptions) -> Result<()> {
+        self.save_internal(target, options)
+    }
+
+    /// Save PDF document to specified file path, encrypted with the standard security handler.
+    ///
+    /// The document itself is left unencrypted. See [`Document::encrypt`].
+    #[cfg(feature = "encrypt")]
+    pub fn save_encrypted<P: AsRef<Path>>(&self, path: P, options: EncryptOptions) -> crate::Result<File> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.save_encrypted_to(&mut file, options)?;
+        Ok(file.into_inner().map_err(|err| err.into_error())?)
+    }
+
+    /// Save PDF to arbitrary target, encrypted with the standard security handler.
+    #[cfg(feature = "encrypt")]
+    pub fn save_encrypted_to<W: Write>(&self, target: &mut W, options: EncryptOptions) -> crate::Result<()> {
+        let mut document = self.clone();
+        document.encrypt(&options)?;
+        document.save_internal(target, SaveOptions::default())?;
+        Ok(())
+    }
+
+    /// Save the document as an incremental update of the file it was loaded from.
+    ///
+    /// `original` must hold the bytes of the file and `previous` the document loaded from them,
+    /// before it was changed into this one. The bytes are written unchanged, followed by the
+    /// objects that were added or changed since, and a cross-reference section for them, which
+    /// also frees the numbers of deleted objects and whose `Prev` points to the last section of
+    /// `original`. See [`IncrementalDocument::create_from_changes`].
+    pub fn save_incremental<W: Write>(
+        &self, original: &[u8], previous: &Document, target: &mut W,
+    ) -> crate::Result<()> {
+        IncrementalDocument::create_from_changes(original.to_vec(), previous.clone(), self).save_to(target)?;
+        Ok(())
+    }
+
+    fn save_internal<W: Write>(&mut self, target: &mut W, options: SaveOptions) -> Result<()> {
         let mut target = CountingWrite {
             inner: target,
             bytes_written: 0,
         };
 
-        let mut xref = Xref::new(self.max_id + 1, self.reference_table.cross_reference_type);
+        // The objects of an encrypted document are encrypted one by one, with a key depending on
+        // their id, so they can't be packed into an object stream.
+        let use_object_streams = options.use_object_streams && !self.trailer.has(b"Encrypt");
+        // Objects in object streams can only be located through a cross reference stream.
+        let xref_type = match use_object_streams {
+            true => XrefType::CrossReferenceStream,
+            false => options.xref_type.unwrap_or(self.reference_table.cross_reference_type),
+        };
+        let mut xref = Xref::new(self.max_id + 1, xref_type);
+        let level = options.compression_level.unwrap_or(Stream::BEST_COMPRESSION);
+        let compression = Compression::new(level.min(Stream::BEST_COMPRESSION));
         writeln!(target, "%PDF-{}", self.version)?;
 
+        let unpackable =
+            [b"Root".as_slice(), b"Encrypt"].map(|key| self.trailer.get(key).and_then(Object::as_reference).ok());
+        let mut packed = Vec::new();
         for (&(id, generation), object) in &self.objects {
             if object
                 .type_name()
@@ -38,9 +99,23 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
                 .ok()
                 != Some(true)
             {
-                Writer::write_indirect_object(&mut target, id, generation, object, &mut xref)?;
+                if use_object_streams
+                    && generation == 0
+                    && !matches!(object, Stream(_))
+                    && !unpackable.contains(&Some((id, generation)))
+                {
+                    packed.push((id, object));
+                } else {
+                    Writer::write_indirect_object(&mut target, id, generation, object, &mut xref)?;
+                }
             }
         }
+        for objects in packed.chunks(OBJECTS_PER_STREAM) {
+            self.max_id += 1;
+            let object_stream = Writer::create_object_stream(objects, self.max_id, &mut xref, compression)?;
+            Writer::write_indirect_object(&mut target, self.max_id, 0, &Stream(object_stream), &mut xref)?;
+        }
+        xref.size = self.max_id + 1;
 
         let xref_start = target.bytes_written;
 
@@ -52,7 +127,12 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
             }
             XrefType::CrossReferenceStream => {
                 // Cross Reference Stream instead of XRef and Trailer
-                self.write_cross_reference_stream(&mut target, &mut xref, xref_start as u32)?;
+                let filter = if options.compress_xref_stream {
+                    XRefStreamFilter::FlateDecode
+                } else {
+                    XRefStreamFilter::None
+                };
+                self.write_cross_reference_stream(&mut target, &mut xref, xref_start as u32, filter, compression)?;
             }
         }
         // Write `startxref` part of trailer
@@ -66,7 +146,8 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
     /// Insert an `Object` to the end of the PDF (not visible when inspecting `Document`).
     /// Note: This is different from the "Cross Reference Table".
     fn write_cross_reference_stream<W: Write>(
-        &mut self, file: &mut CountingWrite<&mut W>, xref: &mut Xref, xref_start: u32,
+        &mut self, file: &mut CountingWrite<&mut W>, xref: &mut Xref, xref_start: u32, filter: XRefStreamFilter,
+        compression: Compression,
     ) -> Result<()> {
         // Increment max_id to account for CRS.
         self.max_id += 1;
@@ -78,33 +159,44 @@ fn write_cross_reference_stream<W: Write>(
                 generation: 0,
             },
         );
-        self.trailer.set("Type", Name(b"XRef".to_vec()));
         // Update `max_id` in trailer
         self.trailer.set("Size", i64::from(self.max_id + 1));
-        // Set the size of each entry in bytes (default for PDFs is `[1 2 1]`)
-        // In our case we use `[u8, u32, u16]` for each entry
-        // to keep things simple and working at all times.
-        self.trailer.set("W", Array(vec![Integer(1), Integer(4), Integer(2)]));
-        // Note that `ASCIIHexDecode` does not work correctly,
-        // but is still useful for debugging sometimes.
-        let filter = XRefStreamFilter::None;
-        let (stream, stream_length, indexes) = Writer::create_xref_steam(xref, filter)?;
-        self.trailer.set("Index", indexes);
-
-        if filter == XRefStreamFilter::ASCIIHexDecode {
-            self.trailer.set("Filter", Name(b"ASCIIHexDecode".to_vec()));
-        } else {
-            self.trailer.remove(b"Filter");
-        }
 
-        self.trailer.set("Length", stream_length as i64);
+        // The stream dictionary holds the trailer entries, but they are not kept in the trailer
+        // so the document can still be saved with a cross reference table afterwards.
+        let mut dict = self.trailer.clone();
+        dict.set("Type", Name(b"XRef".to_vec()));
+        let (content, widths, indexes) = Writer::create_xref_steam(xref);
+        dict.set("W", Array(widths.iter().map(|&width| Integer(width as i64)).collect()));
+        dict.set("Index", indexes);
+        dict.remove(b"DecodeParms");
+
+        let content = match filter {
+            XRefStreamFilter::None => {
+                dict.remove(b"Filter");
+                content
+            }
+            // Note that `ASCIIHexDecode` is mostly useful for debugging.
+            XRefStreamFilter::ASCIIHexDecode => {
+                dict.set("Filter", Name(b"ASCIIHexDecode".to_vec()));
+                filters::ascii_hex::encode(&content)
+            }
+            XRefStreamFilter::FlateDecode => {
+                dict.set("Filter", Name(b"FlateDecode".to_vec()));
+                let mut encoder = ZlibEncoder::new(Vec::new(), compression);
+                encoder.write_all(&content)?;
+                encoder.finish()?
+            }
+        };
+        dict.set("Length", content.len() as i64);
 
-        let trailer = &self.trailer;
         let cross_reference_stream = Stream(Stream {
-            dict: trailer.clone(),
-            allows_compression: true,
-            content: stream,
+            dict,
+            allows_compression: false,
+            content,
             start_position: None,
+            recovered_length: None,
+            decompressed: Default::default(),
         });
         // Insert Cross Reference Stream as an `Object` to the end of the PDF.
         // The `Object` is not added to `Document` because it is generated every time you save.
@@ -145,13 +237,21 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
         // Write previous document versions.
         let prev_document_bytes = self.get_prev_documents_bytes();
         target.inner.write_all(prev_document_bytes)?;
-        target.bytes_written += prev_document_bytes.len();
+        // Offsets in the file are relative to its header, so the ones of the update must be too.
+        let header_offset = self.get_prev_documents().header_offset;
+        target.bytes_written += prev_document_bytes.len().saturating_sub(header_offset);
 
         // Write/Append new document version.
         let mut xref = Xref::new(
             self.new_document.max_id + 1,
             self.get_prev_documents().reference_table.cross_reference_type,
         );
+        // Objects deleted by the update.
+        for (&id, entry) in &self.new_document.reference_table.entries {
+            if let XrefEntry::Free { .. } | XrefEntry::UnusableFree = entry {
+                xref.insert(id, entry.clone());
+            }
+        }
 
         if let Some(last_byte) = prev_document_bytes.last() {
             if *last_byte != b'\n' {
@@ -182,8 +282,13 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
             }
             XrefType::CrossReferenceStream => {
                 // Cross Reference Stream instead of XRef and Trailer
-                self.new_document
-                    .write_cross_reference_stream(&mut target, &mut xref, xref_start as u32)?;
+                self.new_document.write_cross_reference_stream(
+                    &mut target,
+                    &mut xref,
+                    xref_start as u32,
+                    XRefStreamFilter::None,
+                    Compression::best(),
+                )?;
             }
         }
         // Write `startxref` part of trailer
@@ -193,12 +298,36 @@ fn save_internal<W: Write>(&mut self, target: &mut W) -> Result<()> {
     }
 }
 
+/// Options for [`Document::save_with_options`].
+///
+/// The default options save a document the same way as [`Document::save`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SaveOptions {
+    /// Whether to write a cross reference table or a cross reference stream.
+    /// When not set, the type of the document's `reference_table` is used.
+    pub xref_type: Option<XrefType>,
+    /// Whether to compress a cross reference stream with `FlateDecode`.
+    pub compress_xref_stream: bool,
+    /// Whether to pack objects into compressed object streams, which always writes a cross
+    /// reference stream. Streams, objects with a non-zero generation, the catalog and the
+    /// encryption dictionary are still written on their own. Ignored for encrypted documents.
+    pub use_object_streams: bool,
+    /// Zlib level from 0 (no compression) to 9 (best compression) of the object streams and
+    /// cross reference stream written. When not set, 9 is used. Other streams are compressed by
+    /// [`Document::compress_with_level`].
+    pub compression_level: Option<u32>,
+}
+
+/// Maximum number of objects packed into one object stream.
+const OBJECTS_PER_STREAM: usize = 100;
+
 pub struct Writer;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum XRefStreamFilter {
+    #[allow(dead_code)] // Only used for debugging.
     ASCIIHexDecode,
-    _FlateDecode, //this is generally a Zlib compressed Stream.
+    FlateDecode, //this is generally a Zlib compressed Stream.
     None,
 }
 
@@ -238,8 +367,8 @@ fn write_xref(file: &mut dyn Write, xref: &Xref) -> Result<()> {
                     XrefEntry::Compressed { container: _, index: _ } => {
                         xref_section.add_unusable_free_entry();
                     }
-                    XrefEntry::Free => {
-                        xref_section.add_entry(XrefEntry::Free);
+                    XrefEntry::Free { generation } => {
+                        xref_section.add_entry(XrefEntry::Free { generation });
                     }
                     XrefEntry::UnusableFree => {
                         xref_section.add_unusable_free_entry();
@@ -261,7 +390,10 @@ fn write_xref(file: &mut dyn Write, xref: &Xref) -> Result<()> {
     }
 
     /// Create stream for Cross reference stream.
-    fn create_xref_steam(xref: &Xref, filter: XRefStreamFilter) -> Result<(Vec<u8>, usize, Object)> {
+    ///
+    /// Returns the content, the width of each of the three fields of an entry and the `Index` array.
+    /// Each field is only as wide as needed for its largest value.
+    fn create_xref_steam(xref: &Xref) -> (Vec<u8>, [usize; 3], Object) {
         let mut xref_sections = Vec::new();
         let mut xref_section = XrefSection::new(0);
 
@@ -285,60 +417,73 @@ fn create_xref_steam(xref: &Xref, filter: XRefStreamFilter) -> Result<(Vec<u8>,
             xref_sections.push(xref_section);
         }
 
-        let mut xref_stream = Vec::new();
         let mut xref_index = Vec::new();
-
+        let mut rows = Vec::new();
         for section in xref_sections {
             // Add indexes to list
             xref_index.push(Integer(section.starting_id as i64));
             xref_index.push(Integer(section.entries.len() as i64));
-            // Add entries to stream
-            let mut obj_id = section.starting_id;
-            for entry in section.entries {
-                match entry {
-                    XrefEntry::Free => {
-                        // Type 0
-                        xref_stream.push(0); // Type 0
-                        xref_stream.extend(obj_id.to_be_bytes());
-                        xref_stream.extend(vec![0, 0]); // TODO add generation number
-                    }
-                    XrefEntry::UnusableFree => {
-                        // Type 0
-                        xref_stream.push(0); // Type 0
-                        xref_stream.extend(obj_id.to_be_bytes());
-                        xref_stream.extend(65535_u16.to_be_bytes());
-                    }
-                    XrefEntry::Normal { offset, generation } => {
-                        // Type 1
-                        xref_stream.push(1); // Type 1
-                        xref_stream.extend(offset.to_be_bytes());
-                        xref_stream.extend(generation.to_be_bytes());
-                    }
-                    XrefEntry::Compressed { container, index } => {
-                        // Type 2
-                        xref_stream.push(2); // Type 2
-                        xref_stream.extend(container.to_be_bytes());
-                        xref_stream.extend(index.to_be_bytes());
-                    }
-                }
-                obj_id += 1;
+            for (obj_id, entry) in (section.starting_id..).zip(section.entries) {
+                rows.push(match entry {
+                    XrefEntry::Free { generation } => [0, obj_id as u64, generation as u64],
+                    XrefEntry::UnusableFree => [0, obj_id as u64, 65535],
+                    XrefEntry::Normal { offset, generation } => [1, offset as u64, generation as u64],
+                    XrefEntry::Compressed { container, index } => [2, container as u64, index as u64],
+                });
             }
         }
 
-        // The end of line character should not be counted, added later.
-        let stream_length = xref_stream.len();
+        let byte_len = |value: u64| (64 - value.leading_zeros() as usize).div_ceil(8);
+        let mut widths = [1, 1, 0];
+        for row in &rows {
+            for (width, &value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(byte_len(value));
+            }
+        }
 
-        if filter == XRefStreamFilter::ASCIIHexDecode {
-            xref_stream = xref_stream
-                .iter()
-                .flat_map(|c| format!("{:02X}", c).as_bytes().to_vec())
-                .collect::<Vec<u8>>();
+        let mut xref_stream = Vec::with_capacity(rows.len() * widths.iter().sum::<usize>());
+        for row in rows {
+            for (&width, value) in widths.iter().zip(row) {
+                xref_stream.extend_from_slice(&value.to_be_bytes()[8 - width..]);
+            }
         }
 
-        Ok((xref_stream, stream_length, Array(xref_index)))
+        (xref_stream, widths, Array(xref_index))
+    }
+
+    /// Create a compressed object stream holding `objects` and add their entries to `xref`.
+    fn create_object_stream(
+        objects: &[(u32, &Object)], container: u32, xref: &mut Xref, compression: Compression,
+    ) -> Result<Stream> {
+        let mut offsets = Vec::new();
+        let mut body = Vec::new();
+        for (index, &(id, object)) in objects.iter().enumerate() {
+            write!(offsets, "{} {} ", id, body.len())?;
+            Writer::write_object(&mut body, object)?;
+            body.push(b'\n');
+            xref.insert(
+                id,
+                XrefEntry::Compressed {
+                    container,
+                    index: index as u16,
+                },
+            );
+        }
+        let first = offsets.len();
+        offsets.extend(body);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), compression);
+        encoder.write_all(&offsets)?;
+        let dict = dictionary! {
+            "Type" => "ObjStm",
+            "N" => objects.len() as i64,
+            "First" => first as i64,
+            "Filter" => "FlateDecode",
+        };
+        Ok(Stream::new(dict, encoder.finish()?).with_compression(false))
     }
 
-    fn write_indirect_object<W: Write>(
+    pub(crate) fn write_indirect_object<W: Write>(
         file: &mut CountingWrite<&mut W>, id: u32, generation: u16, object: &Object, xref: &mut Xref,
     ) -> Result<()> {
         let offset = file.bytes_written as u32;
@@ -373,7 +518,7 @@ pub fn write_object(file: &mut dyn Write, object: &Object) -> Result<()> {
                 let mut buf = itoa::Buffer::new();
                 file.write_all(buf.format(*value).as_bytes())
             }
-            Real(ref value) => write!(file, "{}", value),
+            Real(ref value) => Writer::write_real(file, *value),
             Name(ref name) => Writer::write_name(file, name),
             String(ref text, ref format) => Writer::write_string(file, text, format),
             Array(ref array) => Writer::write_array(file, array),
@@ -383,6 +528,15 @@ pub fn write_object(file: &mut dyn Write, object: &Object) -> Result<()> {
         }
     }
 
+    /// Write a real number in decimal notation, using the shortest form that reads back as the
+    /// same number. Numbers that can't be represented in PDF are written as 0.
+    fn write_real(file: &mut dyn Write, value: f32) -> Result<()> {
+        if !value.is_finite() || value == 0.0 {
+            return file.write_all(b"0");
+        }
+        write!(file, "{}", value)
+    }
+
     fn write_name(file: &mut dyn Write, name: &[u8]) -> Result<()> {
         file.write_all(b"/")?;
         for &byte in name {
@@ -464,7 +618,7 @@ fn write_array(file: &mut dyn Write, array: &[Object]) -> Result<()> {
         Ok(())
     }
 
-    fn write_dictionary(file: &mut dyn Write, dictionary: &Dictionary) -> Result<()> {
+    pub(crate) fn write_dictionary(file: &mut dyn Write, dictionary: &Dictionary) -> Result<()> {
         file.write_all(b"<<")?;
         for (key, value) in dictionary {
             Writer::write_name(file, key)?;
@@ -487,8 +641,8 @@ fn write_stream(file: &mut dyn Write, stream: &Stream) -> Result<()> {
 }
 
 pub struct CountingWrite<W: Write> {
-    inner: W,
-    bytes_written: usize,
+    pub(crate) inner: W,
+    pub(crate) bytes_written: usize,
 }
 
 impl<W: Write> Write for CountingWrite<W> {
@@ -552,3 +706,211 @@ fn save_document() {
     // Check if the file is above 400 bytes (should be about 610 bytes)
     assert!(file_path.metadata().unwrap().len() > 400);
 }
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[test]
+fn save_to_buffer() {
+    let mut doc = crate::creator::tests::create_document();
+    let mut buffer = Vec::new();
+    doc.save_to(&mut buffer).unwrap();
+
+    let loaded = Document::load_mem(&buffer).unwrap();
+    assert_eq!(loaded.get_pages().len(), 1);
+    // The offsets of the cross-reference table are those of the objects in the buffer.
+    for (&id, entry) in &loaded.reference_table.entries {
+        if let XrefEntry::Normal { offset, generation } = *entry {
+            let header = format!("{} {} obj", id, generation);
+            assert!(buffer[offset as usize..].starts_with(header.as_bytes()));
+        }
+    }
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[test]
+fn name_round_trip() {
+    let name = Name(b"Foo Bar".to_vec());
+    let mut buffer = Vec::new();
+    Writer::write_object(&mut buffer, &name).unwrap();
+    assert_eq!(buffer, b"/Foo#20Bar");
+    assert_eq!(crate::parser::direct_object(&buffer), Some(name));
+
+    let name = Name(b"(A)/[B]#%\n\xE9".to_vec());
+    let mut buffer = Vec::new();
+    Writer::write_object(&mut buffer, &name).unwrap();
+    assert_eq!(buffer, b"/#28A#29#2F#5BB#5D#23#25#0A#E9");
+    assert_eq!(crate::parser::direct_object(&buffer), Some(name));
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[test]
+fn save_with_xref_types() {
+    let mut doc = Document::with_version("1.5");
+    for i in 0..300 {
+        doc.add_object(dictionary! { "Index" => i });
+    }
+    let root = doc.add_object(dictionary! { "Type" => "Catalog" });
+    doc.trailer.set("Root", root);
+
+    let save = |doc: &mut Document, options: SaveOptions| {
+        let mut buffer = Vec::new();
+        doc.save_with_options(&mut buffer, options).unwrap();
+        buffer
+    };
+    let table = save(
+        &mut doc,
+        SaveOptions {
+            xref_type: Some(XrefType::CrossReferenceTable),
+            ..SaveOptions::default()
+        },
+    );
+    let stream = save(
+        &mut doc,
+        SaveOptions {
+            xref_type: Some(XrefType::CrossReferenceStream),
+            compress_xref_stream: true,
+            ..SaveOptions::default()
+        },
+    );
+    assert!(!doc.trailer.has(b"W"));
+    assert!(stream.len() < table.len());
+
+    for buffer in [table, stream] {
+        let loaded = Document::load_mem(&buffer).unwrap();
+        assert_eq!(loaded.get_object((300, 0)).unwrap(), doc.get_object((300, 0)).unwrap());
+        assert_eq!(loaded.catalog().unwrap().type_name().unwrap(), "Catalog");
+    }
+    let loaded = Document::load_mem(&save(&mut doc, SaveOptions::default())).unwrap();
+    assert!(matches!(
+        loaded.reference_table.cross_reference_type,
+        XrefType::CrossReferenceStream
+    ));
+}
+
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[test]
+fn save_with_object_streams() {
+    let mut doc = Document::with_version("1.5");
+    let annots: Vec<Object> = (0..500)
+        .map(|i| {
+            doc.add_object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Text",
+                "Contents" => Object::string_literal(format!("Note {}", i)),
+            })
+            .into()
+        })
+        .collect();
+    let content_id = doc.add_object(Stream::new(dictionary! {}, b"BT ET".to_vec()));
+    let array_id = doc.add_object(annots);
+    let root = doc.add_object(dictionary! { "Type" => "Catalog", "Annots" => array_id, "Content" => content_id });
+    doc.trailer.set("Root", root);
+
+    let mut plain = Vec::new();
+    doc.clone().save_to(&mut plain).unwrap();
+    let mut packed = Vec::new();
+    let options = SaveOptions {
+        use_object_streams: true,
+        ..SaveOptions::default()
+    };
+    doc.clone().save_with_options(&mut packed, options).unwrap();
+    assert!(packed.len() * 2 < plain.len(), "{} {}", packed.len(), plain.len());
+    let mut fast = Vec::new();
+    let options = SaveOptions {
+        compression_level: Some(1),
+        ..options
+    };
+    doc.clone().save_with_options(&mut fast, options).unwrap();
+    assert!(packed.len() <= fast.len() && fast.len() < plain.len());
+    assert_eq!(
+        Document::load_mem(&fast).unwrap().get_object(root).unwrap(),
+        doc.get_object(root).unwrap()
+    );
+
+    let loaded = Document::load_mem(&packed).unwrap();
+    for (id, object) in &doc.objects {
+        assert_eq!(loaded.get_object(*id).unwrap(), object);
+    }
+    let object_streams = loaded
+        .objects
+        .values()
+        .filter(|object| object.type_name().ok() == Some("ObjStm"));
+    assert_eq!(object_streams.count(), 6);
+    assert!(matches!(
+        loaded.reference_table.get(root.0),
+        Some(XrefEntry::Normal { .. })
+    ));
+}
+
+#[cfg(all(feature = "encrypt", any(feature = "pom_parser", feature = "nom_parser")))]
+#[test]
+fn save_encrypted_document_with_object_streams() {
+    let mut doc = Document::with_version("1.5");
+    let info_id = doc.add_object(dictionary! { "Title" => Object::string_literal("Private title") });
+    let root = doc.add_object(dictionary! { "Type" => "Catalog" });
+    doc.trailer.set("Root", root);
+    doc.trailer.set("Info", info_id);
+    doc.encrypt(&EncryptOptions::default()).unwrap();
+
+    let mut buffer = Vec::new();
+    let options = SaveOptions {
+        use_object_streams: true,
+        ..SaveOptions::default()
+    };
+    doc.save_with_options(&mut buffer, options).unwrap();
+    let loaded = Document::load_mem_encrypted(&buffer, "").unwrap();
+    let info = loaded.get_dictionary(info_id).unwrap();
+    assert_eq!(info.get(b"Title").unwrap().as_str().unwrap(), b"Private title");
+    assert!(!buffer.windows(7).any(|window| window == b"/ObjStm"));
+}
+
+#[cfg(all(feature = "encrypt", any(feature = "pom_parser", feature = "nom_parser")))]
+#[test]
+fn save_encrypted_document() {
+    use crate::encryption::CryptMethod;
+
+    let mut doc = Document::with_version("1.5");
+    let content_id = doc.add_object(Stream::new(dictionary! {}, b"BT (Secret text) Tj ET".to_vec()));
+    let info_id = doc.add_object(dictionary! { "Title" => Object::string_literal("Private title") });
+    let root = doc.add_object(dictionary! { "Type" => "Catalog", "Content" => content_id });
+    doc.trailer.set("Root", root);
+    doc.trailer.set("Info", info_id);
+
+    for method in [CryptMethod::Rc4, CryptMethod::Aes128, CryptMethod::Aes256] {
+        let options = EncryptOptions {
+            user_password: "user".to_string(),
+            owner_password: "owner".to_string(),
+            method,
+            ..EncryptOptions::default()
+        };
+        let mut buffer = Vec::new();
+        doc.save_encrypted_to(&mut buffer, options.clone()).unwrap();
+        // Object streams are not used for encrypted documents.
+        let mut packed = Vec::new();
+        let mut encrypted = doc.clone();
+        encrypted.encrypt(&options).unwrap();
+        let save_options = SaveOptions {
+            use_object_streams: true,
+            ..SaveOptions::default()
+        };
+        encrypted.save_with_options(&mut packed, save_options).unwrap();
+
+        for buffer in [buffer, packed] {
+            assert!(!buffer.windows(6).any(|window| window == b"Secret"));
+            assert!(!buffer.windows(7).any(|window| window == b"Private"));
+
+            let encrypted = Document::load_mem(&buffer).unwrap();
+            assert!(encrypted.is_encrypted());
+            assert!(encrypted.trailer.get(b"ID").is_ok());
+            assert!(Document::load_mem_encrypted(&buffer, "wrong").is_err());
+            for password in ["user", "owner"] {
+                let loaded = Document::load_mem_encrypted(&buffer, password).unwrap();
+                assert_eq!(
+                    loaded.get_object(content_id).unwrap(),
+                    doc.get_object(content_id).unwrap()
+                );
+                assert_eq!(loaded.get_object(info_id).unwrap(), doc.get_object(info_id).unwrap());
+            }
+        }
+    }
+    assert!(!doc.is_encrypted());
+}
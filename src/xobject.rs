@@ -25,6 +25,87 @@ pub fn form(boundingbox: Vec<f32>, matrix: Vec<f32>, content: Vec<u8>) -> Stream
     xobject
 }
 
+/// Encoding of the data returned by [`Stream::image_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageEncoding {
+    /// Decoded samples, `BitsPerComponent` bits for each color component of each pixel.
+    Raw,
+    /// A JPEG file, from the `DCTDecode` filter.
+    Jpeg,
+    /// A JPEG 2000 file, from the `JPXDecode` filter.
+    Jpeg2000,
+    /// JBIG2 data, from the `JBIG2Decode` filter.
+    Jbig2,
+    /// CCITT fax data, from the `CCITTFaxDecode` filter.
+    CcittFax,
+}
+
+/// Data of an image XObject or inline image, with the metadata needed to interpret it.
+#[derive(Debug, Clone)]
+pub struct ImageBytes {
+    pub encoding: ImageEncoding,
+    /// The `ColorSpace` entry, which is missing for image masks and JPEG 2000 images.
+    pub color_space: Option<Object>,
+    /// The `BitsPerComponent` entry, 1 for image masks.
+    pub bits_per_component: Option<i64>,
+    pub data: Vec<u8>,
+}
+
+/// An image drawn by a page, as returned by [`Document::extract_images`].
+#[derive(Debug, Clone)]
+pub struct PdfImage {
+    /// Id of the image XObject.
+    pub id: ObjectId,
+    pub width: i64,
+    pub height: i64,
+    /// Whether the image is a stencil mask (`ImageMask`), painting the current color where its
+    /// samples are 0.
+    pub is_mask: bool,
+    /// The image data, with its color space resolved if it was a reference.
+    pub content: ImageBytes,
+    /// The explicit mask image of the `Mask` entry.
+    pub mask: Option<Box<PdfImage>>,
+    /// The soft mask image of the `SMask` entry, giving the alpha channel of the image.
+    pub soft_mask: Option<Box<PdfImage>>,
+}
+
+impl Stream {
+    /// Get the data of an image stream.
+    ///
+    /// All filters are decoded except a final image filter such as `DCTDecode`, whose data is
+    /// returned as is, so a JPEG image can be saved directly as a `.jpg` file.
+    pub fn image_bytes(&self) -> Result<ImageBytes> {
+        let mut filters = match self.dict.get(b"Filter") {
+            Ok(_) => self.filters()?,
+            Err(_) => vec![],
+        };
+        let encoding = match filters.last().map(String::as_str) {
+            Some("DCTDecode") => ImageEncoding::Jpeg,
+            Some("JPXDecode") => ImageEncoding::Jpeg2000,
+            Some("JBIG2Decode") => ImageEncoding::Jbig2,
+            Some("CCITTFaxDecode") => ImageEncoding::CcittFax,
+            _ => ImageEncoding::Raw,
+        };
+        if encoding != ImageEncoding::Raw {
+            filters.pop();
+        }
+
+        let is_mask = self.dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+        let bits_per_component = if is_mask {
+            Some(1)
+        } else {
+            self.dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok()
+        };
+
+        Ok(ImageBytes {
+            encoding,
+            color_space: self.dict.get(b"ColorSpace").ok().cloned(),
+            bits_per_component,
+            data: self.decode_filters(&filters)?,
+        })
+    }
+}
+
 #[cfg(feature = "embed_image")]
 pub fn image<P: AsRef<Path>>(path: P) -> Result<Stream> {
     use std::fs::File;
@@ -110,3 +191,36 @@ async fn insert_image() {
     doc.insert_image(page_id, img, (100.0, 210.0), (400.0, 225.0)).unwrap();
     doc.save("test_5_image.pdf").unwrap();
 }
+
+#[test]
+fn image_bytes() {
+    let jpeg = b"\xFF\xD8\xFF\xE0 JPEG data \xFF\xD9".to_vec();
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        },
+        jpeg.clone(),
+    );
+    stream.compress_with("ASCIIHexDecode").unwrap();
+    let image = stream.image_bytes().unwrap();
+    assert_eq!(image.encoding, ImageEncoding::Jpeg);
+    assert_eq!(image.color_space, Some(Object::Name(b"DeviceRGB".to_vec())));
+    assert_eq!(image.bits_per_component, Some(8));
+    assert_eq!(image.data, jpeg);
+
+    let pixels = vec![0, 64, 128, 255];
+    let mut stream = Stream::new(
+        dictionary! { "Subtype" => "Image", "ImageMask" => true },
+        pixels.clone(),
+    );
+    stream.compress_with("FlateDecode").unwrap();
+    let image = stream.image_bytes().unwrap();
+    assert_eq!(image.encoding, ImageEncoding::Raw);
+    assert_eq!(image.color_space, None);
+    assert_eq!(image.bits_per_component, Some(1));
+    assert_eq!(image.data, pixels);
+}
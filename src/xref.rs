@@ -25,10 +25,19 @@ pub enum XrefType {
 
 #[derive(Debug, Clone)]
 pub enum XrefEntry {
-    Free, // TODO add generation number
+    /// Free object number, with the generation number to use if it is used again.
+    Free {
+        generation: u16,
+    },
     UnusableFree,
-    Normal { offset: u32, generation: u16 },
-    Compressed { container: u32, index: u16 },
+    Normal {
+        offset: u32,
+        generation: u16,
+    },
+    Compressed {
+        container: u32,
+        index: u16,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -92,8 +101,8 @@ pub fn write_xref_entry(&self, file: &mut dyn Write) -> Result<()> {
             XrefEntry::Compressed { container: _, index: _ } => {
                 writeln!(file, "{:>010} {:>05} f ", 0, 65535)?;
             }
-            XrefEntry::Free => {
-                writeln!(file, "{:>010} {:>05} f ", 0, 0)?;
+            XrefEntry::Free { generation } => {
+                writeln!(file, "{:>010} {:>05} f ", 0, generation)?;
             }
             XrefEntry::UnusableFree => {
                 writeln!(file, "{:>010} {:>05} f ", 0, 65535)?;
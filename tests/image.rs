@@ -0,0 +1,87 @@
+use lopdf::xobject::ImageEncoding;
+use lopdf::{dictionary, Document, Object, Stream};
+
+#[test]
+fn extract_images_with_masks() {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let soft_mask_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 2,
+            "Height" => 1,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        },
+        vec![0, 255],
+    ));
+    let color_space_id = doc.add_object(Object::Name(b"DeviceRGB".to_vec()));
+    let jpeg = b"\xFF\xD8\xFF\xE0 JPEG data \xFF\xD9".to_vec();
+    let photo_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 2,
+            "Height" => 1,
+            "ColorSpace" => color_space_id,
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+            "SMask" => soft_mask_id,
+        },
+        jpeg.clone(),
+    ));
+    let mut stencil = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 8,
+            "Height" => 2,
+            "ImageMask" => true,
+        },
+        vec![0b1010_1010, 0b0101_0101],
+    );
+    stencil.compress_with("FlateDecode").unwrap();
+    let stencil_id = doc.add_object(stencil);
+    let form_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im2" => stencil_id, "Im1" => photo_id } },
+        },
+        b"/Im2 Do".to_vec(),
+    ));
+
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => photo_id, "Fm1" => form_id } },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+    );
+
+    let images = doc.extract_images(page_id).unwrap();
+    assert_eq!(images.len(), 2);
+
+    let photo = &images[0];
+    assert_eq!(photo.id, photo_id);
+    assert_eq!((photo.width, photo.height), (2, 1));
+    assert_eq!(photo.content.encoding, ImageEncoding::Jpeg);
+    assert_eq!(photo.content.color_space, Some(Object::Name(b"DeviceRGB".to_vec())));
+    assert_eq!(photo.content.data, jpeg);
+    let soft_mask = photo.soft_mask.as_ref().unwrap();
+    assert_eq!(soft_mask.id, soft_mask_id);
+    assert_eq!(soft_mask.content.data, [0, 255]);
+    assert!(photo.mask.is_none());
+
+    let stencil = &images[1];
+    assert_eq!(stencil.id, stencil_id);
+    assert!(stencil.is_mask);
+    assert_eq!(stencil.content.bits_per_component, Some(1));
+    assert_eq!(stencil.content.data, [0b1010_1010, 0b0101_0101]);
+
+    assert!(doc.extract_images(photo_id).is_err());
+}
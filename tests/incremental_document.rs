@@ -1,7 +1,7 @@
 // Only run test when parser is enabled
 #![cfg(any(feature = "pom_parser", feature = "nom_parser"))]
 
-use lopdf::Result;
+use lopdf::{Document, Object, Result};
 use tempfile::tempdir;
 
 mod utils;
@@ -26,3 +26,72 @@ fn load_incremental_file() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn save_incremental_update() -> Result<()> {
+    let original = std::fs::read("assets/example.pdf")?;
+    let previous = Document::load_mem(&original)?;
+    let mut doc = previous.clone();
+    let page_id = doc.page_iter().next().unwrap();
+    doc.get_dictionary_mut(page_id)?.set("Rotate", 90);
+
+    let mut updated = Vec::new();
+    doc.save_incremental(&original, &previous, &mut updated)?;
+    assert!(updated.starts_with(&original));
+    // Only the changed page is written after the original bytes.
+    let update = String::from_utf8_lossy(&updated[original.len()..]).into_owned();
+    assert_eq!(update.matches(" obj").count(), 1, "{}", update);
+    assert!(update.contains(&format!("/Prev {}", doc.xref_start)), "{}", update);
+
+    let reloaded = Document::load_mem(&updated)?;
+    let page = reloaded.get_dictionary(page_id)?;
+    assert_eq!(page.get(b"Rotate").and_then(Object::as_i64)?, 90);
+    assert_eq!(reloaded.objects.len(), doc.objects.len());
+    assert_eq!(reloaded.get_page_content(page_id)?, doc.get_page_content(page_id)?);
+
+    let original_page = Document::load_mem(&updated[..original.len()])?;
+    assert!(!original_page.get_dictionary(page_id)?.has(b"Rotate"));
+    Ok(())
+}
+
+#[test]
+fn save_incremental_update_with_bytes_before_header() -> Result<()> {
+    let mut original = b"junk before the header\n".to_vec();
+    original.extend(std::fs::read("assets/example.pdf")?);
+    let previous = Document::load_mem(&original)?;
+    assert_eq!(previous.header_offset, 23);
+    let mut doc = previous.clone();
+    let page_id = doc.page_iter().next().unwrap();
+    doc.get_dictionary_mut(page_id)?.set("Rotate", 90);
+
+    let mut updated = Vec::new();
+    doc.save_incremental(&original, &previous, &mut updated)?;
+    assert!(updated.starts_with(&original));
+    let reloaded = Document::load_mem(&updated)?;
+    assert!(!reloaded.xref_rebuilt);
+    let page = reloaded.get_dictionary(page_id)?;
+    assert_eq!(page.get(b"Rotate").and_then(Object::as_i64)?, 90);
+    assert_eq!(reloaded.get_page_content(page_id)?, doc.get_page_content(page_id)?);
+    Ok(())
+}
+
+#[test]
+fn save_incremental_update_deleting_object() -> Result<()> {
+    let original = std::fs::read("assets/example.pdf")?;
+    let previous = Document::load_mem(&original)?;
+    let mut doc = previous.clone();
+    doc.get_dictionary_mut((3, 0))?.set("Font", lopdf::Dictionary::new());
+    doc.objects.remove(&(2, 0));
+
+    let mut updated = Vec::new();
+    doc.save_incremental(&original, &previous, &mut updated)?;
+    // The font is freed, with the generation number to use if its number is used again.
+    let update = String::from_utf8_lossy(&updated[original.len()..]).into_owned();
+    assert!(update.contains("0000000000 00001 f"), "{}", update);
+
+    let reloaded = Document::load_mem(&updated)?;
+    assert!(!reloaded.objects.contains_key(&(2, 0)));
+    assert_eq!(reloaded.objects.len(), doc.objects.len());
+    assert!(Document::load_mem(&updated[..original.len()])?.objects.contains_key(&(2, 0)));
+    Ok(())
+}
@@ -0,0 +1,141 @@
+#[cfg(any(feature = "pom_parser", feature = "nom_parser"))]
+#[cfg(not(feature = "async"))]
+mod tests_with_parsing {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Result, Stream};
+
+    /// Create a document with a single page showing `text` with the font created by `font`.
+    fn document_with_font<F>(font: F, text: Vec<u8>) -> (Document, ObjectId)
+    where
+        F: FnOnce(&mut Document) -> Dictionary,
+    {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font = font(&mut doc);
+        let font_id = doc.add_object(font);
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 720.into()]),
+                Operation::new("Tj", vec![Object::String(text, lopdf::StringFormat::Hexadecimal)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        (doc, page_id)
+    }
+
+    #[test]
+    fn extract_text_with_positions() -> Result<()> {
+        let doc = Document::load("assets/example.pdf")?;
+        let page_id = *doc.get_pages().get(&1).unwrap();
+
+        let runs = doc.extract_text_with_positions(page_id)?;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].text, "Hello World!");
+        assert_eq!(runs[0].font, b"F1");
+        assert_eq!(runs[0].font_size, 48.0);
+        assert_eq!((runs[0].x, runs[0].y), (100.0, 600.0));
+        Ok(())
+    }
+
+    #[test]
+    fn extract_text_with_to_unicode() -> Result<()> {
+        let cmap = b"1 begincodespacerange <00> <FF> endcodespacerange
+2 beginbfchar <01> <0048> <02> <0069> endbfchar
+1 beginbfrange <03> <04> <00210021> endbfrange";
+        let font = |doc: &mut Document| {
+            dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Custom",
+                "ToUnicode" => doc.add_object(Stream::new(dictionary! {}, cmap.to_vec())),
+            }
+        };
+        let (doc, page_id) = document_with_font(font, vec![1, 2, 3, 4]);
+
+        assert_eq!(doc.extract_text(&[1])?, "Hi!!!\"\n");
+        assert_eq!(doc.extract_text_with_positions(page_id)?[0].text, "Hi!!!\"");
+        Ok(())
+    }
+
+    /// Create a composite font with the `Identity-H` encoding.
+    fn cid_font(doc: &mut Document, ordering: &str, to_unicode: Option<&[u8]>) -> Dictionary {
+        let descendant_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "Custom",
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal(ordering),
+                "Supplement" => 0,
+            },
+            "CIDToGIDMap" => "Identity",
+        });
+        let mut font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "Custom",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant_id.into()],
+        };
+        if let Some(cmap) = to_unicode {
+            font.set("ToUnicode", doc.add_object(Stream::new(dictionary! {}, cmap.to_vec())));
+        }
+        font
+    }
+
+    #[test]
+    fn extract_text_from_cid_font() -> Result<()> {
+        let cmap = b"1 begincodespacerange <0000> <FFFF> endcodespacerange
+1 beginbfrange <0010> <0011> <00E4> endbfrange";
+        let (mut doc, _) = document_with_font(|doc| cid_font(doc, "Japan1", Some(cmap)), vec![0, 0x10, 0, 0x11]);
+
+        // Save and load the document to read the fonts back through the parser.
+        let mut buffer = vec![];
+        doc.save_to(&mut buffer)?;
+        let doc = Document::load_mem(&buffer)?;
+        assert_eq!(doc.extract_text(&[1])?, "\u{E4}\u{E5}\n");
+
+        let (doc, _) = document_with_font(|doc| cid_font(doc, "UCS", None), vec![0, 0x48, 0x30, 0x42]);
+        assert_eq!(doc.extract_text(&[1])?, "H\u{3042}\n");
+
+        // Identity CIDs are glyph ids, which can't be decoded without a ToUnicode CMap.
+        let (doc, _) = document_with_font(|doc| cid_font(doc, "Identity", None), vec![0, 0x48, 0x30, 0x42]);
+        assert_eq!(doc.extract_text(&[1])?, "\u{FFFD}\u{FFFD}\n");
+
+        let (doc, _) = document_with_font(|doc| cid_font(doc, "Japan1", None), vec![0, 0x48]);
+        assert_eq!(doc.extract_text(&[1])?, "\u{FFFD}\n");
+        Ok(())
+    }
+
+    #[test]
+    fn extract_text_from_cid_keyed_pdf() -> Result<()> {
+        let mut doc = Document::load("assets/CIDFont.pdf")?;
+        assert_eq!(doc.extract_text(&[1])?, "CID font\u{65E5}\u{65E6}\n");
+
+        // Without its ToUnicode CMap, the glyph ids of the font can't be decoded.
+        let font_id = (5, 0);
+        doc.get_dictionary_mut(font_id)?.remove(b"ToUnicode");
+        assert_eq!(doc.extract_text(&[1])?, format!("{}\n", "\u{FFFD}".repeat(10)));
+        Ok(())
+    }
+}